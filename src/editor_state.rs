@@ -1,5 +1,4 @@
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration;
 
@@ -22,7 +21,16 @@ use undo::Edit;
 use undo::Record;
 use uuid::Uuid;
 
-use crate::helpers::utilities::get_common_os_dir;
+use crate::helpers::camera::{CameraDrag, EditorCamera};
+use crate::helpers::component_edits::LevelEdit;
+use crate::helpers::editor_data::{save_editor_data, save_saved_state_file, EditorData};
+use crate::helpers::editor_render::EditorRenderState;
+use crate::helpers::editor_settings::{load_editor_settings, save_editor_settings, EditorSettings};
+use crate::helpers::gizmos::SelectionDrag;
+use crate::helpers::lights::LightComponent;
+use crate::helpers::measurements::Annotation;
+use crate::helpers::play_mode::PlaySession;
+use crate::helpers::selection::PointerPress;
 
 #[derive(Debug)]
 pub struct ObjectEdit {
@@ -71,6 +79,14 @@ pub struct EditorState {
     // pub object_selected: bool,
     // pub selected_object_id: Uuid,
     pub value_signals: Arc<Mutex<HashMap<String, RwSignal<String>>>>,
+    /// Gizmo drag in progress on the selection
+    pub gizmo_drag: Option<SelectionDrag>,
+    /// Left button press that may become a click select or a box select
+    pub pointer_press: Option<PointerPress>,
+    /// Cursor position in logical pixels, for the marquee overlay
+    pub last_logical_position: [f64; 2],
+    /// Orbit or pan while its mouse button is held
+    pub camera_drag: Option<CameraDrag>,
     // pub current_modifiers: ModifiersState,
     // pub mouse_state: MouseState,
 }
@@ -95,6 +111,10 @@ impl EditorState {
             // object_selected: false,
             // selected_object_id: Uuid::nil(),
             value_signals: Arc::new(Mutex::new(HashMap::new())),
+            gizmo_drag: None,
+            pointer_press: None,
+            last_logical_position: [0.0, 0.0],
+            camera_drag: None,
             // current_modifiers: ModifiersState::empty(),
             // mouse_state: MouseState {
             //     last_mouse_x: 0.0,
@@ -175,6 +195,9 @@ pub struct NamedSignals {
 pub struct StateHelper {
     pub renderer_state: Option<Arc<Mutex<RendererState>>>,
     pub saved_state: Option<Arc<Mutex<SavedState>>>,
+    pub editor_data: Option<Arc<Mutex<EditorData>>>,
+    pub editor_render: Option<Arc<Mutex<EditorRenderState>>>,
+    pub editor_settings: EditorSettings,
     pub last_saved_state: Option<SavedState>, // for comparisons
     pub project_selected_signal: Option<RwSignal<Uuid>>,
     pub auth_token: String,
@@ -183,6 +206,27 @@ pub struct StateHelper {
     pub selected_object_id_signal: Option<RwSignal<Uuid>>,
     pub selected_object_data_signal: Option<RwSignal<ComponentData>>,
     pub current_view_signal: Option<RwSignal<String>>,
+    /// Selected component ids, the last one is the active component
+    pub selection: Vec<String>,
+    pub selection_signal: Option<RwSignal<Vec<String>>>,
+    /// Box select rectangle in logical pixels, x, y, width, height
+    pub marquee_signal: Option<RwSignal<Option<(f64, f64, f64, f64)>>>,
+    pub level_components_signal: Option<RwSignal<Vec<ComponentData>>>,
+    pub level_lights_signal: Option<RwSignal<Vec<LightComponent>>>,
+    pub annotations_signal: Option<RwSignal<Vec<Annotation>>>,
+    /// Level being edited, the first level when none was opened yet
+    pub active_level_id: Option<String>,
+    /// Set after a level opens, once the state helper is unlocked
+    pub active_level_signal: Option<RwSignal<String>>,
+    /// Deleted, duplicated, pasted and moved components for undo, on one timeline. Cleared when a
+    /// project or level opens.
+    pub history: Arc<Mutex<Record<LevelEdit>>>,
+    /// Orbit pivot and view of the viewport camera
+    pub editor_camera: EditorCamera,
+    /// The level as it was before play started, None while editing
+    pub play_session: Option<PlaySession>,
+    /// Landscape tiles whose heightmaps are being read, so camera moves don't load them twice
+    pub loading_tiles: Arc<Mutex<HashSet<String>>>,
 }
 
 #[derive(Clone, Debug)]
@@ -204,6 +248,9 @@ impl StateHelper {
         Self {
             renderer_state: None,
             saved_state: None,
+            editor_data: None,
+            editor_render: None,
+            editor_settings: load_editor_settings(),
             last_saved_state: None,
             project_selected_signal: None,
             auth_token,
@@ -212,6 +259,18 @@ impl StateHelper {
             selected_object_id_signal: None,
             selected_object_data_signal: None,
             current_view_signal: None,
+            selection: Vec::new(),
+            selection_signal: None,
+            marquee_signal: None,
+            level_components_signal: None,
+            level_lights_signal: None,
+            annotations_signal: None,
+            active_level_id: None,
+            active_level_signal: None,
+            history: Arc::new(Mutex::new(Record::new())),
+            editor_camera: EditorCamera::default(),
+            play_session: None,
+            loading_tiles: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
@@ -250,20 +309,39 @@ impl StateHelper {
     }
 
     pub fn save_saved_state_raw(&self, project_id: Uuid, saved_state: SavedState) {
-        let json =
-            serde_json::to_string_pretty(&saved_state).expect("Couldn't serialize saved state");
-        let sync_dir = get_common_os_dir().expect("Couldn't get CommonOS directory");
-        let save_path = sync_dir
-            .join("midpoint")
-            .join("projects")
-            .join(project_id.to_string())
-            .join("midpoint.json");
-
-        println!("Saving saved state... {}", save_path.display());
+        // stopping play throws away what changed meanwhile, so the project file is left alone
+        if self.play_session.is_some() {
+            return;
+        }
 
-        fs::write(&save_path, json).expect("Couldn't write saved state");
+        save_saved_state_file(&project_id.to_string(), &saved_state);
 
         drop(saved_state);
         println!("Saved!");
     }
+
+    pub fn save_current_editor_data(&self) {
+        if self.play_session.is_some() {
+            return;
+        }
+
+        let editor_data = self
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+        let project_id = self
+            .project_selected_signal
+            .expect("Couldn't get project signal")
+            .get();
+
+        save_editor_data(&project_id.to_string(), &editor_data);
+    }
+
+    /// Applies a change to the editor settings and saves them right away
+    pub fn update_editor_settings(&mut self, edit: impl FnOnce(&mut EditorSettings)) {
+        edit(&mut self.editor_settings);
+        save_editor_settings(&self.editor_settings);
+    }
 }
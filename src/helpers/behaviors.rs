@@ -0,0 +1,530 @@
+use std::collections::HashSet;
+
+use nalgebra::Vector3;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::levels::active_level_id;
+use super::nodes::{NodeComponent, NodeType, Port};
+
+/// Connections are followed this deep at most, so a graph wired in a loop still evaluates
+const MAX_GRAPH_DEPTH: usize = 32;
+
+/// What the behavior editor can add, the node types play knows how to run
+pub const BEHAVIOR_NODE_TYPES: [NodeType; 7] = [
+    NodeType::Event,
+    NodeType::Timer,
+    NodeType::Float,
+    NodeType::Vector3,
+    NodeType::MathOp,
+    NodeType::VectorOp,
+    NodeType::PhysicsOp,
+];
+
+/// A node graph a component runs while the level plays. Events and timers trigger PhysicsOp
+/// nodes, which push the component's body along a vector worked out by the value nodes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Behavior {
+    pub component_id: String,
+    pub nodes: Vec<NodeComponent>,
+}
+
+/// What the graphs see of the game on each step
+pub struct BehaviorInput<'a> {
+    /// Seconds since play started, this step included
+    pub elapsed: f32,
+    /// `elapsed` as of the last step, zero on the first
+    pub last_elapsed: f32,
+    /// Keys held down, lowercase with the space bar as "space"
+    pub held_keys: &'a HashSet<String>,
+}
+
+/// What a triggered PhysicsOp node does to its component's body
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BehaviorCommand {
+    ApplyImpulse(Vector3<f32>),
+    /// Pushes for as long as the trigger keeps firing
+    ApplyForce(Vector3<f32>),
+    SetVelocity(Vector3<f32>),
+    AddTorque(Vector3<f32>),
+    SetPosition(Vector3<f32>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BehaviorValue {
+    Float(f32),
+    Vector(Vector3<f32>),
+}
+
+/// A new node of one of the behavior node types, with the inputs the evaluator reads and a value
+/// it can run with straight away
+pub fn behavior_node(node_type: NodeType, position: [u32; 2]) -> NodeComponent {
+    let id = Uuid::new_v4().to_string();
+    let (title, inputs, output, value): (&str, &[&str], Option<&str>, &str) = match node_type {
+        NodeType::Event => ("Event", &[], Some("Fired"), "update"),
+        NodeType::Timer => ("Timer", &[], Some("Fired"), "1.0"),
+        NodeType::Float => ("Float", &[], Some("Value"), "1.0"),
+        NodeType::Vector3 => ("Vector3", &["x", "y", "z"], Some("Value"), "0, 1, 0"),
+        NodeType::MathOp => ("Math", &["a", "b"], Some("Result"), "multiply"),
+        NodeType::VectorOp => ("Vector Op", &["a", "b"], Some("Result"), "scale"),
+        NodeType::PhysicsOp => ("Physics", &["trigger", "vector"], None, "apply_impulse"),
+        _ => ("Node", &[], None, ""),
+    };
+
+    NodeComponent {
+        title: title.to_string(),
+        ui_inputs: inputs
+            .iter()
+            .map(|name| Port {
+                id: format!("{}_{}", id, name),
+                input_name: Some(name.to_string()),
+                display_name: name.to_string(),
+                connected_to: None,
+                is_output: false,
+            })
+            .collect(),
+        ui_outputs: output
+            .map(|name| Port {
+                id: format!("{}_out", id),
+                input_name: None,
+                display_name: name.to_string(),
+                connected_to: None,
+                is_output: true,
+            })
+            .into_iter()
+            .collect(),
+        initial_position: position,
+        value: value.to_string(),
+        ..NodeComponent::new(id, node_type, Default::default())
+    }
+}
+
+/// What the node's value can be set to, shown next to its input
+pub fn value_hint(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::Event => "start, update or key:w",
+        NodeType::Timer => "seconds between firings",
+        NodeType::Float => "number",
+        NodeType::Vector3 => "x, y, z",
+        NodeType::MathOp => "add, subtract, multiply, divide, min, max or power",
+        NodeType::VectorOp => "add, subtract, scale, cross, normalize or length",
+        NodeType::PhysicsOp => {
+            "apply_impulse, apply_force, set_velocity, add_torque or set_position"
+        }
+        _ => "",
+    }
+}
+
+/// Feeds the output port into the input port, replacing whatever fed the input before
+pub fn connect_ports(nodes: &mut [NodeComponent], output_id: &str, input_id: &str) {
+    for node in nodes.iter_mut() {
+        for port in node.ui_inputs.iter_mut().filter(|p| p.id == input_id) {
+            port.connected_to = Some(output_id.to_string());
+        }
+        for port in node.ui_outputs.iter_mut().filter(|p| p.id == output_id) {
+            port.connected_to = Some(input_id.to_string());
+        }
+    }
+}
+
+/// Takes the node out along with the connections into and out of it
+pub fn remove_node(nodes: &mut Vec<NodeComponent>, node_id: &str) {
+    let Some(index) = nodes.iter().position(|n| n.id == node_id) else {
+        return;
+    };
+    let removed = nodes.remove(index);
+    let removed_ports: Vec<&String> = removed
+        .ui_inputs
+        .iter()
+        .chain(removed.ui_outputs.iter())
+        .map(|p| &p.id)
+        .collect();
+
+    for port in nodes
+        .iter_mut()
+        .flat_map(|n| n.ui_inputs.iter_mut().chain(n.ui_outputs.iter_mut()))
+    {
+        if port
+            .connected_to
+            .as_ref()
+            .map_or(false, |id| removed_ports.contains(&id))
+        {
+            port.connected_to = None;
+        }
+    }
+}
+
+/// The node feeding one of the node's inputs, through the output port the input is connected to
+fn input_source<'a>(
+    nodes: &'a [NodeComponent],
+    node: &NodeComponent,
+    input_name: &str,
+) -> Option<&'a NodeComponent> {
+    let port = node
+        .ui_inputs
+        .iter()
+        .find(|p| p.input_name.as_deref() == Some(input_name))?;
+    let source_port = port.connected_to.as_deref()?;
+
+    nodes
+        .iter()
+        .find(|n| n.ui_outputs.iter().any(|p| p.id == source_port))
+}
+
+fn parse_vector(value: &str) -> Option<Vector3<f32>> {
+    let parts: Vec<f32> = value
+        .split(',')
+        .map(|part| part.trim().parse().ok())
+        .collect::<Option<_>>()?;
+
+    match parts.as_slice() {
+        [x, y, z] => Some(Vector3::new(*x, *y, *z)),
+        _ => None,
+    }
+}
+
+/// Whether an Event or Timer node fires on this step
+fn fires(node: &NodeComponent, input: &BehaviorInput) -> bool {
+    let value = node.value.trim();
+
+    match node.node_type {
+        NodeType::Event => match value {
+            "start" => input.last_elapsed <= 0.0,
+            "update" => true,
+            _ => value
+                .strip_prefix("key:")
+                .map_or(false, |key| input.held_keys.contains(&key.to_lowercase())),
+        },
+        NodeType::Timer => {
+            let Some(period) = value.parse::<f32>().ok().filter(|p| *p > 0.0) else {
+                return false;
+            };
+
+            (input.elapsed / period).floor() > (input.last_elapsed / period).floor()
+        }
+        _ => false,
+    }
+}
+
+fn math_op(operation: &str, a: f32, b: f32) -> Option<f32> {
+    match operation.trim() {
+        "add" => Some(a + b),
+        "subtract" => Some(a - b),
+        "multiply" => Some(a * b),
+        "divide" if b != 0.0 => Some(a / b),
+        "min" => Some(a.min(b)),
+        "max" => Some(a.max(b)),
+        "power" => Some(a.powf(b)),
+        _ => None,
+    }
+}
+
+fn vector_op(operation: &str, a: BehaviorValue, b: Option<BehaviorValue>) -> Option<BehaviorValue> {
+    let BehaviorValue::Vector(a) = a else {
+        return None;
+    };
+
+    match (operation.trim(), b) {
+        ("add", Some(BehaviorValue::Vector(b))) => Some(BehaviorValue::Vector(a + b)),
+        ("subtract", Some(BehaviorValue::Vector(b))) => Some(BehaviorValue::Vector(a - b)),
+        ("scale", Some(BehaviorValue::Float(b))) => Some(BehaviorValue::Vector(a * b)),
+        ("cross", Some(BehaviorValue::Vector(b))) => Some(BehaviorValue::Vector(a.cross(&b))),
+        ("normalize", _) => a.try_normalize(1e-6).map(BehaviorValue::Vector),
+        ("length", _) => Some(BehaviorValue::Float(a.norm())),
+        _ => None,
+    }
+}
+
+/// The value a node puts out, None when its inputs are missing or don't fit
+fn value_of(nodes: &[NodeComponent], node: &NodeComponent, depth: usize) -> Option<BehaviorValue> {
+    if depth > MAX_GRAPH_DEPTH {
+        return None;
+    }
+
+    let input = |name: &str| {
+        input_source(nodes, node, name).and_then(|source| value_of(nodes, source, depth + 1))
+    };
+
+    match node.node_type {
+        NodeType::Float => node.value.trim().parse().ok().map(BehaviorValue::Float),
+        NodeType::Vector3 => {
+            // connected axes win over the typed in ones
+            let mut vector = parse_vector(&node.value).unwrap_or_else(Vector3::zeros);
+            for (axis, name) in ["x", "y", "z"].into_iter().enumerate() {
+                if let Some(BehaviorValue::Float(value)) = input(name) {
+                    vector[axis] = value;
+                }
+            }
+
+            Some(BehaviorValue::Vector(vector))
+        }
+        NodeType::MathOp => match (input("a")?, input("b")?) {
+            (BehaviorValue::Float(a), BehaviorValue::Float(b)) => {
+                math_op(&node.value, a, b).map(BehaviorValue::Float)
+            }
+            _ => None,
+        },
+        NodeType::VectorOp => vector_op(&node.value, input("a")?, input("b")),
+        _ => None,
+    }
+}
+
+/// Runs one step of a behavior graph, giving back what its triggered PhysicsOp nodes do
+pub fn evaluate_behavior(nodes: &[NodeComponent], input: &BehaviorInput) -> Vec<BehaviorCommand> {
+    nodes
+        .iter()
+        .filter(|node| node.node_type == NodeType::PhysicsOp)
+        .filter_map(|node| {
+            let trigger = input_source(nodes, node, "trigger")?;
+            if !fires(trigger, input) {
+                return None;
+            }
+
+            let source = input_source(nodes, node, "vector")?;
+            let BehaviorValue::Vector(vector) = value_of(nodes, source, 0)? else {
+                return None;
+            };
+
+            match node.value.trim() {
+                "apply_impulse" => Some(BehaviorCommand::ApplyImpulse(vector)),
+                "apply_force" => Some(BehaviorCommand::ApplyForce(vector)),
+                "set_velocity" => Some(BehaviorCommand::SetVelocity(vector)),
+                "add_torque" => Some(BehaviorCommand::AddTorque(vector)),
+                "set_position" => Some(BehaviorCommand::SetPosition(vector)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// The active level's behaviors, saved in its entry in midpoint.json
+pub fn level_behaviors(state_helper: &StateHelper) -> Vec<Behavior> {
+    let (Some(editor_data), Some(level_id)) = (
+        state_helper.editor_data.as_ref(),
+        active_level_id(state_helper),
+    ) else {
+        return Vec::new();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.behaviors.clone())
+        .unwrap_or_default()
+}
+
+/// The component's graph, empty when it has no behavior yet
+pub fn component_behavior(state_helper: &StateHelper, component_id: &str) -> Vec<NodeComponent> {
+    level_behaviors(state_helper)
+        .into_iter()
+        .find(|behavior| behavior.component_id == component_id)
+        .map(|behavior| behavior.nodes)
+        .unwrap_or_default()
+}
+
+/// Replaces the component's graph, an empty one takes the behavior off the component
+pub fn set_component_behavior(
+    state_helper: &StateHelper,
+    component_id: &str,
+    nodes: Vec<NodeComponent>,
+) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+        let behaviors = &mut editor_data.level_mut(&level_id).behaviors;
+
+        behaviors.retain(|behavior| behavior.component_id != component_id);
+        if !nodes.is_empty() {
+            behaviors.push(Behavior {
+                component_id: component_id.to_string(),
+                nodes,
+            });
+        }
+    }
+
+    state_helper.save_current_editor_data();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(node_type: NodeType, value: &str) -> NodeComponent {
+        NodeComponent {
+            value: value.to_string(),
+            ..behavior_node(node_type, [0, 0])
+        }
+    }
+
+    fn output(node: &NodeComponent) -> String {
+        node.ui_outputs[0].id.clone()
+    }
+
+    fn input(node: &NodeComponent, name: &str) -> String {
+        format!("{}_{}", node.id, name)
+    }
+
+    /// Event or timer into a PhysicsOp, pushing along the vector node
+    fn physics_graph(trigger: NodeComponent, vector: NodeComponent) -> Vec<NodeComponent> {
+        let physics = node(NodeType::PhysicsOp, "apply_impulse");
+        let mut nodes = vec![trigger, vector, physics];
+
+        let (trigger_out, vector_out) = (output(&nodes[0]), output(&nodes[1]));
+        let (trigger_in, vector_in) = (input(&nodes[2], "trigger"), input(&nodes[2], "vector"));
+        connect_ports(&mut nodes, &trigger_out, &trigger_in);
+        connect_ports(&mut nodes, &vector_out, &vector_in);
+
+        nodes
+    }
+
+    fn step(elapsed: f32, last_elapsed: f32, held_keys: &HashSet<String>) -> BehaviorInput {
+        BehaviorInput {
+            elapsed,
+            last_elapsed,
+            held_keys,
+        }
+    }
+
+    #[test]
+    fn events_fire_on_start_every_update_and_while_their_key_is_held() {
+        let none = HashSet::new();
+        let w = HashSet::from(["w".to_string()]);
+        let up = node(NodeType::Vector3, "0, 2, 0");
+
+        let on_start = physics_graph(node(NodeType::Event, "start"), up.clone());
+        assert_eq!(
+            evaluate_behavior(&on_start, &step(0.016, 0.0, &none)),
+            vec![BehaviorCommand::ApplyImpulse(Vector3::new(0.0, 2.0, 0.0))]
+        );
+        assert!(evaluate_behavior(&on_start, &step(0.032, 0.016, &none)).is_empty());
+
+        let on_update = physics_graph(node(NodeType::Event, "update"), up.clone());
+        assert_eq!(
+            evaluate_behavior(&on_update, &step(5.0, 4.984, &none)).len(),
+            1
+        );
+
+        let on_key = physics_graph(node(NodeType::Event, "key:W"), up);
+        assert!(evaluate_behavior(&on_key, &step(1.0, 0.984, &none)).is_empty());
+        assert_eq!(evaluate_behavior(&on_key, &step(1.0, 0.984, &w)).len(), 1);
+    }
+
+    #[test]
+    fn timers_fire_once_per_period() {
+        let none = HashSet::new();
+        let nodes = physics_graph(
+            node(NodeType::Timer, "0.5"),
+            node(NodeType::Vector3, "1, 0, 0"),
+        );
+
+        let fired: usize = (1..=60)
+            .map(|frame| {
+                let (elapsed, last_elapsed) = (frame as f32 / 60.0, (frame - 1) as f32 / 60.0);
+                evaluate_behavior(&nodes, &step(elapsed, last_elapsed, &none))
+            })
+            .map(|commands| commands.len())
+            .sum();
+        assert_eq!(fired, 2);
+
+        let broken = physics_graph(
+            node(NodeType::Timer, "never"),
+            node(NodeType::Vector3, "1, 0, 0"),
+        );
+        assert!(evaluate_behavior(&broken, &step(1.0, 0.0, &none)).is_empty());
+    }
+
+    #[test]
+    fn value_nodes_work_out_the_vector() {
+        let none = HashSet::new();
+        let mut nodes = physics_graph(
+            node(NodeType::Event, "update"),
+            node(NodeType::VectorOp, "scale"),
+        );
+        nodes.push(node(NodeType::Vector3, "1, 2, 3"));
+        nodes.push(node(NodeType::MathOp, "add"));
+        nodes.push(node(NodeType::Float, "1.5"));
+        nodes.push(node(NodeType::Float, "0.5"));
+
+        let vector_out = output(&nodes[3]);
+        let sum_out = output(&nodes[4]);
+        let (a_out, b_out) = (output(&nodes[5]), output(&nodes[6]));
+        let (scale_a, scale_b) = (input(&nodes[1], "a"), input(&nodes[1], "b"));
+        let (sum_a, sum_b) = (input(&nodes[4], "a"), input(&nodes[4], "b"));
+        connect_ports(&mut nodes, &vector_out, &scale_a);
+        connect_ports(&mut nodes, &sum_out, &scale_b);
+        connect_ports(&mut nodes, &a_out, &sum_a);
+        connect_ports(&mut nodes, &b_out, &sum_b);
+
+        assert_eq!(
+            evaluate_behavior(&nodes, &step(1.0, 0.984, &none)),
+            vec![BehaviorCommand::ApplyImpulse(Vector3::new(2.0, 4.0, 6.0))]
+        );
+
+        // a connected axis replaces the typed in one
+        let y_in = input(&nodes[3], "y");
+        connect_ports(&mut nodes, &a_out, &y_in);
+        assert_eq!(
+            evaluate_behavior(&nodes, &step(1.0, 0.984, &none)),
+            vec![BehaviorCommand::ApplyImpulse(Vector3::new(2.0, 3.0, 6.0))]
+        );
+    }
+
+    #[test]
+    fn broken_or_looping_graphs_do_nothing() {
+        let none = HashSet::new();
+
+        // nothing connected to the vector input
+        let mut unconnected = physics_graph(
+            node(NodeType::Event, "update"),
+            node(NodeType::Vector3, "0, 1, 0"),
+        );
+        let vector_id = unconnected[1].id.clone();
+        remove_node(&mut unconnected, &vector_id);
+        assert!(unconnected[1].ui_inputs[1].connected_to.is_none());
+        assert!(evaluate_behavior(&unconnected, &step(1.0, 0.984, &none)).is_empty());
+
+        // a vector op fed by itself
+        let mut looping = physics_graph(
+            node(NodeType::Event, "update"),
+            node(NodeType::VectorOp, "add"),
+        );
+        let (op_out, op_a) = (output(&looping[1]), input(&looping[1], "a"));
+        connect_ports(&mut looping, &op_out, &op_a);
+        assert!(evaluate_behavior(&looping, &step(1.0, 0.984, &none)).is_empty());
+
+        let unknown = {
+            let mut nodes = physics_graph(
+                node(NodeType::Event, "update"),
+                node(NodeType::Vector3, "0, 1, 0"),
+            );
+            nodes[2].value = "explode".to_string();
+            nodes
+        };
+        assert!(evaluate_behavior(&unknown, &step(1.0, 0.984, &none)).is_empty());
+    }
+
+    #[test]
+    fn behaviors_round_trip_through_json() {
+        let behavior = Behavior {
+            component_id: "component".to_string(),
+            nodes: physics_graph(
+                node(NodeType::Timer, "2"),
+                node(NodeType::Vector3, "0, 0, 1"),
+            ),
+        };
+
+        let json = serde_json::to_value(&behavior).unwrap();
+        assert_eq!(serde_json::from_value::<Behavior>(json).unwrap(), behavior);
+    }
+}
@@ -0,0 +1,474 @@
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::handlers::get_camera;
+use midpoint_engine::helpers::saved_data::ComponentData;
+use nalgebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::levels::active_level_id;
+
+/// Orthographic views narrow the field of view by this much and back off to match, so the
+/// engine's perspective camera and picking rays keep working with almost parallel lines
+const ORTHO_FOV_SCALE: f32 = 0.1;
+/// Keeps orbiting short of the poles where the camera would flip over
+const MAX_ORBIT_ELEVATION: f32 = 0.99;
+const MIN_PIVOT_DISTANCE: f32 = 0.5;
+/// Framing distance as a multiple of the selection's bounding radius
+const FRAME_DISTANCE: f32 = 2.5;
+/// Pivot distance used before anything set one
+const DEFAULT_PIVOT_DISTANCE: f32 = 10.0;
+/// How far in front of the camera newly placed objects land
+const PLACEMENT_DISTANCE: f32 = 5.0;
+
+/// What holding the right mouse button and dragging does
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum CameraDragMode {
+    /// Looks around from where the camera stands, the engine's navigation
+    Fly,
+    /// Turns the camera around its pivot
+    Orbit,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct CameraSettings {
+    /// Keyboard navigation speed handed to the engine
+    #[serde(default = "default_move_speed")]
+    pub move_speed: f32,
+    /// Degrees per pixel of mouse travel while orbiting
+    #[serde(default = "default_orbit_sensitivity")]
+    pub orbit_sensitivity: f32,
+    /// Pivot distances per pixel of mouse travel while panning
+    #[serde(default = "default_pan_speed")]
+    pub pan_speed: f32,
+    /// Share of the pivot distance covered by one wheel notch
+    #[serde(default = "default_zoom_speed")]
+    pub zoom_speed: f32,
+    /// Alt always orbits, whatever the right button does
+    #[serde(default = "default_right_drag")]
+    pub right_drag: CameraDragMode,
+}
+
+fn default_move_speed() -> f32 {
+    5.0
+}
+
+fn default_orbit_sensitivity() -> f32 {
+    0.3
+}
+
+fn default_pan_speed() -> f32 {
+    0.002
+}
+
+fn default_zoom_speed() -> f32 {
+    0.1
+}
+
+fn default_right_drag() -> CameraDragMode {
+    CameraDragMode::Fly
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            move_speed: default_move_speed(),
+            orbit_sensitivity: default_orbit_sensitivity(),
+            pan_speed: default_pan_speed(),
+            zoom_speed: default_zoom_speed(),
+            right_drag: default_right_drag(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum CameraView {
+    Perspective,
+    /// Looking down the Y axis
+    Top,
+    /// Looking down the Z axis
+    Front,
+    /// Looking down the X axis
+    Side,
+}
+
+impl CameraView {
+    /// Direction from the pivot towards the camera
+    fn axis(&self) -> Option<Vector3<f32>> {
+        match self {
+            CameraView::Perspective => None,
+            // leaning a hair towards Z keeps the look at away from the up axis
+            CameraView::Top => Some(Vector3::new(0.0, 1.0, 0.001).normalize()),
+            CameraView::Front => Some(Vector3::z()),
+            CameraView::Side => Some(Vector3::x()),
+        }
+    }
+}
+
+/// Camera drag in progress in the viewport
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CameraDrag {
+    Orbit,
+    Pan,
+}
+
+/// Editor side of the camera, the engine camera only knows where it stands and looks
+#[derive(Clone, Debug)]
+pub struct EditorCamera {
+    /// Orbiting turns around this point, panning carries it along
+    pub pivot: Point3<f32>,
+    pub view: CameraView,
+    /// Field of view to restore when leaving an orthographic view
+    perspective_fovy: Option<f32>,
+}
+
+impl Default for EditorCamera {
+    fn default() -> Self {
+        Self {
+            pivot: Point3::origin(),
+            view: CameraView::Perspective,
+            perspective_fovy: None,
+        }
+    }
+}
+
+/// Saved camera placement, stored with the level
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CameraBookmark {
+    pub id: String,
+    pub name: String,
+    pub position: [f32; 3],
+    pub pivot: [f32; 3],
+    pub view: CameraView,
+}
+
+/// Ortho views stand further back so the narrowed view covers the same ground
+fn view_distance_scale(view: CameraView) -> f32 {
+    match view {
+        CameraView::Perspective => 1.0,
+        _ => 1.0 / ORTHO_FOV_SCALE,
+    }
+}
+
+/// Places the engine camera at the position looking at the target
+fn aim_camera(position: Point3<f32>, target: Point3<f32>) {
+    let camera = get_camera();
+    let direction = target - position;
+
+    if direction.norm() <= f32::EPSILON {
+        return;
+    }
+
+    camera.position = position;
+    camera.direction = direction.normalize();
+    camera.update_view_projection_matrix();
+}
+
+fn camera_position() -> Point3<f32> {
+    get_camera().position
+}
+
+/// Where something added from the browsers goes, in front of the camera
+pub fn placement_position() -> [f32; 3] {
+    let camera = get_camera();
+
+    (camera.position + camera.forward_vector().normalize() * PLACEMENT_DISTANCE).into()
+}
+
+/// Keeps the pivot in front of the camera, fly navigation moves the camera without it
+fn sync_pivot(editor_camera: &mut EditorCamera) {
+    let camera = get_camera();
+    let distance = (editor_camera.pivot - camera.position).norm();
+    let distance = if distance < MIN_PIVOT_DISTANCE {
+        DEFAULT_PIVOT_DISTANCE
+    } else {
+        distance
+    };
+
+    editor_camera.pivot = camera.position + camera.forward_vector().normalize() * distance;
+}
+
+/// Turns the camera around the pivot, orthographic views drop back to perspective first
+pub fn orbit_camera(editor_camera: &mut EditorCamera, settings: &CameraSettings, dx: f32, dy: f32) {
+    if editor_camera.view != CameraView::Perspective {
+        set_camera_view(editor_camera, CameraView::Perspective);
+    }
+
+    sync_pivot(editor_camera);
+
+    let offset = camera_position() - editor_camera.pivot;
+    let radians_per_pixel = settings.orbit_sensitivity.to_radians();
+
+    let yaw = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -dx * radians_per_pixel);
+    let mut rotated = yaw * offset;
+
+    let right = Vector3::y().cross(&rotated);
+    if right.norm() > f32::EPSILON {
+        let pitch =
+            UnitQuaternion::from_axis_angle(&Unit::new_normalize(right), dy * radians_per_pixel);
+        let pitched = pitch * rotated;
+
+        if pitched.normalize().y.abs() < MAX_ORBIT_ELEVATION {
+            rotated = pitched;
+        }
+    }
+
+    aim_camera(editor_camera.pivot + rotated, editor_camera.pivot);
+}
+
+/// Slides the camera and its pivot across the view, faster the further away the pivot is
+pub fn pan_camera(editor_camera: &mut EditorCamera, settings: &CameraSettings, dx: f32, dy: f32) {
+    sync_pivot(editor_camera);
+
+    let camera = get_camera();
+    let forward = camera.forward_vector().normalize();
+    let right = forward.cross(&Vector3::y());
+    if right.norm() <= f32::EPSILON {
+        return;
+    }
+    let right = right.normalize();
+    let up = right.cross(&forward);
+
+    // ortho views stand further back but the same drag should cover the same ground
+    let distance =
+        (editor_camera.pivot - camera.position).norm() / view_distance_scale(editor_camera.view);
+    let offset = (-right * dx + up * dy) * settings.pan_speed * distance;
+
+    editor_camera.pivot += offset;
+    let position = camera.position + offset;
+    aim_camera(position, editor_camera.pivot);
+}
+
+/// Moves the camera towards (positive) or away from the pivot, one step per wheel notch
+pub fn dolly_camera(editor_camera: &mut EditorCamera, settings: &CameraSettings, steps: f32) {
+    sync_pivot(editor_camera);
+
+    let offset = camera_position() - editor_camera.pivot;
+    let factor = (1.0 - settings.zoom_speed).powf(steps);
+    let distance = (offset.norm() * factor).max(MIN_PIVOT_DISTANCE);
+
+    aim_camera(
+        editor_camera.pivot + offset.normalize() * distance,
+        editor_camera.pivot,
+    );
+}
+
+/// Switches between perspective and the axis aligned views, keeping the pivot in place
+pub fn set_camera_view(editor_camera: &mut EditorCamera, view: CameraView) {
+    sync_pivot(editor_camera);
+
+    let camera = get_camera();
+    let offset = camera.position - editor_camera.pivot;
+    let distance =
+        offset.norm() / view_distance_scale(editor_camera.view) * view_distance_scale(view);
+
+    // the perspective field of view comes back when leaving the ortho views
+    let perspective_fovy = *editor_camera.perspective_fovy.get_or_insert(camera.fovy);
+    camera.fovy = match view {
+        CameraView::Perspective => perspective_fovy,
+        _ => perspective_fovy * ORTHO_FOV_SCALE,
+    };
+    if view == CameraView::Perspective {
+        editor_camera.perspective_fovy = None;
+    }
+
+    let direction = view.axis().unwrap_or_else(|| offset.normalize());
+    editor_camera.view = view;
+
+    aim_camera(
+        editor_camera.pivot + direction * distance,
+        editor_camera.pivot,
+    );
+}
+
+/// World bounds of the colliders carrying the id, None when it has no colliders
+pub fn collider_bounds(
+    renderer_state: &RendererState,
+    id: &str,
+) -> Option<(Point3<f32>, Point3<f32>)> {
+    let user_data = Uuid::parse_str(id)
+        .map(|id| id.as_u128())
+        .unwrap_or_default();
+
+    renderer_state
+        .collider_set
+        .iter()
+        .filter(|(_, collider)| collider.user_data == user_data)
+        .map(|(_, collider)| collider.compute_aabb())
+        .reduce(|a, b| a.merged(&b))
+        .map(|aabb| (aabb.mins, aabb.maxs))
+}
+
+/// World bounds of a component from its colliders, the transform when it has none
+pub fn component_bounds(
+    renderer_state: &RendererState,
+    component: &ComponentData,
+) -> (Point3<f32>, Point3<f32>) {
+    collider_bounds(renderer_state, &component.id).unwrap_or_else(|| {
+        let position = Point3::from(component.generic_properties.position);
+        let half = Vector3::from(component.generic_properties.scale).abs() / 2.0;
+        (position - half, position + half)
+    })
+}
+
+/// Bounds around all of the components, None for an empty list
+pub fn components_bounds(
+    renderer_state: &RendererState,
+    components: &[ComponentData],
+) -> Option<(Point3<f32>, Point3<f32>)> {
+    components
+        .iter()
+        .map(|component| component_bounds(renderer_state, component))
+        .reduce(|(a_mins, a_maxs), (b_mins, b_maxs)| (a_mins.inf(&b_mins), a_maxs.sup(&b_maxs)))
+}
+
+/// Moves the pivot to the middle of the components and backs off until they fit the view
+pub fn frame_components(
+    editor_camera: &mut EditorCamera,
+    renderer_state: &RendererState,
+    components: &[ComponentData],
+) {
+    let Some((mins, maxs)) = components_bounds(renderer_state, components) else {
+        return;
+    };
+
+    let center = nalgebra::center(&mins, &maxs);
+    let radius = ((maxs - mins).norm() / 2.0).max(MIN_PIVOT_DISTANCE);
+    let distance = radius * FRAME_DISTANCE * view_distance_scale(editor_camera.view);
+
+    let direction = editor_camera.view.axis().unwrap_or_else(|| {
+        let offset = camera_position() - editor_camera.pivot;
+        if offset.norm() > f32::EPSILON {
+            offset.normalize()
+        } else {
+            -get_camera().forward_vector().normalize()
+        }
+    });
+
+    editor_camera.pivot = center;
+    aim_camera(center + direction * distance, center);
+}
+
+pub fn level_camera_bookmarks(state_helper: &StateHelper) -> Vec<CameraBookmark> {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return Vec::new();
+    };
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .and_then(|editor_data| {
+            editor_data
+                .lock()
+                .unwrap()
+                .level(&level_id)
+                .map(|level| level.camera_bookmarks.clone())
+        })
+        .unwrap_or_default()
+}
+
+fn update_camera_bookmarks(
+    state_helper: &StateHelper,
+    edit: impl FnOnce(&mut Vec<CameraBookmark>),
+) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    edit(
+        &mut state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap()
+            .level_mut(&level_id)
+            .camera_bookmarks,
+    );
+
+    state_helper.save_current_editor_data();
+}
+
+/// Saves where the camera is in the active level
+pub fn add_camera_bookmark(state_helper: &mut StateHelper, name: &str) {
+    sync_pivot(&mut state_helper.editor_camera);
+
+    let count = level_camera_bookmarks(state_helper).len();
+    let bookmark = CameraBookmark {
+        id: Uuid::new_v4().to_string(),
+        name: if name.is_empty() {
+            format!("Bookmark {}", count + 1)
+        } else {
+            name.to_string()
+        },
+        position: camera_position().into(),
+        pivot: state_helper.editor_camera.pivot.into(),
+        view: state_helper.editor_camera.view,
+    };
+
+    update_camera_bookmarks(state_helper, |bookmarks| bookmarks.push(bookmark));
+}
+
+pub fn remove_camera_bookmark(state_helper: &StateHelper, bookmark_id: &str) {
+    update_camera_bookmarks(state_helper, |bookmarks| {
+        bookmarks.retain(|bookmark| bookmark.id != bookmark_id)
+    });
+}
+
+/// Puts the camera back where the bookmark was taken
+pub fn go_to_camera_bookmark(editor_camera: &mut EditorCamera, bookmark: &CameraBookmark) {
+    if editor_camera.view != bookmark.view {
+        set_camera_view(editor_camera, bookmark.view);
+    }
+
+    editor_camera.pivot = Point3::from(bookmark.pivot);
+    aim_camera(Point3::from(bookmark.position), editor_camera.pivot);
+}
+
+/// View projection of the engine camera as it stands, for a target of another shape.
+/// The viewport sets its own aspect ratio again on the next frame.
+pub fn current_view_projection(aspect_ratio: f32) -> Matrix4<f32> {
+    let camera = get_camera();
+
+    camera.update_aspect_ratio(aspect_ratio);
+    camera.update_view_projection_matrix();
+
+    camera.view_projection_matrix
+}
+
+/// Where the camera of a perspective view projection stands, the point it sends to w = 0 on
+/// the view axis
+pub fn eye_position(view_projection: &Matrix4<f32>) -> Option<Point3<f32>> {
+    let eye = view_projection.try_inverse()? * Vector4::new(0.0, 0.0, 1.0, 0.0);
+
+    (eye.w.abs() > f32::EPSILON).then(|| Point3::from(eye.xyz() / eye.w))
+}
+
+/// View projection looking at the bounds from the direction with them filling the view.
+/// Borrows the engine camera and puts it back where it was.
+pub fn framed_view_projection(
+    editor_camera: &EditorCamera,
+    mins: Point3<f32>,
+    maxs: Point3<f32>,
+    direction: Vector3<f32>,
+    aspect_ratio: f32,
+) -> Matrix4<f32> {
+    let camera = get_camera();
+    let (position, camera_direction) = (camera.position, camera.direction);
+
+    let center = nalgebra::center(&mins, &maxs);
+    let radius = ((maxs - mins).norm() / 2.0).max(MIN_PIVOT_DISTANCE);
+    // the narrowed ortho field of view is still on the camera
+    let distance = radius * FRAME_DISTANCE * view_distance_scale(editor_camera.view);
+    aim_camera(center + direction.normalize() * distance, center);
+
+    let view_projection = current_view_projection(aspect_ratio);
+
+    let camera = get_camera();
+    camera.position = position;
+    camera.direction = camera_direction;
+    camera.update_view_projection_matrix();
+
+    view_projection
+}
@@ -0,0 +1,938 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::floem::reactive::{SignalGet, SignalUpdate};
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::{
+    handle_add_landscape, handle_add_landscape_texture, handle_add_model,
+};
+use midpoint_engine::helpers::saved_data::{
+    ComponentData, ComponentKind, File, LandscapeData, SavedState,
+};
+use midpoint_engine::helpers::utilities::{
+    get_heightmap_dir, get_models_dir, get_rockmap_dir, get_soilmap_dir, get_textures_dir,
+};
+use nalgebra::{Isometry3, Vector3};
+use serde::{Deserialize, Serialize};
+use undo::Edit;
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::gizmos::{apply_component_transform, component_transform, save_component_transforms};
+use super::instancing::register_model_asset;
+use super::levels::{active_level, active_level_mut};
+use super::lights::{
+    append_lights, insert_lights, is_light_component, level_lights, remove_lights, LightComponent,
+};
+use super::materials::{
+    apply_copied_materials, copy_materials, level_materials, refresh_level_materials,
+    MaterialOverride,
+};
+use super::selection::{place_selection_gizmos, selected_components, set_selection};
+use super::utilities::get_common_os_dir;
+
+/// How far a duplicate lands from its original so the two don't overlap
+pub const DUPLICATE_OFFSET: [f32; 3] = [1.0, 0.0, 1.0];
+
+const CLIPBOARD_FORMAT: &str = "midpoint-components";
+const CLIPBOARD_VERSION: u32 = 1;
+
+/// Copied components along with the assets they use, so they paste into any level or project
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ComponentClipboard {
+    pub format: String,
+    pub version: u32,
+    pub source_project_id: String,
+    pub components: Vec<ComponentData>,
+    #[serde(default)]
+    pub models: Vec<File>,
+    #[serde(default)]
+    pub landscapes: Vec<LandscapeData>,
+    #[serde(default)]
+    pub textures: Vec<File>,
+    #[serde(default)]
+    pub lights: Vec<LightComponent>,
+    /// Overrides of the copied components, by their ids in the clipboard
+    #[serde(default)]
+    pub materials: Vec<MaterialOverride>,
+}
+
+/// Components added to or taken out of the active level, undone by doing the opposite
+pub struct ComponentEdit {
+    pub gpu_resources: Arc<GpuResources>,
+    pub added: Vec<ComponentData>,
+    /// Removed components with where they sat in the level's list
+    pub removed: Vec<(usize, ComponentData)>,
+    /// Lights added to the end of the level's lights
+    pub added_lights: Vec<LightComponent>,
+    /// Removed lights with where they sat in the level's lights
+    pub removed_lights: Vec<(usize, LightComponent)>,
+    pub selection_before: Vec<String>,
+    pub selection_after: Vec<String>,
+}
+
+impl Edit for ComponentEdit {
+    type Target = StateHelper;
+    type Output = ();
+
+    fn edit(&mut self, state_helper: &mut StateHelper) {
+        let removed: Vec<ComponentData> = self.removed.iter().map(|(_, c)| c.clone()).collect();
+
+        remove_components(state_helper, &removed);
+        if !self.removed_lights.is_empty() {
+            let light_ids: Vec<String> = self
+                .removed_lights
+                .iter()
+                .map(|(_, l)| l.id.clone())
+                .collect();
+            remove_lights(state_helper, &light_ids);
+        }
+        insert_components(
+            state_helper,
+            &self.gpu_resources,
+            self.added.iter().cloned().map(|c| (None, c)).collect(),
+        );
+        if !self.added_lights.is_empty() {
+            append_lights(state_helper, &self.added_lights);
+        }
+        set_selection(state_helper, self.selection_after.clone());
+    }
+
+    fn undo(&mut self, state_helper: &mut StateHelper) {
+        remove_components(state_helper, &self.added);
+        if !self.added_lights.is_empty() {
+            let light_ids: Vec<String> = self.added_lights.iter().map(|l| l.id.clone()).collect();
+            remove_lights(state_helper, &light_ids);
+        }
+        insert_components(
+            state_helper,
+            &self.gpu_resources,
+            self.removed
+                .iter()
+                .cloned()
+                .map(|(index, c)| (Some(index), c))
+                .collect(),
+        );
+        if !self.removed_lights.is_empty() {
+            insert_lights(state_helper, &self.removed_lights);
+        }
+        set_selection(state_helper, self.selection_before.clone());
+    }
+}
+
+/// Saves the project and refreshes the component browser's list
+fn save_level(state_helper: &StateHelper) {
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    if let Some(level_components_signal) = state_helper.level_components_signal {
+        level_components_signal.set(
+            active_level(&saved_state, state_helper.active_level_id.as_deref())
+                .and_then(|level| level.components.clone())
+                .unwrap_or_default(),
+        );
+    }
+
+    state_helper.save_saved_state(project_id, saved_state);
+}
+
+/// Drops the colliders carrying the component's id, along with the bodies they hang off
+pub fn remove_component_colliders(renderer_state: &mut RendererState, component_id: &str) {
+    let Ok(component_id) = Uuid::from_str(component_id) else {
+        return;
+    };
+
+    let RendererState {
+        collider_set,
+        rigid_body_set,
+        island_manager,
+        impulse_joint_set,
+        multibody_joint_set,
+        ..
+    } = renderer_state;
+
+    let handles: Vec<_> = collider_set
+        .iter()
+        .filter(|(_, collider)| collider.user_data == component_id.as_u128())
+        .map(|(handle, collider)| (handle, collider.parent()))
+        .collect();
+
+    for (handle, parent) in handles {
+        match parent {
+            Some(body) => {
+                rigid_body_set.remove(
+                    body,
+                    island_manager,
+                    collider_set,
+                    impulse_joint_set,
+                    multibody_joint_set,
+                    true,
+                );
+            }
+            None => {
+                collider_set.remove(handle, island_manager, rigid_body_set, true);
+            }
+        }
+    }
+}
+
+/// Unloads the components' models, terrain and colliders
+pub fn remove_from_scene(renderer_state: &mut RendererState, ids: &[String]) {
+    renderer_state.models.retain(|m| !ids.contains(&m.id));
+    renderer_state
+        .terrain_managers
+        .retain(|t| !ids.contains(&t.id));
+
+    for id in ids {
+        remove_component_colliders(renderer_state, id);
+    }
+}
+
+/// Takes components out of the active level and the scene
+fn remove_components(state_helper: &StateHelper, components: &[ComponentData]) {
+    if components.is_empty() {
+        return;
+    }
+
+    let ids: Vec<String> = components.iter().map(|c| c.id.clone()).collect();
+
+    if let Some(renderer_state) = state_helper.renderer_state.as_ref() {
+        remove_from_scene(&mut renderer_state.lock().unwrap(), &ids);
+    }
+
+    {
+        let mut saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+
+        if let Some(level_components) =
+            active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+                .and_then(|level| level.components.as_mut())
+        {
+            level_components.retain(|c| !ids.contains(&c.id));
+        }
+    }
+
+    save_level(state_helper);
+}
+
+/// Puts components into the active level, at their old index when they have one, and loads
+/// them into the scene
+fn insert_components(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    mut components: Vec<(Option<usize>, ComponentData)>,
+) {
+    if components.is_empty() {
+        return;
+    }
+
+    // earlier indices first so the later ones land where they were
+    components.sort_by_key(|(index, _)| index.unwrap_or(usize::MAX));
+
+    {
+        let mut saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+
+        let level_components =
+            active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+                .expect("Couldn't get active level")
+                .components
+                .get_or_insert_with(Vec::new);
+
+        for (index, component) in &components {
+            match index {
+                Some(index) if *index <= level_components.len() => {
+                    level_components.insert(*index, component.clone())
+                }
+                _ => level_components.push(component.clone()),
+            }
+        }
+    }
+
+    save_level(state_helper);
+
+    for (_, component) in &components {
+        add_to_scene(state_helper, gpu_resources, component);
+    }
+}
+
+/// Loads a component's model or terrain with its transform, colliders and textures
+pub fn add_to_scene(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    component: &ComponentData,
+) {
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get()
+        .to_string();
+    let renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .expect("Couldn't get RendererState");
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap()
+        .clone();
+
+    load_component(
+        renderer_state,
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        &project_id,
+        &saved_state,
+        component,
+    );
+
+    if matches!(component.kind, Some(ComponentKind::Model)) {
+        register_model_asset(state_helper, &component.id, &component.asset_id);
+    }
+}
+
+/// Loads a component from the project's assets, without needing the editor around it
+pub fn load_component(
+    renderer_state: &Arc<Mutex<RendererState>>,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    project_id: &str,
+    saved_state: &SavedState,
+    component: &ComponentData,
+) {
+    let project_id = project_id.to_string();
+    let models = &saved_state.models;
+    let landscapes = saved_state.landscapes.clone().unwrap_or_default();
+    let textures = saved_state.textures.clone().unwrap_or_default();
+
+    let properties = &component.generic_properties;
+
+    match component
+        .kind
+        .as_ref()
+        .expect("Couldn't get component kind")
+    {
+        ComponentKind::Model => {
+            let Some(model) = models.iter().find(|m| m.id == component.asset_id) else {
+                println!("Couldn't find model {}", component.asset_id);
+                return;
+            };
+
+            handle_add_model(
+                renderer_state.clone(),
+                device,
+                queue,
+                project_id,
+                component.asset_id.clone(),
+                component.id.clone(),
+                model.fileName.clone(),
+                Isometry3::new(
+                    Vector3::from(properties.position),
+                    Vector3::from(properties.rotation),
+                ),
+            );
+
+            renderer_state
+                .lock()
+                .unwrap()
+                .add_collider(component.id.clone(), ComponentKind::Model);
+        }
+        ComponentKind::Landscape => {
+            let Some(landscape) = landscapes.iter().find(|l| l.id == component.asset_id) else {
+                println!("Couldn't find landscape {}", component.asset_id);
+                return;
+            };
+            let Some(heightmap) = landscape.heightmap.as_ref() else {
+                return;
+            };
+
+            handle_add_landscape(
+                renderer_state.clone(),
+                device,
+                queue,
+                project_id.clone(),
+                component.asset_id.clone(),
+                component.id.clone(),
+                heightmap.fileName.clone(),
+                properties.position,
+            );
+
+            // the visible textures go back on top of their maps
+            let painted = component.landscape_properties.as_ref().map(|p| {
+                [
+                    (
+                        p.rockmap_texture_id.clone(),
+                        "Rockmap",
+                        landscape.rockmap.as_ref(),
+                    ),
+                    (p.soil_texture_id.clone(), "Soil", landscape.soil.as_ref()),
+                ]
+            });
+
+            for (texture_id, kind, map) in painted.into_iter().flatten() {
+                let texture = texture_id.and_then(|id| textures.iter().find(|t| t.id == id));
+
+                if let (Some(texture), Some(map)) = (texture, map) {
+                    handle_add_landscape_texture(
+                        renderer_state.clone(),
+                        device,
+                        queue,
+                        project_id.clone(),
+                        component.id.clone(),
+                        component.asset_id.clone(),
+                        texture.fileName.clone(),
+                        kind.to_string(),
+                        map.fileName.clone(),
+                    );
+                }
+            }
+        }
+    }
+
+    // rotation and scale aren't part of loading
+    apply_component_transform(
+        &mut renderer_state.lock().unwrap(),
+        component,
+        [properties.position, properties.rotation, properties.scale],
+    );
+}
+
+fn with_index(
+    state_helper: &StateHelper,
+    components: Vec<ComponentData>,
+) -> Vec<(usize, ComponentData)> {
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    let level_components = active_level(&saved_state, state_helper.active_level_id.as_deref())
+        .and_then(|level| level.components.clone())
+        .unwrap_or_default();
+
+    components
+        .into_iter()
+        .filter_map(|component| {
+            level_components
+                .iter()
+                .position(|c| c.id == component.id)
+                .map(|index| (index, component))
+        })
+        .collect()
+}
+
+/// Components moved, turned or scaled by the gizmo or the properties panel, as they were before
+/// and after in the same order, the active component last
+pub struct TransformEdit {
+    pub before: Vec<ComponentData>,
+    pub after: Vec<ComponentData>,
+}
+
+impl TransformEdit {
+    fn apply(state_helper: &StateHelper, components: &[ComponentData]) {
+        {
+            let mut renderer_state = state_helper
+                .renderer_state
+                .as_ref()
+                .expect("Couldn't get RendererState")
+                .lock()
+                .unwrap();
+
+            for component in components {
+                apply_component_transform(
+                    &mut renderer_state,
+                    component,
+                    component_transform(component),
+                );
+            }
+        }
+
+        save_component_transforms(state_helper, components);
+
+        // the gizmo goes back onto whatever is still selected
+        let selected = selected_components(state_helper);
+        let mut renderer_state = state_helper
+            .renderer_state
+            .as_ref()
+            .expect("Couldn't get RendererState")
+            .lock()
+            .unwrap();
+        place_selection_gizmos(
+            &mut renderer_state,
+            &selected,
+            state_helper.editor_settings.transform,
+        );
+    }
+}
+
+impl Edit for TransformEdit {
+    type Target = StateHelper;
+    type Output = ();
+
+    fn edit(&mut self, state_helper: &mut StateHelper) {
+        TransformEdit::apply(state_helper, &self.after);
+    }
+
+    fn undo(&mut self, state_helper: &mut StateHelper) {
+        TransformEdit::apply(state_helper, &self.before);
+    }
+}
+
+/// Anything undo walks back in the active level, kept on one timeline so Ctrl+Z and Ctrl+Y
+/// follow the order things happened in
+pub enum LevelEdit {
+    Components(ComponentEdit),
+    Transforms(TransformEdit),
+}
+
+impl Edit for LevelEdit {
+    type Target = StateHelper;
+    type Output = ();
+
+    fn edit(&mut self, state_helper: &mut StateHelper) {
+        match self {
+            LevelEdit::Components(edit) => edit.edit(state_helper),
+            LevelEdit::Transforms(edit) => edit.edit(state_helper),
+        }
+    }
+
+    fn undo(&mut self, state_helper: &mut StateHelper) {
+        match self {
+            LevelEdit::Components(edit) => edit.undo(state_helper),
+            LevelEdit::Transforms(edit) => edit.undo(state_helper),
+        }
+    }
+}
+
+fn record_level_edit(state_helper: &mut StateHelper, edit: LevelEdit) {
+    let history = state_helper.history.clone();
+    history.lock().unwrap().edit(state_helper, edit);
+}
+
+/// Applies the edit and keeps it for undo
+pub fn record_component_edit(state_helper: &mut StateHelper, edit: ComponentEdit) {
+    record_level_edit(state_helper, LevelEdit::Components(edit));
+}
+
+/// Saves transforms the scene already shows and keeps them for undo, nothing is kept when the
+/// components didn't move
+pub fn record_transform_edit(
+    state_helper: &mut StateHelper,
+    before: Vec<ComponentData>,
+    after: Vec<ComponentData>,
+) {
+    let unchanged = before.len() == after.len()
+        && before
+            .iter()
+            .zip(&after)
+            .all(|(b, a)| b.id == a.id && component_transform(b) == component_transform(a));
+    if unchanged {
+        return;
+    }
+
+    record_level_edit(
+        state_helper,
+        LevelEdit::Transforms(TransformEdit { before, after }),
+    );
+}
+
+pub fn undo_level_edit(state_helper: &mut StateHelper) -> bool {
+    let history = state_helper.history.clone();
+    let undone = history.lock().unwrap().undo(state_helper).is_some();
+    undone
+}
+
+pub fn redo_level_edit(state_helper: &mut StateHelper) -> bool {
+    let history = state_helper.history.clone();
+    let redone = history.lock().unwrap().redo(state_helper).is_some();
+    redone
+}
+
+/// The selected models and landscapes, lights are edited on their own
+fn selected_scene_components(state_helper: &StateHelper) -> Vec<ComponentData> {
+    selected_components(state_helper)
+        .into_iter()
+        .filter(|component| !is_light_component(component))
+        .collect()
+}
+
+/// Removes every selected component and light from the level
+pub fn delete_selection(state_helper: &mut StateHelper, gpu_resources: Arc<GpuResources>) {
+    let light_ids: Vec<String> = selected_components(state_helper)
+        .iter()
+        .filter(|component| is_light_component(component))
+        .map(|component| component.id.clone())
+        .collect();
+    let removed_lights: Vec<(usize, LightComponent)> = level_lights(state_helper)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, light)| light_ids.contains(&light.id))
+        .collect();
+
+    let selected = selected_scene_components(state_helper);
+    if selected.is_empty() && removed_lights.is_empty() {
+        return;
+    }
+
+    let removed = with_index(state_helper, selected);
+
+    record_component_edit(
+        state_helper,
+        ComponentEdit {
+            gpu_resources,
+            added: Vec::new(),
+            removed,
+            added_lights: Vec::new(),
+            removed_lights,
+            selection_before: state_helper.selection.clone(),
+            selection_after: Vec::new(),
+        },
+    );
+}
+
+/// The selected lights, in the level's order
+fn selected_lights(state_helper: &StateHelper) -> Vec<LightComponent> {
+    level_lights(state_helper)
+        .into_iter()
+        .filter(|light| state_helper.selection.contains(&light.id))
+        .collect()
+}
+
+/// A copy of the light under a new id, moved by the offset
+fn clone_light(light: &LightComponent, offset: [f32; 3]) -> LightComponent {
+    let mut clone = light.clone();
+    clone.id = Uuid::new_v4().to_string();
+
+    for axis in 0..3 {
+        clone.position[axis] += offset[axis];
+    }
+
+    clone
+}
+
+/// A copy of the component under a new id, moved by the offset
+fn clone_component(component: &ComponentData, offset: [f32; 3]) -> ComponentData {
+    let mut clone = component.clone();
+    clone.id = Uuid::new_v4().to_string();
+
+    let position = &mut clone.generic_properties.position;
+    for axis in 0..3 {
+        position[axis] += offset[axis];
+    }
+
+    clone
+}
+
+/// Copies the selected components next to the originals and selects the copies
+pub fn duplicate_selection(state_helper: &mut StateHelper, gpu_resources: Arc<GpuResources>) {
+    let selected = selected_scene_components(state_helper);
+    let lights = selected_lights(state_helper);
+    if selected.is_empty() && lights.is_empty() {
+        return;
+    }
+
+    let added: Vec<ComponentData> = selected
+        .iter()
+        .map(|component| {
+            let mut duplicate = clone_component(component, DUPLICATE_OFFSET);
+            duplicate.generic_properties.name =
+                format!("{} Copy", component.generic_properties.name);
+            duplicate
+        })
+        .collect();
+
+    let copies: Vec<(String, String)> = selected
+        .iter()
+        .zip(&added)
+        .map(|(original, duplicate)| (original.id.clone(), duplicate.id.clone()))
+        .collect();
+    copy_materials(state_helper, &copies);
+    refresh_level_materials(state_helper, &gpu_resources.device, &gpu_resources.queue);
+
+    let added_lights: Vec<LightComponent> = lights
+        .iter()
+        .map(|light| {
+            let mut duplicate = clone_light(light, DUPLICATE_OFFSET);
+            duplicate.name = format!("{} Copy", light.name);
+            duplicate
+        })
+        .collect();
+
+    record_component_edit(
+        state_helper,
+        ComponentEdit {
+            gpu_resources,
+            selection_after: added
+                .iter()
+                .map(|c| c.id.clone())
+                .chain(added_lights.iter().map(|l| l.id.clone()))
+                .collect(),
+            added,
+            removed: Vec::new(),
+            added_lights,
+            removed_lights: Vec::new(),
+            selection_before: state_helper.selection.clone(),
+        },
+    );
+}
+
+fn clipboard_path() -> PathBuf {
+    let sync_dir = get_common_os_dir().expect("Couldn't get CommonOS directory");
+    sync_dir.join("midpoint").join("clipboard.json")
+}
+
+/// Writes the selected components and their assets to the clipboard file
+pub fn copy_selection(state_helper: &StateHelper) -> Result<usize, String> {
+    let components = selected_scene_components(state_helper);
+    let lights = selected_lights(state_helper);
+    if components.is_empty() && lights.is_empty() {
+        return Ok(0);
+    }
+
+    // before the saved state is locked, finding the active level locks it too
+    let materials: Vec<MaterialOverride> = level_materials(state_helper)
+        .into_iter()
+        .filter(|m| components.iter().any(|c| c.id == m.component_id))
+        .collect();
+
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    let uses = |id: &String| components.iter().any(|c| c.asset_id == *id);
+    let texture_ids: Vec<String> = components
+        .iter()
+        .filter_map(|c| c.landscape_properties.as_ref())
+        .flat_map(|p| {
+            [
+                p.primary_texture_id.clone(),
+                p.rockmap_texture_id.clone(),
+                p.soil_texture_id.clone(),
+            ]
+        })
+        .chain(materials.iter().flat_map(MaterialOverride::texture_ids))
+        .flatten()
+        .collect();
+
+    let clipboard = ComponentClipboard {
+        format: CLIPBOARD_FORMAT.to_string(),
+        version: CLIPBOARD_VERSION,
+        source_project_id: project_id.to_string(),
+        models: saved_state
+            .models
+            .iter()
+            .filter(|m| uses(&m.id))
+            .cloned()
+            .collect(),
+        landscapes: saved_state
+            .landscapes
+            .iter()
+            .flatten()
+            .filter(|l| uses(&l.id))
+            .cloned()
+            .collect(),
+        textures: saved_state
+            .textures
+            .iter()
+            .flatten()
+            .filter(|t| texture_ids.contains(&t.id))
+            .cloned()
+            .collect(),
+        components,
+        lights,
+        materials,
+    };
+
+    let json = serde_json::to_string_pretty(&clipboard)
+        .map_err(|e| format!("Couldn't serialize clipboard: {}", e))?;
+    let path = clipboard_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Couldn't create midpoint directory: {}", e))?;
+    }
+    fs::write(&path, json).map_err(|e| format!("Couldn't write clipboard: {}", e))?;
+
+    Ok(clipboard.components.len() + clipboard.lights.len())
+}
+
+/// Copies an asset file into another project's directory, keeping its name
+fn copy_asset_file(file: &File, dir: PathBuf) -> Result<File, String> {
+    fs::create_dir_all(&dir).map_err(|e| format!("Couldn't create asset directory: {}", e))?;
+
+    let source = Path::new(&file.normalFilePath);
+    let path = dir.join(source.file_name().unwrap_or(file.fileName.as_ref()));
+    fs::copy(Path::new(&file.normalFilePath), &path)
+        .map_err(|e| format!("Couldn't copy {}: {}", file.fileName, e))?;
+
+    Ok(File {
+        normalFilePath: path.to_string_lossy().into_owned(),
+        ..file.clone()
+    })
+}
+
+/// Copies the clipboard's assets into this project when it came from another one
+fn import_clipboard_assets(
+    state_helper: &StateHelper,
+    clipboard: &ComponentClipboard,
+) -> Result<(), String> {
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get()
+        .to_string();
+
+    if clipboard.source_project_id == project_id {
+        return Ok(());
+    }
+
+    let mut saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    for model in &clipboard.models {
+        if saved_state.models.iter().any(|m| m.id == model.id) {
+            continue;
+        }
+
+        let models_dir =
+            get_models_dir(&project_id).map_err(|e| format!("Couldn't get models dir: {}", e))?;
+        saved_state.models.push(copy_asset_file(model, models_dir)?);
+    }
+
+    for texture in &clipboard.textures {
+        let textures = saved_state.textures.get_or_insert_with(Vec::new);
+        if textures.iter().any(|t| t.id == texture.id) {
+            continue;
+        }
+
+        let textures_dir = get_textures_dir(&project_id)
+            .map_err(|e| format!("Couldn't get textures dir: {}", e))?;
+        textures.push(copy_asset_file(texture, textures_dir)?);
+    }
+
+    for landscape in &clipboard.landscapes {
+        let landscapes = saved_state.landscapes.get_or_insert_with(Vec::new);
+        if landscapes.iter().any(|l| l.id == landscape.id) {
+            continue;
+        }
+
+        let heightmap_dir = get_heightmap_dir(&project_id, &landscape.id)
+            .map_err(|e| format!("Couldn't get heightmap dir: {}", e))?;
+        let rockmap_dir = get_rockmap_dir(&project_id, &landscape.id)
+            .map_err(|e| format!("Couldn't get rockmap dir: {}", e))?;
+        let soilmap_dir = get_soilmap_dir(&project_id, &landscape.id)
+            .map_err(|e| format!("Couldn't get soilmap dir: {}", e))?;
+
+        let copy_map = |map: &Option<File>, dir: PathBuf| {
+            map.as_ref()
+                .map(|file| copy_asset_file(file, dir))
+                .transpose()
+        };
+
+        landscapes.push(LandscapeData {
+            id: landscape.id.clone(),
+            heightmap: copy_map(&landscape.heightmap, heightmap_dir)?,
+            rockmap: copy_map(&landscape.rockmap, rockmap_dir)?,
+            soil: copy_map(&landscape.soil, soilmap_dir)?,
+        });
+    }
+
+    state_helper.save_saved_state(
+        project_id.parse().expect("Couldn't parse project id"),
+        saved_state,
+    );
+
+    Ok(())
+}
+
+/// Adds the clipboard's components to the level under new ids and selects them
+pub fn paste_components(
+    state_helper: &mut StateHelper,
+    gpu_resources: Arc<GpuResources>,
+) -> Result<usize, String> {
+    let json = fs::read_to_string(clipboard_path()).map_err(|_| "Nothing to paste".to_string())?;
+    let clipboard: ComponentClipboard =
+        serde_json::from_str(&json).map_err(|e| format!("Couldn't read clipboard: {}", e))?;
+
+    if clipboard.format != CLIPBOARD_FORMAT || clipboard.version > CLIPBOARD_VERSION {
+        return Err("Clipboard doesn't hold midpoint components".to_string());
+    }
+
+    import_clipboard_assets(state_helper, &clipboard)?;
+
+    let added: Vec<ComponentData> = clipboard
+        .components
+        .iter()
+        .map(|component| clone_component(component, [0.0; 3]))
+        .collect();
+    let added_lights: Vec<LightComponent> = clipboard
+        .lights
+        .iter()
+        .map(|light| clone_light(light, [0.0; 3]))
+        .collect();
+    let pasted = added.len() + added_lights.len();
+
+    let copies: Vec<(String, String)> = clipboard
+        .components
+        .iter()
+        .zip(&added)
+        .map(|(original, copy)| (original.id.clone(), copy.id.clone()))
+        .collect();
+    apply_copied_materials(state_helper, &clipboard.materials, &copies);
+    refresh_level_materials(state_helper, &gpu_resources.device, &gpu_resources.queue);
+
+    record_component_edit(
+        state_helper,
+        ComponentEdit {
+            gpu_resources,
+            selection_after: added
+                .iter()
+                .map(|c| c.id.clone())
+                .chain(added_lights.iter().map(|l| l.id.clone()))
+                .collect(),
+            added,
+            removed: Vec::new(),
+            added_lights,
+            removed_lights: Vec::new(),
+            selection_before: state_helper.selection.clone(),
+        },
+    );
+
+    Ok(pasted)
+}
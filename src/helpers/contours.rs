@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+
+use nalgebra as na;
+
+/// A single iso-line traced through a heightfield
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContourLine {
+    /// Points in grid space, x being the column and y being the row
+    pub points: Vec<[f32; 2]>,
+    /// Whether the last point connects back to the first
+    pub closed: bool,
+}
+
+/// All iso-lines found for one height
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContourLevel {
+    pub height: f32,
+    pub lines: Vec<ContourLine>,
+}
+
+/// Identifies a cell edge so neighbouring cells share the exact same crossing point
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum EdgeKey {
+    /// Edge between (row, col) and (row, col + 1)
+    Horizontal(usize, usize),
+    /// Edge between (row, col) and (row + 1, col)
+    Vertical(usize, usize),
+}
+
+/// Heights at which contours should be drawn, aligned to multiples of the interval
+pub fn contour_heights(min_height: f32, max_height: f32, interval: f32) -> Vec<f32> {
+    let mut heights = Vec::new();
+
+    if interval <= 0.0 || !min_height.is_finite() || !max_height.is_finite() {
+        return heights;
+    }
+
+    let mut step = (min_height / interval).ceil();
+    loop {
+        let height = step * interval;
+        if height > max_height {
+            break;
+        }
+        heights.push(height);
+        step += 1.0;
+    }
+
+    heights
+}
+
+/// Extracts stitched contour polylines for every interval between the heightfield's extremes
+pub fn extract_contours(heights: &na::DMatrix<f32>, interval: f32) -> Vec<ContourLevel> {
+    if heights.is_empty() {
+        return Vec::new();
+    }
+
+    contour_heights(heights.min(), heights.max(), interval)
+        .into_iter()
+        .map(|height| ContourLevel {
+            height,
+            lines: extract_contour_lines(heights, height),
+        })
+        .collect()
+}
+
+/// Runs marching squares for a single iso-level and stitches the cell segments into polylines
+pub fn extract_contour_lines(heights: &na::DMatrix<f32>, level: f32) -> Vec<ContourLine> {
+    let (rows, cols) = heights.shape();
+
+    if rows < 2 || cols < 2 {
+        return Vec::new();
+    }
+
+    let mut segments: Vec<(EdgeKey, EdgeKey)> = Vec::new();
+
+    for y in 0..rows - 1 {
+        for x in 0..cols - 1 {
+            let corners = [
+                heights[(y, x)],         // top left
+                heights[(y, x + 1)],     // top right
+                heights[(y + 1, x + 1)], // bottom right
+                heights[(y + 1, x)],     // bottom left
+            ];
+
+            let cell_config = corners
+                .iter()
+                .enumerate()
+                .fold(0u8, |acc, (i, &v)| acc | ((v >= level) as u8) << i);
+
+            let top = EdgeKey::Horizontal(y, x);
+            let right = EdgeKey::Vertical(y, x + 1);
+            let bottom = EdgeKey::Horizontal(y + 1, x);
+            let left = EdgeKey::Vertical(y, x);
+
+            match cell_config {
+                0 | 15 => {}
+                1 | 14 => segments.push((left, top)),
+                2 | 13 => segments.push((top, right)),
+                3 | 12 => segments.push((left, right)),
+                4 | 11 => segments.push((right, bottom)),
+                6 | 9 => segments.push((top, bottom)),
+                7 | 8 => segments.push((left, bottom)),
+                5 | 10 => {
+                    // saddle: use the cell center to decide which corners are connected
+                    let center = corners.iter().sum::<f32>() / 4.0;
+                    let center_above = center >= level;
+
+                    // case 5 has top left and bottom right above the level
+                    if (cell_config == 5) == center_above {
+                        segments.push((left, bottom));
+                        segments.push((top, right));
+                    } else {
+                        segments.push((left, top));
+                        segments.push((right, bottom));
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    stitch_segments(heights, level, &segments)
+}
+
+fn edge_point(heights: &na::DMatrix<f32>, level: f32, edge: EdgeKey) -> [f32; 2] {
+    let (a, b, start) = match edge {
+        EdgeKey::Horizontal(y, x) => (heights[(y, x)], heights[(y, x + 1)], [x as f32, y as f32]),
+        EdgeKey::Vertical(y, x) => (heights[(y, x)], heights[(y + 1, x)], [x as f32, y as f32]),
+    };
+
+    let t = if (b - a).abs() > f32::EPSILON {
+        ((level - a) / (b - a)).clamp(0.0, 1.0)
+    } else {
+        0.5
+    };
+
+    match edge {
+        EdgeKey::Horizontal(..) => [start[0] + t, start[1]],
+        EdgeKey::Vertical(..) => [start[0], start[1] + t],
+    }
+}
+
+fn stitch_segments(
+    heights: &na::DMatrix<f32>,
+    level: f32,
+    segments: &[(EdgeKey, EdgeKey)],
+) -> Vec<ContourLine> {
+    // every edge is shared by at most two cells, so each key has at most two segments
+    let mut edge_segments: HashMap<EdgeKey, Vec<usize>> = HashMap::new();
+    for (index, (a, b)) in segments.iter().enumerate() {
+        edge_segments.entry(*a).or_default().push(index);
+        edge_segments.entry(*b).or_default().push(index);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = Vec::new();
+
+    // open lines start on the heightfield border, so trace those before closed loops
+    let mut starts: Vec<(usize, EdgeKey)> = segments
+        .iter()
+        .enumerate()
+        .flat_map(|(index, (a, b))| [(index, *a), (index, *b)])
+        .filter(|(_, edge)| edge_segments[edge].len() == 1)
+        .collect();
+    starts.extend(
+        segments
+            .iter()
+            .enumerate()
+            .map(|(index, (a, _))| (index, *a)),
+    );
+
+    for (first_segment, start_edge) in starts {
+        if used[first_segment] {
+            continue;
+        }
+
+        let mut points = vec![edge_point(heights, level, start_edge)];
+        let mut current_segment = first_segment;
+        let mut current_edge = start_edge;
+        let mut closed = false;
+
+        loop {
+            used[current_segment] = true;
+
+            let (a, b) = segments[current_segment];
+            let next_edge = if a == current_edge { b } else { a };
+
+            if next_edge == start_edge {
+                closed = true;
+                break;
+            }
+
+            // a level passing exactly through a grid vertex yields the same point twice
+            let point = edge_point(heights, level, next_edge);
+            if points.last() != Some(&point) {
+                points.push(point);
+            }
+
+            let next_segment = edge_segments[&next_edge]
+                .iter()
+                .copied()
+                .find(|&index| !used[index]);
+
+            match next_segment {
+                Some(index) => {
+                    current_segment = index;
+                    current_edge = next_edge;
+                }
+                None => break,
+            }
+        }
+
+        if closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+
+        // skip lines that collapsed onto a single vertex
+        if points.len() > 1 {
+            lines.push(ContourLine { points, closed });
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cone(size: usize) -> na::DMatrix<f32> {
+        let center = (size - 1) as f32 / 2.0;
+        na::DMatrix::from_fn(size, size, |y, x| {
+            let dx = x as f32 - center;
+            let dy = y as f32 - center;
+            100.0 - (dx * dx + dy * dy).sqrt() * 10.0
+        })
+    }
+
+    #[test]
+    fn flat_heightfield_has_no_contours() {
+        let heights = na::DMatrix::from_element(8, 8, 10.0);
+
+        assert!(extract_contour_lines(&heights, 5.0).is_empty());
+        assert!(extract_contour_lines(&heights, 15.0).is_empty());
+    }
+
+    #[test]
+    fn contour_heights_align_to_interval() {
+        assert_eq!(
+            contour_heights(-12.0, 31.0, 10.0),
+            vec![-10.0, 0.0, 10.0, 20.0, 30.0]
+        );
+        assert!(contour_heights(0.0, 10.0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn ramp_produces_single_open_line_across_grid() {
+        // height increases along x, so the iso-line runs top to bottom
+        let heights = na::DMatrix::from_fn(5, 6, |_, x| x as f32 * 10.0);
+
+        let lines = extract_contour_lines(&heights, 25.0);
+
+        assert_eq!(lines.len(), 1);
+        assert!(!lines[0].closed);
+        assert_eq!(lines[0].points.len(), 5);
+        for point in &lines[0].points {
+            assert!((point[0] - 2.5).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn cone_produces_closed_rings() {
+        let heights = cone(21);
+
+        let lines = extract_contour_lines(&heights, 55.0);
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].closed);
+
+        // every point should sit near the expected radius of 4.5 cells
+        for point in &lines[0].points {
+            let dx = point[0] - 10.0;
+            let dy = point[1] - 10.0;
+            let radius = (dx * dx + dy * dy).sqrt();
+            assert!((radius - 4.5).abs() < 0.3, "radius {}", radius);
+        }
+    }
+
+    #[test]
+    fn full_extraction_keeps_every_level() {
+        let heights = cone(21);
+
+        let levels = extract_contours(&heights, 25.0);
+        let heights_found: Vec<f32> = levels.iter().map(|l| l.height).collect();
+
+        assert_eq!(heights_found, vec![-25.0, 0.0, 25.0, 50.0, 75.0, 100.0]);
+        for level in &levels[2..5] {
+            assert_eq!(level.lines.len(), 1);
+            assert!(level.lines[0].closed);
+        }
+    }
+
+    #[test]
+    fn saddle_is_resolved_by_cell_center() {
+        // high corners top left and bottom right
+        let heights = na::DMatrix::from_row_slice(2, 2, &[10.0, 0.0, 0.0, 10.0]);
+
+        // center (5.0) is above the level, so the high corners stay connected
+        let lines = extract_contour_lines(&heights, 4.0);
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            let touches_top_right = line.points.iter().any(|p| p[0] > 0.5 && p[1] < 0.5);
+            let touches_bottom_left = line.points.iter().any(|p| p[0] < 0.5 && p[1] > 0.5);
+            assert!(touches_top_right || touches_bottom_left);
+            assert!(!(touches_top_right && touches_bottom_left));
+            // the low corner is cut off on its own
+            assert!(line.points.iter().all(|p| p[0] > 0.0 || p[1] > 0.0));
+        }
+
+        // center (5.0) below the level, so the high corners are cut off individually
+        let lines = extract_contour_lines(&heights, 6.0);
+        assert_eq!(lines.len(), 2);
+        for line in &lines {
+            let near_top_left = line.points.iter().all(|p| p[0] + p[1] <= 1.0 + 1e-5);
+            let near_bottom_right = line.points.iter().all(|p| p[0] + p[1] >= 1.0 - 1e-5);
+            assert!(near_top_left || near_bottom_right);
+        }
+    }
+
+    #[test]
+    fn neighbouring_cells_share_points() {
+        let heights = cone(11);
+
+        for line in extract_contour_lines(&heights, 70.0) {
+            assert!(line.closed);
+            for pair in line.points.windows(2) {
+                assert_ne!(pair[0], pair[1]);
+            }
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+
+use std::fs;
+use std::path::Path;
+
+use midpoint_engine::core::RendererState::RendererState;
+use nalgebra::{Matrix4, Point3, Quaternion, UnitQuaternion, Vector3, Vector4};
+use serde_json::Value;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+/// glTF component type of 32-bit floats
+const GLTF_FLOAT: u64 = 5126;
+
+/// Smallest and largest corner of a box
+pub type Bounds = (Point3<f32>, Point3<f32>);
+
+/// The planes of a view projection's frustum, each facing inwards
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    pub fn from_view_projection(view_projection: &Matrix4<f32>) -> Self {
+        let row = |index: usize| view_projection.row(index).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+
+        // the near plane for -1 to 1 depth, a little loose for projections using 0 to 1
+        Self {
+            planes: [w + x, w - x, w + y, w - y, w + z, w - z],
+        }
+    }
+
+    /// False only when the box lies wholly outside one of the planes
+    pub fn intersects(&self, (mins, maxs): &Bounds) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane's normal
+            let corner = Vector4::new(
+                if plane.x >= 0.0 { maxs.x } else { mins.x },
+                if plane.y >= 0.0 { maxs.y } else { mins.y },
+                if plane.z >= 0.0 { maxs.z } else { mins.z },
+                1.0,
+            );
+
+            plane.dot(&corner) >= 0.0
+        })
+    }
+}
+
+/// Box around the eight corners of a box moved by the matrix
+pub fn transformed_bounds(matrix: &Matrix4<f32>, (mins, maxs): &Bounds) -> Bounds {
+    let corners = (0..8).map(|corner| {
+        matrix.transform_point(&Point3::new(
+            if corner & 1 == 0 { mins.x } else { maxs.x },
+            if corner & 2 == 0 { mins.y } else { maxs.y },
+            if corner & 4 == 0 { mins.z } else { maxs.z },
+        ))
+    });
+
+    corners.fold(
+        (Point3::from([f32::MAX; 3]), Point3::from([f32::MIN; 3])),
+        |(mins, maxs), corner| (mins.inf(&corner), maxs.sup(&corner)),
+    )
+}
+
+/// Engine cubes are at most two units across before scaling
+pub fn cube_bounds(position: &Vector3<f32>, scale: &Vector3<f32>) -> Bounds {
+    let radius = Vector3::repeat(scale.norm());
+
+    (
+        Point3::from(position - radius),
+        Point3::from(position + radius),
+    )
+}
+
+/// World bounds of every component's colliders, by the colliders' user data
+pub fn component_collider_bounds(renderer_state: &RendererState) -> HashMap<u128, Bounds> {
+    let mut bounds: HashMap<u128, Bounds> = HashMap::new();
+
+    for (_, collider) in renderer_state.collider_set.iter() {
+        let aabb = collider.compute_aabb();
+
+        bounds
+            .entry(collider.user_data)
+            .and_modify(|(mins, maxs)| {
+                *mins = mins.inf(&aabb.mins);
+                *maxs = maxs.sup(&aabb.maxs);
+            })
+            .or_insert((aabb.mins, aabb.maxs));
+    }
+
+    bounds
+}
+
+fn grow_bounds(bounds: &mut Option<Bounds>, point: Point3<f32>) {
+    *bounds = Some(match *bounds {
+        Some((mins, maxs)) => (mins.inf(&point), maxs.sup(&point)),
+        None => (point, point),
+    });
+}
+
+/// A glTF node's transform, from its matrix or its translation, rotation and scale
+fn gltf_node_matrix(node: &Value) -> Matrix4<f32> {
+    let floats = |key: &str| -> Option<Vec<f32>> {
+        node[key].as_array().map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_f64())
+                .map(|v| v as f32)
+                .collect()
+        })
+    };
+
+    if let Some(matrix) = floats("matrix").filter(|m| m.len() == 16) {
+        return Matrix4::from_column_slice(&matrix);
+    }
+
+    let translation = floats("translation")
+        .filter(|t| t.len() == 3)
+        .map_or(Vector3::zeros(), |t| Vector3::new(t[0], t[1], t[2]));
+    let rotation = floats("rotation")
+        .filter(|r| r.len() == 4)
+        .map_or(UnitQuaternion::identity(), |r| {
+            UnitQuaternion::from_quaternion(Quaternion::new(r[3], r[0], r[1], r[2]))
+        });
+    let scale = floats("scale")
+        .filter(|s| s.len() == 3)
+        .map_or(Vector3::repeat(1.0), |s| Vector3::new(s[0], s[1], s[2]));
+
+    Matrix4::new_translation(&translation)
+        * rotation.to_homogeneous()
+        * Matrix4::new_nonuniform_scaling(&scale)
+}
+
+/// Every node with its transform in the model's space, parents applied
+fn gltf_node_matrices(json: &Value) -> Vec<(usize, Matrix4<f32>)> {
+    let nodes = json["nodes"].as_array().cloned().unwrap_or_default();
+    let index_list = |value: &Value| -> Vec<usize> {
+        value
+            .as_array()
+            .map(|indices| {
+                indices
+                    .iter()
+                    .filter_map(|i| i.as_u64())
+                    .map(|i| i as usize)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let scene = json["scene"].as_u64().unwrap_or(0) as usize;
+    let mut roots = index_list(&json["scenes"][scene]["nodes"]);
+
+    // files without scenes draw every node nothing else parents
+    if json["scenes"]
+        .as_array()
+        .map_or(true, |scenes| scenes.is_empty())
+    {
+        let children: Vec<usize> = nodes
+            .iter()
+            .flat_map(|n| index_list(&n["children"]))
+            .collect();
+        roots = (0..nodes.len()).filter(|i| !children.contains(i)).collect();
+    }
+
+    let mut matrices = Vec::new();
+    let mut to_visit: Vec<(usize, Matrix4<f32>)> = roots
+        .into_iter()
+        .map(|i| (i, Matrix4::identity()))
+        .collect();
+
+    // a malformed file could loop, no tree visits a node more often than there are nodes
+    while let Some((index, parent)) = to_visit.pop() {
+        let Some(node) = nodes.get(index).filter(|_| matrices.len() <= nodes.len()) else {
+            continue;
+        };
+
+        let matrix = parent * gltf_node_matrix(node);
+        to_visit.extend(
+            index_list(&node["children"])
+                .into_iter()
+                .map(|i| (i, matrix)),
+        );
+        matrices.push((index, matrix));
+    }
+
+    matrices
+}
+
+/// The vertex positions of an accessor read from the binary chunk, or the corners of the
+/// accessor's min and max when they live anywhere else
+fn gltf_positions(json: &Value, bin: Option<&[u8]>, accessor_index: usize) -> Vec<Point3<f32>> {
+    let accessor = &json["accessors"][accessor_index];
+
+    let read = || -> Option<Vec<Point3<f32>>> {
+        if accessor["componentType"].as_u64()? != GLTF_FLOAT
+            || accessor["type"].as_str()? != "VEC3"
+            || !accessor["sparse"].is_null()
+        {
+            return None;
+        }
+
+        let view = &json["bufferViews"][accessor["bufferView"].as_u64()? as usize];
+        let buffer = view["buffer"].as_u64()? as usize;
+        // only the glb's own buffer has no uri
+        if buffer != 0 || !json["buffers"][0]["uri"].is_null() {
+            return None;
+        }
+
+        let bin = bin?;
+        let start = view["byteOffset"].as_u64().unwrap_or(0) as usize
+            + accessor["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let stride = view["byteStride"].as_u64().unwrap_or(12) as usize;
+        let count = accessor["count"].as_u64()? as usize;
+
+        (0..count)
+            .map(|vertex| {
+                let offset = start + vertex * stride;
+                let bytes = bin.get(offset..offset + 12)?;
+                let float = |i: usize| {
+                    f32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]])
+                };
+
+                Some(Point3::new(float(0), float(4), float(8)))
+            })
+            .collect()
+    };
+
+    read().unwrap_or_else(|| {
+        let corner = |key: &str| -> Option<Point3<f32>> {
+            let values: Vec<f32> = accessor[key]
+                .as_array()?
+                .iter()
+                .filter_map(|v| v.as_f64())
+                .map(|v| v as f32)
+                .collect();
+
+            (values.len() == 3).then(|| Point3::new(values[0], values[1], values[2]))
+        };
+
+        match (corner("min"), corner("max")) {
+            (Some(mins), Some(maxs)) => (0..8)
+                .map(|corner| {
+                    Point3::new(
+                        if corner & 1 == 0 { mins.x } else { maxs.x },
+                        if corner & 2 == 0 { mins.y } else { maxs.y },
+                        if corner & 4 == 0 { mins.z } else { maxs.z },
+                    )
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    })
+}
+
+/// Box around every mesh vertex in a binary glTF, in the model's own space
+pub fn glb_bounds(bytes: &[u8]) -> Result<Bounds, String> {
+    if bytes.get(0..4) != Some(GLB_MAGIC) {
+        return Err("Not a binary glTF file".to_string());
+    }
+
+    let read_u32 = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    let mut json_chunk = None;
+    let mut bin_chunk = None;
+    let mut offset = 12;
+
+    while let (Some(length), Some(kind)) = (read_u32(offset), read_u32(offset + 4)) {
+        let start = offset + 8;
+        let end = start + length as usize;
+        let chunk = bytes
+            .get(start..end)
+            .ok_or("glTF chunk runs past the end of the file")?;
+
+        match kind {
+            GLB_JSON_CHUNK => json_chunk = Some(chunk),
+            GLB_BIN_CHUNK => bin_chunk = Some(chunk),
+            _ => {}
+        }
+        offset = end;
+    }
+
+    let json: Value = serde_json::from_slice(json_chunk.ok_or("glTF file has no JSON chunk")?)
+        .map_err(|e| format!("Couldn't parse glTF: {}", e))?;
+
+    let mut bounds = None;
+
+    for (node_index, matrix) in gltf_node_matrices(&json) {
+        let Some(mesh) = json["nodes"][node_index]["mesh"].as_u64() else {
+            continue;
+        };
+        let primitives = json["meshes"][mesh as usize]["primitives"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        for primitive in primitives {
+            let Some(accessor) = primitive["attributes"]["POSITION"].as_u64() else {
+                continue;
+            };
+
+            for point in gltf_positions(&json, bin_chunk, accessor as usize) {
+                grow_bounds(&mut bounds, matrix.transform_point(&point));
+            }
+        }
+    }
+
+    bounds.ok_or_else(|| "glTF file has no mesh positions".to_string())
+}
+
+/// Bounds of a model asset's meshes, read from its glb
+pub fn model_file_bounds(path: &Path) -> Result<Bounds, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Couldn't read model: {}", e))?;
+    glb_bounds(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Perspective3;
+
+    /// Looking down -z from the origin, square, 90 degrees across, near 1 and far 100
+    fn frustum() -> Frustum {
+        let projection = Perspective3::new(1.0, std::f32::consts::FRAC_PI_2, 1.0, 100.0);
+        Frustum::from_view_projection(&projection.to_homogeneous())
+    }
+
+    fn bounds(mins: [f32; 3], maxs: [f32; 3]) -> Bounds {
+        (Point3::from(mins), Point3::from(maxs))
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while json.len() % 4 != 0 {
+            json.push(b' ');
+        }
+
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(GLB_MAGIC);
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&GLB_JSON_CHUNK.to_le_bytes());
+        bytes.extend_from_slice(&json);
+        bytes.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&GLB_BIN_CHUNK.to_le_bytes());
+        bytes.extend_from_slice(bin);
+
+        bytes
+    }
+
+    #[test]
+    fn box_in_view_intersects() {
+        assert!(frustum().intersects(&bounds([-1.0, -1.0, -11.0], [1.0, 1.0, -9.0])));
+    }
+
+    #[test]
+    fn boxes_outside_the_view_are_culled() {
+        let frustum = frustum();
+
+        // behind the camera
+        assert!(!frustum.intersects(&bounds([-1.0, -1.0, 9.0], [1.0, 1.0, 11.0])));
+        // off to the side
+        assert!(!frustum.intersects(&bounds([40.0, -1.0, -11.0], [42.0, 1.0, -9.0])));
+        // past the far plane
+        assert!(!frustum.intersects(&bounds([-1.0, -1.0, -200.0], [1.0, 1.0, -150.0])));
+        // between the camera and the near plane
+        assert!(!frustum.intersects(&bounds([-0.1, -0.1, -0.5], [0.1, 0.1, -0.2])));
+    }
+
+    #[test]
+    fn box_straddling_the_near_plane_intersects() {
+        assert!(frustum().intersects(&bounds([-0.5, -0.5, -2.0], [0.5, 0.5, 0.5])));
+    }
+
+    #[test]
+    fn box_straddling_a_side_plane_intersects() {
+        assert!(frustum().intersects(&bounds([9.0, -1.0, -11.0], [12.0, 1.0, -9.0])));
+    }
+
+    #[test]
+    fn glb_bounds_reads_vertices_through_node_transforms() {
+        let json = r#"{
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "translation": [10, 0, 0], "children": [1] }, { "mesh": 0, "scale": [2, 2, 2] }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+            "accessors": [{ "bufferView": 0, "componentType": 5126, "type": "VEC3", "count": 2 }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 24 }],
+            "buffers": [{ "byteLength": 24 }]
+        }"#;
+        let bin: Vec<u8> = [-1.0f32, -2.0, -3.0, 1.0, 2.0, 3.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+
+        let (mins, maxs) = glb_bounds(&glb(json, &bin)).unwrap();
+
+        assert_eq!(mins, Point3::new(8.0, -4.0, -6.0));
+        assert_eq!(maxs, Point3::new(12.0, 4.0, 6.0));
+    }
+
+    #[test]
+    fn glb_bounds_falls_back_to_accessor_min_and_max() {
+        let json = r#"{
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+            "accessors": [{ "componentType": 5126, "type": "VEC3", "count": 3,
+                            "min": [-1, 0, -1], "max": [1, 5, 1] }]
+        }"#;
+
+        let (mins, maxs) = glb_bounds(&glb(json, &[])).unwrap();
+
+        assert_eq!(mins, Point3::new(-1.0, 0.0, -1.0));
+        assert_eq!(maxs, Point3::new(1.0, 5.0, 1.0));
+    }
+
+    #[test]
+    fn glb_bounds_rejects_other_files() {
+        assert!(glb_bounds(b"not a model").is_err());
+        assert!(glb_bounds(&glb(r#"{ "nodes": [] }"#, &[])).is_err());
+    }
+}
@@ -0,0 +1,391 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use midpoint_engine::helpers::saved_data::SavedState;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::behaviors::Behavior;
+use super::camera::CameraBookmark;
+use super::environment::EnvironmentSettings;
+use super::hierarchy::SceneNode;
+use super::lights::{LevelLighting, LightComponent};
+use super::materials::MaterialOverride;
+use super::measurements::Annotation;
+use super::prefabs::{Prefab, PrefabInstance};
+use super::scatter::ScatterLayer;
+use super::tiles::TileGrid;
+use super::utilities::get_common_os_dir;
+use super::water::WaterSettings;
+
+/// Editor project data. Most of it is saved to editor.json next to midpoint.json, what a game
+/// needs too is written into midpoint.json itself, see `SavedStateFields` and `LevelFields`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EditorData {
+    #[serde(default)]
+    pub levels: Vec<LevelEditorData>,
+    /// Level open when the project was last saved
+    #[serde(default)]
+    pub active_level_id: Option<String>,
+    /// Component templates that can be placed in any level, saved in midpoint.json
+    #[serde(default, skip_serializing)]
+    pub prefabs: Vec<Prefab>,
+}
+
+/// Editor-only data belonging to one level, matched by the level id in SavedState
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LevelEditorData {
+    pub level_id: String,
+    /// Name shown in the level browser, levels without one are numbered
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub tile_grids: Vec<TileGrid>,
+    #[serde(default)]
+    pub scatter_layers: Vec<ScatterLayer>,
+    #[serde(default)]
+    pub water: WaterSettings,
+    /// Parents, groups and visibility of the level's components, in outline order
+    #[serde(default)]
+    pub hierarchy: Vec<SceneNode>,
+    /// Saved in the level's entry in midpoint.json
+    #[serde(default, skip_serializing)]
+    pub prefab_instances: Vec<PrefabInstance>,
+    #[serde(default)]
+    pub camera_bookmarks: Vec<CameraBookmark>,
+    /// Saved in the level's entry in midpoint.json, along with the lighting
+    #[serde(default, skip_serializing)]
+    pub lights: Vec<LightComponent>,
+    #[serde(default, skip_serializing)]
+    pub lighting: LevelLighting,
+    /// Looks of model components in place of their glb's textures
+    #[serde(default)]
+    pub materials: Vec<MaterialOverride>,
+    /// Sky, fog and background color, saved in the level's entry in midpoint.json
+    #[serde(default, skip_serializing)]
+    pub environment: EnvironmentSettings,
+    /// Notes pinned around the level, only the editor shows them
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Models handed to physics while playing, by component id
+    #[serde(default)]
+    pub simulated_components: Vec<String>,
+    /// Node graphs the components run while playing, saved in the level's entry in midpoint.json
+    #[serde(default, skip_serializing)]
+    pub behaviors: Vec<Behavior>,
+}
+
+impl EditorData {
+    pub fn level(&self, level_id: &str) -> Option<&LevelEditorData> {
+        self.levels.iter().find(|l| l.level_id == level_id)
+    }
+
+    /// Gets the level's data, creating an empty entry the first time a level is edited
+    pub fn level_mut(&mut self, level_id: &str) -> &mut LevelEditorData {
+        if let Some(index) = self.levels.iter().position(|l| l.level_id == level_id) {
+            return &mut self.levels[index];
+        }
+
+        self.levels.push(LevelEditorData {
+            level_id: level_id.to_string(),
+            ..Default::default()
+        });
+        self.levels.last_mut().expect("Couldn't get new level data")
+    }
+}
+
+/// Editor data written into midpoint.json next to the engine's fields, so whatever loads the
+/// project gets it along with the levels. Fields are only read back when the file has them, older
+/// projects keep what their editor.json had.
+#[derive(Serialize, Deserialize, Default)]
+struct SavedStateFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefabs: Option<Vec<Prefab>>,
+}
+
+/// Editor data written into a level's entry in midpoint.json, next to its id and components
+#[derive(Serialize, Deserialize, Default)]
+struct LevelFields {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prefab_instances: Option<Vec<PrefabInstance>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    environment: Option<EnvironmentSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lights: Option<Vec<LightComponent>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lighting: Option<LevelLighting>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    behaviors: Option<Vec<Behavior>>,
+}
+
+impl SavedStateFields {
+    fn of(editor_data: &EditorData) -> Self {
+        Self {
+            prefabs: Some(editor_data.prefabs.clone()),
+        }
+    }
+
+    fn apply(self, editor_data: &mut EditorData) {
+        if let Some(prefabs) = self.prefabs {
+            editor_data.prefabs = prefabs;
+        }
+    }
+}
+
+impl LevelFields {
+    fn of(level_data: &LevelEditorData) -> Self {
+        Self {
+            prefab_instances: Some(level_data.prefab_instances.clone()),
+            environment: Some(level_data.environment.clone()),
+            lights: Some(level_data.lights.clone()),
+            lighting: Some(level_data.lighting),
+            behaviors: Some(level_data.behaviors.clone()),
+        }
+    }
+
+    fn apply(self, level_data: &mut LevelEditorData) {
+        if let Some(prefab_instances) = self.prefab_instances {
+            level_data.prefab_instances = prefab_instances;
+        }
+        if let Some(environment) = self.environment {
+            level_data.environment = environment;
+        }
+        if let Some(lights) = self.lights {
+            level_data.lights = lights;
+        }
+        if let Some(lighting) = self.lighting {
+            level_data.lighting = lighting;
+        }
+        if let Some(behaviors) = self.behaviors {
+            level_data.behaviors = behaviors;
+        }
+    }
+}
+
+fn project_file_path(project_id: &str, file_name: &str) -> PathBuf {
+    let sync_dir = get_common_os_dir().expect("Couldn't get CommonOS directory");
+
+    sync_dir
+        .join("midpoint")
+        .join("projects")
+        .join(project_id)
+        .join(file_name)
+}
+
+/// The file's JSON, None when the project doesn't have it
+fn read_json_file(path: &Path) -> Result<Option<Value>, String> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+    };
+
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))
+}
+
+/// Adds the fields to a JSON object, replacing keys it already had
+fn merge_fields(target: &mut Value, fields: &impl Serialize) {
+    let Ok(Value::Object(fields)) = serde_json::to_value(fields) else {
+        return;
+    };
+
+    if let Some(target) = target.as_object_mut() {
+        target.extend(fields);
+    }
+}
+
+/// Writes the editor data kept in midpoint.json into the saved state's JSON
+fn embed_editor_fields(saved_state: &mut Value, editor_data: &EditorData) {
+    merge_fields(saved_state, &SavedStateFields::of(editor_data));
+
+    let Some(levels) = saved_state.get_mut("levels").and_then(Value::as_array_mut) else {
+        return;
+    };
+
+    for level in levels.iter_mut() {
+        let level_data = level
+            .get("id")
+            .and_then(Value::as_str)
+            .and_then(|level_id| editor_data.level(level_id));
+
+        if let Some(level_data) = level_data {
+            let fields = LevelFields::of(level_data);
+            merge_fields(level, &fields);
+        }
+    }
+}
+
+/// Takes the editor data midpoint.json has into the editor data
+fn read_editor_fields(saved_state: &Value, editor_data: &mut EditorData) -> Result<(), String> {
+    SavedStateFields::deserialize(saved_state)
+        .map_err(|e| format!("Couldn't read editor data in midpoint.json: {}", e))?
+        .apply(editor_data);
+
+    let levels = saved_state.get("levels").and_then(Value::as_array);
+
+    for level in levels.into_iter().flatten() {
+        let Some(level_id) = level.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+
+        LevelFields::deserialize(level)
+            .map_err(|e| format!("Couldn't read level {} in midpoint.json: {}", level_id, e))?
+            .apply(editor_data.level_mut(level_id));
+    }
+
+    Ok(())
+}
+
+/// Reads the project's editor data, an empty set when the project has none yet. An editor.json
+/// that can't be read is an error rather than empty data, saving over it would lose every level's
+/// editor data.
+pub fn load_editor_data(project_id: &str) -> Result<EditorData, String> {
+    let load_path = project_file_path(project_id, "editor.json");
+
+    // older projects have no editor data yet
+    let mut editor_data = match read_json_file(&load_path)? {
+        Some(json) => serde_json::from_value(json)
+            .map_err(|e| format!("Couldn't parse {}: {}", load_path.display(), e))?,
+        None => EditorData::default(),
+    };
+
+    if let Some(saved_state) = read_json_file(&project_file_path(project_id, "midpoint.json"))? {
+        read_editor_fields(&saved_state, &mut editor_data)?;
+    }
+
+    Ok(editor_data)
+}
+
+/// Saves editor.json and brings the editor data kept in midpoint.json up to date
+pub fn save_editor_data(project_id: &str, editor_data: &EditorData) {
+    let json = serde_json::to_string_pretty(editor_data).expect("Couldn't serialize editor data");
+    let save_path = project_file_path(project_id, "editor.json");
+
+    println!("Saving editor data... {}", save_path.display());
+
+    fs::write(&save_path, json).expect("Couldn't write editor data");
+
+    let saved_state_path = project_file_path(project_id, "midpoint.json");
+    match read_json_file(&saved_state_path) {
+        Ok(Some(mut saved_state)) => {
+            embed_editor_fields(&mut saved_state, editor_data);
+
+            let json =
+                serde_json::to_string_pretty(&saved_state).expect("Couldn't serialize saved state");
+            fs::write(&saved_state_path, json).expect("Couldn't write saved state");
+        }
+        Ok(None) => {}
+        // the engine's data in it isn't ours to overwrite
+        Err(e) => println!("{}", e),
+    }
+}
+
+/// Saves midpoint.json, keeping the editor data the file already had in it
+pub fn save_saved_state_file(project_id: &str, saved_state: &SavedState) {
+    let mut json = serde_json::to_value(saved_state).expect("Couldn't serialize saved state");
+    let save_path = project_file_path(project_id, "midpoint.json");
+
+    if let Ok(Some(previous)) = read_json_file(&save_path) {
+        let mut kept = EditorData::default();
+        match read_editor_fields(&previous, &mut kept) {
+            Ok(()) => embed_editor_fields(&mut json, &kept),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    println!("Saving saved state... {}", save_path.display());
+
+    let json = serde_json::to_string_pretty(&json).expect("Couldn't serialize saved state");
+    fs::write(&save_path, json).expect("Couldn't write saved state");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn instance(id: &str) -> PrefabInstance {
+        PrefabInstance {
+            id: id.to_string(),
+            prefab_id: "prefab".to_string(),
+            transform: [[0.0; 3], [0.0; 3], [1.0; 3]],
+            members: Vec::new(),
+            removed_sources: Vec::new(),
+        }
+    }
+
+    fn editor_data() -> EditorData {
+        let mut editor_data = EditorData {
+            prefabs: vec![Prefab {
+                id: "prefab".to_string(),
+                name: "Crate".to_string(),
+                components: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        editor_data.level_mut("level-1").prefab_instances = vec![instance("instance-1")];
+
+        editor_data
+    }
+
+    #[test]
+    fn editor_fields_go_next_to_the_engine_fields() {
+        let mut saved_state = json!({
+            "models": [],
+            "levels": [{ "id": "level-1", "components": [] }, { "id": "level-2" }],
+        });
+
+        embed_editor_fields(&mut saved_state, &editor_data());
+
+        assert_eq!(saved_state["prefabs"][0]["name"], "Crate");
+        assert_eq!(saved_state["models"], json!([]));
+        assert_eq!(saved_state["levels"][0]["components"], json!([]));
+        assert_eq!(
+            saved_state["levels"][0]["prefab_instances"][0]["id"],
+            "instance-1"
+        );
+        assert!(saved_state["levels"][0]["environment"].is_object());
+        // levels without editor data are left as they are
+        assert_eq!(saved_state["levels"][1], json!({ "id": "level-2" }));
+    }
+
+    #[test]
+    fn editor_fields_read_back_from_the_saved_state() {
+        let mut saved_state = json!({ "levels": [{ "id": "level-1" }] });
+        embed_editor_fields(&mut saved_state, &editor_data());
+
+        let mut read = EditorData::default();
+        read_editor_fields(&saved_state, &mut read).unwrap();
+
+        assert_eq!(read.prefabs[0].id, "prefab");
+        assert_eq!(
+            read.level("level-1").unwrap().prefab_instances[0].id,
+            "instance-1"
+        );
+    }
+
+    #[test]
+    fn saved_states_without_editor_fields_keep_the_editor_json_ones() {
+        let saved_state = json!({ "levels": [{ "id": "level-1", "components": [] }] });
+
+        let mut read = editor_data();
+        read_editor_fields(&saved_state, &mut read).unwrap();
+
+        assert_eq!(read.prefabs.len(), 1);
+        assert_eq!(read.level("level-1").unwrap().prefab_instances.len(), 1);
+    }
+
+    #[test]
+    fn editor_json_leaves_out_what_midpoint_json_holds() {
+        let json = serde_json::to_value(editor_data()).unwrap();
+
+        assert!(json.get("prefabs").is_none());
+        assert!(json["levels"][0].get("prefab_instances").is_none());
+        assert!(json["levels"][0].get("environment").is_none());
+        assert!(json["levels"][0].get("lights").is_none());
+        assert!(json["levels"][0].get("behaviors").is_none());
+    }
+}
@@ -0,0 +1,483 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use bytemuck::{Pod, Zeroable};
+use midpoint_engine::core::RendererState::RendererState;
+use nalgebra::{Matrix4, Point3, Vector3};
+use wgpu::util::DeviceExt;
+
+use super::camera::eye_position;
+use super::environment::{EnvironmentSettings, SkyBinding, SkyUniforms};
+use super::gizmos::translate_handle_meshes;
+use super::instancing::{model_matrix, SceneCache};
+use super::lights::{light_marker_lines, light_uniforms, LevelLighting, LightComponent};
+use super::materials::{MaterialBinding, SurfaceUniform};
+use super::measurements::{measurement_lines, Annotation, Measurement};
+use super::offscreen::OffscreenRenderer;
+use super::performance::PerformanceMonitor;
+use super::play_mode::PlaySimulation;
+use super::scene_pipeline::{
+    create_overlay_pipeline, create_render_mode_pipeline, create_sky_pipeline, SceneLayouts,
+};
+use super::view_modes::{overlay_lines, RenderMode, ViewportSettings};
+use super::viewport_layout::ViewportLayout;
+
+/// Matches the engine's `Vertex` layout (position, normal, tex_coords, color)
+/// so editor-only meshes can go through the primary pipeline
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct ColorVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 3],
+}
+
+// only f32 fields with no padding
+unsafe impl Zeroable for ColorVertex {}
+unsafe impl Pod for ColorVertex {}
+
+/// A vertex-colored mesh drawn by the editor rather than the engine
+pub struct ColorMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    pub model_buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl ColorMesh {
+    pub fn new(
+        device: &wgpu::Device,
+        model_bind_group_layout: &wgpu::BindGroupLayout,
+        vertices: &[ColorVertex],
+        indices: &[u32],
+    ) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Editor Mesh Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Editor Mesh Index Buffer"),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        // vertices are already in world space
+        let identity = Matrix4::<f32>::identity();
+        let model_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Editor Mesh Model Buffer"),
+            contents: bytemuck::cast_slice(identity.as_slice()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: model_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: model_buffer.as_entire_binding(),
+            }],
+            label: Some("Editor Mesh Bind Group"),
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            model_buffer,
+            bind_group,
+        }
+    }
+}
+
+/// GPU resources for things the editor draws on top of the engine's scene
+#[derive(Default)]
+pub struct EditorRenderState {
+    pub model_bind_group_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    pub texture_bind_group_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    /// Texture group with the render mode set to vertex colors
+    pub color_texture_bind_group: Option<wgpu::BindGroup>,
+    pub water_meshes: Vec<ColorMesh>,
+    /// Plane and free-move handles drawn alongside the engine's translation arrows
+    pub translate_handles: Vec<ColorMesh>,
+    /// Components the scene pass skips, set from the level's hierarchy
+    pub hidden_components: HashSet<String>,
+    /// Space the panels leave for the scene, measured by the UI
+    pub viewport_layout: ViewportLayout,
+    /// Draws the scene into textures for screenshots and thumbnails
+    pub offscreen: Option<OffscreenRenderer>,
+    /// Render mode and overlays, copied from the editor settings
+    pub viewport_settings: ViewportSettings,
+    /// The primary pipeline for each render mode the GPU can draw
+    render_mode_pipelines: Vec<(RenderMode, wgpu::RenderPipeline)>,
+    /// The same pipelines drawing copies of an asset in one go
+    instanced_pipelines: Vec<(RenderMode, wgpu::RenderPipeline)>,
+    overlay_pipeline: Option<wgpu::RenderPipeline>,
+    /// Collider and bounds lines, light markers and measurements, rebuilt each frame
+    overlay_mesh: Option<ColorMesh>,
+    /// The active level's lights, moved along with gizmo drags before they are saved
+    pub lights: Vec<LightComponent>,
+    pub lighting: LevelLighting,
+    light_buffer: Option<wgpu::Buffer>,
+    /// Lights for the lit view, in place of the engine's single light
+    pub light_bind_group: Option<wgpu::BindGroup>,
+    /// Texture groups of the active level's material overrides, by component id
+    pub material_bindings: HashMap<String, MaterialBinding>,
+    /// The active level's sky, fog and clear color
+    pub environment: EnvironmentSettings,
+    pub sky_bind_group_layout: Option<Arc<wgpu::BindGroupLayout>>,
+    pub sky_buffer: Option<wgpu::Buffer>,
+    /// The level's sky textures, none when it only has a clear color
+    pub sky_binding: Option<SkyBinding>,
+    sky_pipeline: Option<wgpu::RenderPipeline>,
+    /// Frame timings and counts for the performance view
+    pub performance: PerformanceMonitor,
+    /// The asset each model component was loaded from, copies of one asset are drawn instanced
+    pub model_assets: HashMap<String, String>,
+    /// Uploaded transforms, learned bounds and instances, kept between scene passes
+    pub scene_cache: Mutex<SceneCache>,
+    /// Measure tool in use in the viewport and the points clicked with it
+    pub measurement: Measurement,
+    /// The active level's annotation pins
+    pub annotations: Vec<Annotation>,
+    /// Play, pause or editing, and the models physics moves while playing
+    pub play: PlaySimulation,
+}
+
+impl EditorRenderState {
+    /// Called once the pipeline's bind group layouts exist
+    pub fn initialize(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        layouts: &SceneLayouts,
+    ) {
+        let model_bind_group_layout = layouts.model.clone();
+        let texture_bind_group_layout = layouts.texture.clone();
+
+        // the fragment shader samples six layers even in color mode
+        let size = wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 6,
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Editor Color Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &[255u8; 4 * 6],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(4),
+                rows_per_image: Some(1),
+            },
+            size,
+        );
+
+        let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor::default());
+
+        let render_mode_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Editor Color Render Mode Buffer"),
+            contents: bytemuck::cast_slice(&[SurfaceUniform::render_mode(0)]), // color mode
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let color_texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: render_mode_buffer.as_entire_binding(),
+                },
+            ],
+            label: Some("Editor Color Texture Bind Group"),
+        });
+
+        self.translate_handles = translate_handle_meshes()
+            .iter()
+            .map(|(vertices, indices)| {
+                ColorMesh::new(device, &model_bind_group_layout, vertices, indices)
+            })
+            .collect();
+
+        let light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Editor Light Buffer"),
+            contents: bytemuck::cast_slice(&[light_uniforms(
+                &self.lights,
+                &self.lighting,
+                &self.environment,
+                [0.0; 3],
+            )]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layouts.light,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: light_buffer.as_entire_binding(),
+            }],
+            label: Some("Editor Light Bind Group"),
+        });
+
+        let sky_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Editor Sky Buffer"),
+            contents: bytemuck::cast_slice(&[SkyUniforms::new(
+                &self.environment,
+                &Matrix4::identity(),
+            )]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        self.model_bind_group_layout = Some(model_bind_group_layout);
+        self.texture_bind_group_layout = Some(texture_bind_group_layout);
+        self.color_texture_bind_group = Some(color_texture_bind_group);
+        self.light_buffer = Some(light_buffer);
+        self.light_bind_group = Some(light_bind_group);
+        self.sky_bind_group_layout = Some(layouts.sky.clone());
+        self.sky_buffer = Some(sky_buffer);
+        self.offscreen = Some(OffscreenRenderer::new(device, layouts));
+        self.performance.initialize(device, queue);
+    }
+
+    /// Writes the lights, fog and sky as seen through the view projection
+    pub fn write_frame_uniforms(&self, queue: &wgpu::Queue, view_projection: &Matrix4<f32>) {
+        let (Some(light_buffer), Some(sky_buffer)) =
+            (self.light_buffer.as_ref(), self.sky_buffer.as_ref())
+        else {
+            return;
+        };
+
+        let camera_position = eye_position(view_projection).unwrap_or_else(Point3::origin);
+        let uniforms = light_uniforms(
+            &self.lights,
+            &self.lighting,
+            &self.environment,
+            camera_position.into(),
+        );
+        queue.write_buffer(light_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+
+        let inverse_view_projection = view_projection
+            .try_inverse()
+            .unwrap_or_else(Matrix4::identity);
+        let sky = SkyUniforms::new(&self.environment, &inverse_view_projection);
+        queue.write_buffer(sky_buffer, 0, bytemuck::cast_slice(&[sky]));
+    }
+
+    /// Called once the swapchain format is known
+    pub fn create_view_pipelines(
+        &mut self,
+        device: &wgpu::Device,
+        layouts: &SceneLayouts,
+        format: wgpu::TextureFormat,
+    ) {
+        self.render_mode_pipelines = RenderMode::ALL
+            .into_iter()
+            .filter_map(|mode| {
+                let pipeline = create_render_mode_pipeline(device, layouts, format, mode, false);
+                if pipeline.is_none() {
+                    println!("The GPU can't draw the {} render mode", mode.label());
+                }
+
+                pipeline.map(|pipeline| (mode, pipeline))
+            })
+            .collect();
+        self.instanced_pipelines = RenderMode::ALL
+            .into_iter()
+            .filter_map(|mode| {
+                create_render_mode_pipeline(device, layouts, format, mode, true)
+                    .map(|pipeline| (mode, pipeline))
+            })
+            .collect();
+        self.overlay_pipeline = Some(create_overlay_pipeline(device, layouts, format));
+        self.sky_pipeline = Some(create_sky_pipeline(device, layouts, format));
+    }
+
+    /// None for modes the GPU can't draw, the primary pipeline is used then
+    pub fn render_mode_pipeline(&self) -> Option<&wgpu::RenderPipeline> {
+        self.render_mode_pipelines
+            .iter()
+            .find(|(mode, _)| *mode == self.viewport_settings.render_mode)
+            .map(|(_, pipeline)| pipeline)
+    }
+
+    /// The render mode's pipeline for instanced draws, None along with `render_mode_pipeline`
+    pub fn instanced_pipeline(&self) -> Option<&wgpu::RenderPipeline> {
+        self.instanced_pipelines
+            .iter()
+            .find(|(mode, _)| *mode == self.viewport_settings.render_mode)
+            .map(|(_, pipeline)| pipeline)
+    }
+
+    pub fn update_overlay(&mut self, device: &wgpu::Device, renderer_state: &RendererState) {
+        let mut lines = overlay_lines(
+            renderer_state,
+            &self.viewport_settings,
+            &self.hidden_components,
+        )
+        .unwrap_or_default();
+        light_marker_lines(&mut lines, &self.lights);
+        measurement_lines(&mut lines, &self.measurement, &self.annotations);
+
+        self.overlay_mesh = self
+            .model_bind_group_layout
+            .as_ref()
+            .filter(|_| !lines.indices.is_empty())
+            .map(|model_bind_group_layout| {
+                ColorMesh::new(
+                    device,
+                    model_bind_group_layout,
+                    &lines.vertices,
+                    &lines.indices,
+                )
+            });
+    }
+
+    /// Draws the overlay lines over the scene, leaves the overlay pipeline set
+    pub fn render_overlay(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        let (Some(overlay_pipeline), Some(overlay_mesh)) =
+            (self.overlay_pipeline.as_ref(), self.overlay_mesh.as_ref())
+        else {
+            return;
+        };
+
+        render_pass.set_pipeline(overlay_pipeline);
+        self.draw_meshes(
+            render_pass,
+            camera_bind_group,
+            std::slice::from_ref(overlay_mesh),
+        );
+    }
+
+    /// Draws the level's sky with the window's sky pipeline, leaves it set when there is a sky
+    pub fn render_sky(&self, render_pass: &mut wgpu::RenderPass) {
+        if let Some(sky_pipeline) = self.sky_pipeline.as_ref() {
+            self.draw_sky(render_pass, sky_pipeline);
+        }
+    }
+
+    /// Fills the view with the sky, nothing happens when the level only has a clear color
+    pub fn draw_sky(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        sky_pipeline: &wgpu::RenderPipeline,
+    ) {
+        let Some(sky_binding) = self.sky_binding.as_ref() else {
+            return;
+        };
+
+        render_pass.set_pipeline(sky_pipeline);
+        render_pass.set_bind_group(0, &sky_binding.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// The texture group a component's material override replaces its meshes' with
+    pub fn material_bind_group(&self, component_id: &str) -> Option<&wgpu::BindGroup> {
+        self.material_bindings
+            .get(component_id)
+            .map(|binding| &binding.bind_group)
+    }
+
+    /// GPU memory of the textures the editor made for material overrides and the sky
+    pub fn texture_bytes(&self) -> u64 {
+        let materials: u64 = self
+            .material_bindings
+            .values()
+            .map(|binding| binding.texture_bytes)
+            .sum();
+        let sky = self
+            .sky_binding
+            .as_ref()
+            .map_or(0, |binding| binding.texture_bytes);
+
+        materials + sky
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.color_texture_bind_group.is_some()
+    }
+
+    /// Moves the translate handles onto the gizmo and turns them with it
+    pub fn update_translate_handles(
+        &self,
+        queue: &wgpu::Queue,
+        position: Vector3<f32>,
+        rotation: Vector3<f32>,
+        scale: f32,
+    ) {
+        let model = model_matrix(&position, &rotation, &Vector3::repeat(scale));
+
+        for handle in &self.translate_handles {
+            queue.write_buffer(
+                &handle.model_buffer,
+                0,
+                bytemuck::cast_slice(model.as_slice()),
+            );
+        }
+    }
+
+    /// Draws the editor's scene meshes, expects the primary pipeline to be set
+    pub fn render(&self, render_pass: &mut wgpu::RenderPass, camera_bind_group: &wgpu::BindGroup) {
+        self.draw_meshes(render_pass, camera_bind_group, &self.water_meshes);
+    }
+
+    pub fn render_translate_handles(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        self.draw_meshes(render_pass, camera_bind_group, &self.translate_handles);
+    }
+
+    fn draw_meshes(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        camera_bind_group: &wgpu::BindGroup,
+        meshes: &[ColorMesh],
+    ) {
+        let Some(color_texture_bind_group) = self.color_texture_bind_group.as_ref() else {
+            return;
+        };
+
+        for mesh in meshes {
+            render_pass.set_bind_group(0, camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &mesh.bind_group, &[]);
+            render_pass.set_bind_group(2, color_texture_bind_group, &[]);
+
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+            render_pass.draw_indexed(0..mesh.index_count, 0, 0..1);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use super::camera::CameraSettings;
+use super::offscreen::ScreenshotSettings;
+use super::play_mode::PlaySettings;
+use super::selection::TransformSettings;
+use super::snapping::SnapSettings;
+use super::utilities::get_common_os_dir;
+use super::view_modes::ViewportSettings;
+
+/// Per-user editor preferences shared by every project
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct EditorSettings {
+    #[serde(default)]
+    pub snapping: SnapSettings,
+    #[serde(default)]
+    pub transform: TransformSettings,
+    #[serde(default)]
+    pub camera: CameraSettings,
+    #[serde(default)]
+    pub screenshot: ScreenshotSettings,
+    #[serde(default)]
+    pub viewport: ViewportSettings,
+    #[serde(default)]
+    pub play: PlaySettings,
+}
+
+pub fn load_editor_settings() -> EditorSettings {
+    let sync_dir = get_common_os_dir().expect("Couldn't get CommonOS directory");
+    let load_path = sync_dir.join("midpoint").join("editor_settings.json");
+
+    match fs::read_to_string(&load_path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            println!("Couldn't parse editor settings: {}", e);
+            EditorSettings::default()
+        }),
+        Err(_) => EditorSettings::default(),
+    }
+}
+
+pub fn save_editor_settings(editor_settings: &EditorSettings) {
+    let json =
+        serde_json::to_string_pretty(editor_settings).expect("Couldn't serialize editor settings");
+    let sync_dir = get_common_os_dir().expect("Couldn't get CommonOS directory");
+    let save_dir = sync_dir.join("midpoint");
+
+    fs::create_dir_all(&save_dir).expect("Couldn't create midpoint directory");
+    fs::write(save_dir.join("editor_settings.json"), json).expect("Couldn't write editor settings");
+}
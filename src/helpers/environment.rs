@@ -0,0 +1,533 @@
+use bytemuck::{Pod, Zeroable};
+use image::imageops::FilterType;
+use image::{DynamicImage, Rgba, Rgba32FImage};
+use midpoint_engine::helpers::saved_data::File;
+use nalgebra::Matrix4;
+use serde::{Deserialize, Serialize};
+
+use crate::editor_state::StateHelper;
+
+use super::editor_render::EditorRenderState;
+use super::levels::active_level_id;
+
+/// Cubemap faces are scaled down to this so a large import doesn't eat the GPU's memory
+const MAX_SKY_FACE_SIZE: u32 = 1024;
+/// Width panoramas are scaled down to, they are twice as wide as they are high
+const MAX_SKY_PANORAMA_WIDTH: u32 = 4096;
+/// Half floats keep HDR skies brighter than white and are filterable everywhere
+const SKY_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Cubemap faces in the order wgpu lays out a cube texture
+pub const SKY_FACE_LABELS: [&str; 6] = [
+    "Right (+X)",
+    "Left (-X)",
+    "Top (+Y)",
+    "Bottom (-Y)",
+    "Front (+Z)",
+    "Back (-Z)",
+];
+
+/// What is drawn behind the level
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SkyKind {
+    /// Only the clear color
+    #[default]
+    None,
+    /// Six square textures, one for each side of a cube around the camera
+    Cubemap,
+    /// One panorama covering every direction, usually an HDR
+    Equirectangular,
+}
+
+impl SkyKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkyKind::None => "No Sky",
+            SkyKind::Cubemap => "Cubemap",
+            SkyKind::Equirectangular => "Panorama",
+        }
+    }
+
+    /// Matches the kinds sky.wgsl tells apart
+    fn uniform_value(&self) -> u32 {
+        match self {
+            SkyKind::None => 0,
+            SkyKind::Cubemap => 1,
+            SkyKind::Equirectangular => 2,
+        }
+    }
+}
+
+/// Sky, fog and background of one level
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EnvironmentSettings {
+    #[serde(default)]
+    pub sky: SkyKind,
+    /// Texture ids from the project's library, in the order of `SKY_FACE_LABELS`
+    #[serde(default)]
+    pub sky_faces: [Option<String>; 6],
+    /// Texture id of the equirectangular panorama
+    #[serde(default)]
+    pub sky_panorama: Option<String>,
+    /// Brightness of the sky textures, mostly for HDRs
+    #[serde(default = "default_sky_exposure")]
+    pub sky_exposure: f32,
+    /// Shown where there is no sky and nothing else was drawn
+    #[serde(default = "default_clear_color")]
+    pub clear_color: [f32; 3],
+    /// Tints the ambient light of the lit view
+    #[serde(default = "default_ambient_color")]
+    pub ambient_color: [f32; 3],
+    #[serde(default)]
+    pub fog_enabled: bool,
+    #[serde(default = "default_fog_color")]
+    pub fog_color: [f32; 3],
+    /// Distance from the camera where fog begins
+    #[serde(default = "default_fog_start")]
+    pub fog_start: f32,
+    /// Distance from the camera where fog hides everything
+    #[serde(default = "default_fog_end")]
+    pub fog_end: f32,
+}
+
+fn default_sky_exposure() -> f32 {
+    1.0
+}
+
+/// The white the viewport always cleared to
+fn default_clear_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_ambient_color() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_fog_color() -> [f32; 3] {
+    [0.75, 0.8, 0.85]
+}
+
+fn default_fog_start() -> f32 {
+    50.0
+}
+
+fn default_fog_end() -> f32 {
+    500.0
+}
+
+impl Default for EnvironmentSettings {
+    fn default() -> Self {
+        Self {
+            sky: SkyKind::None,
+            sky_faces: Default::default(),
+            sky_panorama: None,
+            sky_exposure: default_sky_exposure(),
+            clear_color: default_clear_color(),
+            ambient_color: default_ambient_color(),
+            fog_enabled: false,
+            fog_color: default_fog_color(),
+            fog_start: default_fog_start(),
+            fog_end: default_fog_end(),
+        }
+    }
+}
+
+impl EnvironmentSettings {
+    pub fn wgpu_clear_color(&self) -> wgpu::Color {
+        let [r, g, b] = self.clear_color.map(f64::from);
+
+        wgpu::Color { r, g, b, a: 1.0 }
+    }
+
+    /// Textures the sky is made of, empty without a sky
+    fn sky_texture_ids(&self) -> Vec<Option<String>> {
+        match self.sky {
+            SkyKind::None => Vec::new(),
+            SkyKind::Cubemap => self.sky_faces.to_vec(),
+            SkyKind::Equirectangular => vec![self.sky_panorama.clone()],
+        }
+    }
+}
+
+/// Matches SkyUniforms in sky.wgsl, padded to its alignment
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SkyUniforms {
+    pub inverse_view_projection: [[f32; 4]; 4],
+    pub kind: u32,
+    pub exposure: f32,
+    pub _padding: [f32; 2],
+}
+
+// only f32 and u32 fields with explicit padding
+unsafe impl Zeroable for SkyUniforms {}
+unsafe impl Pod for SkyUniforms {}
+
+impl SkyUniforms {
+    pub fn new(environment: &EnvironmentSettings, inverse_view_projection: &Matrix4<f32>) -> Self {
+        Self {
+            inverse_view_projection: (*inverse_view_projection).into(),
+            kind: environment.sky.uniform_value(),
+            exposure: environment.sky_exposure,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
+/// The sky's textures bound for sky.wgsl, the kind not in use gets a black stand-in
+pub struct SkyBinding {
+    pub bind_group: wgpu::BindGroup,
+    kind: SkyKind,
+    texture_ids: Vec<Option<String>>,
+    /// GPU memory of the sky's textures, stand-ins included
+    pub texture_bytes: u64,
+}
+
+/// Half float bits of a color value. Tiny values flush to zero and huge ones clamp to the
+/// largest half, neither shows in a sky.
+fn half_float_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = ((bits >> 13) & 0x3ff) as u16;
+
+    if exponent <= 0 {
+        sign
+    } else if exponent >= 31 {
+        sign | 0x7bff
+    } else {
+        sign | ((exponent as u16) << 10) | mantissa
+    }
+}
+
+fn half_float_data(image: &Rgba32FImage) -> Vec<u8> {
+    image
+        .as_raw()
+        .iter()
+        .flat_map(|value| half_float_bits(*value).to_le_bytes())
+        .collect()
+}
+
+/// Loads a sky texture as linear color. HDRs already are, other images are taken to be sRGB.
+fn load_sky_image(textures: &[File], id: Option<&String>) -> Option<Rgba32FImage> {
+    let file = textures.iter().find(|t| Some(&t.id) == id)?;
+
+    let image = match image::open(&file.normalFilePath) {
+        Ok(image) => image,
+        Err(e) => {
+            println!("Couldn't open sky texture {}: {}", file.fileName, e);
+            return None;
+        }
+    };
+
+    let is_hdr = matches!(
+        image,
+        DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+    );
+    let mut image = image.to_rgba32f();
+    if !is_hdr {
+        for pixel in image.pixels_mut() {
+            for channel in &mut pixel.0[..3] {
+                *channel = channel.powf(2.2);
+            }
+        }
+    }
+
+    Some(image)
+}
+
+fn sky_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    label: &str,
+    size: wgpu::Extent3d,
+    data: &[u8],
+) -> wgpu::Texture {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: SKY_FORMAT,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(8 * size.width),
+            rows_per_image: Some(size.height),
+        },
+        size,
+    );
+
+    texture
+}
+
+/// A cube texture of the faces, all at the size of the largest. None when no face loads.
+fn cubemap_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    faces: &[Option<String>],
+    textures: &[File],
+) -> Option<wgpu::Texture> {
+    let images: Vec<Option<Rgba32FImage>> = faces
+        .iter()
+        .map(|id| load_sky_image(textures, id.as_ref()))
+        .collect();
+
+    let size = images
+        .iter()
+        .flatten()
+        .map(|image| image.width().max(image.height()))
+        .max()?
+        .min(MAX_SKY_FACE_SIZE);
+
+    // faces left out are black
+    let mut data = Vec::with_capacity((size * size * 8 * 6) as usize);
+    for image in images {
+        let face = match image {
+            Some(image) if image.dimensions() == (size, size) => image,
+            Some(image) => image::imageops::resize(&image, size, size, FilterType::Triangle),
+            None => Rgba32FImage::from_pixel(size, size, Rgba([0.0, 0.0, 0.0, 1.0])),
+        };
+
+        data.extend(half_float_data(&face));
+    }
+
+    Some(sky_texture(
+        device,
+        queue,
+        "Sky Cubemap Texture",
+        wgpu::Extent3d {
+            width: size,
+            height: size,
+            depth_or_array_layers: 6,
+        },
+        &data,
+    ))
+}
+
+fn panorama_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    id: Option<&String>,
+    textures: &[File],
+) -> Option<wgpu::Texture> {
+    let mut image = load_sky_image(textures, id)?;
+
+    if image.width() > MAX_SKY_PANORAMA_WIDTH {
+        let height = (image.height() as u64 * MAX_SKY_PANORAMA_WIDTH as u64 / image.width() as u64)
+            .max(1) as u32;
+        image =
+            image::imageops::resize(&image, MAX_SKY_PANORAMA_WIDTH, height, FilterType::Triangle);
+    }
+
+    Some(sky_texture(
+        device,
+        queue,
+        "Sky Panorama Texture",
+        wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth_or_array_layers: 1,
+        },
+        &half_float_data(&image),
+    ))
+}
+
+fn black_sky_texture(device: &wgpu::Device, queue: &wgpu::Queue, layers: u32) -> wgpu::Texture {
+    let pixel: Vec<u8> = [0.0, 0.0, 0.0, 1.0]
+        .iter()
+        .flat_map(|value| half_float_bits(*value).to_le_bytes())
+        .collect();
+    let data = pixel.repeat(layers as usize);
+
+    sky_texture(
+        device,
+        queue,
+        "Sky Stand-in Texture",
+        wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: layers,
+        },
+        &data,
+    )
+}
+
+/// Loads the environment's sky textures, None without a sky or when none of them load
+fn create_sky_binding(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    sky_bind_group_layout: &wgpu::BindGroupLayout,
+    sky_buffer: &wgpu::Buffer,
+    environment: &EnvironmentSettings,
+    textures: &[File],
+) -> Option<SkyBinding> {
+    let (cubemap, panorama) = match environment.sky {
+        SkyKind::None => return None,
+        SkyKind::Cubemap => (
+            cubemap_texture(device, queue, &environment.sky_faces, textures)?,
+            black_sky_texture(device, queue, 1),
+        ),
+        SkyKind::Equirectangular => (
+            black_sky_texture(device, queue, 6),
+            panorama_texture(device, queue, environment.sky_panorama.as_ref(), textures)?,
+        ),
+    };
+
+    let cubemap_view = cubemap.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::Cube),
+        ..Default::default()
+    });
+    let panorama_view = panorama.create_view(&wgpu::TextureViewDescriptor::default());
+    // panoramas wrap around horizontally
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: sky_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: sky_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&cubemap_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&panorama_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+        ],
+        label: Some("Sky Bind Group"),
+    });
+
+    let texture_bytes = [&cubemap, &panorama]
+        .iter()
+        .map(|texture| {
+            let size = texture.size();
+            size.width as u64 * size.height as u64 * size.depth_or_array_layers as u64 * 8
+        })
+        .sum();
+
+    Some(SkyBinding {
+        bind_group,
+        kind: environment.sky,
+        texture_ids: environment.sky_texture_ids(),
+        texture_bytes,
+    })
+}
+
+/// Hands the environment to the render state. Sky textures are only loaded again when a
+/// different sky is picked, other edits apply through the uniforms.
+pub fn sync_environment(
+    editor_render: &mut EditorRenderState,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    environment: &EnvironmentSettings,
+    textures: &[File],
+) {
+    let unchanged = editor_render.sky_binding.as_ref().is_some_and(|binding| {
+        binding.kind == environment.sky && binding.texture_ids == environment.sky_texture_ids()
+    });
+
+    if !unchanged {
+        editor_render.sky_binding = match (
+            editor_render.sky_bind_group_layout.as_ref(),
+            editor_render.sky_buffer.as_ref(),
+        ) {
+            (Some(layout), Some(sky_buffer)) => {
+                create_sky_binding(device, queue, layout, sky_buffer, environment, textures)
+            }
+            _ => None,
+        };
+    }
+
+    editor_render.environment = environment.clone();
+}
+
+/// Environment of the active level, defaults when nothing was saved yet
+pub fn level_environment(state_helper: &StateHelper) -> EnvironmentSettings {
+    let (Some(level_id), Some(editor_data)) = (
+        active_level_id(state_helper),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return EnvironmentSettings::default();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.environment.clone())
+        .unwrap_or_default()
+}
+
+/// Applies a change to the active level's environment and saves it
+pub fn update_level_environment(
+    state_helper: &StateHelper,
+    edit: impl FnOnce(&mut EnvironmentSettings),
+) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    edit(
+        &mut state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap()
+            .level_mut(&level_id)
+            .environment,
+    );
+
+    state_helper.save_current_editor_data();
+}
+
+/// Loads the active level's sky and hands its fog and colors to the render callback
+pub fn refresh_level_environment(
+    state_helper: &StateHelper,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) {
+    let Some(editor_render) = state_helper.editor_render.as_ref() else {
+        return;
+    };
+
+    let environment = level_environment(state_helper);
+    let textures = state_helper
+        .saved_state
+        .as_ref()
+        .and_then(|saved_state| saved_state.lock().unwrap().textures.clone())
+        .unwrap_or_default();
+
+    sync_environment(
+        &mut editor_render.lock().unwrap(),
+        device,
+        queue,
+        &environment,
+        &textures,
+    );
+}
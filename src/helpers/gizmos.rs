@@ -0,0 +1,1148 @@
+use std::str::FromStr;
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::floem::reactive::{SignalGet, SignalUpdate};
+use midpoint_engine::helpers::saved_data::{ComponentData, ComponentKind};
+use nalgebra::{Rotation3, Unit, Vector3};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::editor_render::ColorVertex;
+use super::levels::active_level_mut;
+use super::lights::save_light_transforms;
+use super::selection::{
+    selection_pivot, selection_rotation, PivotMode, TransformSettings, TransformSpace,
+};
+use super::snapping::{snap_value, SnapSettings};
+
+/// Radius of the engine's rotation rings at a gizmo scale of 1.0
+pub const ROTATION_RING_RADIUS: f32 = 1.0;
+/// How far off a ring the cursor can be and still grab it, relative to the radius
+const ROTATION_RING_TOLERANCE: f32 = 0.15;
+/// Distance of the per-axis scale handles from the gizmo center at a gizmo scale of 1.0
+pub const SCALE_HANDLE_DISTANCE: f32 = 1.0;
+/// Pick radius around each scale handle, and around the center for uniform scaling
+const SCALE_HANDLE_RADIUS: f32 = 0.15;
+/// Length of the engine's translation arrows at a gizmo scale of 1.0
+const TRANSLATE_ARROW_LENGTH: f32 = 1.0;
+/// Pick radius around the arrows when they are turned away from their colliders
+const TRANSLATE_ARROW_RADIUS: f32 = 0.1;
+/// Scales never collapse to zero or flip through it
+const MIN_SCALE: f32 = 0.01;
+/// Where the plane handles sit between the arrows, along both of their axes
+const PLANE_HANDLE_RANGE: [f32; 2] = [0.25, 0.5];
+/// Half the size of the free-move cube at the gizmo center
+const FREE_HANDLE_SIZE: f32 = 0.08;
+
+const AXIS_COLORS: [[f32; 3]; 3] = [[0.9, 0.2, 0.2], [0.2, 0.8, 0.2], [0.2, 0.4, 0.9]];
+const FREE_HANDLE_COLOR: [f32; 3] = [0.9, 0.9, 0.9];
+
+/// What a translation drag is allowed to move along
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TranslateConstraint {
+    Axis(usize),
+    /// The plane with this axis as its normal
+    Plane(usize),
+    /// The plane facing the camera
+    Free,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScaleHandle {
+    Axis(usize),
+    Uniform,
+}
+
+/// The handle being dragged, measured in gizmo space where the gizmo axes are x, y and z
+#[derive(Clone, Debug)]
+pub enum GizmoDrag {
+    Translate {
+        constraint: TranslateConstraint,
+        /// Where the ray first met the constraint, so the selection keeps its offset to the cursor
+        grab_point: Vector3<f32>,
+    },
+    Rotate {
+        axis: usize,
+        start_angle: f32,
+    },
+    Scale {
+        handle: ScaleHandle,
+        /// Position along the axis, or up the screen for uniform scaling
+        start_distance: f32,
+        handle_distance: f32,
+    },
+}
+
+/// A gizmo drag on the selection, everything is relative to where it started
+#[derive(Clone, Debug)]
+pub struct SelectionDrag {
+    pub drag: GizmoDrag,
+    pub pivot: Vector3<f32>,
+    /// Turns gizmo space into world space, identity unless working in local space
+    pub frame: Rotation3<f32>,
+    pub settings: TransformSettings,
+    /// The selection as it was when the drag started, active component last
+    pub components: Vec<ComponentData>,
+    /// The selection as of the last ray, saved when the drag ends
+    pub moved: Vec<ComponentData>,
+    /// Children of selected components with the index of the one they follow, as they started
+    pub followers: Vec<(usize, ComponentData)>,
+    /// The followers as of the last ray
+    pub followers_moved: Vec<ComponentData>,
+}
+
+impl SelectionDrag {
+    fn new(
+        drag: GizmoDrag,
+        pivot: Vector3<f32>,
+        frame: Rotation3<f32>,
+        settings: TransformSettings,
+        components: Vec<ComponentData>,
+    ) -> Self {
+        Self {
+            drag,
+            pivot,
+            frame,
+            settings,
+            moved: components.clone(),
+            components,
+            followers: Vec::new(),
+            followers_moved: Vec::new(),
+        }
+    }
+
+    /// Brings along the children of the selected components
+    pub fn with_followers(mut self, followers: Vec<(usize, ComponentData)>) -> Self {
+        self.followers_moved = followers.iter().map(|(_, c)| c.clone()).collect();
+        self.followers = followers;
+        self
+    }
+}
+
+/// Pivot and axes of the gizmo for the selection
+fn selection_frame(
+    components: &[ComponentData],
+    settings: TransformSettings,
+) -> (Vector3<f32>, Rotation3<f32>) {
+    let pivot = Vector3::from(selection_pivot(components, settings.pivot));
+    let [roll, pitch, yaw] = selection_rotation(components, settings.space);
+
+    (pivot, Rotation3::from_euler_angles(roll, pitch, yaw))
+}
+
+/// The ray and camera axes in gizmo space, so picking and dragging can work on plain axes
+fn to_gizmo_space(
+    pivot: Vector3<f32>,
+    frame: &Rotation3<f32>,
+    vectors: [Vector3<f32>; 4],
+) -> [Vector3<f32>; 4] {
+    let inverse = frame.inverse();
+    let [origin, direction, camera_forward, camera_up] = vectors;
+
+    [
+        pivot + inverse * (origin - pivot),
+        inverse * direction,
+        inverse * camera_forward,
+        inverse * camera_up,
+    ]
+}
+
+pub fn axis_vector(axis: usize) -> Vector3<f32> {
+    match axis {
+        0 => Vector3::x(),
+        1 => Vector3::y(),
+        _ => Vector3::z(),
+    }
+}
+
+/// Where a ray meets a plane, along with the distance along the ray
+pub fn ray_plane_intersection(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    plane_point: Vector3<f32>,
+    plane_normal: Vector3<f32>,
+) -> Option<(f32, Vector3<f32>)> {
+    let denom = direction.dot(&plane_normal);
+    if denom.abs() < 1e-6 {
+        return None;
+    }
+
+    let t = (plane_point - origin).dot(&plane_normal) / denom;
+    if t < 0.0 {
+        return None;
+    }
+
+    Some((t, origin + direction * t))
+}
+
+/// Angle of a point around an axis through the center, measured in the plane of the axis
+pub fn angle_around_axis(point: Vector3<f32>, center: Vector3<f32>, axis: usize) -> f32 {
+    let offset = point - center;
+    let (u, v) = match axis {
+        0 => (offset.y, offset.z),
+        1 => (offset.z, offset.x),
+        _ => (offset.x, offset.y),
+    };
+
+    v.atan2(u)
+}
+
+/// Closest approach between a ray and an axis line through the center.
+/// Returns the position along the axis and the gap between the two.
+pub fn closest_on_axis(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    center: Vector3<f32>,
+    axis: usize,
+) -> Option<(f32, f32)> {
+    let axis_dir = axis_vector(axis);
+    let w0 = origin - center;
+
+    let a = direction.dot(&direction);
+    let b = direction.dot(&axis_dir);
+    let d = direction.dot(&w0);
+    let e = axis_dir.dot(&w0);
+
+    let denom = a - b * b;
+    if denom.abs() < 1e-6 {
+        // looking straight down the axis
+        return None;
+    }
+
+    let ray_t = (b * e - d) / denom;
+    let axis_t = (a * e - b * d) / denom;
+
+    let ray_point = origin + direction * ray_t.max(0.0);
+    let axis_point = center + axis_dir * axis_t;
+
+    Some((axis_t, (ray_point - axis_point).norm()))
+}
+
+/// The ring under the ray, nearest first when several line up
+pub fn pick_rotation_ring(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    center: Vector3<f32>,
+    radius: f32,
+) -> Option<usize> {
+    (0..3)
+        .filter_map(|axis| {
+            let (t, hit) = ray_plane_intersection(origin, direction, center, axis_vector(axis))?;
+            let off_ring = ((hit - center).norm() - radius).abs();
+
+            (off_ring <= radius * ROTATION_RING_TOLERANCE).then_some((t, axis))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, axis)| axis)
+}
+
+/// The scale handle under the ray, the center cube taking priority for uniform scaling
+pub fn pick_scale_handle(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    center: Vector3<f32>,
+    handle_distance: f32,
+) -> Option<ScaleHandle> {
+    let pick_radius = handle_distance * SCALE_HANDLE_RADIUS;
+
+    let to_center = center - origin;
+    let along = to_center.dot(&direction) / direction.norm_squared();
+    if along > 0.0 && (to_center - direction * along).norm() <= pick_radius {
+        return Some(ScaleHandle::Uniform);
+    }
+
+    (0..3)
+        .filter_map(|axis| {
+            let (axis_t, gap) = closest_on_axis(origin, direction, center, axis)?;
+
+            ((axis_t - handle_distance).abs() <= pick_radius && gap <= pick_radius)
+                .then_some((gap, axis))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, axis)| ScaleHandle::Axis(axis))
+}
+
+/// The arrow under the ray, for when the gizmo no longer lines up with the arrow colliders
+pub fn pick_translate_arrow(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    center: Vector3<f32>,
+    gizmo_scale: f32,
+) -> Option<usize> {
+    let length = TRANSLATE_ARROW_LENGTH * gizmo_scale;
+    let pick_radius = TRANSLATE_ARROW_RADIUS * gizmo_scale;
+
+    (0..3)
+        .filter_map(|axis| {
+            let (axis_t, gap) = closest_on_axis(origin, direction, center, axis)?;
+
+            ((0.0..=length).contains(&axis_t) && gap <= pick_radius).then_some((gap, axis))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, axis)| axis)
+}
+
+/// The two axes spanning the plane with the given normal
+fn plane_axes(normal_axis: usize) -> (usize, usize) {
+    match normal_axis {
+        0 => (1, 2),
+        1 => (0, 2),
+        _ => (0, 1),
+    }
+}
+
+/// The free-move cube or plane handle under the ray, arrows are picked by their colliders
+pub fn pick_translate_handle(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    center: Vector3<f32>,
+    gizmo_scale: f32,
+) -> Option<TranslateConstraint> {
+    let to_center = center - origin;
+    let along = to_center.dot(&direction) / direction.norm_squared();
+    if along > 0.0 && (to_center - direction * along).norm() <= FREE_HANDLE_SIZE * gizmo_scale * 1.5
+    {
+        return Some(TranslateConstraint::Free);
+    }
+
+    let [min, max] = PLANE_HANDLE_RANGE.map(|r| r * gizmo_scale);
+
+    (0..3)
+        .filter_map(|normal_axis| {
+            let (t, hit) =
+                ray_plane_intersection(origin, direction, center, axis_vector(normal_axis))?;
+            let offset = hit - center;
+            let (u, v) = plane_axes(normal_axis);
+
+            let inside = (min..=max).contains(&offset[u]) && (min..=max).contains(&offset[v]);
+            inside.then_some((t, normal_axis))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, normal_axis)| TranslateConstraint::Plane(normal_axis))
+}
+
+/// Where the ray meets the constraint through the center
+pub fn translate_point(
+    constraint: TranslateConstraint,
+    center: Vector3<f32>,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    camera_forward: Vector3<f32>,
+) -> Option<Vector3<f32>> {
+    match constraint {
+        TranslateConstraint::Axis(axis) => closest_on_axis(origin, direction, center, axis)
+            .map(|(t, _)| center + axis_vector(axis) * t),
+        TranslateConstraint::Plane(normal_axis) => {
+            ray_plane_intersection(origin, direction, center, axis_vector(normal_axis))
+                .map(|(_, hit)| hit)
+        }
+        TranslateConstraint::Free => {
+            ray_plane_intersection(origin, direction, center, camera_forward).map(|(_, hit)| hit)
+        }
+    }
+}
+
+/// Starts a translation along a constraint picked elsewhere, like the arrow colliders
+pub fn start_translate_drag(
+    constraint: TranslateConstraint,
+    components: Vec<ComponentData>,
+    settings: TransformSettings,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    camera_forward: Vector3<f32>,
+) -> Option<SelectionDrag> {
+    let (pivot, frame) = selection_frame(&components, settings);
+    let [origin, direction, camera_forward, _] = to_gizmo_space(
+        pivot,
+        &frame,
+        [origin, direction, camera_forward, Vector3::zeros()],
+    );
+
+    let grab_point = translate_point(constraint, pivot, origin, direction, camera_forward)?;
+
+    Some(SelectionDrag::new(
+        GizmoDrag::Translate {
+            constraint,
+            grab_point,
+        },
+        pivot,
+        frame,
+        settings,
+        components,
+    ))
+}
+
+pub fn snap_angle(angle: f32, step_degrees: f32) -> f32 {
+    let step = step_degrees.to_radians();
+    (angle / step).round() * step
+}
+
+/// Applies a world rotation on top of euler angles
+pub fn rotate_euler(rotation: [f32; 3], turn: &Rotation3<f32>) -> [f32; 3] {
+    let current = Rotation3::from_euler_angles(rotation[0], rotation[1], rotation[2]);
+    let (roll, pitch, yaw) = (turn * current).euler_angles();
+
+    [roll, pitch, yaw]
+}
+
+pub fn scale_by(scale: [f32; 3], handle: ScaleHandle, factor: f32) -> [f32; 3] {
+    let mut scaled = scale;
+
+    match handle {
+        ScaleHandle::Axis(axis) => scaled[axis] *= factor,
+        ScaleHandle::Uniform => scaled.iter_mut().for_each(|s| *s *= factor),
+    }
+
+    scaled.map(|s| s.max(MIN_SCALE))
+}
+
+/// How much of a world direction falls on each of a component's own axes
+fn local_axis_weights(rotation: [f32; 3], direction: Vector3<f32>) -> Vector3<f32> {
+    let turned = Rotation3::from_euler_angles(rotation[0], rotation[1], rotation[2]);
+
+    (turned.inverse() * direction.normalize()).abs()
+}
+
+/// Scales along a world direction. Scale is kept per local axis, so each local axis takes the
+/// factor by how closely it lines up with the direction, which is exact when they line up
+pub fn scale_along(
+    scale: [f32; 3],
+    rotation: [f32; 3],
+    direction: Vector3<f32>,
+    factor: f32,
+) -> [f32; 3] {
+    let weights = local_axis_weights(rotation, direction);
+    let mut scaled = scale;
+
+    for (axis, s) in scaled.iter_mut().enumerate() {
+        *s *= 1.0 + (factor - 1.0) * weights[axis];
+    }
+
+    scaled.map(|s| s.max(MIN_SCALE))
+}
+
+/// Places every gizmo on the selection and turns them to its axes. The arrow colliders only
+/// follow the position, which is why local space picks the arrows itself.
+pub fn place_gizmos(renderer_state: &mut RendererState, position: [f32; 3], rotation: [f32; 3]) {
+    move_gizmos(renderer_state, position);
+
+    renderer_state
+        .translation_gizmo
+        .transform
+        .update_rotation(rotation);
+    renderer_state
+        .rotation_gizmo
+        .transform
+        .update_rotation(rotation);
+    renderer_state
+        .scale_gizmo
+        .transform
+        .update_rotation(rotation);
+}
+
+/// Places every gizmo on the selected object
+pub fn move_gizmos(renderer_state: &mut RendererState, position: [f32; 3]) {
+    renderer_state
+        .translation_gizmo
+        .transform
+        .update_position(position);
+    renderer_state
+        .rotation_gizmo
+        .transform
+        .update_position(position);
+    renderer_state
+        .scale_gizmo
+        .transform
+        .update_position(position);
+
+    renderer_state.update_arrow_collider_position(position);
+}
+
+/// A component's position, rotation and scale
+pub fn component_transform(component: &ComponentData) -> [[f32; 3]; 3] {
+    let properties = &component.generic_properties;
+
+    [properties.position, properties.rotation, properties.scale]
+}
+
+/// The component with a new position, rotation and scale
+pub fn with_transform(component: &ComponentData, transform: [[f32; 3]; 3]) -> ComponentData {
+    let [position, rotation, scale] = transform;
+
+    let mut component_data = component.clone();
+    component_data.generic_properties.position = position;
+    component_data.generic_properties.rotation = rotation;
+    component_data.generic_properties.scale = scale;
+
+    component_data
+}
+
+/// Sets a component's position, rotation and scale on its meshes or terrain, its colliders and,
+/// for the active component, the selected object data
+pub fn apply_component_transform(
+    renderer_state: &mut RendererState,
+    component: &ComponentData,
+    transform: [[f32; 3]; 3],
+) {
+    let [position, rotation, scale] = transform;
+
+    let active = renderer_state.object_selected;
+    let is_active = active.map(|id| id.to_string()) == Some(component.id.clone());
+
+    if is_active {
+        renderer_state.object_selected_data = Some(with_transform(component, transform));
+    }
+
+    // the collider helpers act on object_selected, so point it at this component meanwhile
+    renderer_state.object_selected = Uuid::from_str(&component.id).ok();
+
+    match component.kind.as_ref() {
+        Some(ComponentKind::Model) => {
+            let Some(model) = renderer_state
+                .models
+                .iter_mut()
+                .find(|m| m.id == component.id)
+            else {
+                return;
+            };
+
+            model.meshes.iter_mut().for_each(|mesh| {
+                mesh.transform.update_position(position);
+                mesh.transform.update_rotation(rotation);
+                mesh.transform.update_scale(scale);
+            });
+
+            renderer_state.update_model_collider_position(position);
+            renderer_state.update_model_collider_rotation(rotation);
+            renderer_state.update_model_collider_scale(scale);
+        }
+        Some(ComponentKind::Landscape) => {
+            let Some(terrain_manager) = renderer_state
+                .terrain_managers
+                .iter_mut()
+                .find(|t| t.id == component.id)
+            else {
+                return;
+            };
+
+            terrain_manager.transform.update_position(position);
+            terrain_manager.transform.update_rotation(rotation);
+            terrain_manager.transform.update_scale(scale);
+
+            renderer_state.update_landscape_collider_position(position);
+            renderer_state.update_landscape_collider_rotation(rotation);
+            renderer_state.update_landscape_collider_scale(scale);
+        }
+        // lights are drawn by the editor, which follows them on its own
+        None => {}
+    }
+
+    renderer_state.object_selected = active;
+}
+
+/// How far up the screen the ray passes the center, for uniform scaling
+fn screen_up_offset(
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    center: Vector3<f32>,
+    camera_forward: Vector3<f32>,
+    camera_up: Vector3<f32>,
+) -> Option<f32> {
+    let (_, hit) = ray_plane_intersection(origin, direction, center, camera_forward)?;
+    Some((hit - center).dot(&camera_up))
+}
+
+/// Starts a drag if the ray grabs a handle, ring or cube of the active gizmo
+pub fn start_gizmo_drag(
+    renderer_state: &RendererState,
+    components: Vec<ComponentData>,
+    settings: TransformSettings,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    camera_forward: Vector3<f32>,
+    camera_up: Vector3<f32>,
+) -> Option<SelectionDrag> {
+    let (center, frame) = selection_frame(&components, settings);
+    let [origin, direction, camera_forward, camera_up] = to_gizmo_space(
+        center,
+        &frame,
+        [origin, direction, camera_forward, camera_up],
+    );
+
+    let drag = match renderer_state.active_gizmo.as_str() {
+        "translate" => {
+            let gizmo_scale = renderer_state.translation_gizmo.transform.scale.x;
+            let constraint = pick_translate_handle(origin, direction, center, gizmo_scale)
+                .or_else(|| match settings.space {
+                    TransformSpace::Local => {
+                        pick_translate_arrow(origin, direction, center, gizmo_scale)
+                            .map(TranslateConstraint::Axis)
+                    }
+                    TransformSpace::World => None,
+                })?;
+
+            GizmoDrag::Translate {
+                constraint,
+                grab_point: translate_point(constraint, center, origin, direction, camera_forward)?,
+            }
+        }
+        "rotate" => {
+            let radius = ROTATION_RING_RADIUS * renderer_state.rotation_gizmo.transform.scale.x;
+            let axis = pick_rotation_ring(origin, direction, center, radius)?;
+            let (_, hit) = ray_plane_intersection(origin, direction, center, axis_vector(axis))?;
+
+            GizmoDrag::Rotate {
+                axis,
+                start_angle: angle_around_axis(hit, center, axis),
+            }
+        }
+        "scale" => {
+            let handle_distance =
+                SCALE_HANDLE_DISTANCE * renderer_state.scale_gizmo.transform.scale.x;
+            let handle = pick_scale_handle(origin, direction, center, handle_distance)?;
+
+            let start_distance = match handle {
+                ScaleHandle::Axis(axis) => closest_on_axis(origin, direction, center, axis)?.0,
+                ScaleHandle::Uniform => {
+                    screen_up_offset(origin, direction, center, camera_forward, camera_up)?
+                }
+            };
+
+            GizmoDrag::Scale {
+                handle,
+                start_distance,
+                handle_distance,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(SelectionDrag::new(
+        drag, center, frame, settings, components,
+    ))
+}
+
+/// New transforms for every component in the drag, in the same order, None while the ray can't
+/// be resolved
+pub fn update_gizmo_drag(
+    selection_drag: &SelectionDrag,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    camera_forward: Vector3<f32>,
+    camera_up: Vector3<f32>,
+    snap: Option<&SnapSettings>,
+) -> Option<Vec<[[f32; 3]; 3]>> {
+    let SelectionDrag {
+        drag,
+        pivot,
+        frame,
+        settings,
+        components,
+        ..
+    } = selection_drag;
+    let center = *pivot;
+    let [origin, direction, camera_forward, camera_up] = to_gizmo_space(
+        center,
+        frame,
+        [origin, direction, camera_forward, camera_up],
+    );
+
+    // shared pivots swing the components around, individual origins leave them in place
+    let around_pivot = settings.pivot != PivotMode::IndividualOrigins;
+
+    match drag {
+        GizmoDrag::Translate {
+            constraint,
+            grab_point,
+        } => {
+            let point = translate_point(*constraint, center, origin, direction, camera_forward)?;
+            let mut target = center + (point - grab_point);
+
+            // only the axes being dragged land on the grid, local space snaps the distance moved
+            if let Some(snap) = snap {
+                let moving = match constraint {
+                    TranslateConstraint::Axis(axis) => vec![*axis],
+                    TranslateConstraint::Plane(normal_axis) => {
+                        let (u, v) = plane_axes(*normal_axis);
+                        vec![u, v]
+                    }
+                    TranslateConstraint::Free => vec![0, 1, 2],
+                };
+                let grid_origin = match settings.space {
+                    TransformSpace::World => Vector3::zeros(),
+                    TransformSpace::Local => center,
+                };
+
+                for axis in moving {
+                    target[axis] = grid_origin[axis]
+                        + snap_value(target[axis] - grid_origin[axis], snap.translation_step);
+                }
+            }
+
+            let offset = frame * (target - center);
+
+            Some(
+                components
+                    .iter()
+                    .map(|component| {
+                        let properties = &component.generic_properties;
+                        let position = Vector3::from(properties.position) + offset;
+
+                        [position.into(), properties.rotation, properties.scale]
+                    })
+                    .collect(),
+            )
+        }
+        GizmoDrag::Rotate { axis, start_angle } => {
+            let (_, hit) = ray_plane_intersection(origin, direction, center, axis_vector(*axis))?;
+
+            let mut angle = angle_around_axis(hit, center, *axis) - start_angle;
+            if let Some(snap) = snap {
+                angle = snap_angle(angle, snap.rotation_step_degrees);
+            }
+
+            let turn =
+                Rotation3::from_axis_angle(&Unit::new_normalize(frame * axis_vector(*axis)), angle);
+
+            Some(
+                components
+                    .iter()
+                    .map(|component| {
+                        let properties = &component.generic_properties;
+                        let mut position = Vector3::from(properties.position);
+                        if around_pivot {
+                            position = center + turn * (position - center);
+                        }
+
+                        [
+                            position.into(),
+                            rotate_euler(properties.rotation, &turn),
+                            properties.scale,
+                        ]
+                    })
+                    .collect(),
+            )
+        }
+        GizmoDrag::Scale {
+            handle,
+            start_distance,
+            handle_distance,
+        } => {
+            let mut factor = match handle {
+                ScaleHandle::Axis(axis) => {
+                    if start_distance.abs() < 1e-6 {
+                        return None;
+                    }
+
+                    closest_on_axis(origin, direction, center, *axis)?.0 / start_distance
+                }
+                // dragging up grows and dragging down shrinks, one handle length per doubling
+                ScaleHandle::Uniform => {
+                    let offset =
+                        screen_up_offset(origin, direction, center, camera_forward, camera_up)?;
+                    1.0 + (offset - start_distance) / handle_distance
+                }
+            };
+
+            // uniform steps go on the factor so the proportions survive, axis steps land the
+            // active component's scale on the grid and the rest follow by the same factor
+            match (handle, snap, components.last()) {
+                (ScaleHandle::Uniform, Some(snap), _) => {
+                    factor = snap_value(factor, snap.scale_step);
+                }
+                (ScaleHandle::Axis(axis), Some(snap), Some(active)) => {
+                    // the active component's own axis closest to the handle is the one snapped
+                    let properties = &active.generic_properties;
+                    let weights =
+                        local_axis_weights(properties.rotation, frame * axis_vector(*axis));
+                    let start = properties.scale[weights.imax()].max(MIN_SCALE);
+                    let snapped = snap_value(start * factor, snap.scale_step).max(MIN_SCALE);
+                    factor = snapped / start;
+                }
+                _ => {}
+            }
+
+            let stretch = |offset: Vector3<f32>| {
+                let mut local = frame.inverse() * offset;
+                match handle {
+                    ScaleHandle::Axis(axis) => local[*axis] *= factor,
+                    ScaleHandle::Uniform => local *= factor,
+                }
+                frame * local
+            };
+
+            Some(
+                components
+                    .iter()
+                    .map(|component| {
+                        let properties = &component.generic_properties;
+                        let mut position = Vector3::from(properties.position);
+                        if around_pivot {
+                            position = center + stretch(position - center);
+                        }
+
+                        // a world axis handle is not the component's own axis unless they line up
+                        let scale = match handle {
+                            ScaleHandle::Axis(axis) => scale_along(
+                                properties.scale,
+                                properties.rotation,
+                                frame * axis_vector(*axis),
+                                factor,
+                            ),
+                            ScaleHandle::Uniform => scale_by(properties.scale, *handle, factor),
+                        };
+
+                        [position.into(), properties.rotation, scale]
+                    })
+                    .collect(),
+            )
+        }
+    }
+}
+
+/// Plane handle quads for YZ, XZ and XY, then the free-move cube, all around the origin
+pub fn translate_handle_meshes() -> Vec<(Vec<ColorVertex>, Vec<u32>)> {
+    let [min, max] = PLANE_HANDLE_RANGE;
+    let mut meshes = Vec::new();
+
+    for normal_axis in 0..3 {
+        let (u, v) = plane_axes(normal_axis);
+        let mut normal = [0.0; 3];
+        normal[normal_axis] = 1.0;
+
+        let vertices = [(min, min), (max, min), (max, max), (min, max)]
+            .iter()
+            .map(|(a, b)| {
+                let mut position = [0.0; 3];
+                position[u] = *a;
+                position[v] = *b;
+
+                ColorVertex {
+                    position,
+                    normal,
+                    tex_coords: [0.0, 0.0],
+                    color: AXIS_COLORS[normal_axis],
+                }
+            })
+            .collect();
+
+        // both windings so the handle shows from either side
+        meshes.push((vertices, vec![0, 1, 2, 0, 2, 3, 0, 2, 1, 0, 3, 2]));
+    }
+
+    meshes.push(cube_mesh(FREE_HANDLE_SIZE, FREE_HANDLE_COLOR));
+
+    meshes
+}
+
+fn cube_mesh(half_size: f32, color: [f32; 3]) -> (Vec<ColorVertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for axis in 0..3 {
+        let (u, v) = plane_axes(axis);
+
+        for side in [-1.0, 1.0] {
+            let base = vertices.len() as u32;
+            let mut normal = [0.0; 3];
+            normal[axis] = side;
+
+            for (a, b) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let mut position = [0.0; 3];
+                position[axis] = side * half_size;
+                position[u] = a * half_size;
+                position[v] = b * half_size;
+
+                vertices.push(ColorVertex {
+                    position,
+                    normal,
+                    tex_coords: [0.0, 0.0],
+                    color,
+                });
+            }
+
+            indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+            indices.extend([base, base + 2, base + 1, base, base + 3, base + 2]);
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Writes the components' transforms into the active level and saves the project once
+pub fn save_component_transforms(state_helper: &StateHelper, components: &[ComponentData]) {
+    // lights live in the editor data rather than the saved state
+    save_light_transforms(state_helper, components);
+
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+
+    let mut saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    let Some(saved_components) =
+        active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+            .and_then(|level| level.components.as_mut())
+    else {
+        return;
+    };
+
+    for component in components {
+        if let Some(saved_component) = saved_components.iter_mut().find(|c| c.id == component.id) {
+            saved_component.generic_properties.position = component.generic_properties.position;
+            saved_component.generic_properties.rotation = component.generic_properties.rotation;
+            saved_component.generic_properties.scale = component.generic_properties.scale;
+        }
+    }
+
+    state_helper.save_saved_state(project_id, saved_state);
+
+    // the properties panel follows the active component
+    if let (Some(selected_object_data_signal), Some(active)) =
+        (state_helper.selected_object_data_signal, components.last())
+    {
+        selected_object_data_signal.set(active.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>) {
+        assert!(
+            (actual - expected).norm() < 1e-5,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn ray_plane_intersection_hits_in_front_only() {
+        let down = -Vector3::y();
+
+        let (t, hit) = ray_plane_intersection(
+            Vector3::new(1.0, 5.0, 2.0),
+            down,
+            Vector3::zeros(),
+            Vector3::y(),
+        )
+        .unwrap();
+        assert_eq!(t, 5.0);
+        assert_close(hit, Vector3::new(1.0, 0.0, 2.0));
+
+        // pointing away from the plane
+        assert!(ray_plane_intersection(
+            Vector3::new(0.0, 5.0, 0.0),
+            Vector3::y(),
+            Vector3::zeros(),
+            Vector3::y()
+        )
+        .is_none());
+        // running along it
+        assert!(ray_plane_intersection(
+            Vector3::new(0.0, 5.0, 0.0),
+            Vector3::x(),
+            Vector3::zeros(),
+            Vector3::y()
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn closest_on_axis_measures_along_and_across() {
+        let (along, gap) = closest_on_axis(
+            Vector3::new(2.0, 1.0, 5.0),
+            -Vector3::z(),
+            Vector3::zeros(),
+            0,
+        )
+        .unwrap();
+
+        assert!((along - 2.0).abs() < 1e-5);
+        assert!((gap - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn closest_on_axis_gives_up_looking_down_the_axis() {
+        assert!(closest_on_axis(
+            Vector3::new(5.0, 0.0, 0.0),
+            -Vector3::x(),
+            Vector3::zeros(),
+            0
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn angle_around_axis_turns_from_the_first_plane_axis() {
+        let center = Vector3::zeros();
+
+        assert!(angle_around_axis(Vector3::new(0.0, 0.0, 1.0), center, 1).abs() < 1e-6);
+        assert!(
+            (angle_around_axis(Vector3::new(1.0, 0.0, 0.0), center, 1)
+                - std::f32::consts::FRAC_PI_2)
+                .abs()
+                < 1e-6
+        );
+    }
+
+    #[test]
+    fn pick_rotation_ring_needs_the_ray_near_the_ring() {
+        let down = -Vector3::y();
+
+        assert_eq!(
+            pick_rotation_ring(Vector3::new(1.0, 10.0, 0.0), down, Vector3::zeros(), 1.0),
+            Some(1)
+        );
+        assert_eq!(
+            pick_rotation_ring(Vector3::new(0.5, 10.0, 0.0), down, Vector3::zeros(), 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn pick_scale_handle_prefers_the_center_for_uniform_scaling() {
+        let forward = -Vector3::z();
+
+        assert_eq!(
+            pick_scale_handle(Vector3::new(0.0, 0.0, 10.0), forward, Vector3::zeros(), 1.0),
+            Some(ScaleHandle::Uniform)
+        );
+        assert_eq!(
+            pick_scale_handle(Vector3::new(1.0, 0.0, 10.0), forward, Vector3::zeros(), 1.0),
+            Some(ScaleHandle::Axis(0))
+        );
+        assert_eq!(
+            pick_scale_handle(Vector3::new(0.5, 0.5, 10.0), forward, Vector3::zeros(), 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn snap_angle_rounds_to_the_step() {
+        let step = 15f32.to_radians();
+
+        assert!((snap_angle(0.3, 15.0) - step).abs() < 1e-6);
+        assert!((snap_angle(-0.3, 15.0) + step).abs() < 1e-6);
+        assert_eq!(snap_angle(0.0, 15.0), 0.0);
+    }
+
+    #[test]
+    fn scale_by_scales_one_axis_or_all_and_never_collapses() {
+        assert_eq!(
+            scale_by([1.0; 3], ScaleHandle::Axis(1), 2.0),
+            [1.0, 2.0, 1.0]
+        );
+        assert_eq!(scale_by([1.0; 3], ScaleHandle::Uniform, 0.5), [0.5; 3]);
+        assert_eq!(
+            scale_by([1.0; 3], ScaleHandle::Uniform, 0.0),
+            [MIN_SCALE; 3]
+        );
+        assert_eq!(
+            scale_by([1.0; 3], ScaleHandle::Axis(0), -1.0),
+            [MIN_SCALE, 1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn scale_along_stretches_the_local_axis_that_lines_up() {
+        let quarter_turn = std::f32::consts::FRAC_PI_2;
+
+        // turned a quarter around z, world x runs along the component's own y
+        let scaled = scale_along([1.0; 3], [0.0, 0.0, quarter_turn], Vector3::x(), 2.0);
+        assert!((scaled[0] - 1.0).abs() < 1e-5);
+        assert!((scaled[1] - 2.0).abs() < 1e-5);
+        assert!((scaled[2] - 1.0).abs() < 1e-5);
+
+        assert_eq!(
+            scale_along([1.0; 3], [0.0; 3], Vector3::z(), 3.0),
+            scale_by([1.0; 3], ScaleHandle::Axis(2), 3.0)
+        );
+        assert_eq!(
+            scale_along([1.0; 3], [0.0; 3], Vector3::y(), -1.0),
+            [1.0, MIN_SCALE, 1.0]
+        );
+    }
+
+    #[test]
+    fn translate_point_follows_axis_plane_and_free_constraints() {
+        let center = Vector3::zeros();
+        let forward = -Vector3::z();
+
+        let on_axis = translate_point(
+            TranslateConstraint::Axis(0),
+            center,
+            Vector3::new(3.0, 2.0, 10.0),
+            forward,
+            forward,
+        );
+        assert_close(on_axis.unwrap(), Vector3::new(3.0, 0.0, 0.0));
+
+        let on_plane = translate_point(
+            TranslateConstraint::Plane(1),
+            center,
+            Vector3::new(3.0, 5.0, 2.0),
+            -Vector3::y(),
+            forward,
+        );
+        assert_close(on_plane.unwrap(), Vector3::new(3.0, 0.0, 2.0));
+
+        let free = translate_point(
+            TranslateConstraint::Free,
+            center,
+            Vector3::new(3.0, 2.0, 10.0),
+            forward,
+            forward,
+        );
+        assert_close(free.unwrap(), Vector3::new(3.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn pick_translate_handle_finds_the_free_cube_and_plane_squares() {
+        let down = -Vector3::y();
+        let center = Vector3::zeros();
+
+        assert_eq!(
+            pick_translate_handle(Vector3::new(0.0, 10.0, 0.0), down, center, 1.0),
+            Some(TranslateConstraint::Free)
+        );
+        assert_eq!(
+            pick_translate_handle(Vector3::new(0.4, 10.0, 0.4), down, center, 1.0),
+            Some(TranslateConstraint::Plane(1))
+        );
+        // between the cube and the plane square
+        assert_eq!(
+            pick_translate_handle(Vector3::new(0.1, 10.0, 0.1), down, center, 1.0),
+            None
+        );
+    }
+
+    #[test]
+    fn pick_translate_arrow_stays_within_the_arrow_length() {
+        let forward = -Vector3::z();
+        let center = Vector3::zeros();
+
+        assert_eq!(
+            pick_translate_arrow(Vector3::new(0.5, 0.05, 10.0), forward, center, 1.0),
+            Some(0)
+        );
+        assert_eq!(
+            pick_translate_arrow(Vector3::new(1.5, 0.0, 10.0), forward, center, 1.0),
+            None
+        );
+        // the arrows grow with the gizmo
+        assert_eq!(
+            pick_translate_arrow(Vector3::new(1.5, 0.0, 10.0), forward, center, 2.0),
+            Some(0)
+        );
+    }
+}
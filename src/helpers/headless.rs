@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::core::Viewport::Viewport;
+use midpoint_engine::handlers::get_camera;
+use midpoint_engine::helpers::saved_data::ComponentKind;
+use midpoint_engine::helpers::utilities::load_project_state;
+use nalgebra::Vector3;
+
+use crate::editor_state::StateHelper;
+
+use super::camera::{
+    components_bounds, current_view_projection, framed_view_projection, go_to_camera_bookmark,
+    level_camera_bookmarks,
+};
+use super::component_edits::load_component;
+use super::editor_data::load_editor_data;
+use super::editor_render::EditorRenderState;
+use super::environment::refresh_level_environment;
+use super::hierarchy::refresh_hidden_components;
+use super::instancing::register_model_asset;
+use super::levels::active_level_components;
+use super::lights::refresh_level_lights;
+use super::materials::refresh_level_materials;
+use super::offscreen::{request_headless_device, save_png};
+use super::scene_pipeline::{SceneBindings, SceneLayouts};
+use super::water::refresh_level_water;
+
+/// Levels without a bookmark are looked at from the front right and above
+const LEVEL_DIRECTION: [f32; 3] = [1.0, 1.0, 1.0];
+
+/// A screenshot asked for on the command line, taken without opening the editor window
+///
+/// `midpoint-editor --screenshot <project id> <output.png> [--level <level id>]
+/// [--bookmark <name>] [--size <width>x<height>] [--software]`
+#[derive(Clone, Debug)]
+pub struct ScreenshotArgs {
+    pub project_id: String,
+    pub output: PathBuf,
+    /// The level the project was last saved with when not given
+    pub level_id: Option<String>,
+    /// Camera bookmark to shoot from, otherwise the whole level is framed
+    pub bookmark: Option<String>,
+    pub size: [u32; 2],
+    /// Skips looking for a GPU, for machines without one
+    pub software: bool,
+}
+
+fn parse_size(value: &str) -> Option<[u32; 2]> {
+    let (width, height) = value.split_once('x')?;
+
+    Some([width.parse().ok()?, height.parse().ok()?])
+}
+
+/// None when the arguments don't ask for a screenshot
+pub fn parse_screenshot_args(args: &[String]) -> Option<Result<ScreenshotArgs, String>> {
+    let start = args.iter().position(|arg| arg == "--screenshot")?;
+    let mut args = args[start + 1..].iter();
+
+    let (Some(project_id), Some(output)) = (args.next(), args.next()) else {
+        return Some(Err(
+            "Usage: --screenshot <project id> <output.png> [--level <level id>] \
+             [--bookmark <name>] [--size <width>x<height>] [--software]"
+                .to_string(),
+        ));
+    };
+
+    let mut screenshot = ScreenshotArgs {
+        project_id: project_id.clone(),
+        output: PathBuf::from(output),
+        level_id: None,
+        bookmark: None,
+        size: [1920, 1080],
+        software: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("{} needs a value", arg))
+        };
+
+        let parsed = match arg.as_str() {
+            "--level" => value().map(|level_id| screenshot.level_id = Some(level_id)),
+            "--bookmark" => value().map(|bookmark| screenshot.bookmark = Some(bookmark)),
+            "--size" => value().and_then(|size| {
+                parse_size(&size)
+                    .map(|size| screenshot.size = size)
+                    .ok_or_else(|| format!("Couldn't read size {}, expected 1920x1080", size))
+            }),
+            "--software" => {
+                screenshot.software = true;
+                Ok(())
+            }
+            _ => Err(format!("Unknown screenshot option {}", arg)),
+        };
+
+        if let Err(e) = parsed {
+            return Some(Err(e));
+        }
+    }
+
+    Some(Ok(screenshot))
+}
+
+/// Loads the project's level into a renderer of its own, renders it and writes the PNG
+pub async fn run_screenshot(screenshot: ScreenshotArgs) -> Result<PathBuf, String> {
+    let (device, queue) = request_headless_device(screenshot.software).await?;
+    let [width, height] = screenshot.size;
+
+    let camera = get_camera();
+    camera.update_aspect_ratio(width as f32 / height as f32);
+    camera.update_view_projection_matrix();
+
+    let layouts = SceneLayouts::new(&device);
+    let bindings = SceneBindings::new(&device, &layouts, camera.view_projection_matrix.as_slice());
+
+    let viewport = Arc::new(Mutex::new(Viewport::new(width as f32, height as f32)));
+    let renderer_state = RendererState::new(
+        viewport,
+        &device,
+        &queue,
+        layouts.model.clone(),
+        layouts.texture.clone(),
+        bindings.reg_texture_render_mode_buffer.clone(),
+        bindings.texture_render_mode_buffer.clone(),
+        bindings.color_render_mode_buffer.clone(),
+        bindings.camera_uniform_buffer.clone(),
+        bindings.camera_bind_group.clone(),
+        &camera,
+        width,
+        height,
+        layouts.camera.clone(),
+        layouts.light.clone(),
+    )
+    .await;
+    let renderer_state = Arc::new(Mutex::new(renderer_state));
+
+    let mut editor_render = EditorRenderState::default();
+    editor_render.initialize(&device, &queue, &layouts);
+    let editor_render = Arc::new(Mutex::new(editor_render));
+
+    let saved_state = load_project_state(&screenshot.project_id)
+        .map_err(|e| format!("Couldn't load project {}: {:?}", screenshot.project_id, e))?;
+    let editor_data = load_editor_data(&screenshot.project_id)?;
+
+    // just enough of the editor for the level helpers to find their data
+    let mut state_helper = StateHelper::new(String::new());
+    state_helper.active_level_id = screenshot
+        .level_id
+        .clone()
+        .or_else(|| editor_data.active_level_id.clone());
+    state_helper.renderer_state = Some(renderer_state.clone());
+    state_helper.saved_state = Some(Arc::new(Mutex::new(saved_state.clone())));
+    state_helper.editor_data = Some(Arc::new(Mutex::new(editor_data)));
+    state_helper.editor_render = Some(editor_render.clone());
+
+    let components = active_level_components(&state_helper);
+    for component in &components {
+        load_component(
+            &renderer_state,
+            &device,
+            &queue,
+            &screenshot.project_id,
+            &saved_state,
+            component,
+        );
+
+        if matches!(component.kind, Some(ComponentKind::Model)) {
+            register_model_asset(&state_helper, &component.id, &component.asset_id);
+        }
+    }
+
+    refresh_hidden_components(&state_helper);
+    refresh_level_water(&state_helper, &device);
+    refresh_level_lights(&state_helper);
+    refresh_level_materials(&state_helper, &device, &queue);
+    refresh_level_environment(&state_helper, &device, &queue);
+
+    let view_projection = match screenshot.bookmark.as_deref() {
+        Some(name) => {
+            let bookmark = level_camera_bookmarks(&state_helper)
+                .into_iter()
+                .find(|bookmark| bookmark.name == name)
+                .ok_or_else(|| format!("Couldn't find camera bookmark {}", name))?;
+
+            go_to_camera_bookmark(&mut state_helper.editor_camera, &bookmark);
+            current_view_projection(width as f32 / height as f32)
+        }
+        None => {
+            let bounds = components_bounds(&renderer_state.lock().unwrap(), &components)
+                .ok_or("The level has no components to frame")?;
+
+            framed_view_projection(
+                &state_helper.editor_camera,
+                bounds.0,
+                bounds.1,
+                Vector3::from(LEVEL_DIRECTION),
+                width as f32 / height as f32,
+            )
+        }
+    };
+
+    let image = {
+        let mut renderer_state = renderer_state.lock().unwrap();
+        let editor_render = editor_render.lock().unwrap();
+        let offscreen = editor_render
+            .offscreen
+            .as_ref()
+            .ok_or("Offscreen rendering isn't set up")?;
+
+        // terrain tiles only come in as the terrain updates
+        renderer_state.update_terrain_managers(&device, 0.1);
+
+        offscreen.render(
+            &device,
+            &queue,
+            &renderer_state,
+            &editor_render,
+            &view_projection,
+            screenshot.size,
+            None,
+        )?
+    };
+
+    save_png(&image, &screenshot.output)?;
+
+    Ok(screenshot.output)
+}
@@ -0,0 +1,313 @@
+use std::fs;
+use std::path::Path;
+
+use image::{ImageBuffer, Luma};
+use nalgebra as na;
+
+/// Source formats accepted when importing a landscape heightmap
+#[derive(Clone, Debug, PartialEq)]
+pub enum HeightmapFormat {
+    Tiff,
+    Png,
+    Exr,
+    /// Headerless little-endian 16-bit samples, dimensions are provided by the user
+    Raw16 {
+        width: usize,
+        height: usize,
+    },
+    /// ESRI ASCII grid (.asc)
+    AsciiGrid,
+}
+
+/// Extensions offered in the heightmap file dialog
+pub const HEIGHTMAP_EXTENSIONS: [&str; 7] = ["tif", "tiff", "png", "exr", "raw", "r16", "asc"];
+
+/// Settings chosen in the import dialog before the heightmap is committed
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeightmapImportSettings {
+    /// Multiplier applied to the normalized 0..1 heights
+    pub vertical_scale: f32,
+    /// Offset added after scaling, also in normalized 0..1 units
+    pub base_height: f32,
+}
+
+impl Default for HeightmapImportSettings {
+    fn default() -> Self {
+        Self {
+            vertical_scale: 1.0,
+            base_height: 0.0,
+        }
+    }
+}
+
+impl HeightmapImportSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl HeightmapFormat {
+    /// Guesses the format from the file extension, RAW files still need their dimensions
+    pub fn from_path(path: &Path) -> Option<HeightmapFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "tif" | "tiff" => Some(HeightmapFormat::Tiff),
+            "png" => Some(HeightmapFormat::Png),
+            "exr" => Some(HeightmapFormat::Exr),
+            "raw" | "r16" => Some(HeightmapFormat::Raw16 {
+                width: 0,
+                height: 0,
+            }),
+            "asc" => Some(HeightmapFormat::AsciiGrid),
+            _ => None,
+        }
+    }
+
+    pub fn needs_dimensions(&self) -> bool {
+        matches!(self, HeightmapFormat::Raw16 { .. })
+    }
+}
+
+/// Reads a heightmap in its source units, rows being the image height
+pub fn load_heightmap(path: &Path, format: &HeightmapFormat) -> Result<na::DMatrix<f32>, String> {
+    match format {
+        HeightmapFormat::Tiff | HeightmapFormat::Png => {
+            let img = image::open(path).map_err(|e| format!("Couldn't open heightmap: {}", e))?;
+            let luma = img.to_luma16();
+            let (width, height) = luma.dimensions();
+
+            Ok(na::DMatrix::from_fn(
+                height as usize,
+                width as usize,
+                |y, x| luma.get_pixel(x as u32, y as u32)[0] as f32,
+            ))
+        }
+        HeightmapFormat::Exr => {
+            let img = image::open(path).map_err(|e| format!("Couldn't open heightmap: {}", e))?;
+            let luma = img.to_luma32f();
+            let (width, height) = luma.dimensions();
+
+            Ok(na::DMatrix::from_fn(
+                height as usize,
+                width as usize,
+                |y, x| luma.get_pixel(x as u32, y as u32)[0],
+            ))
+        }
+        HeightmapFormat::Raw16 { width, height } => {
+            let bytes = fs::read(path).map_err(|e| format!("Couldn't read heightmap: {}", e))?;
+            parse_raw16(&bytes, *width, *height)
+        }
+        HeightmapFormat::AsciiGrid => {
+            let contents =
+                fs::read_to_string(path).map_err(|e| format!("Couldn't read heightmap: {}", e))?;
+            parse_ascii_grid(&contents)
+        }
+    }
+}
+
+pub fn parse_raw16(bytes: &[u8], width: usize, height: usize) -> Result<na::DMatrix<f32>, String> {
+    if width == 0 || height == 0 {
+        return Err("RAW heightmaps need a width and height".to_string());
+    }
+
+    if bytes.len() != width * height * 2 {
+        return Err(format!(
+            "RAW heightmap is {} bytes but {}x{} needs {}",
+            bytes.len(),
+            width,
+            height,
+            width * height * 2
+        ));
+    }
+
+    Ok(na::DMatrix::from_fn(height, width, |y, x| {
+        let i = (y * width + x) * 2;
+        u16::from_le_bytes([bytes[i], bytes[i + 1]]) as f32
+    }))
+}
+
+pub fn parse_ascii_grid(contents: &str) -> Result<na::DMatrix<f32>, String> {
+    let mut cols: Option<usize> = None;
+    let mut rows: Option<usize> = None;
+    let mut no_data: Option<f32> = None;
+    let mut values: Vec<f32> = Vec::new();
+
+    for line in contents.lines() {
+        let mut parts = line.split_whitespace().peekable();
+
+        let Some(first) = parts.peek() else {
+            continue;
+        };
+
+        // header lines start with a keyword, data lines with a number
+        if first.parse::<f32>().is_err() {
+            let key = first.to_lowercase();
+            parts.next();
+            let value = parts
+                .next()
+                .ok_or_else(|| format!("Missing value for {}", key))?;
+
+            match key.as_str() {
+                "ncols" => cols = value.parse().ok(),
+                "nrows" => rows = value.parse().ok(),
+                "nodata_value" => no_data = value.parse().ok(),
+                // georeferencing does not affect the heights themselves
+                _ => {}
+            }
+            continue;
+        }
+
+        for part in parts {
+            let value = part
+                .parse::<f32>()
+                .map_err(|_| format!("Invalid grid value {}", part))?;
+            values.push(value);
+        }
+    }
+
+    let cols = cols.ok_or("ASCII grid is missing ncols")?;
+    let rows = rows.ok_or("ASCII grid is missing nrows")?;
+
+    if values.len() != cols * rows {
+        return Err(format!(
+            "ASCII grid has {} values but {}x{} needs {}",
+            values.len(),
+            cols,
+            rows,
+            cols * rows
+        ));
+    }
+
+    // fill missing samples with the lowest real height so they don't spike the range
+    let lowest = values
+        .iter()
+        .filter(|v| Some(**v) != no_data)
+        .fold(f32::INFINITY, |a, &b| a.min(b));
+    let lowest = if lowest.is_finite() { lowest } else { 0.0 };
+
+    Ok(na::DMatrix::from_fn(rows, cols, |y, x| {
+        let value = values[y * cols + x];
+        if Some(value) == no_data {
+            lowest
+        } else {
+            value
+        }
+    }))
+}
+
+/// Maps source heights into 0..1 and applies the import dialog's scale and base height
+pub fn normalize_heights(
+    heights: &na::DMatrix<f32>,
+    settings: &HeightmapImportSettings,
+) -> na::DMatrix<f32> {
+    let min = heights.min();
+    let max = heights.max();
+    let range = max - min;
+
+    heights.map(|h| {
+        let normalized = if range > f32::EPSILON {
+            (h - min) / range
+        } else {
+            0.0
+        };
+
+        (normalized * settings.vertical_scale + settings.base_height).clamp(0.0, 1.0)
+    })
+}
+
+/// Writes normalized heights as the 16-bit grayscale TIFF the engine loads landscapes from
+pub fn save_engine_heightmap(path: &Path, normalized: &na::DMatrix<f32>) -> Result<(), String> {
+    let (rows, cols) = normalized.shape();
+
+    let buffer: ImageBuffer<Luma<u16>, Vec<u16>> =
+        ImageBuffer::from_fn(cols as u32, rows as u32, |x, y| {
+            let value = normalized[(y as usize, x as usize)].clamp(0.0, 1.0);
+            Luma([(value * u16::MAX as f32).round() as u16])
+        });
+
+    buffer
+        .save_with_format(path, image::ImageFormat::Tiff)
+        .map_err(|e| format!("Couldn't save heightmap: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raw16_reads_little_endian_rows() {
+        let bytes = [0x01, 0x00, 0x00, 0x01, 0xff, 0xff, 0x10, 0x00];
+
+        let heights = parse_raw16(&bytes, 2, 2).unwrap();
+
+        assert_eq!(heights.shape(), (2, 2));
+        assert_eq!(heights[(0, 0)], 1.0);
+        assert_eq!(heights[(0, 1)], 256.0);
+        assert_eq!(heights[(1, 0)], 65535.0);
+        assert_eq!(heights[(1, 1)], 16.0);
+    }
+
+    #[test]
+    fn raw16_rejects_missing_dimensions() {
+        assert!(parse_raw16(&[0, 0], 0, 1).is_err());
+        assert!(parse_raw16(&[0, 0], 1, 0).is_err());
+    }
+
+    #[test]
+    fn raw16_rejects_wrong_sizes_and_odd_byte_counts() {
+        // one sample short of 2x2
+        assert!(parse_raw16(&[0; 6], 2, 2).is_err());
+        // one sample too many
+        assert!(parse_raw16(&[0; 10], 2, 2).is_err());
+        // half a sample left over
+        assert!(parse_raw16(&[0; 9], 2, 2).is_err());
+        assert!(parse_raw16(&[0; 3], 1, 1).is_err());
+    }
+
+    #[test]
+    fn ascii_grid_reads_header_and_rows() {
+        let contents = "ncols 3\nnrows 2\nxllcorner 0.0\nyllcorner 0.0\ncellsize 1.0\n\
+                        1 2 3\n4 5 6\n";
+
+        let heights = parse_ascii_grid(contents).unwrap();
+
+        assert_eq!(heights.shape(), (2, 3));
+        assert_eq!(heights[(0, 2)], 3.0);
+        assert_eq!(heights[(1, 0)], 4.0);
+    }
+
+    #[test]
+    fn ascii_grid_header_keys_ignore_case() {
+        let heights = parse_ascii_grid("NCOLS 2\nNROWS 1\n7 8\n").unwrap();
+
+        assert_eq!(heights.shape(), (1, 2));
+    }
+
+    #[test]
+    fn ascii_grid_fills_no_data_with_lowest_height() {
+        let contents = "ncols 2\nnrows 2\nNODATA_value -9999\n5 -9999\n3 8\n";
+
+        let heights = parse_ascii_grid(contents).unwrap();
+
+        assert_eq!(heights[(0, 1)], 3.0);
+        assert_eq!(heights.min(), 3.0);
+    }
+
+    #[test]
+    fn ascii_grid_rejects_malformed_headers() {
+        // no ncols at all
+        assert!(parse_ascii_grid("nrows 1\n1 2\n").is_err());
+        // keyword without a value
+        assert!(parse_ascii_grid("ncols\nnrows 1\n1\n").is_err());
+        // value that isn't a number
+        assert!(parse_ascii_grid("ncols two\nnrows 1\n1 2\n").is_err());
+    }
+
+    #[test]
+    fn ascii_grid_rejects_bad_values_and_counts() {
+        assert!(parse_ascii_grid("ncols 2\nnrows 1\n1 x\n").is_err());
+        assert!(parse_ascii_grid("ncols 2\nnrows 2\n1 2 3\n").is_err());
+        assert!(parse_ascii_grid("ncols 2\nnrows 1\n1 2 3\n").is_err());
+    }
+}
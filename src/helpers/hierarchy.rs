@@ -0,0 +1,474 @@
+use std::collections::HashSet;
+
+use midpoint_engine::animations::skeleton::Joint;
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::helpers::saved_data::ComponentData;
+use nalgebra::{Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::gizmos::{apply_component_transform, component_transform, rotate_euler, with_transform};
+use super::levels::{active_level_components, active_level_id};
+
+/// Parent scales closer to zero than this don't scale their children
+const MIN_PARENT_SCALE: f32 = 1e-6;
+
+/// Anything arranged in a parent/child tree, such as skeleton joints and scene nodes
+pub trait TreeNode {
+    fn node_id(&self) -> &str;
+    fn parent_node_id(&self) -> Option<&str>;
+}
+
+impl TreeNode for Joint {
+    fn node_id(&self) -> &str {
+        &self.id
+    }
+
+    fn parent_node_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+}
+
+pub fn would_create_cycle<T: TreeNode>(nodes: &[T], dragged_id: &str, new_parent_id: &str) -> bool {
+    let mut current_id: Option<_> = Some(new_parent_id);
+    while let Some(id) = current_id {
+        if id == dragged_id {
+            return true;
+        }
+        current_id = nodes
+            .iter()
+            .find(|n| n.node_id() == id)
+            .and_then(|n| n.parent_node_id());
+    }
+    false
+}
+
+pub fn get_all_child_ids<T: TreeNode>(nodes: &[T], parent_id: &str) -> Vec<String> {
+    let mut children = Vec::new();
+    let mut to_process = vec![parent_id];
+
+    while let Some(current_id) = to_process.pop() {
+        for node in nodes.iter() {
+            if node.parent_node_id() == Some(current_id) {
+                children.push(node.node_id().to_string());
+                to_process.push(node.node_id());
+            }
+        }
+    }
+
+    children
+}
+
+pub fn calculate_depth<T: TreeNode>(nodes: &[T], node_id: &str) -> usize {
+    let mut depth = 0;
+    let mut current_node = nodes.iter().find(|n| n.node_id() == node_id);
+
+    while let Some(node) = current_node {
+        if let Some(parent_id) = node.parent_node_id() {
+            depth += 1;
+            current_node = nodes.iter().find(|n| n.node_id() == parent_id);
+        } else {
+            break;
+        }
+    }
+    depth
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SceneNodeKind {
+    /// Stands for the level component with the same id
+    Component,
+    /// A named folder that only holds other nodes
+    Group,
+}
+
+/// A component or group in a level's outline
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SceneNode {
+    pub id: String,
+    /// Group name, components show their own name
+    #[serde(default)]
+    pub name: String,
+    pub kind: SceneNodeKind,
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    #[serde(default)]
+    pub locked: bool,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+impl SceneNode {
+    pub fn component(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            name: String::new(),
+            kind: SceneNodeKind::Component,
+            parent_id: None,
+            visible: true,
+            locked: false,
+        }
+    }
+
+    pub fn group(name: &str) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            kind: SceneNodeKind::Group,
+            parent_id: None,
+            visible: true,
+            locked: false,
+        }
+    }
+}
+
+impl TreeNode for SceneNode {
+    fn node_id(&self) -> &str {
+        &self.id
+    }
+
+    fn parent_node_id(&self) -> Option<&str> {
+        self.parent_id.as_deref()
+    }
+}
+
+/// Lines the saved outline up with the level's components. Deleted components drop out with
+/// their children moving up to the grandparent, and new components show up at the root.
+pub fn sync_scene_nodes(nodes: &[SceneNode], components: &[ComponentData]) -> Vec<SceneNode> {
+    let exists = |node: &SceneNode| {
+        node.kind == SceneNodeKind::Group || components.iter().any(|c| c.id == node.id)
+    };
+
+    let mut synced: Vec<SceneNode> = nodes.iter().filter(|n| exists(n)).cloned().collect();
+
+    for node in synced.iter_mut() {
+        let mut parent_id = node.parent_id.clone();
+        while let Some(id) = parent_id.clone() {
+            match nodes.iter().find(|n| n.id == id) {
+                Some(parent) if exists(parent) => break,
+                Some(parent) => parent_id = parent.parent_id.clone(),
+                None => parent_id = None,
+            }
+        }
+        node.parent_id = parent_id;
+    }
+
+    for component in components {
+        if !synced.iter().any(|n| n.id == component.id) {
+            synced.push(SceneNode::component(&component.id));
+        }
+    }
+
+    synced
+}
+
+/// Nodes depth first with their depth, children right under their parent
+pub fn outline_order(nodes: &[SceneNode]) -> Vec<(SceneNode, usize)> {
+    fn visit(
+        nodes: &[SceneNode],
+        parent_id: Option<&str>,
+        depth: usize,
+        outline: &mut Vec<(SceneNode, usize)>,
+    ) {
+        for node in nodes.iter().filter(|n| n.parent_id.as_deref() == parent_id) {
+            outline.push((node.clone(), depth));
+            visit(nodes, Some(&node.id), depth + 1, outline);
+        }
+    }
+
+    let mut outline = Vec::new();
+    visit(nodes, None, 0, &mut outline);
+    outline
+}
+
+/// Outline of the active level, every component at the root when nothing was saved yet
+pub fn level_hierarchy(state_helper: &StateHelper) -> Vec<SceneNode> {
+    let components = active_level_components(state_helper);
+
+    let nodes = match (
+        active_level_id(state_helper),
+        state_helper.editor_data.as_ref(),
+    ) {
+        (Some(level_id), Some(editor_data)) => editor_data
+            .lock()
+            .unwrap()
+            .level(&level_id)
+            .map(|level| level.hierarchy.clone())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    sync_scene_nodes(&nodes, &components)
+}
+
+/// Stores the active level's outline, saves the editor data and updates what the viewport hides
+pub fn save_level_hierarchy(state_helper: &StateHelper, nodes: Vec<SceneNode>) {
+    let level_id = active_level_id(state_helper).expect("Couldn't get active level");
+
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().hidden_components = hidden_component_ids(&nodes);
+    }
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id)
+        .hierarchy = nodes;
+
+    state_helper.save_current_editor_data();
+}
+
+/// Tells the render callback which components to skip, for when a project or level opens
+pub fn refresh_hidden_components(state_helper: &StateHelper) {
+    let nodes = level_hierarchy(state_helper);
+
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().hidden_components = hidden_component_ids(&nodes);
+    }
+}
+
+/// Ids of nodes where the node or one of its ancestors matches
+fn inherited_ids(nodes: &[SceneNode], flag: impl Fn(&SceneNode) -> bool) -> HashSet<String> {
+    nodes
+        .iter()
+        .filter(|node| {
+            let mut current = Some(*node);
+            while let Some(node) = current {
+                if flag(node) {
+                    return true;
+                }
+                current = node
+                    .parent_id
+                    .as_ref()
+                    .and_then(|id| nodes.iter().find(|n| n.id == *id));
+            }
+            false
+        })
+        .map(|node| node.id.clone())
+        .collect()
+}
+
+/// Components hidden by themselves or by a hidden parent or group
+pub fn hidden_component_ids(nodes: &[SceneNode]) -> HashSet<String> {
+    inherited_ids(nodes, |node| !node.visible)
+}
+
+/// Components locked by themselves or by a locked parent or group
+pub fn locked_component_ids(nodes: &[SceneNode]) -> HashSet<String> {
+    inherited_ids(nodes, |node| node.locked)
+}
+
+/// Whether a click or box select in the viewport may pick the component
+pub fn is_pickable(state_helper: &StateHelper, id: &str) -> bool {
+    let nodes = level_hierarchy(state_helper);
+
+    !hidden_component_ids(&nodes).contains(id) && !locked_component_ids(&nodes).contains(id)
+}
+
+/// Components under the node, groups excluded
+pub fn descendant_components(nodes: &[SceneNode], node_id: &str) -> Vec<String> {
+    get_all_child_ids(nodes, node_id)
+        .into_iter()
+        .filter(|id| {
+            nodes
+                .iter()
+                .any(|n| n.id == *id && n.kind == SceneNodeKind::Component)
+        })
+        .collect()
+}
+
+/// Components that move along with the selection because a selected component is one of their
+/// ancestors, each with the index of that ancestor in `components`
+pub fn follower_components(
+    state_helper: &StateHelper,
+    components: &[ComponentData],
+) -> Vec<(usize, ComponentData)> {
+    let nodes = level_hierarchy(state_helper);
+    let level_components = active_level_components(state_helper);
+
+    let mut followers: Vec<(usize, ComponentData)> = Vec::new();
+
+    for component in &level_components {
+        if components.iter().any(|c| c.id == component.id) {
+            continue;
+        }
+
+        // the closest selected ancestor leads
+        let mut parent_id = nodes
+            .iter()
+            .find(|n| n.id == component.id)
+            .and_then(|n| n.parent_id.clone());
+
+        while let Some(id) = parent_id {
+            if let Some(index) = components.iter().position(|c| c.id == id) {
+                followers.push((index, component.clone()));
+                break;
+            }
+            parent_id = nodes
+                .iter()
+                .find(|n| n.id == id)
+                .and_then(|n| n.parent_id.clone());
+        }
+    }
+
+    followers
+}
+
+/// Where a child goes when its parent's transform changes from `before` to `after`, keeping the
+/// offset it had in the parent's space
+pub fn follow_transform(
+    before: [[f32; 3]; 3],
+    after: [[f32; 3]; 3],
+    child: [[f32; 3]; 3],
+) -> [[f32; 3]; 3] {
+    let [position_before, rotation_before, scale_before] = before;
+    let [position_after, rotation_after, scale_after] = after;
+    let [position, rotation, scale] = child;
+
+    let turn_before =
+        Rotation3::from_euler_angles(rotation_before[0], rotation_before[1], rotation_before[2]);
+    let turn_after =
+        Rotation3::from_euler_angles(rotation_after[0], rotation_after[1], rotation_after[2]);
+    // a parent flattened to nothing on an axis can't say how far to scale its children along it
+    let ratio = Vector3::from_fn(|axis, _| {
+        if scale_before[axis].abs() > MIN_PARENT_SCALE {
+            scale_after[axis] / scale_before[axis]
+        } else {
+            1.0
+        }
+    });
+
+    let offset = turn_before.inverse() * (Vector3::from(position) - Vector3::from(position_before));
+    let position = Vector3::from(position_after) + turn_after * offset.component_mul(&ratio);
+
+    [
+        position.into(),
+        rotate_euler(rotation, &(turn_after * turn_before.inverse())),
+        [scale[0] * ratio.x, scale[1] * ratio.y, scale[2] * ratio.z],
+    ]
+}
+
+/// Moves followers after their leaders went from `before` to `after`, returns them moved
+pub fn move_followers(
+    renderer_state: &mut RendererState,
+    before: &[ComponentData],
+    after: &[ComponentData],
+    followers: &[(usize, ComponentData)],
+) -> Vec<ComponentData> {
+    followers
+        .iter()
+        .map(|(leader, follower)| {
+            let transform = follow_transform(
+                component_transform(&before[*leader]),
+                component_transform(&after[*leader]),
+                component_transform(follower),
+            );
+
+            apply_component_transform(renderer_state, follower, transform);
+            with_transform(follower, transform)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY: [[f32; 3]; 3] = [[0.0; 3], [0.0; 3], [1.0; 3]];
+
+    fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+        for axis in 0..3 {
+            assert!(
+                (actual[axis] - expected[axis]).abs() < 1e-5,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    fn child_of(id: &str, parent_id: &str) -> SceneNode {
+        SceneNode {
+            parent_id: Some(parent_id.to_string()),
+            ..SceneNode::component(id)
+        }
+    }
+
+    #[test]
+    fn follow_transform_carries_the_child_along_a_move() {
+        let after = [[5.0, 0.0, -2.0], [0.0; 3], [1.0; 3]];
+        let child = [[1.0, 2.0, 3.0], [0.0; 3], [1.0; 3]];
+
+        let [position, rotation, scale] = follow_transform(IDENTITY, after, child);
+
+        assert_close(position, [6.0, 2.0, 1.0]);
+        assert_close(rotation, [0.0; 3]);
+        assert_close(scale, [1.0; 3]);
+    }
+
+    #[test]
+    fn follow_transform_swings_the_child_around_a_turning_parent() {
+        let after = [[0.0; 3], [0.0, 0.0, std::f32::consts::FRAC_PI_2], [1.0; 3]];
+        let child = [[1.0, 0.0, 0.0], [0.0; 3], [1.0; 3]];
+
+        let [position, rotation, _] = follow_transform(IDENTITY, after, child);
+
+        assert_close(position, [0.0, 1.0, 0.0]);
+        assert_close(rotation, [0.0, 0.0, std::f32::consts::FRAC_PI_2]);
+    }
+
+    #[test]
+    fn follow_transform_scales_the_offset_and_the_child() {
+        let after = [[0.0; 3], [0.0; 3], [2.0, 1.0, 3.0]];
+        let child = [[1.0, 1.0, 1.0], [0.0; 3], [0.5; 3]];
+
+        let [position, _, scale] = follow_transform(IDENTITY, after, child);
+
+        assert_close(position, [2.0, 1.0, 3.0]);
+        assert_close(scale, [1.0, 0.5, 1.5]);
+    }
+
+    #[test]
+    fn follow_transform_skips_axes_the_parent_was_flattened_on() {
+        let before = [[0.0; 3], [0.0; 3], [0.0, 1.0, 1.0]];
+        let after = [[0.0; 3], [0.0; 3], [2.0, 2.0, 1.0]];
+        let child = [[1.0, 1.0, 1.0], [0.0; 3], [1.0; 3]];
+
+        let [position, _, scale] = follow_transform(before, after, child);
+
+        assert!(position.iter().chain(scale.iter()).all(|v| v.is_finite()));
+        assert_close(position, [1.0, 2.0, 1.0]);
+        assert_close(scale, [1.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn would_create_cycle_catches_parenting_under_a_descendant() {
+        let nodes = vec![
+            SceneNode::component("root"),
+            child_of("child", "root"),
+            child_of("grandchild", "child"),
+            SceneNode::component("other"),
+        ];
+
+        assert!(would_create_cycle(&nodes, "root", "grandchild"));
+        assert!(would_create_cycle(&nodes, "child", "child"));
+        assert!(!would_create_cycle(&nodes, "grandchild", "root"));
+        assert!(!would_create_cycle(&nodes, "root", "other"));
+    }
+
+    #[test]
+    fn would_create_cycle_stops_at_unknown_parents() {
+        let nodes = vec![child_of("child", "missing")];
+
+        assert!(!would_create_cycle(&nodes, "other", "child"));
+    }
+}
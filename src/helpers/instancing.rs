@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use bytemuck::{Pod, Zeroable};
+use nalgebra::{Matrix4, Rotation3, Vector3};
+
+use crate::editor_state::StateHelper;
+
+use super::culling::{model_file_bounds, transformed_bounds, Bounds};
+
+/// Fewer copies of an asset than this are drawn one by one
+pub const MIN_INSTANCES: usize = 2;
+
+/// Model matrix of one instance, fed to `main_instanced` in primary_vertex.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct InstanceRaw {
+    pub model: [[f32; 4]; 4],
+}
+
+// only f32 fields with no padding
+unsafe impl Zeroable for InstanceRaw {}
+unsafe impl Pod for InstanceRaw {}
+
+impl InstanceRaw {
+    // after the engine's four vertex attributes
+    const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+    ];
+
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// The matrix the engine builds from a transform's position, euler rotation and scale
+pub fn model_matrix(
+    position: &Vector3<f32>,
+    rotation: &Vector3<f32>,
+    scale: &Vector3<f32>,
+) -> Matrix4<f32> {
+    Matrix4::new_translation(position)
+        * Rotation3::from_euler_angles(rotation.x, rotation.y, rotation.z).to_homogeneous()
+        * Matrix4::new_nonuniform_scaling(scale)
+}
+
+/// Tells the scene pass which asset a model component was loaded from, so it can batch copies
+/// and cull them by the asset's mesh bounds, read from the glb the first time the asset loads
+pub fn register_model_asset(state_helper: &StateHelper, component_id: &str, asset_id: &str) {
+    let Some(editor_render) = state_helper.editor_render.as_ref() else {
+        return;
+    };
+
+    editor_render
+        .lock()
+        .unwrap()
+        .model_assets
+        .insert(component_id.to_string(), asset_id.to_string());
+
+    let known = editor_render
+        .lock()
+        .unwrap()
+        .scene_cache
+        .lock()
+        .unwrap()
+        .has_asset_bounds(asset_id);
+    if known {
+        return;
+    }
+
+    let model_path = state_helper.saved_state.as_ref().and_then(|saved_state| {
+        saved_state
+            .lock()
+            .unwrap()
+            .models
+            .iter()
+            .find(|model| model.id == asset_id)
+            .map(|model| model.normalFilePath.clone())
+    });
+    let Some(model_path) = model_path else {
+        return;
+    };
+
+    match model_file_bounds(Path::new(&model_path)) {
+        Ok(bounds) => editor_render
+            .lock()
+            .unwrap()
+            .scene_cache
+            .lock()
+            .unwrap()
+            .set_asset_bounds(asset_id, bounds),
+        Err(e) => println!("Couldn't read bounds of model {}: {}", asset_id, e),
+    }
+}
+
+/// What the scene pass keeps between frames so unchanged work isn't redone
+#[derive(Default)]
+pub struct SceneCache {
+    /// Transform last written into each mesh's uniform buffer, by the mesh's model bind group
+    uploaded: HashMap<wgpu::Id<wgpu::BindGroup>, [f32; 9]>,
+    /// An asset's bounds in its own space, read from its mesh vertices
+    asset_bounds: HashMap<String, Bounds>,
+    instance_buffer: Option<wgpu::Buffer>,
+}
+
+impl SceneCache {
+    /// Remembers the transform and returns whether it differs from the one last uploaded
+    pub fn transform_changed(
+        &mut self,
+        bind_group: &wgpu::BindGroup,
+        position: &Vector3<f32>,
+        rotation: &Vector3<f32>,
+        scale: &Vector3<f32>,
+    ) -> bool {
+        let transform = [
+            position.x, position.y, position.z, rotation.x, rotation.y, rotation.z, scale.x,
+            scale.y, scale.z,
+        ];
+
+        self.uploaded.insert(bind_group.global_id(), transform) != Some(transform)
+    }
+
+    /// Drops meshes that left the scene, once more are remembered than the scene has
+    pub fn forget_removed(
+        &mut self,
+        mesh_count: usize,
+        live: impl FnOnce() -> HashSet<wgpu::Id<wgpu::BindGroup>>,
+    ) {
+        if self.uploaded.len() > mesh_count {
+            let live = live();
+            self.uploaded.retain(|id, _| live.contains(id));
+        }
+    }
+
+    pub fn has_asset_bounds(&self, asset_id: &str) -> bool {
+        self.asset_bounds.contains_key(asset_id)
+    }
+
+    pub fn set_asset_bounds(&mut self, asset_id: &str, bounds: Bounds) {
+        self.asset_bounds.insert(asset_id.to_string(), bounds);
+    }
+
+    /// World bounds of a copy of an asset, from the asset's mesh bounds read when it loaded.
+    /// Assets whose glb couldn't be read fall back to the copy's colliders.
+    pub fn asset_world_bounds(
+        &self,
+        asset_id: Option<&String>,
+        model: &Matrix4<f32>,
+        collider_bounds: Option<&Bounds>,
+    ) -> Option<Bounds> {
+        asset_id
+            .and_then(|asset_id| self.asset_bounds.get(asset_id))
+            .map(|local| transformed_bounds(model, local))
+            .or(collider_bounds.copied())
+    }
+
+    /// Writes the frame's instances, growing the buffer when they don't fit
+    pub fn write_instances(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &[InstanceRaw],
+    ) -> Option<&wgpu::Buffer> {
+        if instances.is_empty() {
+            return None;
+        }
+
+        let size = std::mem::size_of_val(instances) as wgpu::BufferAddress;
+        if self
+            .instance_buffer
+            .as_ref()
+            .map_or(true, |buffer| buffer.size() < size)
+        {
+            self.instance_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Scene Instance Buffer"),
+                size: size.next_power_of_two(),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        let buffer = self.instance_buffer.as_ref()?;
+        queue.write_buffer(buffer, 0, bytemuck::cast_slice(instances));
+
+        Some(buffer)
+    }
+}
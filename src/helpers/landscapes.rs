@@ -82,34 +82,32 @@ pub fn save_landscape(
     "success".to_string()
 }
 
+/// Sets which texture the level's landscape component shows for the map
 pub fn save_landscape_texture(
-    levels: Option<Vec<LevelData>>,
+    level: &mut LevelData,
     component_id: String,
     texture_kind: LandscapeTextureKinds,
     value: String,
 ) {
-    let mut new_levels = levels.clone().unwrap_or_default();
-    if let Some(level) = new_levels.last_mut() {
-        if let Some(components) = &mut level.components {
-            if let Some(component) = components.iter_mut().find(|c| c.id == component_id) {
-                if let Some(landscape_properties) = &mut component.landscape_properties {
-                    match texture_kind {
-                        LandscapeTextureKinds::Primary => {
-                            landscape_properties.primary_texture_id = Some(value)
-                        }
-                        LandscapeTextureKinds::Rockmap => {
-                            landscape_properties.rockmap_texture_id = Some(value)
-                        }
-                        LandscapeTextureKinds::Soil => {
-                            landscape_properties.soil_texture_id = Some(value)
-                        }
-                        _ => {
-                            // web_sys::console::error_1(
-                            //     &format!("Invalid texture kind: {}", value).into(),
-                            // );
-                            println!("Invalid TextureKind");
-                            // return;
-                        }
+    if let Some(components) = &mut level.components {
+        if let Some(component) = components.iter_mut().find(|c| c.id == component_id) {
+            if let Some(landscape_properties) = &mut component.landscape_properties {
+                match texture_kind {
+                    LandscapeTextureKinds::Primary => {
+                        landscape_properties.primary_texture_id = Some(value)
+                    }
+                    LandscapeTextureKinds::Rockmap => {
+                        landscape_properties.rockmap_texture_id = Some(value)
+                    }
+                    LandscapeTextureKinds::Soil => {
+                        landscape_properties.soil_texture_id = Some(value)
+                    }
+                    _ => {
+                        // web_sys::console::error_1(
+                        //     &format!("Invalid texture kind: {}", value).into(),
+                        // );
+                        println!("Invalid TextureKind");
+                        // return;
                     }
                 }
             }
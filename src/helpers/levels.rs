@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+
+use midpoint_engine::floem::reactive::{SignalGet, SignalUpdate};
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::get_camera;
+use midpoint_engine::helpers::saved_data::{ComponentData, LevelData, SavedState};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::component_edits::{add_to_scene, remove_from_scene};
+use super::editor_data::LevelEditorData;
+use super::environment::refresh_level_environment;
+use super::hierarchy::refresh_hidden_components;
+use super::lights::refresh_level_lights;
+use super::materials::refresh_level_materials;
+use super::measurements::{clear_measurement, refresh_level_annotations};
+use super::selection::set_selection;
+use super::tiles::stream_landscape_tiles;
+use super::water::refresh_level_water;
+
+/// Position of the active level, the first level when none was opened or it no longer exists
+pub fn active_level_index(levels: &[LevelData], active_level_id: Option<&str>) -> Option<usize> {
+    active_level_id
+        .and_then(|id| levels.iter().position(|level| level.id == id))
+        .or_else(|| (!levels.is_empty()).then_some(0))
+}
+
+pub fn active_level<'a>(
+    saved_state: &'a SavedState,
+    active_level_id: Option<&str>,
+) -> Option<&'a LevelData> {
+    let levels = saved_state.levels.as_ref()?;
+    let index = active_level_index(levels, active_level_id)?;
+
+    levels.get(index)
+}
+
+pub fn active_level_mut<'a>(
+    saved_state: &'a mut SavedState,
+    active_level_id: Option<&str>,
+) -> Option<&'a mut LevelData> {
+    let levels = saved_state.levels.as_mut()?;
+    let index = active_level_index(levels, active_level_id)?;
+
+    levels.get_mut(index)
+}
+
+/// Id of the level being edited
+pub fn active_level_id(state_helper: &StateHelper) -> Option<String> {
+    let saved_state = state_helper.saved_state.as_ref()?.lock().unwrap();
+
+    active_level(&saved_state, state_helper.active_level_id.as_deref())
+        .map(|level| level.id.clone())
+}
+
+/// Components of the level being edited
+pub fn active_level_components(state_helper: &StateHelper) -> Vec<ComponentData> {
+    state_helper
+        .saved_state
+        .as_ref()
+        .and_then(|saved_state| {
+            active_level(
+                &saved_state.lock().unwrap(),
+                state_helper.active_level_id.as_deref(),
+            )
+            .and_then(|level| level.components.clone())
+        })
+        .unwrap_or_default()
+}
+
+/// Name shown for a level, levels without one are numbered
+pub fn level_name(state_helper: &StateHelper, level_id: &str, index: usize) -> String {
+    state_helper
+        .editor_data
+        .as_ref()
+        .and_then(|editor_data| {
+            editor_data
+                .lock()
+                .unwrap()
+                .level(level_id)
+                .and_then(|level| level.name.clone())
+        })
+        .unwrap_or_else(|| format!("Level {}", index + 1))
+}
+
+fn save_levels(state_helper: &StateHelper) {
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    state_helper.save_saved_state(project_id, saved_state);
+    state_helper.save_current_editor_data();
+}
+
+/// Adds an empty level at the end of the list and returns its id
+pub fn create_level(state_helper: &StateHelper, name: &str) -> String {
+    let level_id = Uuid::new_v4().to_string();
+
+    state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap()
+        .levels
+        .get_or_insert_with(Vec::new)
+        .push(LevelData {
+            id: level_id.clone(),
+            components: Some(Vec::new()),
+        });
+
+    if !name.is_empty() {
+        state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap()
+            .level_mut(&level_id)
+            .name = Some(name.to_string());
+    }
+
+    save_levels(state_helper);
+
+    level_id
+}
+
+pub fn rename_level(state_helper: &StateHelper, level_id: &str, name: &str) {
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(level_id)
+        .name = (!name.is_empty()).then(|| name.to_string());
+
+    state_helper.save_current_editor_data();
+}
+
+/// Points a copied level's editor data at the copied components
+fn remap_level_editor_data(
+    level_data: &LevelEditorData,
+    level_id: &str,
+    ids: &HashMap<String, String>,
+) -> LevelEditorData {
+    let remap = |id: &String| ids.get(id).cloned().unwrap_or_else(|| id.clone());
+
+    let mut copy = level_data.clone();
+    copy.level_id = level_id.to_string();
+    copy.name = level_data
+        .name
+        .as_ref()
+        .map(|name| format!("{} Copy", name));
+
+    for grid in copy.tile_grids.iter_mut() {
+        for tile in grid.tiles.iter_mut() {
+            tile.component_id = remap(&tile.component_id);
+        }
+    }
+    for layer in copy.scatter_layers.iter_mut() {
+        layer.landscape_component_id = remap(&layer.landscape_component_id);
+        layer.instance_ids = layer.instance_ids.iter().map(remap).collect();
+    }
+    for river in copy.water.rivers.iter_mut() {
+        river.landscape_component_id = remap(&river.landscape_component_id);
+    }
+    for node in copy.hierarchy.iter_mut() {
+        node.id = remap(&node.id);
+        node.parent_id = node.parent_id.as_ref().map(remap);
+    }
+    for instance in copy.prefab_instances.iter_mut() {
+        instance.id = Uuid::new_v4().to_string();
+        for member in instance.members.iter_mut() {
+            member.component_id = remap(&member.component_id);
+        }
+    }
+    for annotation in copy.annotations.iter_mut() {
+        annotation.id = Uuid::new_v4().to_string();
+    }
+    for material in copy.materials.iter_mut() {
+        material.component_id = remap(&material.component_id);
+    }
+    copy.simulated_components = copy.simulated_components.iter().map(remap).collect();
+
+    copy
+}
+
+/// Copies the level right after itself with new component ids and returns the copy's id
+pub fn duplicate_level(state_helper: &StateHelper, level_id: &str) -> Option<String> {
+    let copy_id = Uuid::new_v4().to_string();
+    let mut ids: HashMap<String, String> = HashMap::new();
+
+    {
+        let mut saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+        let levels = saved_state.levels.as_mut()?;
+        let index = levels.iter().position(|level| level.id == level_id)?;
+
+        let mut copy = levels[index].clone();
+        copy.id = copy_id.clone();
+        for component in copy.components.iter_mut().flatten() {
+            let new_id = Uuid::new_v4().to_string();
+            ids.insert(component.id.clone(), new_id.clone());
+            component.id = new_id;
+        }
+
+        levels.insert(index + 1, copy);
+    }
+
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+
+        if let Some(level_data) = editor_data.level(level_id) {
+            let copy = remap_level_editor_data(level_data, &copy_id, &ids);
+            editor_data.levels.push(copy);
+        }
+    }
+
+    save_levels(state_helper);
+
+    Some(copy_id)
+}
+
+/// Removes a level and its editor data, the last remaining level can't be deleted
+pub fn delete_level(state_helper: &StateHelper, level_id: &str) -> bool {
+    {
+        let mut saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+        let Some(levels) = saved_state.levels.as_mut() else {
+            return false;
+        };
+        if levels.len() <= 1 {
+            return false;
+        }
+
+        levels.retain(|level| level.id != level_id);
+    }
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .levels
+        .retain(|level| level.level_id != level_id);
+
+    save_levels(state_helper);
+
+    true
+}
+
+/// Moves a level up (negative) or down (positive) the list
+pub fn move_level(state_helper: &StateHelper, level_id: &str, offset: isize) {
+    {
+        let mut saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+        let Some(levels) = saved_state.levels.as_mut() else {
+            return;
+        };
+        let Some(index) = levels.iter().position(|level| level.id == level_id) else {
+            return;
+        };
+
+        let target = index as isize + offset;
+        if target < 0 || target >= levels.len() as isize {
+            return;
+        }
+
+        let level = levels.remove(index);
+        levels.insert(target as usize, level);
+    }
+
+    save_levels(state_helper);
+}
+
+/// Makes the level the one being edited. The renderer drops every level's models and terrain and
+/// loads this level's components, tiles stream back in around the camera.
+pub fn open_level(state_helper: &mut StateHelper, gpu_resources: &GpuResources, level_id: &str) {
+    set_selection(state_helper, Vec::new());
+
+    let (all_ids, tiled_ids) = {
+        let saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+        let editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+
+        let all_ids: Vec<String> = saved_state
+            .levels
+            .iter()
+            .flatten()
+            .flat_map(|level| level.components.iter().flatten())
+            .map(|component| component.id.clone())
+            .collect();
+        let tiled_ids: Vec<String> = editor_data
+            .level(level_id)
+            .map(|level| {
+                level
+                    .tile_grids
+                    .iter()
+                    .flat_map(|grid| grid.tiles.iter())
+                    .map(|tile| tile.component_id.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        (all_ids, tiled_ids)
+    };
+
+    if let Some(renderer_state) = state_helper.renderer_state.as_ref() {
+        remove_from_scene(&mut renderer_state.lock().unwrap(), &all_ids);
+    }
+
+    state_helper.active_level_id = Some(level_id.to_string());
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .active_level_id = Some(level_id.to_string());
+    state_helper.save_current_editor_data();
+
+    // component undo steps point into the level they were made in
+    state_helper.history.lock().unwrap().clear();
+
+    let components = active_level_components(state_helper);
+    for component in components.iter().filter(|c| !tiled_ids.contains(&c.id)) {
+        add_to_scene(state_helper, gpu_resources, component);
+    }
+
+    if let Some(level_components_signal) = state_helper.level_components_signal {
+        level_components_signal.set(components);
+    }
+
+    refresh_hidden_components(state_helper);
+    refresh_level_water(state_helper, &gpu_resources.device);
+    refresh_level_lights(state_helper);
+    refresh_level_materials(state_helper, &gpu_resources.device, &gpu_resources.queue);
+    refresh_level_annotations(state_helper);
+    clear_measurement(state_helper);
+    refresh_level_environment(state_helper, &gpu_resources.device, &gpu_resources.queue);
+
+    let camera = get_camera();
+    stream_landscape_tiles(
+        state_helper,
+        gpu_resources,
+        camera.position.x,
+        camera.position.z,
+    );
+}
+
+/// Narrows what restoring the project loaded down to the level that was open last, projects
+/// with a single level are left as they are
+pub fn restore_active_level(state_helper: &mut StateHelper, gpu_resources: &GpuResources) {
+    let level_count = state_helper
+        .saved_state
+        .as_ref()
+        .and_then(|saved_state| saved_state.lock().unwrap().levels.as_ref().map(Vec::len))
+        .unwrap_or_default();
+
+    if level_count <= 1 {
+        return;
+    }
+
+    if let Some(level_id) = active_level_id(state_helper) {
+        open_level(state_helper, gpu_resources, &level_id);
+    }
+}
@@ -0,0 +1,500 @@
+use std::f32::consts::{PI, TAU};
+
+use bytemuck::{Pod, Zeroable};
+use midpoint_engine::floem::reactive::SignalUpdate;
+use midpoint_engine::helpers::saved_data::{ComponentData, GenericProperties};
+use nalgebra::{Point3, Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::editor_render::EditorRenderState;
+use super::environment::EnvironmentSettings;
+use super::levels::active_level_id;
+use super::view_modes::OverlayLines;
+
+/// Point and spot lights the lit view can shade with, matches the array in primary_fragment.wgsl
+pub const MAX_SCENE_LIGHTS: usize = 16;
+/// Size of the marker drawn at a light, also how close a click has to pass to pick it
+const LIGHT_MARKER_SIZE: f32 = 0.5;
+/// Length of the line showing where suns and spots point
+const LIGHT_DIRECTION_LENGTH: f32 = 3.0;
+/// Keeps the noon sun from standing straight overhead
+const SUN_TILT: f32 = 0.3;
+
+/// How a light reaches the level
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightKind {
+    /// Parallel light from far away, turned by the level's time of day
+    Directional,
+    Point,
+    Spot,
+}
+
+impl LightKind {
+    pub const ALL: [LightKind; 3] = [LightKind::Directional, LightKind::Point, LightKind::Spot];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LightKind::Directional => "Sun",
+            LightKind::Point => "Point",
+            LightKind::Spot => "Spot",
+        }
+    }
+}
+
+/// A light placed in a level. Lights have no kind in the engine's components, so they are saved
+/// in the level's own entry in midpoint.json and join the selection as components without a kind.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LightComponent {
+    pub id: String,
+    pub name: String,
+    pub kind: LightKind,
+    pub position: [f32; 3],
+    /// Euler angles turning the light, which points straight down without any
+    pub rotation: [f32; 3],
+    pub color: [f32; 3],
+    pub intensity: f32,
+    /// Distance where point and spot lights have faded out
+    pub range: f32,
+    /// Half angle of a spot light's cone, in degrees
+    pub spot_angle: f32,
+    /// Saved for the game, the editor doesn't draw shadows
+    pub cast_shadows: bool,
+}
+
+impl LightComponent {
+    pub fn new(kind: LightKind, position: [f32; 3]) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: format!("{} Light", kind.label()),
+            kind,
+            position,
+            rotation: [0.0; 3],
+            color: [1.0, 1.0, 1.0],
+            intensity: 1.0,
+            range: 10.0,
+            spot_angle: 30.0,
+            cast_shadows: kind == LightKind::Directional,
+        }
+    }
+
+    /// Where the light shines towards
+    pub fn direction(&self) -> Vector3<f32> {
+        let [roll, pitch, yaw] = self.rotation;
+        Rotation3::from_euler_angles(roll, pitch, yaw) * -Vector3::y()
+    }
+
+    /// Stand-in for the light in the selection, so gizmos and the properties panel can move it
+    pub fn component_data(&self) -> ComponentData {
+        ComponentData {
+            id: self.id.clone(),
+            kind: None,
+            asset_id: String::new(),
+            generic_properties: GenericProperties {
+                name: self.name.clone(),
+                position: self.position,
+                rotation: self.rotation,
+                scale: [1.0, 1.0, 1.0],
+            },
+            landscape_properties: None,
+            model_properties: None,
+        }
+    }
+}
+
+/// Whether the selected component stands in for a light rather than a model or landscape
+pub fn is_light_component(component: &ComponentData) -> bool {
+    component.kind.is_none()
+}
+
+/// Light settings shared by the whole level
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct LevelLighting {
+    /// Fraction of the day, sunrise at 0.25 and sunset at 0.75
+    #[serde(default = "default_time_of_day")]
+    pub time_of_day: f32,
+    #[serde(default = "default_ambient_intensity")]
+    pub ambient_intensity: f32,
+}
+
+fn default_time_of_day() -> f32 {
+    0.5
+}
+
+fn default_ambient_intensity() -> f32 {
+    0.2
+}
+
+impl Default for LevelLighting {
+    fn default() -> Self {
+        Self {
+            time_of_day: default_time_of_day(),
+            ambient_intensity: default_ambient_intensity(),
+        }
+    }
+}
+
+/// Direction of sunlight at the time of day, the sun rising along +x
+pub fn sun_direction(time_of_day: f32) -> Vector3<f32> {
+    let angle = (time_of_day - 0.25) * TAU;
+
+    -Vector3::new(angle.cos(), angle.sin(), SUN_TILT).normalize()
+}
+
+/// Euler angles pointing a light along the direction
+pub fn rotation_towards(direction: Vector3<f32>) -> [f32; 3] {
+    let rotation = Rotation3::rotation_between(&-Vector3::y(), &direction.normalize())
+        // straight up, where any half turn will do
+        .unwrap_or_else(|| Rotation3::from_axis_angle(&Vector3::x_axis(), PI));
+    let (roll, pitch, yaw) = rotation.euler_angles();
+
+    [roll, pitch, yaw]
+}
+
+/// Matches SceneLight in primary_fragment.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SceneLightUniform {
+    pub position: [f32; 3],
+    pub range: f32,
+    pub direction: [f32; 3],
+    pub cos_cutoff: f32,
+    pub color: [f32; 3],
+    pub _padding: f32,
+}
+
+/// Matches LightUniforms in primary_fragment.wgsl, padded to its alignment
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct LightUniforms {
+    pub direction: [f32; 3],
+    pub _padding: f32,
+    pub color: [f32; 3],
+    pub ambient_intensity: f32,
+    pub time_of_day: f32,
+    pub light_count: u32,
+    pub _padding_2: [f32; 2],
+    pub ambient_color: [f32; 3],
+    pub fog_start: f32,
+    pub fog_color: [f32; 3],
+    pub fog_end: f32,
+    /// Fog is measured from here
+    pub camera_position: [f32; 3],
+    pub fog_enabled: u32,
+    pub lights: [SceneLightUniform; MAX_SCENE_LIGHTS],
+}
+
+// only f32 and u32 fields with explicit padding
+unsafe impl Zeroable for SceneLightUniform {}
+unsafe impl Pod for SceneLightUniform {}
+unsafe impl Zeroable for LightUniforms {}
+unsafe impl Pod for LightUniforms {}
+
+/// The first sun lights the level, point and spot lights past the limit are left out. The
+/// environment's fog and ambient color go along with the lights.
+pub fn light_uniforms(
+    lights: &[LightComponent],
+    lighting: &LevelLighting,
+    environment: &EnvironmentSettings,
+    camera_position: [f32; 3],
+) -> LightUniforms {
+    let sun = lights.iter().find(|l| l.kind == LightKind::Directional);
+
+    let scene_lights: Vec<SceneLightUniform> = lights
+        .iter()
+        .filter(|l| l.kind != LightKind::Directional)
+        .take(MAX_SCENE_LIGHTS)
+        .map(|light| SceneLightUniform {
+            position: light.position,
+            range: light.range,
+            direction: light.direction().into(),
+            cos_cutoff: match light.kind {
+                LightKind::Spot => light.spot_angle.to_radians().cos(),
+                // the whole sphere is inside
+                _ => -2.0,
+            },
+            color: light.color.map(|c| c * light.intensity),
+            _padding: 0.0,
+        })
+        .collect();
+
+    let mut uniforms = LightUniforms {
+        direction: sun
+            .map(|sun| sun.direction())
+            .unwrap_or_else(|| sun_direction(lighting.time_of_day))
+            .into(),
+        _padding: 0.0,
+        color: sun.map_or([0.0; 3], |sun| sun.color.map(|c| c * sun.intensity)),
+        ambient_intensity: lighting.ambient_intensity,
+        time_of_day: lighting.time_of_day,
+        light_count: scene_lights.len() as u32,
+        _padding_2: [0.0; 2],
+        ambient_color: environment.ambient_color,
+        fog_start: environment.fog_start,
+        fog_color: environment.fog_color,
+        fog_end: environment.fog_end,
+        camera_position,
+        fog_enabled: environment.fog_enabled as u32,
+        lights: [SceneLightUniform::default(); MAX_SCENE_LIGHTS],
+    };
+    uniforms.lights[..scene_lights.len()].copy_from_slice(&scene_lights);
+
+    uniforms
+}
+
+/// A star at each light, with its direction for suns and its cone for spots
+pub fn light_marker_lines(lines: &mut OverlayLines, lights: &[LightComponent]) {
+    for light in lights {
+        let position = Point3::from(light.position);
+        let color = light.color;
+
+        for axis in [Vector3::x(), Vector3::y(), Vector3::z()] {
+            lines.line(
+                position - axis * LIGHT_MARKER_SIZE,
+                position + axis * LIGHT_MARKER_SIZE,
+                color,
+            );
+        }
+
+        let direction = light.direction();
+
+        match light.kind {
+            LightKind::Directional => {
+                lines.line(
+                    position,
+                    position + direction * LIGHT_DIRECTION_LENGTH,
+                    color,
+                );
+            }
+            LightKind::Point => {
+                lines.circle(
+                    position,
+                    Vector3::x(),
+                    Vector3::z(),
+                    LIGHT_MARKER_SIZE,
+                    color,
+                );
+            }
+            LightKind::Spot => {
+                let length = light.range.min(LIGHT_DIRECTION_LENGTH);
+                let radius = length * light.spot_angle.to_radians().tan();
+                let rotation = Rotation3::rotation_between(&-Vector3::y(), &direction)
+                    .unwrap_or_else(|| Rotation3::from_axis_angle(&Vector3::x_axis(), PI));
+                let (u, v) = (rotation * Vector3::x(), rotation * Vector3::z());
+                let base = position + direction * length;
+
+                lines.circle(base, u, v, radius, color);
+                for edge in [u, -u, v, -v] {
+                    lines.line(position, base + edge * radius, color);
+                }
+            }
+        }
+    }
+}
+
+/// The nearest light whose marker the ray passes through
+pub fn pick_light(
+    lights: &[LightComponent],
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+) -> Option<String> {
+    let direction = direction.normalize();
+
+    lights
+        .iter()
+        .filter_map(|light| {
+            let to_light = Point3::from(light.position) - origin;
+            let along = to_light.dot(&direction);
+            let gap = (to_light - direction * along).norm();
+
+            (along > 0.0 && gap <= LIGHT_MARKER_SIZE).then_some((along, light))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, light)| light.id.clone())
+}
+
+pub fn level_lights(state_helper: &StateHelper) -> Vec<LightComponent> {
+    let (Some(level_id), Some(editor_data)) = (
+        active_level_id(state_helper),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.lights.clone())
+        .unwrap_or_default()
+}
+
+pub fn level_lighting(state_helper: &StateHelper) -> LevelLighting {
+    let (Some(level_id), Some(editor_data)) = (
+        active_level_id(state_helper),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return LevelLighting::default();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.lighting)
+        .unwrap_or_default()
+}
+
+/// Stand-ins for the active level's lights, for the selection
+pub fn light_components(state_helper: &StateHelper) -> Vec<ComponentData> {
+    level_lights(state_helper)
+        .iter()
+        .map(LightComponent::component_data)
+        .collect()
+}
+
+/// Applies a change to the active level's lights, saves it and shows it in the viewport
+pub fn update_level_lights(
+    state_helper: &StateHelper,
+    edit: impl FnOnce(&mut Vec<LightComponent>, &mut LevelLighting),
+) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+        let level = editor_data.level_mut(&level_id);
+
+        edit(&mut level.lights, &mut level.lighting);
+    }
+
+    state_helper.save_current_editor_data();
+    refresh_level_lights(state_helper);
+}
+
+/// Hands the active level's lights to the render callback and the component browser
+pub fn refresh_level_lights(state_helper: &StateHelper) {
+    let lights = level_lights(state_helper);
+
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        let mut editor_render = editor_render.lock().unwrap();
+        editor_render.lights = lights.clone();
+        editor_render.lighting = level_lighting(state_helper);
+    }
+
+    if let Some(level_lights_signal) = state_helper.level_lights_signal {
+        level_lights_signal.set(lights);
+    }
+}
+
+/// Adds a light at the position, suns turned to the level's time of day
+pub fn add_light(state_helper: &StateHelper, kind: LightKind, position: [f32; 3]) -> String {
+    let mut light = LightComponent::new(kind, position);
+    let id = light.id.clone();
+
+    update_level_lights(state_helper, |lights, lighting| {
+        if kind == LightKind::Directional {
+            light.rotation = rotation_towards(sun_direction(lighting.time_of_day));
+        }
+        lights.push(light);
+    });
+
+    id
+}
+
+pub fn remove_lights(state_helper: &StateHelper, ids: &[String]) {
+    update_level_lights(state_helper, |lights, _| {
+        lights.retain(|light| !ids.contains(&light.id))
+    });
+}
+
+/// Adds copied or pasted lights to the end of the level's list
+pub fn append_lights(state_helper: &StateHelper, added: &[LightComponent]) {
+    update_level_lights(state_helper, |lights, _| {
+        lights.extend(added.iter().cloned())
+    });
+}
+
+/// Puts removed lights back where they sat in the level's list
+pub fn insert_lights(state_helper: &StateHelper, removed: &[(usize, LightComponent)]) {
+    update_level_lights(state_helper, |lights, _| {
+        for (index, light) in removed.iter().cloned() {
+            lights.insert(index.min(lights.len()), light);
+        }
+    });
+}
+
+/// Edits one light, the rest of the level stays as it is
+pub fn update_light(state_helper: &StateHelper, id: &str, edit: impl FnOnce(&mut LightComponent)) {
+    update_level_lights(state_helper, |lights, _| {
+        if let Some(light) = lights.iter_mut().find(|light| light.id == id) {
+            edit(light);
+        }
+    });
+}
+
+/// Sets the time of day and points the suns along with it
+fn turn_suns(lights: &mut [LightComponent], lighting: &mut LevelLighting, time_of_day: f32) {
+    let rotation = rotation_towards(sun_direction(time_of_day));
+    lighting.time_of_day = time_of_day;
+
+    for light in lights
+        .iter_mut()
+        .filter(|l| l.kind == LightKind::Directional)
+    {
+        light.rotation = rotation;
+    }
+}
+
+/// Turns the level's suns to the time of day
+pub fn set_time_of_day(state_helper: &StateHelper, time_of_day: f32) {
+    update_level_lights(state_helper, |lights, lighting| {
+        turn_suns(lights, lighting, time_of_day)
+    });
+}
+
+/// Turns the suns in the viewport while the slider moves, saved when it is let go
+pub fn preview_time_of_day(editor_render: &mut EditorRenderState, time_of_day: f32) {
+    let EditorRenderState {
+        lights, lighting, ..
+    } = editor_render;
+
+    turn_suns(lights, lighting, time_of_day);
+}
+
+/// Copies moved stand-ins back onto the lights they belong to
+fn apply_light_transforms(lights: &mut [LightComponent], components: &[ComponentData]) {
+    for component in components.iter().filter(|c| is_light_component(c)) {
+        if let Some(light) = lights.iter_mut().find(|l| l.id == component.id) {
+            light.position = component.generic_properties.position;
+            light.rotation = component.generic_properties.rotation;
+        }
+    }
+}
+
+/// Moves the lights in the viewport while a gizmo drags them, saved when the drag ends
+pub fn preview_light_transforms(
+    editor_render: &mut EditorRenderState,
+    components: &[ComponentData],
+) {
+    apply_light_transforms(&mut editor_render.lights, components);
+}
+
+/// Saves where the gizmo or the properties panel left the lights among the components
+pub fn save_light_transforms(state_helper: &StateHelper, components: &[ComponentData]) {
+    if !components.iter().any(is_light_component) {
+        return;
+    }
+
+    update_level_lights(state_helper, |lights, _| {
+        apply_light_transforms(lights, components);
+    });
+}
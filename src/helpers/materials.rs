@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+
+use bytemuck::{Pod, Zeroable};
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+use midpoint_engine::helpers::saved_data::File;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt;
+
+use crate::editor_state::StateHelper;
+
+use super::editor_render::EditorRenderState;
+use super::levels::active_level_id;
+
+/// Render mode in primary_fragment.wgsl that reads the material override
+pub const MATERIAL_RENDER_MODE: i32 = 3;
+/// Override textures are scaled down to this so a large import doesn't eat the GPU's memory
+const MAX_MATERIAL_TEXTURE_SIZE: u32 = 2048;
+/// The fragment shader samples six layers, the override's textures take the first three
+const MATERIAL_TEXTURE_LAYERS: u32 = 6;
+/// Points straight out of the surface in tangent space
+const FLAT_NORMAL: Rgba<u8> = Rgba([128, 128, 255, 255]);
+
+/// Which of an override's textures a texture from the project's library is used as
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterialSlot {
+    BaseColor,
+    Normal,
+    /// Roughness in green and metallic in blue, as in glTF
+    RoughnessMetallic,
+}
+
+impl MaterialSlot {
+    pub const ALL: [MaterialSlot; 3] = [
+        MaterialSlot::BaseColor,
+        MaterialSlot::Normal,
+        MaterialSlot::RoughnessMetallic,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MaterialSlot::BaseColor => "Base Color Texture",
+            MaterialSlot::Normal => "Normal Texture",
+            MaterialSlot::RoughnessMetallic => "Roughness/Metallic Texture",
+        }
+    }
+
+    /// What the slot reads as when no texture is picked
+    fn fill(&self) -> Rgba<u8> {
+        match self {
+            MaterialSlot::Normal => FLAT_NORMAL,
+            _ => Rgba([255, 255, 255, 255]),
+        }
+    }
+}
+
+/// Replaces how a model component looks, in place of the textures in its glb. Kept per
+/// component so instances of the same model can look different.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MaterialOverride {
+    pub component_id: String,
+    /// Multiplies the base color texture, or is the color on its own without one. Alpha is
+    /// kept for the game, the editor draws everything opaque.
+    #[serde(default = "default_base_color")]
+    pub base_color: [f32; 4],
+    #[serde(default = "default_factor")]
+    pub roughness: f32,
+    #[serde(default)]
+    pub metallic: f32,
+    /// Ids of textures in the project's library
+    #[serde(default)]
+    pub base_color_texture: Option<String>,
+    #[serde(default)]
+    pub normal_texture: Option<String>,
+    #[serde(default)]
+    pub roughness_metallic_texture: Option<String>,
+    #[serde(default = "default_tiling")]
+    pub tiling: [f32; 2],
+    #[serde(default)]
+    pub offset: [f32; 2],
+}
+
+fn default_base_color() -> [f32; 4] {
+    [1.0; 4]
+}
+
+fn default_factor() -> f32 {
+    1.0
+}
+
+fn default_tiling() -> [f32; 2] {
+    [1.0; 2]
+}
+
+impl MaterialOverride {
+    pub fn new(component_id: &str) -> Self {
+        Self {
+            component_id: component_id.to_string(),
+            base_color: default_base_color(),
+            roughness: default_factor(),
+            metallic: 0.0,
+            base_color_texture: None,
+            normal_texture: None,
+            roughness_metallic_texture: None,
+            tiling: default_tiling(),
+            offset: [0.0; 2],
+        }
+    }
+
+    pub fn texture(&self, slot: MaterialSlot) -> Option<&String> {
+        match slot {
+            MaterialSlot::BaseColor => self.base_color_texture.as_ref(),
+            MaterialSlot::Normal => self.normal_texture.as_ref(),
+            MaterialSlot::RoughnessMetallic => self.roughness_metallic_texture.as_ref(),
+        }
+    }
+
+    pub fn texture_mut(&mut self, slot: MaterialSlot) -> &mut Option<String> {
+        match slot {
+            MaterialSlot::BaseColor => &mut self.base_color_texture,
+            MaterialSlot::Normal => &mut self.normal_texture,
+            MaterialSlot::RoughnessMetallic => &mut self.roughness_metallic_texture,
+        }
+    }
+
+    pub fn texture_ids(&self) -> [Option<String>; 3] {
+        MaterialSlot::ALL.map(|slot| self.texture(slot).cloned())
+    }
+}
+
+/// Matches Surface in primary_fragment.wgsl, the texture group's uniform
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SurfaceUniform {
+    pub render_mode: i32,
+    pub roughness: f32,
+    pub metallic: f32,
+    pub _padding: f32,
+    pub base_color: [f32; 4],
+    pub tiling: [f32; 2],
+    pub offset: [f32; 2],
+}
+
+// only i32 and f32 fields with explicit padding
+unsafe impl Zeroable for SurfaceUniform {}
+unsafe impl Pod for SurfaceUniform {}
+
+impl SurfaceUniform {
+    /// A surface the render mode textures on its own, for the engine's meshes and terrain
+    pub fn render_mode(render_mode: i32) -> Self {
+        Self {
+            render_mode,
+            roughness: 1.0,
+            metallic: 0.0,
+            _padding: 0.0,
+            base_color: [1.0; 4],
+            tiling: [1.0; 2],
+            offset: [0.0; 2],
+        }
+    }
+
+    pub fn material(material: &MaterialOverride) -> Self {
+        Self {
+            render_mode: MATERIAL_RENDER_MODE,
+            roughness: material.roughness,
+            metallic: material.metallic,
+            _padding: 0.0,
+            base_color: material.base_color,
+            tiling: material.tiling,
+            offset: material.offset,
+        }
+    }
+}
+
+/// The texture group a component with an override is drawn with
+pub struct MaterialBinding {
+    surface_buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+    /// Textures the bind group was built from, it's rebuilt when they change
+    texture_ids: [Option<String>; 3],
+    /// GPU memory of the texture array
+    pub texture_bytes: u64,
+}
+
+fn load_material_image(textures: &[File], id: Option<&String>) -> Option<RgbaImage> {
+    let file = textures.iter().find(|t| Some(&t.id) == id)?;
+
+    match image::open(&file.normalFilePath) {
+        Ok(image) => Some(image.to_rgba8()),
+        Err(e) => {
+            println!("Couldn't open texture {}: {}", file.fileName, e);
+            None
+        }
+    }
+}
+
+/// Layers of the override's texture array, all at the size of the largest texture picked
+fn material_layers(material: &MaterialOverride, textures: &[File]) -> (u32, u32, Vec<u8>) {
+    let images =
+        MaterialSlot::ALL.map(|slot| load_material_image(textures, material.texture(slot)));
+
+    let (width, height) = images
+        .iter()
+        .flatten()
+        .map(|image| image.dimensions())
+        .max_by_key(|(width, height)| width * height)
+        .map(|(width, height)| {
+            (
+                width.min(MAX_MATERIAL_TEXTURE_SIZE),
+                height.min(MAX_MATERIAL_TEXTURE_SIZE),
+            )
+        })
+        .unwrap_or((1, 1));
+
+    let mut images = images.into_iter();
+    let mut data = Vec::with_capacity((width * height * 4 * MATERIAL_TEXTURE_LAYERS) as usize);
+    for layer in 0..MATERIAL_TEXTURE_LAYERS as usize {
+        let image = match (images.next().flatten(), MaterialSlot::ALL.get(layer)) {
+            (Some(image), _) if image.dimensions() == (width, height) => image,
+            (Some(image), _) => {
+                image::imageops::resize(&image, width, height, FilterType::Triangle)
+            }
+            (None, Some(slot)) => RgbaImage::from_pixel(width, height, slot.fill()),
+            (None, None) => RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255])),
+        };
+
+        data.extend_from_slice(image.as_raw());
+    }
+
+    (width, height, data)
+}
+
+fn create_material_binding(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture_bind_group_layout: &wgpu::BindGroupLayout,
+    material: &MaterialOverride,
+    textures: &[File],
+) -> MaterialBinding {
+    let (width, height, data) = material_layers(material, textures);
+
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: MATERIAL_TEXTURE_LAYERS,
+    };
+    // linear, normals and roughness would be skewed by sRGB and the shader converts base color
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Material Override Texture"),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        &data,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        size,
+    );
+
+    let texture_view = texture.create_view(&wgpu::TextureViewDescriptor {
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        ..Default::default()
+    });
+    // tiling relies on the texture repeating
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::Repeat,
+        address_mode_v: wgpu::AddressMode::Repeat,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let surface_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Material Override Surface Buffer"),
+        contents: bytemuck::cast_slice(&[SurfaceUniform::material(material)]),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: texture_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(&sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: surface_buffer.as_entire_binding(),
+            },
+        ],
+        label: Some("Material Override Bind Group"),
+    });
+
+    MaterialBinding {
+        surface_buffer,
+        bind_group,
+        texture_ids: material.texture_ids(),
+        texture_bytes: data.len() as u64,
+    }
+}
+
+/// Brings the bind groups in line with the overrides. Textures are only loaded again when a
+/// different one is picked, other edits just rewrite the uniform.
+pub fn sync_material_bindings(
+    editor_render: &mut EditorRenderState,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    materials: &[MaterialOverride],
+    textures: &[File],
+) {
+    let Some(texture_bind_group_layout) = editor_render.texture_bind_group_layout.clone() else {
+        return;
+    };
+
+    let mut bindings: HashMap<String, MaterialBinding> =
+        std::mem::take(&mut editor_render.material_bindings);
+
+    for material in materials {
+        let binding = match bindings.remove(&material.component_id) {
+            Some(binding) if binding.texture_ids == material.texture_ids() => {
+                queue.write_buffer(
+                    &binding.surface_buffer,
+                    0,
+                    bytemuck::cast_slice(&[SurfaceUniform::material(material)]),
+                );
+                binding
+            }
+            _ => create_material_binding(
+                device,
+                queue,
+                &texture_bind_group_layout,
+                material,
+                textures,
+            ),
+        };
+
+        editor_render
+            .material_bindings
+            .insert(material.component_id.clone(), binding);
+    }
+}
+
+pub fn level_materials(state_helper: &StateHelper) -> Vec<MaterialOverride> {
+    let (Some(level_id), Some(editor_data)) = (
+        active_level_id(state_helper),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.materials.clone())
+        .unwrap_or_default()
+}
+
+pub fn component_material(
+    state_helper: &StateHelper,
+    component_id: &str,
+) -> Option<MaterialOverride> {
+    level_materials(state_helper)
+        .into_iter()
+        .find(|material| material.component_id == component_id)
+}
+
+/// Rebuilds what changed in the active level's material overrides
+pub fn refresh_level_materials(
+    state_helper: &StateHelper,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) {
+    let Some(editor_render) = state_helper.editor_render.as_ref() else {
+        return;
+    };
+
+    let materials = level_materials(state_helper);
+    let textures = state_helper
+        .saved_state
+        .as_ref()
+        .and_then(|saved_state| saved_state.lock().unwrap().textures.clone())
+        .unwrap_or_default();
+
+    sync_material_bindings(
+        &mut editor_render.lock().unwrap(),
+        device,
+        queue,
+        &materials,
+        &textures,
+    );
+}
+
+/// Applies a change to a component's override, starting one the first time, and saves it
+pub fn update_material(
+    state_helper: &StateHelper,
+    component_id: &str,
+    edit: impl FnOnce(&mut MaterialOverride),
+) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+        let materials = &mut editor_data.level_mut(&level_id).materials;
+
+        let index = match materials
+            .iter()
+            .position(|m| m.component_id == component_id)
+        {
+            Some(index) => index,
+            None => {
+                materials.push(MaterialOverride::new(component_id));
+                materials.len() - 1
+            }
+        };
+
+        edit(&mut materials[index]);
+    }
+
+    state_helper.save_current_editor_data();
+}
+
+/// Drops a component's override so it's drawn with its glb's textures again
+pub fn clear_material(state_helper: &StateHelper, component_id: &str) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id)
+        .materials
+        .retain(|m| m.component_id != component_id);
+
+    state_helper.save_current_editor_data();
+}
+
+/// Gives duplicates the look of the components they were copied from
+pub fn copy_materials(state_helper: &StateHelper, copies: &[(String, String)]) {
+    apply_copied_materials(state_helper, &level_materials(state_helper), copies);
+}
+
+/// Gives copies the overrides of their originals, from a list that may come from another level
+pub fn apply_copied_materials(
+    state_helper: &StateHelper,
+    materials: &[MaterialOverride],
+    copies: &[(String, String)],
+) {
+    let copied: Vec<MaterialOverride> = copies
+        .iter()
+        .filter_map(|(original_id, copy_id)| {
+            let material = materials.iter().find(|m| m.component_id == *original_id)?;
+
+            Some(MaterialOverride {
+                component_id: copy_id.clone(),
+                ..material.clone()
+            })
+        })
+        .collect();
+
+    for material in copied {
+        update_material(state_helper, &material.component_id.clone(), |m| {
+            *m = material
+        });
+    }
+}
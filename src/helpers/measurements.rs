@@ -0,0 +1,425 @@
+use midpoint_engine::floem::reactive::{SignalGet, SignalUpdate};
+use midpoint_engine::handlers::get_camera;
+use nalgebra::{Point3, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::levels::active_level_id;
+use super::selection::project_to_screen;
+use super::view_modes::OverlayLines;
+
+/// Height of the line standing a pin up from the surface it was placed on
+const PIN_HEIGHT: f32 = 1.5;
+const PIN_HEAD_RADIUS: f32 = 0.2;
+/// Size of the cross marking each measured point
+const POINT_MARKER_SIZE: f32 = 0.15;
+const MEASURE_COLOR: [f32; 3] = [1.0, 0.85, 0.1];
+const PIN_COLOR: [f32; 3] = [1.0, 0.35, 0.35];
+
+/// A note pinned to a spot in a level. Kept in the editor data only, so games never load it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Annotation {
+    pub id: String,
+    pub position: [f32; 3],
+    pub text: String,
+}
+
+/// What a click in the viewport does while measuring, in place of selecting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeasureTool {
+    /// Distance between two points, a third click starts over
+    Ruler,
+    /// Length of a line through every clicked point
+    Path,
+    /// Area enclosed by the clicked points
+    Area,
+    /// Places an annotation
+    Pin,
+}
+
+impl MeasureTool {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MeasureTool::Ruler => "Ruler",
+            MeasureTool::Path => "Path",
+            MeasureTool::Area => "Area",
+            MeasureTool::Pin => "Pin",
+        }
+    }
+}
+
+/// The measure tool in use and the points clicked with it so far
+#[derive(Clone, Debug, Default)]
+pub struct Measurement {
+    pub tool: Option<MeasureTool>,
+    pub points: Vec<[f32; 3]>,
+}
+
+impl Measurement {
+    fn add_point(&mut self, point: [f32; 3]) {
+        if self.tool == Some(MeasureTool::Ruler) && self.points.len() >= 2 {
+            self.points.clear();
+        }
+
+        self.points.push(point);
+    }
+
+    fn path_length(&self) -> f32 {
+        self.points
+            .windows(2)
+            .map(|pair| (Point3::from(pair[1]) - Point3::from(pair[0])).norm())
+            .sum()
+    }
+
+    /// Area of the polygon through the points, which don't have to lie flat
+    fn enclosed_area(&self) -> f32 {
+        let count = self.points.len();
+        let doubled: Vector3<f32> = (0..count)
+            .map(|index| {
+                let start = Vector3::from(self.points[index]);
+                let end = Vector3::from(self.points[(index + 1) % count]);
+                start.cross(&end)
+            })
+            .sum();
+
+        doubled.norm() / 2.0
+    }
+
+    /// What has been measured so far, None until there is something to show
+    pub fn readout(&self) -> Option<String> {
+        match self.tool? {
+            MeasureTool::Ruler => {
+                let [start, end] = self.points.get(..2)? else {
+                    return None;
+                };
+                let offset = Point3::from(*end) - Point3::from(*start);
+                let horizontal = Vector3::new(offset.x, 0.0, offset.z).norm();
+
+                Some(format!(
+                    "{:.2} units ({:.2} across, {:.2} up)",
+                    offset.norm(),
+                    horizontal,
+                    offset.y
+                ))
+            }
+            MeasureTool::Path if self.points.len() >= 2 => {
+                Some(format!("Path: {:.2} units", self.path_length()))
+            }
+            MeasureTool::Area if self.points.len() >= 3 => {
+                let perimeter = self.path_length()
+                    + (Point3::from(self.points[0])
+                        - Point3::from(self.points[self.points.len() - 1]))
+                    .norm();
+
+                Some(format!(
+                    "Area: {:.2} sq units (perimeter {:.2})",
+                    self.enclosed_area(),
+                    perimeter
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Text drawn over the viewport, at a position in logical window pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewportLabel {
+    pub position: [f64; 2],
+    pub text: String,
+}
+
+pub fn measure_tool(state_helper: &StateHelper) -> Option<MeasureTool> {
+    state_helper
+        .editor_render
+        .as_ref()
+        .and_then(|editor_render| editor_render.lock().unwrap().measurement.tool)
+}
+
+/// Switches tools, dropping the points measured with the last one
+pub fn set_measure_tool(state_helper: &StateHelper, tool: Option<MeasureTool>) {
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().measurement = Measurement {
+            tool,
+            points: Vec::new(),
+        };
+    }
+}
+
+pub fn clear_measurement(state_helper: &StateHelper) {
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().measurement.points.clear();
+    }
+}
+
+/// Adds a clicked surface point to the measurement, or pins a note there with the pin tool
+pub fn place_measure_point(state_helper: &StateHelper, point: [f32; 3]) {
+    match measure_tool(state_helper) {
+        Some(MeasureTool::Pin) => {
+            add_annotation(state_helper, point);
+        }
+        Some(_) => {
+            if let Some(editor_render) = state_helper.editor_render.as_ref() {
+                editor_render.lock().unwrap().measurement.add_point(point);
+            }
+        }
+        None => {}
+    }
+}
+
+pub fn level_annotations(state_helper: &StateHelper) -> Vec<Annotation> {
+    let (Some(editor_data), Some(level_id)) = (
+        state_helper.editor_data.as_ref(),
+        active_level_id(state_helper),
+    ) else {
+        return Vec::new();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.annotations.clone())
+        .unwrap_or_default()
+}
+
+/// Applies a change to the active level's annotations, saves it and shows it in the viewport
+fn update_level_annotations(state_helper: &StateHelper, edit: impl FnOnce(&mut Vec<Annotation>)) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    edit(
+        &mut state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap()
+            .level_mut(&level_id)
+            .annotations,
+    );
+
+    state_helper.save_current_editor_data();
+    refresh_level_annotations(state_helper);
+}
+
+/// Hands the active level's annotations to the render callback and the annotation list
+pub fn refresh_level_annotations(state_helper: &StateHelper) {
+    let annotations = level_annotations(state_helper);
+
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().annotations = annotations.clone();
+    }
+
+    if let Some(annotations_signal) = state_helper.annotations_signal {
+        annotations_signal.set(annotations);
+    }
+}
+
+pub fn add_annotation(state_helper: &StateHelper, position: [f32; 3]) -> String {
+    let id = Uuid::new_v4().to_string();
+    let annotation = Annotation {
+        id: id.clone(),
+        position,
+        text: format!("Note {}", level_annotations(state_helper).len() + 1),
+    };
+
+    update_level_annotations(state_helper, |annotations| annotations.push(annotation));
+
+    id
+}
+
+pub fn set_annotation_text(state_helper: &StateHelper, id: &str, text: &str) {
+    update_level_annotations(state_helper, |annotations| {
+        if let Some(annotation) = annotations.iter_mut().find(|a| a.id == id) {
+            annotation.text = text.to_string();
+        }
+    });
+}
+
+pub fn remove_annotation(state_helper: &StateHelper, id: &str) {
+    update_level_annotations(state_helper, |annotations| {
+        annotations.retain(|annotation| annotation.id != id)
+    });
+}
+
+/// Lines between the measured points and a standing marker for each pin
+pub fn measurement_lines(
+    lines: &mut OverlayLines,
+    measurement: &Measurement,
+    annotations: &[Annotation],
+) {
+    for point in measurement.points.iter() {
+        let point = Point3::from(*point);
+
+        for axis in [Vector3::x(), Vector3::y(), Vector3::z()] {
+            lines.line(
+                point - axis * POINT_MARKER_SIZE,
+                point + axis * POINT_MARKER_SIZE,
+                MEASURE_COLOR,
+            );
+        }
+    }
+
+    for pair in measurement.points.windows(2) {
+        lines.line(Point3::from(pair[0]), Point3::from(pair[1]), MEASURE_COLOR);
+    }
+
+    // the area closes back on its first point
+    if let (Some(MeasureTool::Area), [first, _, .., last]) =
+        (measurement.tool, measurement.points.as_slice())
+    {
+        lines.line(Point3::from(*last), Point3::from(*first), MEASURE_COLOR);
+    }
+
+    for annotation in annotations {
+        let base = Point3::from(annotation.position);
+        let head = base + Vector3::y() * PIN_HEIGHT;
+
+        lines.line(base, head, PIN_COLOR);
+        lines.circle(head, Vector3::x(), Vector3::z(), PIN_HEAD_RADIUS, PIN_COLOR);
+    }
+}
+
+/// Pin notes and the measurement readout where they land in the viewport, only in the scene view
+pub fn viewport_labels(state_helper: &StateHelper) -> Vec<ViewportLabel> {
+    let in_scene = state_helper
+        .current_view_signal
+        .map_or(false, |current_view| {
+            current_view.get_untracked() == "scene"
+        });
+    let Some(editor_render) = state_helper.editor_render.as_ref().filter(|_| in_scene) else {
+        return Vec::new();
+    };
+    let editor_render = editor_render.lock().unwrap();
+    let layout = editor_render.viewport_layout;
+    let Some([width, height]) = layout.size else {
+        return Vec::new();
+    };
+
+    let view_projection = get_camera().view_projection_matrix;
+    let place = |point: [f32; 3], text: String| {
+        let [x, y] = project_to_screen(point, &view_projection, width as f32, height as f32)?;
+        let (x, y) = (x as f64, y as f64);
+
+        // whole pixels, so a still camera gives the same labels every time
+        (x >= 0.0 && y >= 0.0 && x < width && y < height).then(|| ViewportLabel {
+            position: [
+                (layout.origin[0] + x).round(),
+                (layout.origin[1] + y).round(),
+            ],
+            text,
+        })
+    };
+
+    let measurement = &editor_render.measurement;
+    let readout = measurement
+        .readout()
+        .zip(measurement.points.last())
+        .and_then(|(readout, point)| place(*point, readout));
+
+    editor_render
+        .annotations
+        .iter()
+        .filter_map(|annotation| {
+            let [x, y, z] = annotation.position;
+            place([x, y + PIN_HEIGHT, z], annotation.text.clone())
+        })
+        .chain(readout)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measured(tool: MeasureTool, points: &[[f32; 3]]) -> Measurement {
+        let mut measurement = Measurement {
+            tool: Some(tool),
+            points: Vec::new(),
+        };
+        for point in points {
+            measurement.add_point(*point);
+        }
+
+        measurement
+    }
+
+    #[test]
+    fn ruler_measures_distance_across_and_up() {
+        let ruler = measured(MeasureTool::Ruler, &[[1.0, 0.0, 1.0], [4.0, 4.0, 1.0]]);
+
+        assert_eq!(
+            ruler.readout().as_deref(),
+            Some("5.00 units (3.00 across, 4.00 up)")
+        );
+    }
+
+    #[test]
+    fn ruler_starts_over_on_a_third_point() {
+        let ruler = measured(
+            MeasureTool::Ruler,
+            &[[0.0; 3], [1.0, 0.0, 0.0], [5.0, 0.0, 0.0]],
+        );
+
+        assert_eq!(ruler.points, vec![[5.0, 0.0, 0.0]]);
+        assert_eq!(ruler.readout(), None);
+    }
+
+    #[test]
+    fn path_adds_up_every_segment() {
+        let path = measured(
+            MeasureTool::Path,
+            &[[0.0; 3], [3.0, 4.0, 0.0], [3.0, 4.0, 10.0]],
+        );
+
+        assert!((path.path_length() - 15.0).abs() < 1e-5);
+        assert_eq!(path.readout().as_deref(), Some("Path: 15.00 units"));
+    }
+
+    #[test]
+    fn area_of_a_square_anywhere_in_the_level() {
+        let corners = [
+            [0.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [2.0, 0.0, 2.0],
+            [0.0, 0.0, 2.0],
+        ];
+        let moved = corners.map(|[x, y, z]| [x + 10.0, y + 5.0, z - 3.0]);
+
+        for points in [corners, moved] {
+            let area = measured(MeasureTool::Area, &points);
+
+            assert!((area.enclosed_area() - 4.0).abs() < 1e-4);
+            assert_eq!(
+                area.readout().as_deref(),
+                Some("Area: 4.00 sq units (perimeter 8.00)")
+            );
+        }
+    }
+
+    #[test]
+    fn area_of_a_tilted_triangle() {
+        // right triangle with legs of 3 and 4, standing on its edge
+        let area = measured(
+            MeasureTool::Area,
+            &[[0.0; 3], [3.0, 0.0, 0.0], [0.0, 4.0, 0.0]],
+        );
+
+        assert!((area.enclosed_area() - 6.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn readout_waits_for_enough_points() {
+        assert_eq!(measured(MeasureTool::Ruler, &[[0.0; 3]]).readout(), None);
+        assert_eq!(measured(MeasureTool::Path, &[[0.0; 3]]).readout(), None);
+        assert_eq!(
+            measured(MeasureTool::Area, &[[0.0; 3], [1.0, 0.0, 0.0]]).readout(),
+            None
+        );
+        assert_eq!(Measurement::default().readout(), None);
+    }
+}
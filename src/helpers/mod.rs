@@ -1,10 +1,40 @@
 pub mod animations;
 pub mod auth;
+pub mod behaviors;
+pub mod camera;
+pub mod component_edits;
 pub mod concepts;
+pub mod contours;
+pub mod culling;
+pub mod editor_data;
+pub mod editor_render;
+pub mod editor_settings;
+pub mod environment;
+pub mod gizmos;
+pub mod headless;
+pub mod heightmaps;
+pub mod hierarchy;
+pub mod instancing;
 pub mod landscapes;
+pub mod levels;
+pub mod lights;
+pub mod materials;
+pub mod measurements;
 pub mod models;
 pub mod nodes;
+pub mod offscreen;
+pub mod performance;
+pub mod play_mode;
+pub mod prefabs;
 pub mod projects;
+pub mod scatter;
+pub mod scene_pipeline;
+pub mod selection;
+pub mod snapping;
 pub mod textures;
+pub mod tiles;
 pub mod utilities;
+pub mod view_modes;
+pub mod viewport_layout;
+pub mod water;
 pub mod websocket;
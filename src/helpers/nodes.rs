@@ -1,8 +1,9 @@
 use midpoint_engine::floem::peniko::Color;
 use nalgebra_glm::Vec2;
+use serde::{Deserialize, Serialize};
 
 // Node components would also benefit from signals for dynamic properties
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct NodeComponent {
     pub id: String,
     pub title: String,
@@ -14,6 +15,9 @@ pub struct NodeComponent {
     pub parent: Option<String>,
     pub children: Vec<String>,
     pub initial_position: [u32; 2],
+    /// What the node is set to, a Float's number or which operation a MathOp does
+    #[serde(default)]
+    pub value: String,
 }
 
 impl NodeComponent {
@@ -28,6 +32,7 @@ impl NodeComponent {
             parent: None,
             children: Vec::new(),
             initial_position: [0, 0],
+            value: String::new(),
         }
     }
 }
@@ -81,7 +86,7 @@ impl NodeComponent {
 }
 
 // Port system using labels instead of paths
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Port {
     pub id: String,
     pub input_name: Option<String>, // corresponds to input_nodes which hold live state
@@ -98,7 +103,7 @@ pub struct Port {
 //     // etc
 // }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum NodeType {
     DataType,
     Boolean,
@@ -0,0 +1,490 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use chrono::Local;
+use image::RgbaImage;
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::handle_add_model;
+use midpoint_engine::helpers::saved_data::ComponentKind;
+use nalgebra::{Isometry3, Matrix4, Point3, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::camera::{collider_bounds, current_view_projection, framed_view_projection};
+use super::component_edits::remove_from_scene;
+use super::editor_render::EditorRenderState;
+use super::scene_pipeline::{
+    camera_binding, create_render_mode_pipeline, create_sky_pipeline, draw_level, LevelView,
+    SceneLayouts, SCENE_DEPTH_FORMAT, SCENE_SAMPLE_COUNT,
+};
+use super::utilities::get_common_os_dir;
+use super::view_modes::RenderMode;
+
+/// Offscreen targets are read back as plain RGBA so they save straight to PNG
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+pub const THUMBNAIL_SIZE: [u32; 2] = [256, 256];
+pub const PROJECT_THUMBNAIL_SIZE: [u32; 2] = [320, 180];
+/// Thumbnails look at their model from the front right and a little above
+const THUMBNAIL_DIRECTION: [f32; 3] = [1.0, 0.8, 1.0];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ScreenshotSettings {
+    #[serde(default = "default_screenshot_width")]
+    pub width: u32,
+    #[serde(default = "default_screenshot_height")]
+    pub height: u32,
+}
+
+fn default_screenshot_width() -> u32 {
+    1920
+}
+
+fn default_screenshot_height() -> u32 {
+    1080
+}
+
+impl Default for ScreenshotSettings {
+    fn default() -> Self {
+        Self {
+            width: default_screenshot_width(),
+            height: default_screenshot_height(),
+        }
+    }
+}
+
+/// The scene shaded by the level's lights under its sky and fog, drawing into a readable texture
+/// instead of the window
+pub struct OffscreenRenderer {
+    pipeline: wgpu::RenderPipeline,
+    instanced_pipeline: wgpu::RenderPipeline,
+    sky_pipeline: wgpu::RenderPipeline,
+    camera_uniform_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+}
+
+impl OffscreenRenderer {
+    pub fn new(device: &wgpu::Device, layouts: &SceneLayouts) -> Self {
+        // lit whatever the viewport's render mode, draw_level binds the editor's lights
+        let lit_pipeline = |instanced| {
+            create_render_mode_pipeline(
+                device,
+                layouts,
+                OFFSCREEN_FORMAT,
+                RenderMode::Lit,
+                instanced,
+            )
+            .expect("Couldn't create lit pipeline")
+        };
+        let pipeline = lit_pipeline(false);
+        let instanced_pipeline = lit_pipeline(true);
+        let sky_pipeline = create_sky_pipeline(device, layouts, OFFSCREEN_FORMAT);
+        // its own camera so renders don't disturb the viewport's
+        let (camera_uniform_buffer, camera_bind_group) =
+            camera_binding(device, layouts, Matrix4::<f32>::identity().as_slice());
+
+        Self {
+            pipeline,
+            instanced_pipeline,
+            sky_pipeline,
+            camera_uniform_buffer,
+            camera_bind_group,
+        }
+    }
+
+    /// Draws the level, or just the `only` component, and waits for the pixels
+    pub fn render(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        engine: &RendererState,
+        editor_render: &EditorRenderState,
+        view_projection: &Matrix4<f32>,
+        size: [u32; 2],
+        only: Option<&str>,
+    ) -> Result<RgbaImage, String> {
+        let [width, height] = size;
+        let max_dimension = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+            return Err(format!(
+                "Image size {}x{} is outside 1 to {}",
+                width, height, max_dimension
+            ));
+        }
+
+        queue.write_buffer(
+            &self.camera_uniform_buffer,
+            0,
+            bytemuck::cast_slice(view_projection.as_slice()),
+        );
+        // lights, fog and sky from this camera, the viewport writes its own again next frame
+        editor_render.write_frame_uniforms(queue, view_projection);
+
+        let extent = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let target = |label: &str,
+                      sample_count: u32,
+                      format: wgpu::TextureFormat,
+                      usage: wgpu::TextureUsages| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: extent,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+
+        let multisampled_view = target(
+            "Offscreen Multisampled Texture",
+            SCENE_SAMPLE_COUNT,
+            OFFSCREEN_FORMAT,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let depth_view = target(
+            "Offscreen Depth Texture",
+            SCENE_SAMPLE_COUNT,
+            SCENE_DEPTH_FORMAT,
+            wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let resolve_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Resolve Texture"),
+            size: extent,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: OFFSCREEN_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let resolve_view = resolve_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // rows of a texture copy have to line up with the copy alignment
+        let unpadded_bytes_per_row = width * 4;
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(alignment) * alignment;
+
+        let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Output Buffer"),
+            size: (padded_bytes_per_row * height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Encoder"),
+        });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Offscreen Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &multisampled_view,
+                    resolve_target: Some(&resolve_view),
+                    ops: wgpu::Operations {
+                        // same background as the viewport
+                        load: wgpu::LoadOp::Clear(editor_render.environment.wgpu_clear_color()),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            editor_render.draw_sky(&mut render_pass, &self.sky_pipeline);
+            render_pass.set_pipeline(&self.pipeline);
+            draw_level(
+                &mut render_pass,
+                engine,
+                editor_render,
+                device,
+                queue,
+                &LevelView {
+                    camera_bind_group: &self.camera_bind_group,
+                    view_projection,
+                    instanced_pipeline: Some(&self.instanced_pipeline),
+                },
+                only,
+            );
+        }
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &resolve_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            extent,
+        );
+
+        queue.submit(Some(encoder.finish()));
+
+        let buffer_slice = output_buffer.slice(..);
+        let (tx, rx) = mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+
+        rx.recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Couldn't read back the render: {}", e))?;
+
+        let pixels = {
+            let padded = buffer_slice.get_mapped_range();
+            padded
+                .chunks(padded_bytes_per_row as usize)
+                .flat_map(|row| &row[..unpadded_bytes_per_row as usize])
+                .copied()
+                .collect::<Vec<u8>>()
+        };
+        output_buffer.unmap();
+
+        RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| "Render didn't fill the image".to_string())
+    }
+}
+
+pub fn save_png(image: &RgbaImage, path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())
+}
+
+/// Device for rendering without a window. The software adapter is tried when asked for
+/// or when no GPU turns up, so screenshots still work on machines without one.
+pub async fn request_headless_device(
+    force_software: bool,
+) -> Result<(wgpu::Device, wgpu::Queue), String> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+    let request = |force_fallback_adapter| {
+        instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter,
+            compatible_surface: None,
+        })
+    };
+
+    let adapter = match request(force_software).await {
+        Some(adapter) => adapter,
+        None if !force_software => request(true)
+            .await
+            .ok_or("Couldn't find a GPU or software adapter")?,
+        None => return Err("Couldn't find a software adapter".to_string()),
+    };
+
+    println!("Rendering with {:?}", adapter.get_info());
+
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("Headless Device"),
+                required_features: wgpu::Features::empty(),
+                // software adapters often fall short of the default limits
+                required_limits: wgpu::Limits::downlevel_defaults()
+                    .using_resolution(adapter.limits()),
+                memory_hints: wgpu::MemoryHints::MemoryUsage,
+            },
+            None,
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub fn project_dir(project_id: &str) -> Result<PathBuf, String> {
+    get_common_os_dir()
+        .map(|sync_dir| sync_dir.join("midpoint/projects").join(project_id))
+        .ok_or_else(|| "Couldn't get CommonOS directory".to_string())
+}
+
+/// Shown next to the project in the project browser
+pub fn project_thumbnail_path(project_id: &str) -> Result<PathBuf, String> {
+    Ok(project_dir(project_id)?.join("thumbnail.png"))
+}
+
+/// Shown on the model's card in the model browser
+pub fn model_thumbnail_path(project_id: &str, model_id: &str) -> Result<PathBuf, String> {
+    Ok(project_dir(project_id)?
+        .join("thumbnails")
+        .join(format!("{}.png", model_id)))
+}
+
+fn render_editor_scene(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    view_projection: &Matrix4<f32>,
+    size: [u32; 2],
+    only: Option<&str>,
+) -> Result<RgbaImage, String> {
+    let mut renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .ok_or("Couldn't get RendererState")?
+        .lock()
+        .unwrap();
+    let editor_render = state_helper
+        .editor_render
+        .as_ref()
+        .ok_or("Couldn't get editor render state")?
+        .lock()
+        .unwrap();
+    let offscreen = editor_render
+        .offscreen
+        .as_ref()
+        .ok_or("Offscreen rendering isn't set up yet")?;
+
+    // terrain tiles only come in as the terrain updates
+    renderer_state.update_terrain_managers(&gpu_resources.device, 0.1);
+
+    offscreen.render(
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        &renderer_state,
+        &editor_render,
+        view_projection,
+        size,
+        only,
+    )
+}
+
+/// The level as the viewport camera sees it
+pub fn render_level_image(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    size: [u32; 2],
+) -> Result<RgbaImage, String> {
+    let view_projection = current_view_projection(size[0] as f32 / size[1] as f32);
+
+    render_editor_scene(state_helper, gpu_resources, &view_projection, size, None)
+}
+
+/// Saves a screenshot of the level into the project's screenshots folder and makes a smaller
+/// copy the project's thumbnail. Returns where the screenshot went.
+pub fn save_level_screenshot(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+) -> Result<PathBuf, String> {
+    let project_id = state_helper
+        .project_selected_signal
+        .ok_or("Couldn't get project signal")?
+        .get()
+        .to_string();
+    let settings = state_helper.editor_settings.screenshot;
+
+    let image = render_level_image(
+        state_helper,
+        gpu_resources,
+        [settings.width, settings.height],
+    )?;
+    let path = project_dir(&project_id)?
+        .join("screenshots")
+        .join(format!("{}.png", Local::now().format("%Y-%m-%d_%H-%M-%S")));
+    save_png(&image, &path)?;
+
+    let thumbnail = render_level_image(state_helper, gpu_resources, PROJECT_THUMBNAIL_SIZE)?;
+    save_png(&thumbnail, &project_thumbnail_path(&project_id)?)?;
+
+    Ok(path)
+}
+
+/// Loads the model on its own for a moment, renders it framed from the front and saves the
+/// thumbnail the model browser shows
+pub fn save_model_thumbnail(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    model_id: &str,
+    filename: &str,
+) -> Result<PathBuf, String> {
+    let project_id = state_helper
+        .project_selected_signal
+        .ok_or("Couldn't get project signal")?
+        .get()
+        .to_string();
+    let renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .ok_or("Couldn't get RendererState")?;
+
+    // placed far below the level so nothing else ends up in the shot
+    let position = Vector3::new(0.0, -10000.0, 0.0);
+    let temporary_id = Uuid::new_v4().to_string();
+
+    handle_add_model(
+        renderer_state.clone(),
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        project_id.clone(),
+        model_id.to_string(),
+        temporary_id.clone(),
+        filename.to_string(),
+        Isometry3::new(position, Vector3::zeros()),
+    );
+    renderer_state
+        .lock()
+        .unwrap()
+        .add_collider(temporary_id.clone(), ComponentKind::Model);
+
+    let bounds = collider_bounds(&renderer_state.lock().unwrap(), &temporary_id);
+    let (mins, maxs) = bounds.unwrap_or_else(|| {
+        let center = Point3::from(position);
+        (center - Vector3::repeat(0.5), center + Vector3::repeat(0.5))
+    });
+
+    let [width, height] = THUMBNAIL_SIZE;
+    let view_projection = framed_view_projection(
+        &state_helper.editor_camera,
+        mins,
+        maxs,
+        Vector3::from(THUMBNAIL_DIRECTION),
+        width as f32 / height as f32,
+    );
+
+    let image = render_editor_scene(
+        state_helper,
+        gpu_resources,
+        &view_projection,
+        THUMBNAIL_SIZE,
+        Some(&temporary_id),
+    );
+
+    remove_from_scene(&mut renderer_state.lock().unwrap(), &[temporary_id]);
+
+    let path = model_thumbnail_path(&project_id, model_id)?;
+    save_png(&image?, &path)?;
+
+    Ok(path)
+}
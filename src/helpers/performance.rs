@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::Local;
+
+use super::offscreen::project_dir;
+
+/// Frames kept for the performance view's graph and averages
+pub const FRAME_HISTORY: usize = 240;
+/// A capture stops taking frames past this, about ten minutes at 60 fps
+const MAX_CAPTURE_FRAMES: usize = 36_000;
+/// Two timestamps, at the start and end of the scene pass
+const TIMESTAMP_BYTES: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+/// Draws the scene pass made and how many triangles they covered
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DrawCounts {
+    pub draw_calls: u32,
+    pub triangles: u64,
+    /// Cubes and models left out for being outside the view
+    pub culled: u32,
+}
+
+impl DrawCounts {
+    pub fn add_draw(&mut self, index_count: u32) {
+        self.add_instanced_draw(index_count, 1);
+    }
+
+    pub fn add_instanced_draw(&mut self, index_count: u32, instance_count: u32) {
+        self.draw_calls += 1;
+        self.triangles += (index_count / 3) as u64 * instance_count as u64;
+    }
+}
+
+/// What one frame of the render callback cost
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    /// Time since the frame before started
+    pub frame_ms: f32,
+    pub rapier_ms: f32,
+    pub animations_ms: f32,
+    pub terrain_ms: f32,
+    /// The scene pass on the GPU, from timestamps read back a frame or two late
+    pub gpu_ms: Option<f32>,
+    /// Terrain is drawn inside the engine and isn't counted
+    pub draws: DrawCounts,
+    /// Textures the editor made for material overrides and the sky
+    pub texture_bytes: u64,
+}
+
+pub fn elapsed_ms(start: Instant) -> f32 {
+    start.elapsed().as_secs_f32() * 1000.0
+}
+
+/// Times the scene pass with timestamp queries. Results are read back a couple of frames
+/// late so the render callback never waits on the GPU.
+pub struct GpuTimer {
+    query_set: Arc<wgpu::QuerySet>,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    /// Timestamps were copied for reading in the frame before
+    copied: bool,
+    /// Waiting for the readback buffer to map, no copies go into it meanwhile
+    mapping: bool,
+    mapped: Arc<AtomicBool>,
+    last_ms: Option<f32>,
+}
+
+impl GpuTimer {
+    /// None when the device wasn't created with timestamp queries
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Scene Pass Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Scene Pass Timestamp Resolve Buffer"),
+            size: TIMESTAMP_BYTES,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Scene Pass Timestamp Readback Buffer"),
+            size: TIMESTAMP_BYTES,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            query_set: Arc::new(query_set),
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            copied: false,
+            mapping: false,
+            mapped: Arc::new(AtomicBool::new(false)),
+            last_ms: None,
+        })
+    }
+
+    /// Reads timestamps that finished mapping, or starts mapping the ones copied last frame,
+    /// which has been submitted by now
+    fn collect(&mut self, device: &wgpu::Device) -> Option<f32> {
+        device.poll(wgpu::Maintain::Poll);
+
+        if self.mapping && self.mapped.load(Ordering::Acquire) {
+            {
+                let data = self.readback_buffer.slice(..).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                let ticks = timestamps[1].saturating_sub(timestamps[0]);
+
+                self.last_ms = Some(ticks as f32 * self.period / 1_000_000.0);
+            }
+
+            self.readback_buffer.unmap();
+            self.mapped.store(false, Ordering::Release);
+            self.mapping = false;
+        } else if self.copied {
+            let mapped = Arc::clone(&self.mapped);
+            self.readback_buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    mapped.store(result.is_ok(), Ordering::Release);
+                });
+
+            self.copied = false;
+            self.mapping = true;
+        }
+
+        self.last_ms
+    }
+
+    fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+
+        if !self.copied && !self.mapping {
+            encoder.copy_buffer_to_buffer(
+                &self.resolve_buffer,
+                0,
+                &self.readback_buffer,
+                0,
+                TIMESTAMP_BYTES,
+            );
+            self.copied = true;
+        }
+    }
+}
+
+/// Timestamps written at the start and end of the scene pass
+pub fn scene_timestamp_writes(query_set: &wgpu::QuerySet) -> wgpu::RenderPassTimestampWrites {
+    wgpu::RenderPassTimestampWrites {
+        query_set,
+        beginning_of_pass_write_index: Some(0),
+        end_of_pass_write_index: Some(1),
+    }
+}
+
+/// Frame timings and scene counts from the render callback, for the performance view
+#[derive(Default)]
+pub struct PerformanceMonitor {
+    pub history: VecDeque<FrameStats>,
+    /// Frames taken since a capture started, None when not capturing
+    pub capture: Option<Vec<FrameStats>>,
+    last_frame_start: Option<Instant>,
+    gpu_timer: Option<GpuTimer>,
+}
+
+impl PerformanceMonitor {
+    pub fn initialize(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.gpu_timer = GpuTimer::new(device, queue);
+    }
+
+    pub fn has_gpu_timer(&self) -> bool {
+        self.gpu_timer.is_some()
+    }
+
+    /// Kept outside the editor render lock, the scene pass borrows it for its whole length
+    pub fn timestamp_query_set(&self) -> Option<Arc<wgpu::QuerySet>> {
+        self.gpu_timer
+            .as_ref()
+            .map(|gpu_timer| Arc::clone(&gpu_timer.query_set))
+    }
+
+    /// Starts timing a frame, with the time since the last one and the latest GPU time filled in
+    pub fn start_frame(&mut self, device: &wgpu::Device) -> FrameStats {
+        let now = Instant::now();
+        let frame_ms = self
+            .last_frame_start
+            .replace(now)
+            .map_or(0.0, |last| (now - last).as_secs_f32() * 1000.0);
+
+        FrameStats {
+            frame_ms,
+            gpu_ms: self
+                .gpu_timer
+                .as_mut()
+                .and_then(|gpu_timer| gpu_timer.collect(device)),
+            ..Default::default()
+        }
+    }
+
+    /// Resolves the scene pass's timestamps once the pass has ended and records the frame
+    pub fn finish_frame(&mut self, encoder: &mut wgpu::CommandEncoder, stats: FrameStats) {
+        if let Some(gpu_timer) = self.gpu_timer.as_mut() {
+            gpu_timer.resolve(encoder);
+        }
+
+        if self.history.len() == FRAME_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(stats);
+
+        if let Some(capture) = self
+            .capture
+            .as_mut()
+            .filter(|capture| capture.len() < MAX_CAPTURE_FRAMES)
+        {
+            capture.push(stats);
+        }
+    }
+}
+
+/// One row per frame, times in milliseconds
+pub fn capture_csv(frames: &[FrameStats]) -> String {
+    let mut csv = String::from(
+        "frame,frame_ms,gpu_ms,rapier_ms,animations_ms,terrain_ms,draw_calls,triangles,culled,texture_bytes\n",
+    );
+
+    for (index, frame) in frames.iter().enumerate() {
+        csv.push_str(&format!(
+            "{},{:.3},{},{:.3},{:.3},{:.3},{},{},{},{}\n",
+            index,
+            frame.frame_ms,
+            frame
+                .gpu_ms
+                .map(|gpu_ms| format!("{:.3}", gpu_ms))
+                .unwrap_or_default(),
+            frame.rapier_ms,
+            frame.animations_ms,
+            frame.terrain_ms,
+            frame.draws.draw_calls,
+            frame.draws.triangles,
+            frame.draws.culled,
+            frame.texture_bytes,
+        ));
+    }
+
+    csv
+}
+
+/// Saves the capture into the project's performance folder. Returns where it went.
+pub fn save_capture(project_id: &str, frames: &[FrameStats]) -> Result<PathBuf, String> {
+    let path = project_dir(project_id)?
+        .join("performance")
+        .join(format!("{}.csv", Local::now().format("%Y-%m-%d_%H-%M-%S")));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    fs::write(&path, capture_csv(frames)).map_err(|e| e.to_string())?;
+
+    Ok(path)
+}
@@ -0,0 +1,773 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Instant;
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::get_camera;
+use midpoint_engine::helpers::saved_data::{ComponentData, ComponentKind, SavedState};
+use midpoint_engine::rapier3d::control::KinematicCharacterController;
+use midpoint_engine::rapier3d::prelude::{
+    Collider, ColliderHandle, QueryFilter, RigidBodyHandle, RigidBodyType, SharedShape,
+};
+use nalgebra::{Isometry3, Point3, Translation3, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::behaviors::{
+    evaluate_behavior, level_behaviors, Behavior, BehaviorCommand, BehaviorInput,
+};
+use super::camera::EditorCamera;
+use super::editor_data::LevelEditorData;
+use super::levels::{active_level_components, active_level_id, active_level_mut, open_level};
+use super::selection::selected_components;
+use super::snapping::surface_under_ray;
+
+/// The player walks around as an upright capsule, eye height 1.7 above its feet
+const PLAYER_RADIUS: f32 = 0.3;
+const PLAYER_HALF_HEIGHT: f32 = 0.6;
+/// Eye above the capsule's center
+const PLAYER_EYE_OFFSET: f32 = 0.8;
+/// Units per second
+const PLAYER_WALK_SPEED: f32 = 4.0;
+const PLAYER_JUMP_SPEED: f32 = 5.0;
+const GRAVITY: f32 = 9.81;
+/// Radians the view turns per pixel the mouse moves
+const LOOK_SENSITIVITY: f32 = 0.003;
+/// Keeps the view short of straight up or down, where turning flips over
+const MAX_PITCH: f32 = 1.5;
+/// Falling this far below where play started puts the player back there
+const FALL_LIMIT: f32 = 500.0;
+/// Seconds the first step takes, and the most any step takes so a hitch doesn't throw the
+/// player through a wall
+const FIRST_STEP_SECONDS: f32 = 1.0 / 60.0;
+const MAX_STEP_SECONDS: f32 = 0.1;
+
+/// Whether the level is being edited or run as a game
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayState {
+    #[default]
+    Editing,
+    Playing,
+    /// Physics and animations hold still until play carries on
+    Paused,
+}
+
+impl PlayState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlayState::Editing => "Editing",
+            PlayState::Playing => "Playing",
+            PlayState::Paused => "Paused",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct PlaySettings {
+    /// Stopping leaves the selected components the way play left them rather than restoring them,
+    /// their lights, materials, outline entries, scatter layers, physics and behaviors included
+    #[serde(default)]
+    pub keep_selected_changes: bool,
+}
+
+/// The level as it was when play started, put back on stop. Nothing is written to disk while a
+/// session is open.
+pub struct PlaySession {
+    saved_state: SavedState,
+    level_id: String,
+    level_data: LevelEditorData,
+    editor_camera: EditorCamera,
+    camera_position: Point3<f32>,
+    camera_direction: Vector3<f32>,
+}
+
+/// A model carried along by its rigid body while playing
+struct SimulatedBody {
+    component_id: String,
+    body: RigidBodyHandle,
+    /// Where the model sits relative to its body
+    body_to_model: Isometry3<f32>,
+    scale: [f32; 3],
+}
+
+/// The camera while playing, walking on whatever it collides with
+struct PlayerController {
+    /// Center of the capsule
+    position: Vector3<f32>,
+    /// Where play started, for when the player falls off the level
+    spawn: Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+    vertical_speed: f32,
+    grounded: bool,
+    shape: SharedShape,
+    controller: KinematicCharacterController,
+}
+
+impl PlayerController {
+    /// Stands the player where the camera is, on the ground under it when there is one
+    fn new(renderer_state: &RendererState) -> Self {
+        let camera = get_camera();
+        let eye = camera.position.coords;
+        let position = match surface_under_ray(renderer_state, eye, -Vector3::y(), "") {
+            Some((ground, _)) => ground + Vector3::y() * (PLAYER_HALF_HEIGHT + PLAYER_RADIUS),
+            None => eye - Vector3::y() * PLAYER_EYE_OFFSET,
+        };
+        let (yaw, pitch) = yaw_pitch(camera.direction);
+
+        Self {
+            position,
+            spawn: position,
+            yaw,
+            pitch,
+            vertical_speed: 0.0,
+            grounded: false,
+            shape: SharedShape::capsule_y(PLAYER_HALF_HEIGHT, PLAYER_RADIUS),
+            controller: KinematicCharacterController::default(),
+        }
+    }
+
+    fn look(&mut self, dx: f32, dy: f32) {
+        self.yaw += dx * LOOK_SENSITIVITY;
+        self.pitch = (self.pitch - dy * LOOK_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    /// Walks, jumps and falls for one step, sliding along whatever the capsule runs into
+    fn update(&mut self, dt: f32, renderer_state: &RendererState, held_keys: &HashSet<String>) {
+        if self.grounded && held_keys.contains("space") {
+            self.vertical_speed = PLAYER_JUMP_SPEED;
+        }
+        self.vertical_speed -= GRAVITY * dt;
+
+        let walk = walk_direction(self.yaw, held_keys) * PLAYER_WALK_SPEED;
+        let desired = Vector3::new(walk.x, self.vertical_speed, walk.z) * dt;
+
+        let gizmo_ids: Vec<u128> = renderer_state
+            .translation_gizmo
+            .arrows
+            .iter()
+            .map(|arrow| arrow.id.as_u128())
+            .collect();
+        let predicate =
+            |_: ColliderHandle, collider: &Collider| !gizmo_ids.contains(&collider.user_data);
+
+        let movement = self.controller.move_shape(
+            dt,
+            &renderer_state.rigid_body_set,
+            &renderer_state.collider_set,
+            &renderer_state.query_pipeline,
+            &*self.shape,
+            &Isometry3::translation(self.position.x, self.position.y, self.position.z),
+            desired,
+            QueryFilter::default().predicate(&predicate),
+            |_| {},
+        );
+
+        self.position += movement.translation;
+        self.grounded = movement.grounded;
+        if self.grounded && self.vertical_speed < 0.0 {
+            self.vertical_speed = 0.0;
+        }
+
+        if self.position.y < self.spawn.y - FALL_LIMIT {
+            self.position = self.spawn;
+            self.vertical_speed = 0.0;
+        }
+    }
+
+    fn place_camera(&self) {
+        let camera = get_camera();
+        camera.position = Point3::from(self.position + Vector3::y() * PLAYER_EYE_OFFSET);
+        camera.direction = look_direction(self.yaw, self.pitch);
+        camera.update_view_projection_matrix();
+    }
+}
+
+/// Turn around the up axis and angle above the horizon of a view direction
+fn yaw_pitch(direction: Vector3<f32>) -> (f32, f32) {
+    let direction = direction
+        .try_normalize(1e-6)
+        .unwrap_or_else(|| -Vector3::z());
+
+    (
+        direction.z.atan2(direction.x),
+        direction
+            .y
+            .clamp(-1.0, 1.0)
+            .asin()
+            .clamp(-MAX_PITCH, MAX_PITCH),
+    )
+}
+
+fn look_direction(yaw: f32, pitch: f32) -> Vector3<f32> {
+    Vector3::new(
+        pitch.cos() * yaw.cos(),
+        pitch.sin(),
+        pitch.cos() * yaw.sin(),
+    )
+}
+
+/// Way along the ground the held WASD keys walk, zero when none or only opposing ones are held
+fn walk_direction(yaw: f32, held_keys: &HashSet<String>) -> Vector3<f32> {
+    let forward = Vector3::new(yaw.cos(), 0.0, yaw.sin());
+    let right = forward.cross(&Vector3::y());
+    let held = |key: &str| if held_keys.contains(key) { 1.0 } else { 0.0 };
+
+    (forward * (held("w") - held("s")) + right * (held("d") - held("a")))
+        .try_normalize(1e-6)
+        .unwrap_or_else(Vector3::zeros)
+}
+
+/// Name of a key in the held keys and behavior events, from the text the key types
+pub fn play_key_name(key_text: &str) -> String {
+    match key_text {
+        " " => "space".to_string(),
+        text => text.to_lowercase(),
+    }
+}
+
+/// What the render callback runs while playing
+#[derive(Default)]
+pub struct PlaySimulation {
+    pub state: PlayState,
+    bodies: Vec<SimulatedBody>,
+    /// Latest transform of each simulated model, by component id
+    transforms: HashMap<String, [[f32; 3]; 3]>,
+    /// Graphs of the level's components, run on each step
+    behaviors: Vec<Behavior>,
+    /// Seconds played, pauses left out
+    elapsed: f32,
+    last_step: Option<Instant>,
+    held_keys: HashSet<String>,
+    player: Option<PlayerController>,
+}
+
+impl PlaySimulation {
+    /// Runs the behaviors, moves the simulated models to their bodies and walks the player,
+    /// called once physics has stepped
+    pub fn step(&mut self, renderer_state: &mut RendererState) {
+        if self.state != PlayState::Playing {
+            // time spent paused doesn't count toward the next step
+            self.last_step = None;
+            return;
+        }
+
+        let now = Instant::now();
+        let dt = self
+            .last_step
+            .map_or(FIRST_STEP_SECONDS, |last| (now - last).as_secs_f32())
+            .min(MAX_STEP_SECONDS);
+        self.last_step = Some(now);
+        let last_elapsed = self.elapsed;
+        self.elapsed += dt;
+
+        self.run_behaviors(renderer_state, last_elapsed);
+
+        for simulated in self.bodies.iter() {
+            let Some(body) = renderer_state.rigid_body_set.get(simulated.body) else {
+                continue;
+            };
+            let model_isometry = body.position() * simulated.body_to_model;
+            let (roll, pitch, yaw) = model_isometry.rotation.euler_angles();
+            let position: [f32; 3] = model_isometry.translation.vector.into();
+            let rotation = [roll, pitch, yaw];
+
+            if let Some(model) = renderer_state
+                .models
+                .iter_mut()
+                .find(|m| m.id == simulated.component_id)
+            {
+                model.meshes.iter_mut().for_each(|mesh| {
+                    mesh.transform.update_position(position);
+                    mesh.transform.update_rotation(rotation);
+                });
+            }
+
+            self.transforms.insert(
+                simulated.component_id.clone(),
+                [position, rotation, simulated.scale],
+            );
+        }
+
+        if let Some(player) = self.player.as_mut() {
+            player.update(dt, renderer_state, &self.held_keys);
+            player.place_camera();
+        }
+    }
+
+    /// Evaluates the components' graphs and does what they trigger to the components' bodies,
+    /// the physics step after this one carries it out
+    fn run_behaviors(&self, renderer_state: &mut RendererState, last_elapsed: f32) {
+        let input = BehaviorInput {
+            elapsed: self.elapsed,
+            last_elapsed,
+            held_keys: &self.held_keys,
+        };
+
+        for simulated in self.bodies.iter() {
+            let Some(body) = renderer_state.rigid_body_set.get_mut(simulated.body) else {
+                continue;
+            };
+            // forces only push for as long as their trigger keeps firing
+            body.reset_forces(true);
+            body.reset_torques(true);
+
+            let Some(behavior) = self
+                .behaviors
+                .iter()
+                .find(|behavior| behavior.component_id == simulated.component_id)
+            else {
+                continue;
+            };
+
+            for command in evaluate_behavior(&behavior.nodes, &input) {
+                match command {
+                    BehaviorCommand::ApplyImpulse(impulse) => body.apply_impulse(impulse, true),
+                    BehaviorCommand::ApplyForce(force) => body.add_force(force, true),
+                    BehaviorCommand::SetVelocity(velocity) => body.set_linvel(velocity, true),
+                    BehaviorCommand::AddTorque(torque) => body.add_torque(torque, true),
+                    BehaviorCommand::SetPosition(position) => {
+                        // the model lands on the position rather than its body
+                        let offset = body.rotation() * simulated.body_to_model.translation.vector;
+                        body.set_translation(position - offset, true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Notes a key going down or up for the player and behaviors. False while editing, when the
+    /// fly camera gets the key instead.
+    pub fn set_key_held(&mut self, key: &str, held: bool) -> bool {
+        if self.state == PlayState::Editing {
+            return false;
+        }
+
+        if held {
+            self.held_keys.insert(key.to_string());
+        } else {
+            self.held_keys.remove(key);
+        }
+
+        true
+    }
+
+    /// Turns the player with the mouse, false while editing when the fly camera turns instead
+    pub fn look(&mut self, dx: f32, dy: f32) -> bool {
+        let Some(player) = self.player.as_mut() else {
+            return false;
+        };
+
+        // a paused player holds still
+        if self.state == PlayState::Playing {
+            player.look(dx, dy);
+        }
+
+        true
+    }
+}
+
+/// Models that fall and collide while playing, the rest stay where they were placed
+pub fn is_simulated(state_helper: &StateHelper, component_id: &str) -> bool {
+    let (Some(editor_data), Some(level_id)) = (
+        state_helper.editor_data.as_ref(),
+        active_level_id(state_helper),
+    ) else {
+        return false;
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map_or(false, |level| {
+            level
+                .simulated_components
+                .iter()
+                .any(|id| id == component_id)
+        })
+}
+
+pub fn set_simulated(state_helper: &StateHelper, component_id: &str, simulated: bool) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+        let simulated_components = &mut editor_data.level_mut(&level_id).simulated_components;
+
+        simulated_components.retain(|id| id != component_id);
+        if simulated {
+            simulated_components.push(component_id.to_string());
+        }
+    }
+
+    state_helper.save_current_editor_data();
+}
+
+pub fn play_state(state_helper: &StateHelper) -> PlayState {
+    state_helper
+        .editor_render
+        .as_ref()
+        .map(|editor_render| editor_render.lock().unwrap().play.state)
+        .unwrap_or_default()
+}
+
+/// Turns the bodies of the components' colliders dynamic, noting where each model sits on its body
+fn simulate_bodies(
+    renderer_state: &mut RendererState,
+    components: &[ComponentData],
+) -> Vec<SimulatedBody> {
+    let mut bodies = Vec::new();
+
+    for component in components {
+        let Ok(id) = Uuid::from_str(&component.id) else {
+            continue;
+        };
+        let Some(handle) = renderer_state
+            .collider_set
+            .iter()
+            .filter(|(_, collider)| collider.user_data == id.as_u128())
+            .find_map(|(_, collider)| collider.parent())
+        else {
+            continue;
+        };
+        let Some(body) = renderer_state.rigid_body_set.get_mut(handle) else {
+            continue;
+        };
+
+        let properties = &component.generic_properties;
+        let [roll, pitch, yaw] = properties.rotation;
+        let model_isometry = Isometry3::from_parts(
+            Translation3::from(Vector3::from(properties.position)),
+            UnitQuaternion::from_euler_angles(roll, pitch, yaw),
+        );
+
+        body.set_body_type(RigidBodyType::Dynamic, true);
+
+        bodies.push(SimulatedBody {
+            component_id: component.id.clone(),
+            body: handle,
+            body_to_model: body.position().inverse() * model_isometry,
+            scale: properties.scale,
+        });
+    }
+
+    bodies
+}
+
+/// Runs the active level as a game, or carries on after a pause
+pub fn play(state_helper: &mut StateHelper) {
+    match play_state(state_helper) {
+        PlayState::Editing => start_play(state_helper),
+        PlayState::Paused => set_play_state(state_helper, PlayState::Playing),
+        PlayState::Playing => {}
+    }
+}
+
+pub fn pause(state_helper: &StateHelper) {
+    if play_state(state_helper) == PlayState::Playing {
+        set_play_state(state_helper, PlayState::Paused);
+    }
+}
+
+fn set_play_state(state_helper: &StateHelper, state: PlayState) {
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().play.state = state;
+    }
+}
+
+/// Snapshots the saved state, the level's editor data and the camera, then hands the simulated
+/// models and those with a behavior to physics and the camera to the player
+fn start_play(state_helper: &mut StateHelper) {
+    let Some(level_id) = active_level_id(state_helper) else {
+        return;
+    };
+
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap()
+        .clone();
+    let level_data = state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id)
+        .clone();
+    let camera = get_camera();
+
+    state_helper.play_session = Some(PlaySession {
+        saved_state,
+        level_id,
+        level_data,
+        editor_camera: state_helper.editor_camera.clone(),
+        camera_position: camera.position,
+        camera_direction: camera.direction,
+    });
+
+    let behaviors = level_behaviors(state_helper);
+    let simulated: Vec<ComponentData> = active_level_components(state_helper)
+        .into_iter()
+        .filter(|component| component.kind == Some(ComponentKind::Model))
+        .filter(|component| {
+            is_simulated(state_helper, &component.id)
+                || behaviors.iter().any(|b| b.component_id == component.id)
+        })
+        .collect();
+
+    let (bodies, player) = {
+        let mut renderer_state = state_helper
+            .renderer_state
+            .as_ref()
+            .expect("Couldn't get RendererState")
+            .lock()
+            .unwrap();
+
+        (
+            simulate_bodies(&mut renderer_state, &simulated),
+            PlayerController::new(&renderer_state),
+        )
+    };
+    player.place_camera();
+
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().play = PlaySimulation {
+            state: PlayState::Playing,
+            bodies,
+            behaviors,
+            player: Some(player),
+            ..Default::default()
+        };
+    }
+}
+
+/// Replaces or adds the components in the level's list
+fn keep_components(saved_state: &mut SavedState, level_id: &str, kept: Vec<ComponentData>) {
+    let Some(components) =
+        active_level_mut(saved_state, Some(level_id)).and_then(|level| level.components.as_mut())
+    else {
+        return;
+    };
+
+    for component in kept {
+        match components.iter_mut().find(|c| c.id == component.id) {
+            Some(saved_component) => *saved_component = component,
+            None => components.push(component),
+        }
+    }
+}
+
+/// Swaps the selected components' entries in the restored list for the ones play left, dropping
+/// those deleted meanwhile and adding those created
+fn keep_entries<T: Clone>(
+    restored: &mut Vec<T>,
+    current: &[T],
+    ids: &[String],
+    id_of: impl Fn(&T) -> &str,
+) {
+    let kept = |entry: &T| ids.iter().any(|id| id == id_of(entry));
+
+    restored.retain(|entry| !kept(entry) || current.iter().any(|c| id_of(c) == id_of(entry)));
+    for entry in current.iter().filter(|entry| kept(entry)) {
+        match restored.iter_mut().find(|r| id_of(r) == id_of(entry)) {
+            Some(restored_entry) => *restored_entry = entry.clone(),
+            None => restored.push(entry.clone()),
+        }
+    }
+}
+
+/// Carries the selected components' editor data over from play onto the restored level. Level
+/// wide settings like the environment and lighting go back to how they were.
+fn keep_level_data(restored: &mut LevelEditorData, current: &LevelEditorData, ids: &[String]) {
+    keep_entries(&mut restored.lights, &current.lights, ids, |l| {
+        l.id.as_str()
+    });
+    keep_entries(&mut restored.materials, &current.materials, ids, |m| {
+        m.component_id.as_str()
+    });
+    keep_entries(&mut restored.hierarchy, &current.hierarchy, ids, |n| {
+        n.id.as_str()
+    });
+    keep_entries(
+        &mut restored.scatter_layers,
+        &current.scatter_layers,
+        ids,
+        |l| l.landscape_component_id.as_str(),
+    );
+    keep_entries(
+        &mut restored.simulated_components,
+        &current.simulated_components,
+        ids,
+        |id| id.as_str(),
+    );
+    keep_entries(&mut restored.behaviors, &current.behaviors, ids, |b| {
+        b.component_id.as_str()
+    });
+}
+
+/// Puts the level, its editor data and the camera back the way they were when play started,
+/// from memory. With the keep option on, the selected components stay as play left them, edits
+/// made to them meanwhile included, along with their editor data, and only then is the project
+/// saved.
+pub fn stop_play(state_helper: &mut StateHelper, gpu_resources: &GpuResources) {
+    let Some(session) = state_helper.play_session.as_ref() else {
+        return;
+    };
+    let level_id = session.level_id.clone();
+
+    let simulation = state_helper
+        .editor_render
+        .as_ref()
+        .map(|editor_render| std::mem::take(&mut editor_render.lock().unwrap().play))
+        .unwrap_or_default();
+
+    let mut saved_state = session.saved_state.clone();
+    let mut level_data = session.level_data.clone();
+    let keep_selected = state_helper.editor_settings.play.keep_selected_changes;
+
+    if keep_selected {
+        let kept: Vec<ComponentData> = selected_components(state_helper)
+            .into_iter()
+            .filter(|component| component.kind.is_some())
+            .map(|mut component| {
+                if let Some([position, rotation, _]) = simulation.transforms.get(&component.id) {
+                    component.generic_properties.position = *position;
+                    component.generic_properties.rotation = *rotation;
+                }
+
+                component
+            })
+            .collect();
+
+        keep_components(&mut saved_state, &level_id, kept);
+
+        let current = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap()
+            .level_mut(&level_id)
+            .clone();
+        keep_level_data(&mut level_data, &current, &state_helper.selection);
+    }
+
+    *state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap() = saved_state;
+    *state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id) = level_data;
+
+    // reloading the level drops the bodies physics moved along with the models, the session is
+    // still open so nothing it saves reaches the disk
+    open_level(state_helper, gpu_resources, &level_id);
+
+    let Some(session) = state_helper.play_session.take() else {
+        return;
+    };
+
+    if keep_selected {
+        let project_id = state_helper
+            .project_selected_signal
+            .expect("Couldn't get project signal")
+            .get();
+        let saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+
+        state_helper.save_saved_state(project_id, saved_state);
+        state_helper.save_current_editor_data();
+    }
+
+    state_helper.editor_camera = session.editor_camera;
+
+    let camera = get_camera();
+    camera.position = session.camera_position;
+    camera.direction = session.camera_direction;
+    camera.update_view_projection_matrix();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::lights::{LightComponent, LightKind};
+    use crate::helpers::materials::MaterialOverride;
+
+    fn keys(held: &[&str]) -> HashSet<String> {
+        held.iter().map(|key| key.to_string()).collect()
+    }
+
+    #[test]
+    fn the_player_walks_the_way_it_looks() {
+        let direction = Vector3::new(0.0, -0.5, -1.0).normalize();
+        let (yaw, pitch) = yaw_pitch(direction);
+        assert!((look_direction(yaw, pitch) - direction).norm() < 1e-5);
+
+        // forward follows the view flattened onto the ground, right is to its right
+        let forward = walk_direction(yaw, &keys(&["w"]));
+        assert!((forward - Vector3::new(0.0, 0.0, -1.0)).norm() < 1e-5);
+        let right = walk_direction(yaw, &keys(&["d"]));
+        assert!((right - Vector3::new(1.0, 0.0, 0.0)).norm() < 1e-5);
+
+        // diagonals aren't faster and opposing keys cancel out
+        assert!((walk_direction(yaw, &keys(&["w", "a"])).norm() - 1.0).abs() < 1e-5);
+        assert_eq!(walk_direction(yaw, &keys(&["w", "s"])), Vector3::zeros());
+    }
+
+    #[test]
+    fn keys_are_named_the_way_behaviors_name_them() {
+        assert_eq!(play_key_name("W"), "w");
+        assert_eq!(play_key_name(" "), "space");
+    }
+
+    #[test]
+    fn kept_changes_only_carry_over_the_selected_components_data() {
+        let light = LightComponent::new(LightKind::Point, [0.0; 3]);
+        let other_light = LightComponent::new(LightKind::Spot, [0.0; 3]);
+        let mut restored = LevelEditorData {
+            lights: vec![light.clone(), other_light.clone()],
+            materials: vec![MaterialOverride::new("deleted")],
+            ..Default::default()
+        };
+
+        let mut current = restored.clone();
+        current.lights[0].intensity = 42.0;
+        current.lights[1].intensity = 42.0;
+        current.materials = vec![MaterialOverride::new("model")];
+        current.simulated_components = vec!["model".to_string(), "unselected".to_string()];
+
+        let selection = vec![light.id.clone(), "model".to_string(), "deleted".to_string()];
+        keep_level_data(&mut restored, &current, &selection);
+
+        assert_eq!(restored.lights[0].intensity, 42.0);
+        assert_eq!(restored.lights[1].intensity, other_light.intensity);
+        assert_eq!(restored.materials, vec![MaterialOverride::new("model")]);
+        assert_eq!(restored.simulated_components, vec!["model".to_string()]);
+    }
+}
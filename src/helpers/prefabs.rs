@@ -0,0 +1,683 @@
+use std::sync::Arc;
+
+use midpoint_engine::floem::reactive::{SignalGet, SignalUpdate};
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::helpers::saved_data::ComponentData;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::component_edits::{
+    add_to_scene, record_component_edit, remove_from_scene, ComponentEdit,
+};
+use super::gizmos::{component_transform, with_transform};
+use super::hierarchy::follow_transform;
+use super::levels::{active_level, active_level_components, active_level_id};
+use super::lights::is_light_component;
+use super::selection::{selected_components, selection_pivot, PivotMode};
+
+/// Transform of a prefab's own origin
+const PREFAB_ORIGIN: [[f32; 3]; 3] = [[0.0; 3], [0.0; 3], [1.0; 3]];
+
+/// Differences smaller than this don't count as an override
+const OVERRIDE_EPSILON: f32 = 0.0001;
+
+/// Part of a placed component that an instance keeps when its prefab changes
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrefabField {
+    Name,
+    Position,
+    Rotation,
+    Scale,
+    Asset,
+    Textures,
+}
+
+/// Components saved as a reusable template, positioned relative to the prefab's origin
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Prefab {
+    pub id: String,
+    pub name: String,
+    /// Source components, their ids are what instance members point back to
+    pub components: Vec<ComponentData>,
+}
+
+/// A level component placed from one of the prefab's source components
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PrefabMember {
+    pub source_id: String,
+    pub component_id: String,
+    #[serde(default)]
+    pub overrides: Vec<PrefabField>,
+}
+
+/// One placement of a prefab in a level
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PrefabInstance {
+    pub id: String,
+    pub prefab_id: String,
+    /// Position, euler rotation and scale the prefab's origin was placed at
+    pub transform: [[f32; 3]; 3],
+    pub members: Vec<PrefabMember>,
+    /// Source components deleted from this instance, so prefab edits don't bring them back
+    #[serde(default)]
+    pub removed_sources: Vec<String>,
+}
+
+/// The source component placed under the instance transform
+fn placed_component(transform: [[f32; 3]; 3], source: &ComponentData) -> ComponentData {
+    with_transform(
+        source,
+        follow_transform(PREFAB_ORIGIN, transform, component_transform(source)),
+    )
+}
+
+/// The placed component taken back into the prefab's own space
+fn source_component(transform: [[f32; 3]; 3], component: &ComponentData) -> ComponentData {
+    with_transform(
+        component,
+        follow_transform(transform, PREFAB_ORIGIN, component_transform(component)),
+    )
+}
+
+fn differs(a: [f32; 3], b: [f32; 3]) -> bool {
+    a.iter()
+        .zip(b)
+        .any(|(a, b)| (a - b).abs() > OVERRIDE_EPSILON)
+}
+
+fn texture_ids(component: &ComponentData) -> [Option<String>; 3] {
+    component
+        .landscape_properties
+        .as_ref()
+        .map(|p| {
+            [
+                p.primary_texture_id.clone(),
+                p.rockmap_texture_id.clone(),
+                p.soil_texture_id.clone(),
+            ]
+        })
+        .unwrap_or_default()
+}
+
+/// Fields the component no longer shares with what its prefab placed
+fn changed_fields(placed: &ComponentData, current: &ComponentData) -> Vec<PrefabField> {
+    let (placed_props, current_props) = (&placed.generic_properties, &current.generic_properties);
+
+    [
+        (PrefabField::Name, placed_props.name != current_props.name),
+        (
+            PrefabField::Position,
+            differs(placed_props.position, current_props.position),
+        ),
+        (
+            PrefabField::Rotation,
+            differs(placed_props.rotation, current_props.rotation),
+        ),
+        (
+            PrefabField::Scale,
+            differs(placed_props.scale, current_props.scale),
+        ),
+        (PrefabField::Asset, placed.asset_id != current.asset_id),
+        (
+            PrefabField::Textures,
+            texture_ids(placed) != texture_ids(current),
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| field)
+    .collect()
+}
+
+/// The freshly placed component with the instance's overridden fields kept
+fn with_overrides(
+    placed: &ComponentData,
+    current: &ComponentData,
+    overrides: &[PrefabField],
+) -> ComponentData {
+    let mut component = placed.clone();
+    component.id = current.id.clone();
+
+    for field in overrides {
+        match field {
+            PrefabField::Name => {
+                component.generic_properties.name = current.generic_properties.name.clone()
+            }
+            PrefabField::Position => {
+                component.generic_properties.position = current.generic_properties.position
+            }
+            PrefabField::Rotation => {
+                component.generic_properties.rotation = current.generic_properties.rotation
+            }
+            PrefabField::Scale => {
+                component.generic_properties.scale = current.generic_properties.scale
+            }
+            PrefabField::Asset => component.asset_id = current.asset_id.clone(),
+            PrefabField::Textures => {
+                component.landscape_properties = current.landscape_properties.clone()
+            }
+        }
+    }
+
+    component
+}
+
+/// Drops members whose component was deleted and instances left without any
+pub fn sync_prefab_instances(
+    instances: &[PrefabInstance],
+    components: &[ComponentData],
+) -> Vec<PrefabInstance> {
+    instances
+        .iter()
+        .cloned()
+        .filter_map(|mut instance| {
+            let (kept, deleted): (Vec<_>, Vec<_>) = instance
+                .members
+                .into_iter()
+                .partition(|m| components.iter().any(|c| c.id == m.component_id));
+
+            instance.members = kept;
+            instance
+                .removed_sources
+                .extend(deleted.into_iter().map(|m| m.source_id));
+
+            (!instance.members.is_empty()).then_some(instance)
+        })
+        .collect()
+}
+
+pub fn project_prefabs(state_helper: &StateHelper) -> Vec<Prefab> {
+    state_helper
+        .editor_data
+        .as_ref()
+        .map(|editor_data| editor_data.lock().unwrap().prefabs.clone())
+        .unwrap_or_default()
+}
+
+/// Prefab instances in the active level that still have components
+pub fn level_prefab_instances(state_helper: &StateHelper) -> Vec<PrefabInstance> {
+    let (Some(saved_state), Some(editor_data)) = (
+        state_helper.saved_state.as_ref(),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    let saved_state = saved_state.lock().unwrap();
+    let Some(level) = active_level(&saved_state, state_helper.active_level_id.as_deref()) else {
+        return Vec::new();
+    };
+
+    let editor_data = editor_data.lock().unwrap();
+    let instances = editor_data
+        .level(&level.id)
+        .map(|level_data| level_data.prefab_instances.clone())
+        .unwrap_or_default();
+
+    sync_prefab_instances(&instances, level.components.as_deref().unwrap_or_default())
+}
+
+/// Instance the component was placed by, if any
+pub fn component_prefab_instance(
+    state_helper: &StateHelper,
+    component_id: &str,
+) -> Option<PrefabInstance> {
+    level_prefab_instances(state_helper)
+        .into_iter()
+        .find(|instance| {
+            instance
+                .members
+                .iter()
+                .any(|m| m.component_id == component_id)
+        })
+}
+
+fn store_level_instances(state_helper: &StateHelper, instances: Vec<PrefabInstance>) {
+    let level_id = active_level_id(state_helper).expect("Couldn't get active level");
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id)
+        .prefab_instances = instances;
+
+    state_helper.save_current_editor_data();
+}
+
+/// Saves the selected components as a prefab around their median, the selection becomes its
+/// first instance
+pub fn create_prefab(state_helper: &StateHelper, name: &str) -> Result<String, String> {
+    // lights aren't engine components, so prefabs leave them out
+    let components: Vec<ComponentData> = selected_components(state_helper)
+        .into_iter()
+        .filter(|component| !is_light_component(component))
+        .collect();
+    if components.is_empty() {
+        return Err("Select the components to save as a prefab".to_string());
+    }
+
+    let mut instances = level_prefab_instances(state_helper);
+    if components.iter().any(|c| {
+        instances
+            .iter()
+            .any(|i| i.members.iter().any(|m| m.component_id == c.id))
+    }) {
+        return Err("The selection is already part of a prefab".to_string());
+    }
+
+    let transform = [
+        selection_pivot(&components, PivotMode::Median),
+        [0.0; 3],
+        [1.0; 3],
+    ];
+
+    let mut members = Vec::new();
+    let sources: Vec<ComponentData> = components
+        .iter()
+        .map(|component| {
+            let mut source = source_component(transform, component);
+            source.id = Uuid::new_v4().to_string();
+
+            members.push(PrefabMember {
+                source_id: source.id.clone(),
+                component_id: component.id.clone(),
+                overrides: Vec::new(),
+            });
+
+            source
+        })
+        .collect();
+
+    let prefab = Prefab {
+        id: Uuid::new_v4().to_string(),
+        name: if name.is_empty() {
+            "Prefab".to_string()
+        } else {
+            name.to_string()
+        },
+        components: sources,
+    };
+    let prefab_id = prefab.id.clone();
+
+    instances.push(PrefabInstance {
+        id: Uuid::new_v4().to_string(),
+        prefab_id: prefab_id.clone(),
+        transform,
+        members,
+        removed_sources: Vec::new(),
+    });
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .prefabs
+        .push(prefab);
+
+    store_level_instances(state_helper, instances);
+
+    Ok(prefab_id)
+}
+
+/// Places a new instance of the prefab at the position, undone like any added components
+pub fn place_prefab(
+    state_helper: &mut StateHelper,
+    gpu_resources: Arc<GpuResources>,
+    prefab_id: &str,
+    position: [f32; 3],
+) -> Result<(), String> {
+    let prefab = project_prefabs(state_helper)
+        .into_iter()
+        .find(|p| p.id == prefab_id)
+        .ok_or("Couldn't find the prefab")?;
+
+    let transform = [position, [0.0; 3], [1.0; 3]];
+
+    let mut members = Vec::new();
+    let added: Vec<ComponentData> = prefab
+        .components
+        .iter()
+        .map(|source| {
+            let mut component = placed_component(transform, source);
+            component.id = Uuid::new_v4().to_string();
+
+            members.push(PrefabMember {
+                source_id: source.id.clone(),
+                component_id: component.id.clone(),
+                overrides: Vec::new(),
+            });
+
+            component
+        })
+        .collect();
+
+    record_component_edit(
+        state_helper,
+        ComponentEdit {
+            gpu_resources,
+            selection_after: added.iter().map(|c| c.id.clone()).collect(),
+            added,
+            removed: Vec::new(),
+            added_lights: Vec::new(),
+            removed_lights: Vec::new(),
+            selection_before: state_helper.selection.clone(),
+        },
+    );
+
+    // undoing the placement leaves the instance without components, which drops it
+    let mut instances = level_prefab_instances(state_helper);
+    instances.push(PrefabInstance {
+        id: Uuid::new_v4().to_string(),
+        prefab_id: prefab.id.clone(),
+        transform,
+        members,
+        removed_sources: Vec::new(),
+    });
+    store_level_instances(state_helper, instances);
+
+    Ok(())
+}
+
+/// Components an instance update touched in its level
+#[derive(Default)]
+struct InstanceUpdate {
+    changed: Vec<ComponentData>,
+    removed: Vec<String>,
+    added: Vec<ComponentData>,
+}
+
+/// Moves an instance from the old prefab source to the new one. Fields that differ from what the
+/// old source placed become overrides and are kept, without an old source every field follows.
+fn update_instance(
+    instance: &mut PrefabInstance,
+    components: &mut Vec<ComponentData>,
+    old: Option<&Prefab>,
+    new: &Prefab,
+) -> InstanceUpdate {
+    let mut update = InstanceUpdate::default();
+    let transform = instance.transform;
+
+    instance.members.retain_mut(|member| {
+        let Some(index) = components.iter().position(|c| c.id == member.component_id) else {
+            return false;
+        };
+        let current = components[index].clone();
+
+        let old_source =
+            old.and_then(|old| old.components.iter().find(|c| c.id == member.source_id));
+        if let Some(old_source) = old_source {
+            for field in changed_fields(&placed_component(transform, old_source), &current) {
+                if !member.overrides.contains(&field) {
+                    member.overrides.push(field);
+                }
+            }
+        }
+
+        match new.components.iter().find(|c| c.id == member.source_id) {
+            Some(new_source) => {
+                let placed = placed_component(transform, new_source);
+                components[index] = with_overrides(&placed, &current, &member.overrides);
+                update.changed.push(components[index].clone());
+                true
+            }
+            None => {
+                components.remove(index);
+                update.removed.push(member.component_id.clone());
+                false
+            }
+        }
+    });
+
+    for source in &new.components {
+        let placed_already = instance.members.iter().any(|m| m.source_id == source.id);
+        if placed_already || instance.removed_sources.contains(&source.id) {
+            continue;
+        }
+
+        let mut component = placed_component(transform, source);
+        component.id = Uuid::new_v4().to_string();
+
+        instance.members.push(PrefabMember {
+            source_id: source.id.clone(),
+            component_id: component.id.clone(),
+            overrides: Vec::new(),
+        });
+        components.push(component.clone());
+        update.added.push(component);
+    }
+
+    update
+}
+
+/// Runs the update over every instance of the prefab in every level, reloading what changed in
+/// the active level and saving the project and editor data
+fn update_prefab_instances(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    old: Option<&Prefab>,
+    new: &Prefab,
+    only_instance: Option<&str>,
+) {
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+    let active_id = active_level_id(state_helper);
+    let mut active_update = InstanceUpdate::default();
+
+    {
+        let mut saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+
+        for level in saved_state.levels.iter_mut().flatten() {
+            let Some(level_data) = editor_data
+                .levels
+                .iter_mut()
+                .find(|l| l.level_id == level.id)
+            else {
+                continue;
+            };
+            let components = level.components.get_or_insert_with(Vec::new);
+
+            level_data.prefab_instances =
+                sync_prefab_instances(&level_data.prefab_instances, components);
+
+            for instance in level_data.prefab_instances.iter_mut() {
+                let skipped = only_instance.is_some_and(|id| id != instance.id);
+                if instance.prefab_id != new.id || skipped {
+                    continue;
+                }
+
+                let update = update_instance(instance, components, old, new);
+
+                if active_id.as_deref() == Some(level.id.as_str()) {
+                    active_update.changed.extend(update.changed);
+                    active_update.removed.extend(update.removed);
+                    active_update.added.extend(update.added);
+                }
+            }
+        }
+
+        if let Some(level_components_signal) = state_helper.level_components_signal {
+            level_components_signal.set(
+                active_level(&saved_state, state_helper.active_level_id.as_deref())
+                    .and_then(|level| level.components.clone())
+                    .unwrap_or_default(),
+            );
+        }
+
+        state_helper.save_saved_state(project_id, saved_state);
+    }
+
+    state_helper.save_current_editor_data();
+
+    // assets and textures may have changed too, so the components load again
+    if let Some(renderer_state) = state_helper.renderer_state.as_ref() {
+        let ids: Vec<String> = active_update
+            .changed
+            .iter()
+            .map(|c| c.id.clone())
+            .chain(active_update.removed)
+            .collect();
+
+        remove_from_scene(&mut renderer_state.lock().unwrap(), &ids);
+    }
+
+    for component in active_update.changed.iter().chain(&active_update.added) {
+        add_to_scene(state_helper, gpu_resources, component);
+    }
+}
+
+/// Makes the instance's components the prefab's new source and updates every other instance,
+/// keeping what each of them overrides
+pub fn apply_instance_to_prefab(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    instance_id: &str,
+) -> Result<(), String> {
+    let instance = level_prefab_instances(state_helper)
+        .into_iter()
+        .find(|i| i.id == instance_id)
+        .ok_or("Couldn't find the prefab instance")?;
+    let components = active_level_components(state_helper);
+
+    let old = project_prefabs(state_helper)
+        .into_iter()
+        .find(|p| p.id == instance.prefab_id)
+        .ok_or("Couldn't find the prefab")?;
+
+    let mut new = old.clone();
+    new.components = instance
+        .members
+        .iter()
+        .filter_map(|member| {
+            let component = components.iter().find(|c| c.id == member.component_id)?;
+            let mut source = source_component(instance.transform, component);
+            source.id = member.source_id.clone();
+            Some(source)
+        })
+        .collect();
+
+    if let Some(prefab) = state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .prefabs
+        .iter_mut()
+        .find(|p| p.id == new.id)
+    {
+        *prefab = new.clone();
+    }
+
+    update_prefab_instances(state_helper, gpu_resources, Some(&old), &new, None);
+
+    // the applied instance picked up its own edits as overrides, it matches its prefab again
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+
+        for applied in editor_data
+            .levels
+            .iter_mut()
+            .flat_map(|level_data| level_data.prefab_instances.iter_mut())
+            .filter(|i| i.id == instance.id)
+        {
+            applied.removed_sources.clear();
+            for member in applied.members.iter_mut() {
+                member.overrides.clear();
+            }
+        }
+    }
+
+    state_helper.save_current_editor_data();
+
+    Ok(())
+}
+
+/// Puts the instance back to what its prefab places, dropping its overrides
+pub fn revert_prefab_instance(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    instance_id: &str,
+) -> Result<(), String> {
+    let prefab_id = {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+
+        let instance = editor_data
+            .levels
+            .iter_mut()
+            .flat_map(|level_data| level_data.prefab_instances.iter_mut())
+            .find(|i| i.id == instance_id)
+            .ok_or("Couldn't find the prefab instance")?;
+
+        for member in instance.members.iter_mut() {
+            member.overrides.clear();
+        }
+
+        instance.prefab_id.clone()
+    };
+
+    let prefab = project_prefabs(state_helper)
+        .into_iter()
+        .find(|p| p.id == prefab_id)
+        .ok_or("Couldn't find the prefab")?;
+
+    update_prefab_instances(
+        state_helper,
+        gpu_resources,
+        None,
+        &prefab,
+        Some(instance_id),
+    );
+
+    Ok(())
+}
+
+/// Forgets the prefab, its placed components stay in their levels as plain components
+pub fn delete_prefab(state_helper: &StateHelper, prefab_id: &str) {
+    {
+        let mut editor_data = state_helper
+            .editor_data
+            .as_ref()
+            .expect("Couldn't get editor data")
+            .lock()
+            .unwrap();
+
+        editor_data.prefabs.retain(|p| p.id != prefab_id);
+        for level_data in editor_data.levels.iter_mut() {
+            level_data
+                .prefab_instances
+                .retain(|i| i.prefab_id != prefab_id);
+        }
+    }
+
+    state_helper.save_current_editor_data();
+}
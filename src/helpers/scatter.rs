@@ -0,0 +1,501 @@
+use std::path::Path;
+
+use image::DynamicImage;
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::handle_add_model;
+use midpoint_engine::helpers::saved_data::{ComponentData, ComponentKind, GenericProperties};
+use nalgebra::{self as na, Isometry3, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::heightmaps::{
+    load_heightmap, normalize_heights, HeightmapFormat, HeightmapImportSettings,
+};
+use super::instancing::register_model_asset;
+use super::levels::{active_level, active_level_mut};
+
+/// Landscape map a scatter layer can be masked by
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ScatterMaskMap {
+    Rockmap,
+    Soil,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ScatterMask {
+    pub map: ScatterMaskMap,
+    /// 0 = red, 1 = green, 2 = blue, 3 = alpha
+    pub channel: usize,
+    /// Instances are only placed where the channel is at least this value (0..1)
+    pub threshold: f32,
+}
+
+/// A set of models distributed over one landscape component, regenerated from its settings
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScatterLayer {
+    pub id: String,
+    pub name: String,
+    pub landscape_component_id: String,
+    /// Model asset ids picked from at random for each instance
+    pub model_ids: Vec<String>,
+    /// Attempted instances per 100x100 world units, before the limits are applied
+    pub density: f32,
+    /// Allowed slope in degrees
+    pub slope_range: [f32; 2],
+    /// Allowed height in the landscape's normalized 0..1 range
+    pub height_range: [f32; 2],
+    pub mask: Option<ScatterMask>,
+    /// Random rotation around the up axis, in degrees
+    pub rotation_range: [f32; 2],
+    pub scale_range: [f32; 2],
+    pub seed: u64,
+    /// World units covered by the heightmap, must match the landscape's terrain size
+    pub world_size: f32,
+    /// World height of a normalized height of 1.0
+    pub height_scale: f32,
+    /// Level components created by the last generation, replaced on regenerate
+    #[serde(default)]
+    pub instance_ids: Vec<String>,
+}
+
+impl Default for ScatterLayer {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: "New Scatter".to_string(),
+            landscape_component_id: String::new(),
+            model_ids: Vec::new(),
+            density: 1.0,
+            slope_range: [0.0, 30.0],
+            height_range: [0.0, 1.0],
+            mask: None,
+            rotation_range: [0.0, 360.0],
+            scale_range: [0.8, 1.2],
+            seed: 1,
+            world_size: 1024.0,
+            height_scale: 200.0,
+            instance_ids: Vec::new(),
+        }
+    }
+}
+
+/// One placed model, relative to the landscape's position
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScatterInstance {
+    pub model_id: String,
+    pub position: [f32; 3],
+    /// Rotation around the up axis in radians
+    pub rotation_y: f32,
+    pub scale: f32,
+}
+
+/// Small deterministic generator so a seed always gives the same layout
+pub struct ScatterRng(u64);
+
+impl ScatterRng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    // splitmix64
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in 0..1
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, range: [f32; 2]) -> f32 {
+        range[0] + (range[1] - range[0]) * self.next_f32()
+    }
+}
+
+/// Bilinear sample with x and y in 0..1 across the whole matrix
+pub fn sample_bilinear(values: &na::DMatrix<f32>, x: f32, y: f32) -> f32 {
+    let (rows, cols) = values.shape();
+
+    let fx = (x.clamp(0.0, 1.0) * (cols - 1) as f32).min((cols - 1) as f32);
+    let fy = (y.clamp(0.0, 1.0) * (rows - 1) as f32).min((rows - 1) as f32);
+
+    let x0 = fx.floor() as usize;
+    let y0 = fy.floor() as usize;
+    let x1 = (x0 + 1).min(cols - 1);
+    let y1 = (y0 + 1).min(rows - 1);
+    let tx = fx - x0 as f32;
+    let ty = fy - y0 as f32;
+
+    let top = values[(y0, x0)] * (1.0 - tx) + values[(y0, x1)] * tx;
+    let bottom = values[(y1, x0)] * (1.0 - tx) + values[(y1, x1)] * tx;
+
+    top * (1.0 - ty) + bottom * ty
+}
+
+/// Slope in degrees at a normalized position, using world units for both axes
+fn slope_at(heights: &na::DMatrix<f32>, x: f32, y: f32, world_size: f32, height_scale: f32) -> f32 {
+    let (rows, cols) = heights.shape();
+    let step_x = 1.0 / (cols - 1).max(1) as f32;
+    let step_y = 1.0 / (rows - 1).max(1) as f32;
+
+    // one-sided at the borders so edge samples don't report half the slope
+    let (x0, x1) = ((x - step_x).max(0.0), (x + step_x).min(1.0));
+    let (y0, y1) = ((y - step_y).max(0.0), (y + step_y).min(1.0));
+
+    let dx = (sample_bilinear(heights, x1, y) - sample_bilinear(heights, x0, y)) * height_scale
+        / ((x1 - x0) * world_size);
+    let dz = (sample_bilinear(heights, x, y1) - sample_bilinear(heights, x, y0)) * height_scale
+        / ((y1 - y0) * world_size);
+
+    (dx * dx + dz * dz).sqrt().atan().to_degrees()
+}
+
+/// Reads one channel of a rock or soil map as 0..1 values
+pub fn mask_channel(img: &DynamicImage, channel: usize) -> na::DMatrix<f32> {
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+
+    na::DMatrix::from_fn(height as usize, width as usize, |y, x| {
+        rgba.get_pixel(x as u32, y as u32)[channel.min(3)] as f32 / 255.0
+    })
+}
+
+/// Distributes the layer's models over a normalized heightmap
+pub fn generate_scatter(
+    layer: &ScatterLayer,
+    heights: &na::DMatrix<f32>,
+    mask: Option<&na::DMatrix<f32>>,
+) -> Vec<ScatterInstance> {
+    let mut instances = Vec::new();
+
+    if layer.model_ids.is_empty() || heights.nrows() < 2 || heights.ncols() < 2 {
+        return instances;
+    }
+
+    let area = layer.world_size * layer.world_size;
+    let attempts = (layer.density.max(0.0) * area / (100.0 * 100.0)).round() as usize;
+
+    let mut rng = ScatterRng::new(layer.seed);
+
+    for _ in 0..attempts {
+        // always draw every value so one rejected candidate doesn't shift the rest
+        let x = rng.next_f32();
+        let y = rng.next_f32();
+        let model_roll = rng.next_u64();
+        let rotation = rng.range(layer.rotation_range);
+        let scale = rng.range(layer.scale_range);
+
+        let height = sample_bilinear(heights, x, y);
+        if height < layer.height_range[0] || height > layer.height_range[1] {
+            continue;
+        }
+
+        let slope = slope_at(heights, x, y, layer.world_size, layer.height_scale);
+        if slope < layer.slope_range[0] || slope > layer.slope_range[1] {
+            continue;
+        }
+
+        if let (Some(mask), Some(settings)) = (mask, layer.mask.as_ref()) {
+            if sample_bilinear(mask, x, y) < settings.threshold {
+                continue;
+            }
+        }
+
+        instances.push(ScatterInstance {
+            model_id: layer.model_ids[(model_roll % layer.model_ids.len() as u64) as usize].clone(),
+            position: [
+                x * layer.world_size,
+                height * layer.height_scale,
+                y * layer.world_size,
+            ],
+            rotation_y: rotation.to_radians(),
+            scale,
+        });
+    }
+
+    instances
+}
+
+/// Takes the layer's previous instances out of the level and the scene
+pub fn remove_scatter_instances(state_helper: &StateHelper, layer: &ScatterLayer) {
+    if layer.instance_ids.is_empty() {
+        return;
+    }
+
+    if let Some(renderer_state) = state_helper.renderer_state.as_ref() {
+        let mut renderer_state = renderer_state.lock().unwrap();
+        renderer_state
+            .models
+            .retain(|m| !layer.instance_ids.contains(&m.id));
+    }
+
+    let mut saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    if let Some(components) =
+        active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+            .and_then(|level| level.components.as_mut())
+    {
+        components.retain(|c| !layer.instance_ids.contains(&c.id));
+    }
+}
+
+/// Generates the layer and places every instance as a model component in the active level,
+/// returning the ids of the new components
+pub fn apply_scatter_layer(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    layer: &ScatterLayer,
+) -> Result<Vec<String>, String> {
+    remove_scatter_instances(state_helper, layer);
+
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+
+    let mut saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    let landscape_component = active_level(&saved_state, state_helper.active_level_id.as_deref())
+        .and_then(|level| level.components.as_ref())
+        .and_then(|components| {
+            components
+                .iter()
+                .find(|c| c.id == layer.landscape_component_id)
+        })
+        .cloned()
+        .ok_or("Couldn't find the scatter layer's landscape")?;
+
+    let landscape = saved_state
+        .landscapes
+        .as_ref()
+        .and_then(|landscapes| {
+            landscapes
+                .iter()
+                .find(|l| l.id == landscape_component.asset_id)
+        })
+        .cloned()
+        .ok_or("Couldn't find landscape data")?;
+
+    let heightmap = landscape.heightmap.ok_or("Landscape has no heightmap")?;
+    let heights = load_heightmap(Path::new(&heightmap.normalFilePath), &HeightmapFormat::Tiff)?;
+    let heights = normalize_heights(&heights, &HeightmapImportSettings::default());
+
+    let mask = match layer.mask.as_ref() {
+        Some(settings) => {
+            let file = match settings.map {
+                ScatterMaskMap::Rockmap => landscape.rockmap,
+                ScatterMaskMap::Soil => landscape.soil,
+            }
+            .ok_or("Landscape has no map for the scatter mask")?;
+
+            let img = image::open(&file.normalFilePath)
+                .map_err(|e| format!("Couldn't open scatter mask: {}", e))?;
+
+            Some(mask_channel(&img, settings.channel))
+        }
+        None => None,
+    };
+
+    let instances = generate_scatter(layer, &heights, mask.as_ref());
+    let origin = landscape_component.generic_properties.position;
+
+    println!(
+        "Scattering {} instances for {}",
+        instances.len(),
+        layer.name
+    );
+
+    let mut components = Vec::new();
+    let mut placed = Vec::new();
+
+    for (index, instance) in instances.iter().enumerate() {
+        let Some(model) = saved_state
+            .models
+            .iter()
+            .find(|m| m.id == instance.model_id)
+        else {
+            continue;
+        };
+
+        let component_id = Uuid::new_v4().to_string();
+        let position = [
+            origin[0] + instance.position[0],
+            origin[1] + instance.position[1],
+            origin[2] + instance.position[2],
+        ];
+
+        placed.push((
+            instance.model_id.clone(),
+            component_id.clone(),
+            model.fileName.clone(),
+            position,
+            instance.rotation_y,
+            instance.scale,
+        ));
+
+        components.push(ComponentData {
+            id: component_id,
+            kind: Some(ComponentKind::Model),
+            asset_id: instance.model_id.clone(),
+            generic_properties: GenericProperties {
+                name: format!("{} {}", layer.name, index + 1),
+                position,
+                rotation: [0.0, instance.rotation_y, 0.0],
+                scale: [instance.scale, instance.scale, instance.scale],
+            },
+            landscape_properties: None,
+            model_properties: None,
+        });
+    }
+
+    let instance_ids = components.iter().map(|c| c.id.clone()).collect();
+
+    active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+        .expect("Couldn't get active level")
+        .components
+        .get_or_insert_with(Vec::new)
+        .extend(components);
+
+    state_helper.save_saved_state(project_id, saved_state);
+
+    let renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .expect("Couldn't get RendererState");
+
+    // scattered instances skip colliders so they don't get in the way of picking
+    for (model_id, component_id, filename, position, rotation_y, scale) in placed {
+        register_model_asset(state_helper, &component_id, &model_id);
+        handle_add_model(
+            renderer_state.clone(),
+            &gpu_resources.device,
+            &gpu_resources.queue,
+            project_id.to_string(),
+            model_id,
+            component_id.clone(),
+            filename,
+            Isometry3::new(
+                Vector3::new(position[0], position[1], position[2]),
+                Vector3::new(0.0, rotation_y, 0.0),
+            ),
+        );
+
+        let mut renderer_state = renderer_state.lock().unwrap();
+        if let Some(model) = renderer_state
+            .models
+            .iter_mut()
+            .find(|m| m.id == component_id)
+        {
+            model.meshes.iter_mut().for_each(|mesh| {
+                mesh.transform.update_scale([scale, scale, scale]);
+            });
+        }
+    }
+
+    Ok(instance_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(seed: u64) -> ScatterLayer {
+        ScatterLayer {
+            model_ids: vec!["rock".to_string(), "tree".to_string()],
+            density: 40.0,
+            world_size: 100.0,
+            height_scale: 200.0,
+            seed,
+            ..Default::default()
+        }
+    }
+
+    fn flat(height: f32) -> na::DMatrix<f32> {
+        na::DMatrix::from_element(16, 16, height)
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_layout() {
+        let heights = flat(0.5);
+
+        let first = generate_scatter(&layer(7), &heights, None);
+        let second = generate_scatter(&layer(7), &heights, None);
+
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_seeds_give_different_layouts() {
+        let heights = flat(0.5);
+
+        assert_ne!(
+            generate_scatter(&layer(7), &heights, None),
+            generate_scatter(&layer(8), &heights, None)
+        );
+    }
+
+    #[test]
+    fn density_sets_the_attempts_per_hundred_units_squared() {
+        // flat ground in range accepts every attempt
+        let instances = generate_scatter(&layer(3), &flat(0.5), None);
+
+        assert_eq!(instances.len(), 40);
+        for instance in &instances {
+            assert!((0.0..=100.0).contains(&instance.position[0]));
+            assert!((0.0..=100.0).contains(&instance.position[2]));
+            assert_eq!(instance.position[1], 100.0);
+            assert!((0.8..=1.2).contains(&instance.scale));
+        }
+    }
+
+    #[test]
+    fn heights_out_of_range_are_skipped() {
+        let mut high_only = layer(3);
+        high_only.height_range = [0.6, 1.0];
+
+        assert!(generate_scatter(&high_only, &flat(0.5), None).is_empty());
+    }
+
+    #[test]
+    fn steep_ground_is_skipped() {
+        // rises 200 units over 100, about 63 degrees
+        let ramp = na::DMatrix::from_fn(16, 16, |_, x| x as f32 / 15.0);
+
+        assert!(generate_scatter(&layer(3), &ramp, None).is_empty());
+    }
+
+    #[test]
+    fn layers_without_models_place_nothing() {
+        let mut empty = layer(3);
+        empty.model_ids.clear();
+
+        assert!(generate_scatter(&empty, &flat(0.5), None).is_empty());
+    }
+
+    #[test]
+    fn sample_bilinear_blends_between_samples() {
+        let values = na::DMatrix::from_row_slice(2, 2, &[0.0, 1.0, 2.0, 3.0]);
+
+        assert_eq!(sample_bilinear(&values, 0.0, 0.0), 0.0);
+        assert_eq!(sample_bilinear(&values, 1.0, 1.0), 3.0);
+        assert_eq!(sample_bilinear(&values, 0.5, 0.5), 1.5);
+    }
+}
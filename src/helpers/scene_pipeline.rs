@@ -0,0 +1,660 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::handlers::Vertex;
+use nalgebra::Matrix4;
+use uuid::Uuid;
+use wgpu::util::DeviceExt;
+
+use super::culling::{component_collider_bounds, cube_bounds, Frustum};
+use super::editor_render::EditorRenderState;
+use super::instancing::{model_matrix, InstanceRaw, MIN_INSTANCES};
+use super::materials::SurfaceUniform;
+use super::performance::DrawCounts;
+use super::view_modes::RenderMode;
+
+/// Multisampling of the primary pipeline, every target it draws into has to match
+pub const SCENE_SAMPLE_COUNT: u32 = 4;
+pub const SCENE_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
+
+fn uniform_layout_entry(visibility: wgpu::ShaderStages) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding: 0,
+        visibility,
+        ty: wgpu::BindingType::Buffer {
+            ty: wgpu::BufferBindingType::Uniform,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Bind group layouts of the primary pipeline. The window and offscreen pipelines share them so
+/// the engine's mesh, texture and light bind groups work with both.
+pub struct SceneLayouts {
+    pub camera: Arc<wgpu::BindGroupLayout>,
+    pub model: Arc<wgpu::BindGroupLayout>,
+    pub texture: Arc<wgpu::BindGroupLayout>,
+    pub light: Arc<wgpu::BindGroupLayout>,
+    pub pipeline_layout: Arc<wgpu::PipelineLayout>,
+    /// The sky pipeline's only group, kept apart from the primary pipeline
+    pub sky: Arc<wgpu::BindGroupLayout>,
+}
+
+impl SceneLayouts {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let camera = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[uniform_layout_entry(wgpu::ShaderStages::VERTEX)],
+        });
+
+        let model = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("model_bind_group_layout"),
+            entries: &[uniform_layout_entry(wgpu::ShaderStages::VERTEX)],
+        });
+
+        let light = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("light_bind_group_layout"),
+            entries: &[uniform_layout_entry(wgpu::ShaderStages::FRAGMENT)],
+        });
+
+        let texture = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    ..uniform_layout_entry(wgpu::ShaderStages::FRAGMENT)
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&camera, &model, &texture, &light],
+            push_constant_ranges: &[],
+        });
+
+        let sky_texture_entry = |binding, view_dimension| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Texture {
+                multisampled: false,
+                view_dimension,
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            },
+            count: None,
+        };
+
+        let sky = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Sky Bind Group Layout"),
+            entries: &[
+                uniform_layout_entry(wgpu::ShaderStages::FRAGMENT),
+                sky_texture_entry(1, wgpu::TextureViewDimension::Cube),
+                sky_texture_entry(2, wgpu::TextureViewDimension::D2),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
+        Self {
+            camera: Arc::new(camera),
+            model: Arc::new(model),
+            texture: Arc::new(texture),
+            light: Arc::new(light),
+            pipeline_layout: Arc::new(pipeline_layout),
+            sky: Arc::new(sky),
+        }
+    }
+}
+
+/// Camera and render mode buffers the engine's RendererState is created with. The render mode
+/// buffers hold a whole `SurfaceUniform` so they fit the shader's texture group.
+pub struct SceneBindings {
+    pub camera_uniform_buffer: Arc<wgpu::Buffer>,
+    pub camera_bind_group: Arc<wgpu::BindGroup>,
+    pub color_render_mode_buffer: Arc<wgpu::Buffer>,
+    pub texture_render_mode_buffer: Arc<wgpu::Buffer>,
+    pub reg_texture_render_mode_buffer: Arc<wgpu::Buffer>,
+}
+
+fn render_mode_buffer(device: &wgpu::Device, label: &str, mode: i32) -> Arc<wgpu::Buffer> {
+    Arc::new(
+        device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytemuck::cast_slice(&[SurfaceUniform::render_mode(mode)]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        }),
+    )
+}
+
+/// Camera uniform and bind group for a view projection matrix
+pub fn camera_binding(
+    device: &wgpu::Device,
+    layouts: &SceneLayouts,
+    view_projection: &[f32],
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Camera Uniform Buffer"),
+        contents: bytemuck::cast_slice(view_projection),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &layouts.camera,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: buffer.as_entire_binding(),
+        }],
+        label: Some("Camera Bind Group"),
+    });
+
+    (buffer, bind_group)
+}
+
+impl SceneBindings {
+    pub fn new(device: &wgpu::Device, layouts: &SceneLayouts, view_projection: &[f32]) -> Self {
+        let (camera_uniform_buffer, camera_bind_group) =
+            camera_binding(device, layouts, view_projection);
+
+        Self {
+            camera_uniform_buffer: Arc::new(camera_uniform_buffer),
+            camera_bind_group: Arc::new(camera_bind_group),
+            color_render_mode_buffer: render_mode_buffer(device, "Color Render Mode Buffer", 0),
+            texture_render_mode_buffer: render_mode_buffer(device, "Texture Render Mode Buffer", 1),
+            reg_texture_render_mode_buffer: render_mode_buffer(
+                device,
+                "Regular Texture Render Mode Buffer",
+                2,
+            ),
+        }
+    }
+}
+
+/// How a variant of the primary pipeline differs from the default one
+struct PipelineVariant {
+    label: &'static str,
+    fragment_entry_point: &'static str,
+    topology: wgpu::PrimitiveTopology,
+    polygon_mode: wgpu::PolygonMode,
+    depth_write_enabled: bool,
+    depth_compare: wgpu::CompareFunction,
+    /// Takes model matrices from an instance buffer rather than the model uniform
+    instanced: bool,
+}
+
+fn create_pipeline_variant(
+    device: &wgpu::Device,
+    layouts: &SceneLayouts,
+    format: wgpu::TextureFormat,
+    variant: PipelineVariant,
+) -> wgpu::RenderPipeline {
+    let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Primary Vert Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/primary_vertex.wgsl").into()),
+    });
+
+    let fragment_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Primary Frag Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/primary_fragment.wgsl").into()),
+    });
+
+    let vertex_buffers = [Vertex::desc(), InstanceRaw::desc()];
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(variant.label),
+        layout: Some(&layouts.pipeline_layout),
+        multiview: None,
+        cache: None,
+        vertex: wgpu::VertexState {
+            module: &vertex_shader,
+            entry_point: if variant.instanced {
+                "main_instanced"
+            } else {
+                "main"
+            },
+            buffers: if variant.instanced {
+                &vertex_buffers
+            } else {
+                &vertex_buffers[..1]
+            },
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &fragment_shader,
+            entry_point: variant.fragment_entry_point,
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState {
+                    color: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha: wgpu::BlendComponent {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                }),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            conservative: false,
+            topology: variant.topology,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: variant.polygon_mode,
+            unclipped_depth: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: SCENE_DEPTH_FORMAT,
+            depth_write_enabled: variant.depth_write_enabled,
+            depth_compare: variant.depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: SCENE_SAMPLE_COUNT,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+    })
+}
+
+/// The primary pipeline drawing into targets of the given format
+pub fn create_scene_pipeline(
+    device: &wgpu::Device,
+    layouts: &SceneLayouts,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    create_pipeline_variant(
+        device,
+        layouts,
+        format,
+        PipelineVariant {
+            label: "Midpoint Primary Render Pipeline",
+            fragment_entry_point: "main",
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            instanced: false,
+        },
+    )
+}
+
+/// The primary pipeline shading surfaces the render mode's way, or its twin drawing instances.
+/// None when the GPU can't draw the mode, wireframes need line polygon mode.
+pub fn create_render_mode_pipeline(
+    device: &wgpu::Device,
+    layouts: &SceneLayouts,
+    format: wgpu::TextureFormat,
+    mode: RenderMode,
+    instanced: bool,
+) -> Option<wgpu::RenderPipeline> {
+    let polygon_mode = match mode {
+        RenderMode::Wireframe => {
+            if !device
+                .features()
+                .contains(wgpu::Features::POLYGON_MODE_LINE)
+            {
+                return None;
+            }
+            wgpu::PolygonMode::Line
+        }
+        _ => wgpu::PolygonMode::Fill,
+    };
+
+    Some(create_pipeline_variant(
+        device,
+        layouts,
+        format,
+        PipelineVariant {
+            label: if instanced {
+                "Midpoint Instanced Render Mode Pipeline"
+            } else {
+                "Midpoint Render Mode Pipeline"
+            },
+            fragment_entry_point: mode.fragment_entry_point(),
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            polygon_mode,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            instanced,
+        },
+    ))
+}
+
+/// Line lists in vertex colors drawn over the scene, for collider and bounds overlays
+pub fn create_overlay_pipeline(
+    device: &wgpu::Device,
+    layouts: &SceneLayouts,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    create_pipeline_variant(
+        device,
+        layouts,
+        format,
+        PipelineVariant {
+            label: "Midpoint Overlay Pipeline",
+            fragment_entry_point: "main",
+            topology: wgpu::PrimitiveTopology::LineList,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            // lines lying on a surface still show
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            instanced: false,
+        },
+    )
+}
+
+/// The level's sky behind everything else, drawn first without touching the depth buffer
+pub fn create_sky_pipeline(
+    device: &wgpu::Device,
+    layouts: &SceneLayouts,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Sky Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("../shaders/sky.wgsl").into()),
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Sky Pipeline Layout"),
+        bind_group_layouts: &[&layouts.sky],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Midpoint Sky Pipeline"),
+        layout: Some(&pipeline_layout),
+        multiview: None,
+        cache: None,
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_sky",
+            buffers: &[],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_sky",
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: SCENE_DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: SCENE_SAMPLE_COUNT,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+    })
+}
+
+/// The camera and pipelines a level is drawn with
+pub struct LevelView<'a> {
+    pub camera_bind_group: &'a wgpu::BindGroup,
+    /// What the camera bind group holds, cubes and models outside it are skipped
+    pub view_projection: &'a Matrix4<f32>,
+    /// Draws copies of one asset together, None draws every mesh on its own
+    pub instanced_pipeline: Option<&'a wgpu::RenderPipeline>,
+}
+
+/// Draws the level's cubes, models, terrain and editor meshes, skipping hidden components and
+/// cubes and models outside the view. With `only` set just that component is drawn. Expects the
+/// primary pipeline to be set. Copies of one asset are drawn instanced last, leaving the
+/// instanced pipeline set. Returns what was drawn, leaving out the terrain the engine draws.
+pub fn draw_level(
+    render_pass: &mut wgpu::RenderPass,
+    engine: &RendererState,
+    editor_render: &EditorRenderState,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    view: &LevelView,
+    only: Option<&str>,
+) -> DrawCounts {
+    let mut counts = DrawCounts::default();
+    let mut scene_cache = editor_render.scene_cache.lock().unwrap();
+    let frustum = Frustum::from_view_projection(view.view_projection);
+    let collider_bounds = component_collider_bounds(engine);
+    let shown = |id: &str| {
+        !editor_render.hidden_components.contains(id) && only.map_or(true, |only| only == id)
+    };
+
+    // the level's own lights once the editor has set them up
+    let light_bind_group = editor_render
+        .light_bind_group
+        .as_ref()
+        .unwrap_or(&engine.light_state.bind_group);
+    render_pass.set_bind_group(3, light_bind_group, &[]);
+
+    if only.is_none() {
+        for cube in &engine.cubes {
+            let transform = &cube.transform;
+            if !frustum.intersects(&cube_bounds(&transform.position, &transform.scale)) {
+                counts.culled += 1;
+                continue;
+            }
+
+            // transforms only go to the GPU when they change
+            if scene_cache.transform_changed(
+                &cube.bind_group,
+                &transform.position,
+                &transform.rotation,
+                &transform.scale,
+            ) {
+                transform.update_uniform_buffer(queue);
+            }
+            render_pass.set_bind_group(0, view.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &cube.bind_group, &[]);
+
+            render_pass.set_vertex_buffer(0, cube.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(cube.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+
+            render_pass.draw_indexed(0..cube.index_count as u32, 0, 0..1);
+            counts.add_draw(cube.index_count as u32);
+        }
+    }
+
+    // models by index, copies of one asset wait to be drawn together
+    let mut singles = Vec::new();
+    let mut batches: HashMap<&String, Vec<usize>> = HashMap::new();
+
+    for (index, model) in engine.models.iter().enumerate() {
+        let Some(first_mesh) = model.meshes.first().filter(|_| shown(&model.id)) else {
+            continue;
+        };
+
+        let asset_id = editor_render.model_assets.get(&model.id);
+        let transform = &first_mesh.transform;
+        let matrix = model_matrix(&transform.position, &transform.rotation, &transform.scale);
+        let colliders = Uuid::parse_str(&model.id)
+            .ok()
+            .and_then(|id| collider_bounds.get(&id.as_u128()));
+
+        // models with no known bounds are always drawn
+        if let Some(bounds) = scene_cache.asset_world_bounds(asset_id, &matrix, colliders) {
+            if !frustum.intersects(&bounds) {
+                counts.culled += 1;
+                continue;
+            }
+        }
+
+        // a material override gives the copy textures of its own
+        let batch_asset = asset_id.filter(|_| {
+            only.is_none()
+                && view.instanced_pipeline.is_some()
+                && editor_render.material_bind_group(&model.id).is_none()
+        });
+
+        match batch_asset {
+            Some(asset_id) => batches.entry(asset_id).or_default().push(index),
+            None => singles.push(index),
+        }
+    }
+
+    batches.retain(|_, models| {
+        if models.len() < MIN_INSTANCES {
+            singles.append(models);
+        }
+
+        !models.is_empty()
+    });
+    singles.sort_unstable();
+
+    for model in singles.into_iter().map(|index| &engine.models[index]) {
+        // a material override stands in for every mesh's own textures
+        let material_bind_group = editor_render.material_bind_group(&model.id);
+
+        for mesh in &model.meshes {
+            let transform = &mesh.transform;
+            if scene_cache.transform_changed(
+                &mesh.bind_group,
+                &transform.position,
+                &transform.rotation,
+                &transform.scale,
+            ) {
+                transform.update_uniform_buffer(queue);
+            }
+            render_pass.set_bind_group(0, view.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &mesh.bind_group, &[]);
+            render_pass.set_bind_group(
+                2,
+                material_bind_group.unwrap_or(&mesh.texture_bind_group),
+                &[],
+            );
+
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+            render_pass.draw_indexed(0..mesh.index_count as u32, 0, 0..1);
+            counts.add_draw(mesh.index_count as u32);
+        }
+    }
+
+    for terrain_manager in engine
+        .terrain_managers
+        .iter()
+        .filter(|terrain_manager| shown(&terrain_manager.id))
+    {
+        terrain_manager.render(render_pass, view.camera_bind_group, queue);
+    }
+
+    // editor meshes such as water go after the terrain
+    if only.is_none() {
+        for mesh in &editor_render.water_meshes {
+            counts.add_draw(mesh.index_count);
+        }
+        editor_render.render(render_pass, view.camera_bind_group);
+
+        let mesh_count = engine.cubes.len()
+            + engine
+                .models
+                .iter()
+                .map(|model| model.meshes.len())
+                .sum::<usize>();
+        scene_cache.forget_removed(mesh_count, || {
+            engine
+                .cubes
+                .iter()
+                .map(|cube| cube.bind_group.global_id())
+                .chain(
+                    engine
+                        .models
+                        .iter()
+                        .flat_map(|model| &model.meshes)
+                        .map(|mesh| mesh.bind_group.global_id()),
+                )
+                .collect()
+        });
+    }
+
+    let Some(instanced_pipeline) = view.instanced_pipeline else {
+        return counts;
+    };
+
+    // one draw per mesh of an asset, its copies' matrices side by side in the instance buffer
+    let mut instances = Vec::new();
+    let mut instanced_draws = Vec::new();
+
+    for models in batches.values() {
+        for mesh_index in 0..engine.models[models[0]].meshes.len() {
+            let start = instances.len() as u32;
+            instances.extend(
+                models
+                    .iter()
+                    .filter_map(|index| engine.models[*index].meshes.get(mesh_index))
+                    .map(|mesh| InstanceRaw {
+                        model: model_matrix(
+                            &mesh.transform.position,
+                            &mesh.transform.rotation,
+                            &mesh.transform.scale,
+                        )
+                        .into(),
+                    }),
+            );
+
+            instanced_draws.push((models[0], mesh_index, start..instances.len() as u32));
+        }
+    }
+
+    let Some(instance_buffer) = scene_cache.write_instances(device, queue, &instances) else {
+        return counts;
+    };
+
+    render_pass.set_pipeline(instanced_pipeline);
+    render_pass.set_vertex_buffer(1, instance_buffer.slice(..));
+
+    for (model_index, mesh_index, instance_range) in instanced_draws {
+        let mesh = &engine.models[model_index].meshes[mesh_index];
+
+        // the instanced shader skips the model uniform, the layout still wants the group
+        render_pass.set_bind_group(0, view.camera_bind_group, &[]);
+        render_pass.set_bind_group(1, &mesh.bind_group, &[]);
+        render_pass.set_bind_group(2, &mesh.texture_bind_group, &[]);
+
+        render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        render_pass.draw_indexed(0..mesh.index_count as u32, 0, instance_range.clone());
+        counts.add_instanced_draw(mesh.index_count as u32, instance_range.len() as u32);
+    }
+
+    counts
+}
@@ -0,0 +1,441 @@
+use std::str::FromStr;
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::floem::reactive::SignalUpdate;
+use midpoint_engine::helpers::saved_data::ComponentData;
+use nalgebra::{Matrix4, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::gizmos::place_gizmos;
+use super::hierarchy::{hidden_component_ids, level_hierarchy, locked_component_ids};
+use super::levels::active_level_components;
+use super::lights::light_components;
+
+/// How far the mouse travels before a click turns into a box select, in pixels
+pub const BOX_SELECT_THRESHOLD: f64 = 4.0;
+
+/// Where the left button went down, kept until it comes back up
+#[derive(Clone, Debug)]
+pub struct PointerPress {
+    pub position: [f64; 2],
+    pub logical_position: [f64; 2],
+    pub box_selecting: bool,
+}
+
+/// Where a multi-object transform happens around
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PivotMode {
+    /// Middle of all selected origins
+    Median,
+    FirstSelected,
+    /// Each component rotates and scales around its own origin
+    IndividualOrigins,
+}
+
+/// Whether gizmo axes follow the world or the active component's rotation
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum TransformSpace {
+    World,
+    Local,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct TransformSettings {
+    #[serde(default = "default_pivot")]
+    pub pivot: PivotMode,
+    #[serde(default = "default_space")]
+    pub space: TransformSpace,
+}
+
+fn default_pivot() -> PivotMode {
+    PivotMode::Median
+}
+
+fn default_space() -> TransformSpace {
+    TransformSpace::World
+}
+
+impl Default for TransformSettings {
+    fn default() -> Self {
+        Self {
+            pivot: default_pivot(),
+            space: default_space(),
+        }
+    }
+}
+
+/// Marquee corners in logical pixels, top left then size, for the overlay
+pub fn marquee_rect(from: [f64; 2], to: [f64; 2]) -> (f64, f64, f64, f64) {
+    (
+        from[0].min(to[0]),
+        from[1].min(to[1]),
+        (from[0] - to[0]).abs(),
+        (from[1] - to[1]).abs(),
+    )
+}
+
+/// The level's components along with its lights, everything the selection can hold
+pub fn selectable_components(state_helper: &StateHelper) -> Vec<ComponentData> {
+    let mut components = active_level_components(state_helper);
+    components.extend(light_components(state_helper));
+
+    components
+}
+
+/// The selected components in selection order, the active component last
+pub fn selected_components(state_helper: &StateHelper) -> Vec<ComponentData> {
+    let components = selectable_components(state_helper);
+
+    state_helper
+        .selection
+        .iter()
+        .filter_map(|id| components.iter().find(|c| c.id == *id).cloned())
+        .collect()
+}
+
+/// Where the gizmo sits for the selection, individual origins still show it at the median
+pub fn selection_pivot(components: &[ComponentData], pivot: PivotMode) -> [f32; 3] {
+    match (pivot, components.first()) {
+        (_, None) => [0.0; 3],
+        (PivotMode::FirstSelected, Some(first)) => first.generic_properties.position,
+        _ => {
+            let sum = components
+                .iter()
+                .map(|c| Vector3::from(c.generic_properties.position))
+                .sum::<Vector3<f32>>();
+
+            (sum / components.len() as f32).into()
+        }
+    }
+}
+
+/// Euler rotation of the gizmo axes
+pub fn selection_rotation(components: &[ComponentData], space: TransformSpace) -> [f32; 3] {
+    match (space, components.last()) {
+        (TransformSpace::Local, Some(active)) => active.generic_properties.rotation,
+        _ => [0.0; 3],
+    }
+}
+
+/// Puts the gizmos on the selection's pivot, turned to the transform space
+pub fn place_selection_gizmos(
+    renderer_state: &mut RendererState,
+    components: &[ComponentData],
+    settings: TransformSettings,
+) {
+    place_gizmos(
+        renderer_state,
+        selection_pivot(components, settings.pivot),
+        selection_rotation(components, settings.space),
+    );
+}
+
+/// Moves the gizmos after the pivot or space setting changes
+pub fn refresh_selection_gizmos(state_helper: &StateHelper) {
+    let components = selected_components(state_helper);
+    if components.is_empty() {
+        return;
+    }
+
+    let mut renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .expect("Couldn't get RendererState")
+        .lock()
+        .unwrap();
+
+    place_selection_gizmos(
+        &mut renderer_state,
+        &components,
+        state_helper.editor_settings.transform,
+    );
+}
+
+/// Replaces the selection. The last id is the active component, which the gizmo, the
+/// properties panel and RendererState's `object_selected` follow.
+pub fn set_selection(state_helper: &mut StateHelper, ids: Vec<String>) {
+    let components = selectable_components(state_helper);
+
+    let mut selection: Vec<String> = Vec::new();
+    for id in ids {
+        if components.iter().any(|c| c.id == id) && !selection.contains(&id) {
+            selection.push(id);
+        }
+    }
+
+    let active = selection
+        .last()
+        .and_then(|id| components.iter().find(|c| c.id == *id))
+        .cloned();
+
+    state_helper.selection = selection.clone();
+    let selected = selected_components(state_helper);
+
+    if let Some(selection_signal) = state_helper.selection_signal {
+        selection_signal.set(selection);
+    }
+
+    if let Some(component) = active.as_ref() {
+        let component_id = Uuid::from_str(&component.id).expect("Couldn't extract uuid");
+
+        if let Some(selected_object_id_signal) = state_helper.selected_object_id_signal {
+            selected_object_id_signal.set(component_id);
+        }
+        if let Some(selected_object_data_signal) = state_helper.selected_object_data_signal {
+            selected_object_data_signal.set(component.clone());
+        }
+    }
+
+    if let Some(object_selected_signal) = state_helper.object_selected_signal {
+        object_selected_signal.set(active.is_some());
+    }
+
+    let mut renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .expect("Couldn't get RendererState")
+        .lock()
+        .unwrap();
+
+    match active {
+        Some(component) => {
+            renderer_state.object_selected =
+                Some(Uuid::from_str(&component.id).expect("Couldn't extract uuid"));
+            renderer_state.object_selected_kind = component.kind.clone();
+            renderer_state.object_selected_data = Some(component.clone());
+
+            place_selection_gizmos(
+                &mut renderer_state,
+                &selected,
+                state_helper.editor_settings.transform,
+            );
+        }
+        None => {
+            renderer_state.object_selected = None;
+            renderer_state.object_selected_kind = None;
+            renderer_state.object_selected_data = None;
+        }
+    }
+}
+
+/// Adds the id, or takes it out when it was already selected
+pub fn toggle_selected(selection: &[String], id: &str) -> Vec<String> {
+    if selection.iter().any(|s| s == id) {
+        selection.iter().filter(|s| *s != id).cloned().collect()
+    } else {
+        let mut toggled = selection.to_vec();
+        toggled.push(id.to_string());
+        toggled
+    }
+}
+
+/// Window position of a world point, None when it is behind the camera
+pub fn project_to_screen(
+    point: [f32; 3],
+    view_projection: &Matrix4<f32>,
+    width: f32,
+    height: f32,
+) -> Option<[f32; 2]> {
+    let clip = view_projection * Vector4::new(point[0], point[1], point[2], 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+
+    let ndc_x = clip.x / clip.w;
+    let ndc_y = clip.y / clip.w;
+
+    Some([(ndc_x + 1.0) / 2.0 * width, (1.0 - ndc_y) / 2.0 * height])
+}
+
+/// Components whose origin lands inside the box, corners in window pixels
+pub fn components_in_box(
+    components: &[ComponentData],
+    view_projection: &Matrix4<f32>,
+    window_size: [f32; 2],
+    from: [f32; 2],
+    to: [f32; 2],
+) -> Vec<String> {
+    let (min_x, max_x) = (from[0].min(to[0]), from[0].max(to[0]));
+    let (min_y, max_y) = (from[1].min(to[1]), from[1].max(to[1]));
+
+    components
+        .iter()
+        .filter(|component| {
+            project_to_screen(
+                component.generic_properties.position,
+                view_projection,
+                window_size[0],
+                window_size[1],
+            )
+            .map_or(false, |[x, y]| {
+                (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y)
+            })
+        })
+        .map(|component| component.id.clone())
+        .collect()
+}
+
+/// Finishes a click or box select on release, shift or ctrl add to the selection
+pub fn finish_selection(
+    state_helper: &mut StateHelper,
+    picked: Vec<String>,
+    clicked: bool,
+    additive: bool,
+) {
+    let current = state_helper.selection.clone();
+
+    let selection = match (clicked, additive) {
+        (true, true) => picked
+            .iter()
+            .fold(current, |selection, id| toggle_selected(&selection, id)),
+        (false, true) => current.into_iter().chain(picked).collect(),
+        (_, false) => picked,
+    };
+
+    set_selection(state_helper, selection);
+}
+
+pub fn box_select_components(
+    state_helper: &StateHelper,
+    view_projection: &Matrix4<f32>,
+    window_size: [f32; 2],
+    from: [f32; 2],
+    to: [f32; 2],
+) -> Vec<String> {
+    // hidden and locked components can't be picked in the viewport
+    let nodes = level_hierarchy(state_helper);
+    let hidden = hidden_component_ids(&nodes);
+    let locked = locked_component_ids(&nodes);
+
+    let components: Vec<ComponentData> = selectable_components(state_helper)
+        .into_iter()
+        .filter(|c| !hidden.contains(&c.id) && !locked.contains(&c.id))
+        .collect();
+
+    components_in_box(&components, view_projection, window_size, from, to)
+}
+
+/// Whether the id belongs to a component in the level rather than a gizmo or helper collider
+pub fn is_level_component(state_helper: &StateHelper, id: &str) -> bool {
+    active_level_components(state_helper)
+        .iter()
+        .any(|c| c.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use midpoint_engine::helpers::saved_data::{ComponentKind, GenericProperties};
+    use nalgebra::Perspective3;
+
+    fn component(id: &str, position: [f32; 3]) -> ComponentData {
+        ComponentData {
+            id: id.to_string(),
+            kind: Some(ComponentKind::Model),
+            asset_id: String::new(),
+            generic_properties: GenericProperties {
+                name: id.to_string(),
+                position,
+                rotation: [0.0; 3],
+                scale: [1.0; 3],
+            },
+            landscape_properties: None,
+            model_properties: None,
+        }
+    }
+
+    #[test]
+    fn project_to_screen_puts_y_up_at_the_top() {
+        let identity = Matrix4::identity();
+
+        assert_eq!(
+            project_to_screen([0.0, 0.0, 0.0], &identity, 100.0, 50.0),
+            Some([50.0, 25.0])
+        );
+        assert_eq!(
+            project_to_screen([0.5, 0.5, 0.0], &identity, 100.0, 50.0),
+            Some([75.0, 12.5])
+        );
+    }
+
+    #[test]
+    fn project_to_screen_skips_points_behind_the_camera() {
+        let projection = Perspective3::new(1.0, 1.0, 0.1, 100.0).to_homogeneous();
+
+        assert!(project_to_screen([0.0, 0.0, -5.0], &projection, 100.0, 100.0).is_some());
+        assert!(project_to_screen([0.0, 0.0, 5.0], &projection, 100.0, 100.0).is_none());
+    }
+
+    #[test]
+    fn components_in_box_picks_origins_inside_the_box() {
+        let components = [
+            component("center", [0.0, 0.0, 0.0]),
+            component("top_right", [0.5, 0.5, 0.0]),
+            component("bottom_left", [-0.5, -0.5, 0.0]),
+        ];
+        let identity = Matrix4::identity();
+
+        let picked = components_in_box(
+            &components,
+            &identity,
+            [100.0, 100.0],
+            [40.0, 10.0],
+            [90.0, 60.0],
+        );
+
+        assert_eq!(picked, vec!["center".to_string(), "top_right".to_string()]);
+    }
+
+    #[test]
+    fn components_in_box_accepts_boxes_dragged_either_way() {
+        let components = [component("center", [0.0, 0.0, 0.0])];
+        let identity = Matrix4::identity();
+
+        let forwards = components_in_box(
+            &components,
+            &identity,
+            [100.0, 100.0],
+            [40.0, 40.0],
+            [60.0, 60.0],
+        );
+        let backwards = components_in_box(
+            &components,
+            &identity,
+            [100.0, 100.0],
+            [60.0, 60.0],
+            [40.0, 40.0],
+        );
+
+        assert_eq!(forwards, backwards);
+        assert_eq!(forwards.len(), 1);
+    }
+
+    #[test]
+    fn components_in_box_leaves_out_components_behind_the_camera() {
+        let components = [component("behind", [0.0, 0.0, 5.0])];
+        let projection = Perspective3::new(1.0, 1.0, 0.1, 100.0).to_homogeneous();
+
+        assert!(components_in_box(
+            &components,
+            &projection,
+            [100.0, 100.0],
+            [0.0, 0.0],
+            [100.0, 100.0]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn toggle_selected_adds_and_removes() {
+        let selection = vec!["a".to_string()];
+
+        assert_eq!(
+            toggle_selected(&selection, "b"),
+            vec!["a".to_string(), "b".to_string()]
+        );
+        assert!(toggle_selected(&selection, "a").is_empty());
+    }
+}
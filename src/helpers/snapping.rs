@@ -0,0 +1,160 @@
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::helpers::saved_data::{ComponentData, ComponentKind};
+use midpoint_engine::rapier3d::prelude::{Collider, ColliderHandle, QueryFilter, Ray};
+use nalgebra::{Point3, UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How far the surface snap looks along the mouse ray
+const SURFACE_SNAP_DISTANCE: f32 = 10000.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SnapSettings {
+    /// Snapping while dragging, holding alt flips it for the drag
+    #[serde(default)]
+    pub enabled: bool,
+    /// World units, the engine's utility grids have a line every unit from the origin
+    #[serde(default = "default_translation_step")]
+    pub translation_step: f32,
+    #[serde(default = "default_rotation_step_degrees")]
+    pub rotation_step_degrees: f32,
+    #[serde(default = "default_scale_step")]
+    pub scale_step: f32,
+    /// Drops translated models onto whatever is under the mouse instead of following the gizmo
+    #[serde(default)]
+    pub surface: bool,
+    /// Turns surface snapped models so their up axis follows the surface normal
+    #[serde(default)]
+    pub align_to_normal: bool,
+}
+
+fn default_translation_step() -> f32 {
+    1.0
+}
+
+fn default_rotation_step_degrees() -> f32 {
+    15.0
+}
+
+fn default_scale_step() -> f32 {
+    0.1
+}
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            translation_step: default_translation_step(),
+            rotation_step_degrees: default_rotation_step_degrees(),
+            scale_step: default_scale_step(),
+            surface: false,
+            align_to_normal: false,
+        }
+    }
+}
+
+/// Rounds to the nearest multiple of the step, steps of zero or less turn snapping off
+pub fn snap_value(value: f32, step: f32) -> f32 {
+    if step <= 0.0 {
+        return value;
+    }
+
+    (value / step).round() * step
+}
+
+/// Euler angles turning the up axis onto the normal
+pub fn align_to_normal(normal: Vector3<f32>) -> [f32; 3] {
+    let rotation = UnitQuaternion::rotation_between(&Vector3::y(), &normal)
+        // pointing straight down has no unique rotation
+        .unwrap_or_else(|| UnitQuaternion::from_euler_angles(std::f32::consts::PI, 0.0, 0.0));
+    let (roll, pitch, yaw) = rotation.euler_angles();
+
+    [roll, pitch, yaw]
+}
+
+/// First landscape or model collider under the ray, skipping the dragged component and the gizmo
+pub fn surface_under_ray(
+    renderer_state: &RendererState,
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    dragged_component_id: &str,
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let ray = Ray::new(Point3::from(origin), direction);
+
+    let gizmo_ids: Vec<Uuid> = renderer_state
+        .translation_gizmo
+        .arrows
+        .iter()
+        .map(|arrow| arrow.id)
+        .collect();
+    let skip = |collider_id: Uuid| {
+        collider_id.to_string() == dragged_component_id || gizmo_ids.contains(&collider_id)
+    };
+    let predicate =
+        |_: ColliderHandle, collider: &Collider| !skip(Uuid::from_u128(collider.user_data));
+
+    let (_, intersection) = renderer_state.query_pipeline.cast_ray_and_get_normal(
+        &renderer_state.rigid_body_set,
+        &renderer_state.collider_set,
+        &ray,
+        SURFACE_SNAP_DISTANCE,
+        true,
+        QueryFilter::default().predicate(&predicate),
+    )?;
+
+    let point = ray.point_at(intersection.time_of_impact);
+
+    Some((point.coords, intersection.normal))
+}
+
+/// Moves a dragged model onto the surface under the mouse, landscapes are left as they are
+pub fn snap_to_surface(
+    renderer_state: &RendererState,
+    component: &ComponentData,
+    transform: &mut [[f32; 3]; 3],
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+    align: bool,
+) {
+    if component.kind != Some(ComponentKind::Model) {
+        return;
+    }
+
+    let Some((point, normal)) = surface_under_ray(renderer_state, origin, direction, &component.id)
+    else {
+        return;
+    };
+
+    transform[0] = point.into();
+    if align {
+        transform[1] = align_to_normal(normal);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_value_rounds_to_the_nearest_step() {
+        assert_eq!(snap_value(1.2, 0.5), 1.0);
+        assert_eq!(snap_value(1.3, 0.5), 1.5);
+        assert_eq!(snap_value(7.0, 5.0), 5.0);
+        assert_eq!(snap_value(8.0, 5.0), 10.0);
+    }
+
+    #[test]
+    fn snap_value_handles_negative_values() {
+        assert_eq!(snap_value(-1.2, 0.5), -1.0);
+        assert_eq!(snap_value(-1.3, 0.5), -1.5);
+        assert_eq!(snap_value(-0.2, 1.0), 0.0);
+        assert_eq!(snap_value(-4.0, 2.0), -4.0);
+    }
+
+    #[test]
+    fn snap_value_leaves_values_alone_without_a_step() {
+        assert_eq!(snap_value(1.234, 0.0), 1.234);
+        assert_eq!(snap_value(-1.234, 0.0), -1.234);
+        assert_eq!(snap_value(1.234, -1.0), 1.234);
+    }
+}
@@ -0,0 +1,582 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use image::DynamicImage;
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::handle_add_landscape;
+use midpoint_engine::helpers::saved_data::{
+    ComponentData, ComponentKind, File, GenericProperties, LandscapeData, LandscapeProperties,
+};
+use midpoint_engine::helpers::utilities::{get_heightmap_dir, get_rockmap_dir, get_soilmap_dir};
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::component_edits::remove_component_colliders;
+use super::heightmaps::{
+    load_heightmap, normalize_heights, save_engine_heightmap, HeightmapFormat,
+    HeightmapImportSettings,
+};
+use super::levels::{active_level, active_level_mut};
+use super::utilities::get_common_os_dir;
+
+/// Unloading happens a bit further out than loading so tiles don't flicker at the boundary
+const UNLOAD_DISTANCE_FACTOR: f32 = 1.2;
+
+/// A grid of landscape components that together form one world
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TileGrid {
+    pub id: String,
+    pub name: String,
+    pub columns: usize,
+    pub rows: usize,
+    /// World units covered by one tile along x and z
+    pub tile_size: f32,
+    /// Position of the first tile's corner
+    pub origin: [f32; 3],
+    /// Tiles closer than this to the camera are kept in the scene
+    pub stream_distance: f32,
+    /// The heightmap the tiles were split from, used for the map overview
+    pub source_heightmap: Option<String>,
+    pub tiles: Vec<LandscapeTile>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LandscapeTile {
+    pub column: usize,
+    pub row: usize,
+    /// LandscapeData asset holding this tile's maps
+    pub landscape_id: String,
+    /// Level component that places the tile
+    pub component_id: String,
+    /// Saved as `lod` before the settings were named for what they do
+    #[serde(default, alias = "lod")]
+    pub streaming: TileStreamSettings,
+}
+
+/// When the tile comes into the scene. Tiles always load at full detail, these only move the
+/// distance they stream in at.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TileStreamSettings {
+    /// Multiplies the grid's stream distance for this tile
+    pub distance_scale: f32,
+    /// Keep the tile in the scene regardless of camera distance
+    pub always_loaded: bool,
+}
+
+impl Default for TileStreamSettings {
+    fn default() -> Self {
+        Self {
+            distance_scale: 1.0,
+            always_loaded: false,
+        }
+    }
+}
+
+impl TileGrid {
+    pub fn tile_position(&self, column: usize, row: usize) -> [f32; 3] {
+        [
+            self.origin[0] + column as f32 * self.tile_size,
+            self.origin[1],
+            self.origin[2] + row as f32 * self.tile_size,
+        ]
+    }
+
+    /// Horizontal distance from a point to the tile's footprint, zero when inside it
+    pub fn distance_to_tile(&self, tile: &LandscapeTile, x: f32, z: f32) -> f32 {
+        let position = self.tile_position(tile.column, tile.row);
+
+        let dx = (position[0] - x)
+            .max(x - (position[0] + self.tile_size))
+            .max(0.0);
+        let dz = (position[2] - z)
+            .max(z - (position[2] + self.tile_size))
+            .max(0.0);
+
+        (dx * dx + dz * dz).sqrt()
+    }
+
+    pub fn tile_for_component(&self, component_id: &str) -> Option<&LandscapeTile> {
+        self.tiles.iter().find(|t| t.component_id == component_id)
+    }
+
+    pub fn tile_for_component_mut(&mut self, component_id: &str) -> Option<&mut LandscapeTile> {
+        self.tiles
+            .iter_mut()
+            .find(|t| t.component_id == component_id)
+    }
+}
+
+/// Splits a sample count into `count` ranges that share their border samples. Neighbouring
+/// tiles start from the same heights along their edge, that is all the stitching there is.
+pub fn tile_sample_ranges(total: usize, count: usize) -> Vec<(usize, usize)> {
+    if count == 0 || total < 2 {
+        return Vec::new();
+    }
+
+    let cells = total - 1;
+
+    (0..count)
+        .map(|i| {
+            let start = i * cells / count;
+            let end = (i + 1) * cells / count;
+            (start, end - start + 1)
+        })
+        .collect()
+}
+
+/// Cuts a heightmap into a grid of tiles, returned row by row as (column, row, heights)
+pub fn split_heightmap(
+    heights: &na::DMatrix<f32>,
+    columns: usize,
+    rows: usize,
+) -> Vec<(usize, usize, na::DMatrix<f32>)> {
+    let (height, width) = heights.shape();
+    let column_ranges = tile_sample_ranges(width, columns);
+    let row_ranges = tile_sample_ranges(height, rows);
+
+    let mut tiles = Vec::new();
+    for (row, (start_y, len_y)) in row_ranges.iter().enumerate() {
+        for (column, (start_x, len_x)) in column_ranges.iter().enumerate() {
+            let tile = heights
+                .view((*start_y, *start_x), (*len_y, *len_x))
+                .clone_owned();
+            tiles.push((column, row, tile));
+        }
+    }
+
+    tiles
+}
+
+/// Cuts a rock or soil map into the same grid as the heightmap
+pub fn split_image(img: &DynamicImage, columns: usize, rows: usize) -> Vec<DynamicImage> {
+    let column_ranges = tile_sample_ranges(img.width() as usize, columns);
+    let row_ranges = tile_sample_ranges(img.height() as usize, rows);
+
+    let mut tiles = Vec::new();
+    for (start_y, len_y) in &row_ranges {
+        for (start_x, len_x) in &column_ranges {
+            tiles.push(img.crop_imm(
+                *start_x as u32,
+                *start_y as u32,
+                *len_x as u32,
+                *len_y as u32,
+            ));
+        }
+    }
+
+    tiles
+}
+
+/// A tile that came into range and isn't in the scene yet
+struct TileLoad {
+    landscape_id: String,
+    component_id: String,
+    heightmap_filename: String,
+    position: [f32; 3],
+}
+
+/// Drops the tiles that are far from the camera and marks the ones that came into range as
+/// loading. Tiles that are already loading are left to the load that started them.
+fn update_streamed_tiles(
+    state_helper: &StateHelper,
+    camera_x: f32,
+    camera_z: f32,
+) -> Option<(String, Vec<TileLoad>)> {
+    let saved_state = state_helper.saved_state.as_ref()?.lock().unwrap();
+    let editor_data = state_helper.editor_data.as_ref()?.lock().unwrap();
+
+    let level_id = active_level(&saved_state, state_helper.active_level_id.as_deref())
+        .map(|level| level.id.clone());
+    let level_data = level_id.and_then(|id| editor_data.level(&id))?;
+
+    if level_data.tile_grids.is_empty() {
+        return None;
+    }
+
+    let mut renderer = state_helper.renderer_state.as_ref()?.lock().unwrap();
+    let project_id = renderer.project_selected?.to_string();
+    let mut loading_tiles = state_helper.loading_tiles.lock().unwrap();
+
+    let mut to_load = Vec::new();
+    let mut to_unload = Vec::new();
+
+    for grid in &level_data.tile_grids {
+        for tile in &grid.tiles {
+            let is_loaded = loading_tiles.contains(&tile.component_id)
+                || renderer
+                    .terrain_managers
+                    .iter()
+                    .any(|t| t.id == tile.component_id);
+            let distance = grid.distance_to_tile(tile, camera_x, camera_z);
+            let load_distance = grid.stream_distance * tile.streaming.distance_scale;
+
+            if !is_loaded && (tile.streaming.always_loaded || distance <= load_distance) {
+                let heightmap = saved_state
+                    .landscapes
+                    .as_ref()
+                    .and_then(|landscapes| landscapes.iter().find(|l| l.id == tile.landscape_id))
+                    .and_then(|landscape| landscape.heightmap.clone());
+
+                if let Some(heightmap) = heightmap {
+                    loading_tiles.insert(tile.component_id.clone());
+                    to_load.push(TileLoad {
+                        landscape_id: tile.landscape_id.clone(),
+                        component_id: tile.component_id.clone(),
+                        heightmap_filename: heightmap.fileName,
+                        position: grid.tile_position(tile.column, tile.row),
+                    });
+                }
+            } else if is_loaded
+                && !tile.streaming.always_loaded
+                && distance > load_distance * UNLOAD_DISTANCE_FACTOR
+            {
+                to_unload.push(tile.component_id.clone());
+            }
+        }
+    }
+
+    if !to_unload.is_empty() {
+        println!("Unloading {} landscape tiles", to_unload.len());
+
+        renderer
+            .terrain_managers
+            .retain(|t| !to_unload.contains(&t.id));
+
+        // picking, snapping and physics would still hit a heightfield left behind
+        for component_id in to_unload.iter() {
+            remove_component_colliders(&mut renderer, component_id);
+        }
+    }
+
+    Some((project_id, to_load))
+}
+
+/// Reads the tile's heightmap and adds it to the scene, then clears its loading mark
+fn load_tile(
+    renderer_state: &Arc<Mutex<RendererState>>,
+    loading_tiles: &Mutex<HashSet<String>>,
+    gpu_resources: &GpuResources,
+    project_id: &str,
+    load: TileLoad,
+) {
+    println!("Streaming in landscape tile {}", load.component_id);
+
+    handle_add_landscape(
+        renderer_state.clone(),
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        project_id.to_string(),
+        load.landscape_id,
+        load.component_id.clone(),
+        load.heightmap_filename,
+        load.position,
+    );
+
+    loading_tiles.lock().unwrap().remove(&load.component_id);
+}
+
+/// Loads tiles near the camera into the renderer and drops the ones that are far away, waiting
+/// for their heightmaps. For opening a level or a grid, where the tiles should be there right away.
+pub fn stream_landscape_tiles(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    camera_x: f32,
+    camera_z: f32,
+) {
+    let Some((project_id, to_load)) = update_streamed_tiles(state_helper, camera_x, camera_z)
+    else {
+        return;
+    };
+    let Some(renderer_state) = state_helper.renderer_state.as_ref() else {
+        return;
+    };
+
+    for load in to_load {
+        load_tile(
+            renderer_state,
+            &state_helper.loading_tiles,
+            gpu_resources,
+            &project_id,
+            load,
+        );
+    }
+}
+
+/// Same as `stream_landscape_tiles` for camera moves. The heightmaps are read on their own thread
+/// so the input handlers don't wait for them, the tiles show up as they finish. They can't be
+/// queued for the render callback, which already holds the RendererState that
+/// handle_add_landscape locks.
+pub fn stream_landscape_tiles_in_background(
+    state_helper: &StateHelper,
+    gpu_resources: Arc<GpuResources>,
+    camera_x: f32,
+    camera_z: f32,
+) {
+    let Some((project_id, to_load)) = update_streamed_tiles(state_helper, camera_x, camera_z)
+    else {
+        return;
+    };
+    let Some(renderer_state) = state_helper.renderer_state.clone() else {
+        return;
+    };
+
+    if to_load.is_empty() {
+        return;
+    }
+
+    let loading_tiles = Arc::clone(&state_helper.loading_tiles);
+
+    thread::spawn(move || {
+        for load in to_load {
+            load_tile(
+                &renderer_state,
+                &loading_tiles,
+                &gpu_resources,
+                &project_id,
+                load,
+            );
+        }
+    });
+}
+
+/// Everything the tiled landscape dialog collects
+pub struct TiledLandscapeImport {
+    pub name: String,
+    pub heightmap_path: PathBuf,
+    pub rockmap_path: PathBuf,
+    pub soilmap_path: PathBuf,
+    pub columns: usize,
+    pub rows: usize,
+    pub tile_size: f32,
+    pub stream_distance: f32,
+}
+
+/// Splits a large heightmap and its rock and soil maps into landscape assets,
+/// places one component per tile in the active level and records the grid in the editor data
+pub fn import_tiled_landscape(
+    state_helper: &StateHelper,
+    import: TiledLandscapeImport,
+) -> Result<TileGrid, String> {
+    if import.columns == 0 || import.rows == 0 {
+        return Err("A tile grid needs at least one column and row".to_string());
+    }
+
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get()
+        .to_string();
+
+    let format = HeightmapFormat::from_path(&import.heightmap_path)
+        .filter(|format| !format.needs_dimensions())
+        .ok_or("Tiled landscapes need a tif, png, exr or asc heightmap")?;
+    let heights = load_heightmap(&import.heightmap_path, &format)?;
+    let normalized = normalize_heights(&heights, &HeightmapImportSettings::default());
+
+    let rockmap =
+        image::open(&import.rockmap_path).map_err(|e| format!("Couldn't open rock map: {}", e))?;
+    let soilmap =
+        image::open(&import.soilmap_path).map_err(|e| format!("Couldn't open soil map: {}", e))?;
+
+    let height_tiles = split_heightmap(&normalized, import.columns, import.rows);
+    let rock_tiles = split_image(&rockmap, import.columns, import.rows);
+    let soil_tiles = split_image(&soilmap, import.columns, import.rows);
+
+    let grid_id = Uuid::new_v4().to_string();
+
+    // keep the full heightmap around for the map overview
+    let sync_dir = get_common_os_dir().expect("Couldn't get CommonOS directory");
+    let grid_dir = sync_dir.join(format!(
+        "midpoint/projects/{}/landscapes/grids/{}",
+        project_id, grid_id
+    ));
+    fs::create_dir_all(&grid_dir).map_err(|e| format!("Couldn't create grid dir: {}", e))?;
+    let source_path = grid_dir.join("source.tif");
+    save_engine_heightmap(&source_path, &normalized)?;
+
+    let mut grid = TileGrid {
+        id: grid_id,
+        name: import.name,
+        columns: import.columns,
+        rows: import.rows,
+        tile_size: import.tile_size,
+        origin: [0.0, 0.0, 0.0],
+        stream_distance: import.stream_distance,
+        source_heightmap: Some(source_path.to_string_lossy().into_owned()),
+        tiles: Vec::new(),
+    };
+
+    let mut landscapes = Vec::new();
+    let mut components = Vec::new();
+
+    for (((column, row, tile_heights), rock_tile), soil_tile) in height_tiles
+        .into_iter()
+        .zip(rock_tiles.into_iter())
+        .zip(soil_tiles.into_iter())
+    {
+        let landscape_id = Uuid::new_v4().to_string();
+        let component_id = Uuid::new_v4().to_string();
+
+        let heightmap_dir = get_heightmap_dir(&project_id, &landscape_id)
+            .map_err(|e| format!("Couldn't get heightmap dir: {}", e))?;
+        let rockmap_dir = get_rockmap_dir(&project_id, &landscape_id)
+            .map_err(|e| format!("Couldn't get rockmap dir: {}", e))?;
+        let soilmap_dir = get_soilmap_dir(&project_id, &landscape_id)
+            .map_err(|e| format!("Couldn't get soilmap dir: {}", e))?;
+
+        let heightmap_name = format!("tile_{}_{}.tif", column, row);
+        let rockmap_name = format!("tile_{}_{}_rock.png", column, row);
+        let soil_name = format!("tile_{}_{}_soil.png", column, row);
+
+        let heightmap_path = heightmap_dir.join(&heightmap_name);
+        let rockmap_path = rockmap_dir.join(&rockmap_name);
+        let soil_path = soilmap_dir.join(&soil_name);
+
+        save_engine_heightmap(&heightmap_path, &tile_heights)?;
+        rock_tile
+            .save(&rockmap_path)
+            .map_err(|e| format!("Couldn't save rock map tile: {}", e))?;
+        soil_tile
+            .save(&soil_path)
+            .map_err(|e| format!("Couldn't save soil map tile: {}", e))?;
+
+        let file = |name: &String, path: &PathBuf| File {
+            id: Uuid::new_v4().to_string(),
+            fileName: name.clone(),
+            cloudfrontUrl: "".to_string(),
+            normalFilePath: path.to_string_lossy().into_owned(),
+        };
+
+        landscapes.push(LandscapeData {
+            id: landscape_id.clone(),
+            heightmap: Some(file(&heightmap_name, &heightmap_path)),
+            rockmap: Some(file(&rockmap_name, &rockmap_path)),
+            soil: Some(file(&soil_name, &soil_path)),
+        });
+
+        components.push(ComponentData {
+            id: component_id.clone(),
+            kind: Some(ComponentKind::Landscape),
+            asset_id: landscape_id.clone(),
+            generic_properties: GenericProperties {
+                name: format!("{} Tile {},{}", grid.name, column, row),
+                position: grid.tile_position(column, row),
+                rotation: [0.0, 0.0, 0.0],
+                scale: [1.0, 1.0, 1.0],
+            },
+            landscape_properties: Some(LandscapeProperties {
+                primary_texture_id: None,
+                rockmap_texture_id: None,
+                soil_texture_id: None,
+            }),
+            model_properties: None,
+        });
+
+        grid.tiles.push(LandscapeTile {
+            column,
+            row,
+            landscape_id,
+            component_id,
+            streaming: TileStreamSettings::default(),
+        });
+    }
+
+    let mut saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    saved_state
+        .landscapes
+        .get_or_insert_with(Vec::new)
+        .extend(landscapes);
+
+    let level = active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+        .expect("Couldn't get active level");
+    let level_id = level.id.clone();
+    level
+        .components
+        .get_or_insert_with(Vec::new)
+        .extend(components);
+
+    state_helper.save_saved_state(
+        Uuid::from_str(&project_id).expect("Couldn't get project id"),
+        saved_state,
+    );
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id)
+        .tile_grids
+        .push(grid.clone());
+
+    state_helper.save_current_editor_data();
+
+    Ok(grid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_sample_ranges_share_their_border_samples() {
+        assert_eq!(tile_sample_ranges(9, 2), vec![(0, 5), (4, 5)]);
+        assert_eq!(tile_sample_ranges(10, 3), vec![(0, 4), (3, 4), (6, 4)]);
+
+        // every range picks up where the last one ended and the last one reaches the edge
+        let ranges = tile_sample_ranges(1025, 7);
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].0 + pair[0].1 - 1, pair[1].0);
+        }
+        let (start, len) = ranges.last().copied().unwrap();
+        assert_eq!(start + len, 1025);
+    }
+
+    #[test]
+    fn tile_sample_ranges_need_a_tile_and_two_samples() {
+        assert!(tile_sample_ranges(9, 0).is_empty());
+        assert!(tile_sample_ranges(1, 2).is_empty());
+        assert_eq!(tile_sample_ranges(2, 1), vec![(0, 2)]);
+    }
+
+    #[test]
+    fn split_heightmap_goes_row_by_row_with_matching_edges() {
+        let heights = na::DMatrix::from_fn(5, 7, |y, x| (y * 10 + x) as f32);
+        let tiles = split_heightmap(&heights, 2, 2);
+
+        let order: Vec<_> = tiles
+            .iter()
+            .map(|(column, row, _)| (*column, *row))
+            .collect();
+        assert_eq!(order, vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+
+        let (_, _, first) = &tiles[0];
+        assert_eq!(first.shape(), (3, 4));
+        assert_eq!(first[(0, 0)], 0.0);
+
+        // the right edge of a tile is the left edge of the next one, likewise down the rows
+        let (_, _, right) = &tiles[1];
+        assert_eq!(first.column(3), right.column(0));
+        let (_, _, below) = &tiles[2];
+        assert_eq!(first.row(2), below.row(0));
+
+        let (_, _, last) = &tiles[3];
+        assert_eq!(last[(last.nrows() - 1, last.ncols() - 1)], heights[(4, 6)]);
+    }
+}
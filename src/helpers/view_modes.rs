@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+use std::f32::consts::TAU;
+
+use midpoint_engine::core::RendererState::RendererState;
+use midpoint_engine::rapier3d::prelude::{Collider, TypedShape};
+use nalgebra::{Point3, Vector3};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::camera::collider_bounds;
+use super::editor_render::ColorVertex;
+
+/// Overlay lines stop here, a dense collision mesh shouldn't stall the viewport
+const MAX_OVERLAY_LINES: usize = 200_000;
+/// Heightfield colliders are drawn as a grid with at most this many lines each way
+const HEIGHTFIELD_GRID_LINES: usize = 64;
+const CIRCLE_SEGMENTS: usize = 24;
+
+const COLLIDER_COLOR: [f32; 3] = [0.1, 0.8, 0.2];
+const BOUNDS_COLOR: [f32; 3] = [1.0, 0.6, 0.0];
+
+/// How the scene's surfaces are shaded in the viewport
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Textures and vertex colors as they are under the level's fog, the editor's usual look
+    Unlit,
+    /// Shaded by the level's light
+    Lit,
+    /// Triangle edges only, needs line polygon mode from the GPU
+    Wireframe,
+    /// Surface directions as colors
+    Normals,
+    /// A checker pattern laid out by the texture coordinates, shows stretching and seams
+    UvChecker,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 5] = [
+        RenderMode::Unlit,
+        RenderMode::Lit,
+        RenderMode::Wireframe,
+        RenderMode::Normals,
+        RenderMode::UvChecker,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RenderMode::Unlit => "Unlit",
+            RenderMode::Lit => "Lit",
+            RenderMode::Wireframe => "Wireframe",
+            RenderMode::Normals => "Normals",
+            RenderMode::UvChecker => "UV Checker",
+        }
+    }
+
+    /// Entry point in primary_fragment.wgsl
+    pub fn fragment_entry_point(&self) -> &'static str {
+        match self {
+            RenderMode::Unlit => "fs_unlit",
+            RenderMode::Wireframe => "main",
+            RenderMode::Lit => "fs_lit",
+            RenderMode::Normals => "fs_normals",
+            RenderMode::UvChecker => "fs_uv_checker",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ViewportSettings {
+    #[serde(default = "default_render_mode")]
+    pub render_mode: RenderMode,
+    /// Outlines of the rapier colliders of the level's components
+    #[serde(default)]
+    pub show_colliders: bool,
+    /// Bounding boxes around the level's components
+    #[serde(default)]
+    pub show_bounds: bool,
+    /// The last picking ray from the mouse
+    #[serde(default)]
+    pub show_debug_ray: bool,
+}
+
+fn default_render_mode() -> RenderMode {
+    RenderMode::Unlit
+}
+
+impl Default for ViewportSettings {
+    fn default() -> Self {
+        Self {
+            render_mode: default_render_mode(),
+            show_colliders: false,
+            show_bounds: false,
+            show_debug_ray: false,
+        }
+    }
+}
+
+/// Saves the change and hands the settings to the render callback
+pub fn update_viewport_settings(
+    state_helper: &mut StateHelper,
+    edit: impl FnOnce(&mut ViewportSettings),
+) {
+    state_helper.update_editor_settings(|settings| edit(&mut settings.viewport));
+
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().viewport_settings = state_helper.editor_settings.viewport;
+    }
+}
+
+/// Line list geometry for the overlays, in world space
+#[derive(Default)]
+pub struct OverlayLines {
+    pub vertices: Vec<ColorVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl OverlayLines {
+    fn is_full(&self) -> bool {
+        self.indices.len() / 2 >= MAX_OVERLAY_LINES
+    }
+
+    pub fn line(&mut self, start: Point3<f32>, end: Point3<f32>, color: [f32; 3]) {
+        if self.is_full() {
+            return;
+        }
+
+        let index = self.vertices.len() as u32;
+        for position in [start, end] {
+            self.vertices.push(ColorVertex {
+                position: position.into(),
+                normal: [0.0, 1.0, 0.0],
+                tex_coords: [0.0, 0.0],
+                color,
+            });
+        }
+        self.indices.extend_from_slice(&[index, index + 1]);
+    }
+
+    /// A circle in the plane of u and v, which are at right angles
+    pub fn circle(
+        &mut self,
+        center: Point3<f32>,
+        u: Vector3<f32>,
+        v: Vector3<f32>,
+        radius: f32,
+        color: [f32; 3],
+    ) {
+        let point = |step: usize| {
+            let angle = step as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+            center + (u * angle.cos() + v * angle.sin()) * radius
+        };
+
+        for step in 0..CIRCLE_SEGMENTS {
+            self.line(point(step), point(step + 1), color);
+        }
+    }
+
+    /// The twelve edges of a box, corners ordered as the bits of x, y and z
+    fn box_edges(&mut self, corners: [Point3<f32>; 8], color: [f32; 3]) {
+        for (a, b) in [
+            (0, 1),
+            (2, 3),
+            (4, 5),
+            (6, 7),
+            (0, 2),
+            (1, 3),
+            (4, 6),
+            (5, 7),
+            (0, 4),
+            (1, 5),
+            (2, 6),
+            (3, 7),
+        ] {
+            self.line(corners[a], corners[b], color);
+        }
+    }
+
+    fn aabb(&mut self, mins: Point3<f32>, maxs: Point3<f32>, color: [f32; 3]) {
+        let corners = box_corners(|x, y, z| {
+            Point3::new(
+                if x { maxs.x } else { mins.x },
+                if y { maxs.y } else { mins.y },
+                if z { maxs.z } else { mins.z },
+            )
+        });
+
+        self.box_edges(corners, color);
+    }
+
+    fn collider(&mut self, collider: &Collider, color: [f32; 3]) {
+        let position = collider.position();
+
+        match collider.shape().as_typed_shape() {
+            TypedShape::Cuboid(cuboid) => {
+                let half = cuboid.half_extents;
+                let corners = box_corners(|x, y, z| {
+                    position
+                        * Point3::new(
+                            if x { half.x } else { -half.x },
+                            if y { half.y } else { -half.y },
+                            if z { half.z } else { -half.z },
+                        )
+                });
+
+                self.box_edges(corners, color);
+            }
+            TypedShape::Ball(ball) => {
+                // a ring around each axis
+                let center = position * Point3::origin();
+                for (u, v) in [
+                    (Vector3::x(), Vector3::y()),
+                    (Vector3::y(), Vector3::z()),
+                    (Vector3::z(), Vector3::x()),
+                ] {
+                    self.circle(center, u, v, ball.radius, color);
+                }
+            }
+            TypedShape::TriMesh(trimesh) => {
+                let vertices = trimesh.vertices();
+
+                for triangle in trimesh.indices() {
+                    for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                        self.line(
+                            position * vertices[triangle[a] as usize],
+                            position * vertices[triangle[b] as usize],
+                            color,
+                        );
+                    }
+                }
+            }
+            TypedShape::HeightField(heightfield) => {
+                let heights = heightfield.heights();
+                let scale = heightfield.scale();
+                let (rows, columns) = (heights.nrows(), heights.ncols());
+                if rows < 2 || columns < 2 {
+                    return;
+                }
+
+                // heightfields are centered on the collider, rows run along z and columns along x
+                let point = |row: usize, column: usize| {
+                    position
+                        * Point3::new(
+                            (-0.5 + column as f32 / (columns - 1) as f32) * scale.x,
+                            heights[(row, column)] * scale.y,
+                            (-0.5 + row as f32 / (rows - 1) as f32) * scale.z,
+                        )
+                };
+                let row_step = (rows / HEIGHTFIELD_GRID_LINES).max(1);
+                let column_step = (columns / HEIGHTFIELD_GRID_LINES).max(1);
+
+                for row in (0..rows).step_by(row_step) {
+                    for column in (column_step..columns).step_by(column_step) {
+                        self.line(point(row, column - column_step), point(row, column), color);
+                    }
+                }
+                for column in (0..columns).step_by(column_step) {
+                    for row in (row_step..rows).step_by(row_step) {
+                        self.line(point(row - row_step, column), point(row, column), color);
+                    }
+                }
+            }
+            // anything else shows its bounds
+            _ => {
+                let aabb = collider.compute_aabb();
+                self.aabb(aabb.mins, aabb.maxs, color);
+            }
+        }
+    }
+}
+
+fn box_corners(corner: impl Fn(bool, bool, bool) -> Point3<f32>) -> [Point3<f32>; 8] {
+    std::array::from_fn(|bits| corner(bits & 1 != 0, bits & 2 != 0, bits & 4 != 0))
+}
+
+/// Lines for the overlays switched on, None when there's nothing to draw. Only the visible
+/// models and terrain count, so the gizmo's own colliders stay out of it.
+pub fn overlay_lines(
+    renderer_state: &RendererState,
+    settings: &ViewportSettings,
+    hidden_components: &HashSet<String>,
+) -> Option<OverlayLines> {
+    if !settings.show_colliders && !settings.show_bounds {
+        return None;
+    }
+
+    let component_ids: Vec<&String> = renderer_state
+        .models
+        .iter()
+        .map(|model| &model.id)
+        .chain(
+            renderer_state
+                .terrain_managers
+                .iter()
+                .map(|terrain| &terrain.id),
+        )
+        .filter(|id| !hidden_components.contains(*id))
+        .collect();
+
+    let mut lines = OverlayLines::default();
+
+    if settings.show_colliders {
+        let user_data: HashSet<u128> = component_ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .map(|id| id.as_u128())
+            .collect();
+
+        for (_, collider) in renderer_state.collider_set.iter() {
+            if user_data.contains(&collider.user_data) {
+                lines.collider(collider, COLLIDER_COLOR);
+            }
+        }
+    }
+
+    if settings.show_bounds {
+        for id in &component_ids {
+            if let Some((mins, maxs)) = collider_bounds(renderer_state, id) {
+                lines.aabb(mins, maxs, BOUNDS_COLOR);
+            }
+        }
+    }
+
+    (!lines.indices.is_empty()).then_some(lines)
+}
@@ -0,0 +1,102 @@
+use crate::editor_state::StateHelper;
+
+/// Area of the window the 3D view draws into, in physical pixels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    /// Window position relative to the rect's top left corner
+    pub fn to_local(&self, position: [f32; 2]) -> [f32; 2] {
+        [position[0] - self.x, position[1] - self.y]
+    }
+
+    pub fn size(&self) -> [f32; 2] {
+        [self.width, self.height]
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width / self.height
+    }
+}
+
+/// Where the panels leave room for the 3D view, reported by the floem layout in logical pixels.
+/// Rendering, picking rays and input filtering all read the rect from here.
+#[derive(Clone, Copy, Debug)]
+pub struct ViewportLayout {
+    /// Physical pixels per logical pixel, from the monitor the window opened on and then each resize
+    pub scale_factor: f64,
+    /// Top left corner of the space next to the panels
+    pub origin: [f64; 2],
+    /// Size of that space, None until the layout first runs
+    pub size: Option<[f64; 2]>,
+    /// Top edge of the keyframe timeline, which covers the bottom of the skeleton animation view
+    pub timeline_top: Option<f64>,
+}
+
+impl Default for ViewportLayout {
+    fn default() -> Self {
+        Self {
+            scale_factor: 1.0,
+            origin: [0.0, 0.0],
+            size: None,
+            timeline_top: None,
+        }
+    }
+}
+
+impl ViewportLayout {
+    /// Rect for the current view inside a window of the given physical size
+    pub fn rect(&self, window_size: [f32; 2], current_view: &str) -> ViewportRect {
+        let scale = self.scale_factor;
+        let [window_width, window_height] = window_size;
+
+        let Some([width, height]) = self.size else {
+            // nothing laid out yet, the whole window is better than nothing
+            return ViewportRect {
+                x: 0.0,
+                y: 0.0,
+                width: window_width.max(1.0),
+                height: window_height.max(1.0),
+            };
+        };
+
+        let left = (self.origin[0] * scale) as f32;
+        let top = (self.origin[1] * scale) as f32;
+        let mut bottom = ((self.origin[1] + height) * scale) as f32;
+
+        if current_view == "animation_skeleton" {
+            if let Some(timeline_top) = self.timeline_top {
+                bottom = bottom.min((timeline_top * scale) as f32);
+            }
+        }
+
+        let x = left.clamp(0.0, window_width - 1.0);
+        let y = top.clamp(0.0, window_height - 1.0);
+        let right = (left + (width * scale) as f32).min(window_width);
+        let bottom = bottom.min(window_height);
+
+        // scissor and viewport rects need at least a pixel
+        ViewportRect {
+            x,
+            y,
+            width: (right - x).max(1.0),
+            height: (bottom - y).max(1.0),
+        }
+    }
+}
+
+/// Edits the layout the render callback and input handlers read
+pub fn update_viewport_layout(state_helper: &StateHelper, edit: impl FnOnce(&mut ViewportLayout)) {
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        edit(&mut editor_render.lock().unwrap().viewport_layout);
+    }
+}
@@ -0,0 +1,625 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem_renderer::gpu_resources::GpuResources;
+use midpoint_engine::handlers::handle_add_landscape;
+use midpoint_engine::helpers::saved_data::{ComponentData, File};
+use nalgebra as na;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::editor_state::StateHelper;
+
+use super::component_edits::remove_component_colliders;
+use super::editor_render::{ColorMesh, ColorVertex, EditorRenderState};
+use super::heightmaps::{load_heightmap, save_engine_heightmap, HeightmapFormat};
+use super::levels::{active_level, active_level_id};
+
+const WATER_COLOR: [f32; 3] = [0.15, 0.4, 0.75];
+const RIVER_COLOR: [f32; 3] = [0.2, 0.5, 0.8];
+/// Spline samples between two river control points
+const RIVER_SEGMENT_SAMPLES: usize = 8;
+
+/// Water belonging to one level
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WaterSettings {
+    /// World height of the sea, no sea is drawn when unset
+    pub sea_level: Option<f32>,
+    /// Half the width of the square sea plane around the world origin
+    #[serde(default = "default_sea_extent")]
+    pub sea_extent: f32,
+    /// World units covered by a landscape heightmap, used to carve rivers and draw the map
+    #[serde(default = "default_landscape_size")]
+    pub landscape_size: f32,
+    /// World height of a normalized landscape height of 1.0
+    #[serde(default = "default_landscape_height_scale")]
+    pub landscape_height_scale: f32,
+    #[serde(default)]
+    pub planes: Vec<WaterPlane>,
+    #[serde(default)]
+    pub rivers: Vec<River>,
+}
+
+fn default_sea_extent() -> f32 {
+    4096.0
+}
+
+fn default_landscape_size() -> f32 {
+    1024.0
+}
+
+fn default_landscape_height_scale() -> f32 {
+    200.0
+}
+
+impl Default for WaterSettings {
+    fn default() -> Self {
+        Self {
+            sea_level: None,
+            sea_extent: default_sea_extent(),
+            landscape_size: default_landscape_size(),
+            landscape_height_scale: default_landscape_height_scale(),
+            planes: Vec::new(),
+            rivers: Vec::new(),
+        }
+    }
+}
+
+/// A flat rectangle of water, for lakes and ponds
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WaterPlane {
+    pub id: String,
+    pub name: String,
+    /// Center of the plane, y being the water surface
+    pub position: [f32; 3],
+    /// Width along x and depth along z
+    pub size: [f32; 2],
+}
+
+/// A river following a Catmull-Rom spline through its control points
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct River {
+    pub id: String,
+    pub name: String,
+    /// Landscape component the bed is carved into
+    pub landscape_component_id: String,
+    /// Control points in world space, y being the water surface
+    pub points: Vec<[f32; 3]>,
+    pub width: f32,
+    /// How far below the surface the bed is carved
+    pub depth: f32,
+}
+
+impl Default for River {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: "New River".to_string(),
+            landscape_component_id: String::new(),
+            points: Vec::new(),
+            width: 20.0,
+            depth: 4.0,
+        }
+    }
+}
+
+impl River {
+    /// Smoothed path through the control points
+    pub fn path(&self) -> Vec<[f32; 3]> {
+        catmull_rom(&self.points, RIVER_SEGMENT_SAMPLES)
+    }
+}
+
+/// Samples a Catmull-Rom spline that passes through every point
+pub fn catmull_rom(points: &[[f32; 3]], samples_per_segment: usize) -> Vec<[f32; 3]> {
+    if points.len() < 3 || samples_per_segment == 0 {
+        return points.to_vec();
+    }
+
+    let mut path = Vec::new();
+
+    for i in 0..points.len() - 1 {
+        // duplicate the end points so the curve reaches them
+        let p0 = points[i.saturating_sub(1)];
+        let p1 = points[i];
+        let p2 = points[i + 1];
+        let p3 = points[(i + 2).min(points.len() - 1)];
+
+        for step in 0..samples_per_segment {
+            let t = step as f32 / samples_per_segment as f32;
+            let t2 = t * t;
+            let t3 = t2 * t;
+
+            let mut point = [0.0; 3];
+            for axis in 0..3 {
+                point[axis] = 0.5
+                    * ((2.0 * p1[axis])
+                        + (-p0[axis] + p2[axis]) * t
+                        + (2.0 * p0[axis] - 5.0 * p1[axis] + 4.0 * p2[axis] - p3[axis]) * t2
+                        + (-p0[axis] + 3.0 * p1[axis] - 3.0 * p2[axis] + p3[axis]) * t3);
+            }
+            path.push(point);
+        }
+    }
+
+    path.push(points[points.len() - 1]);
+
+    path
+}
+
+pub fn plane_mesh(
+    center: [f32; 3],
+    size: [f32; 2],
+    color: [f32; 3],
+) -> (Vec<ColorVertex>, Vec<u32>) {
+    let half_x = size[0] / 2.0;
+    let half_z = size[1] / 2.0;
+
+    let corners = [
+        ([-half_x, -half_z], [0.0, 0.0]),
+        ([half_x, -half_z], [1.0, 0.0]),
+        ([half_x, half_z], [1.0, 1.0]),
+        ([-half_x, half_z], [0.0, 1.0]),
+    ];
+
+    let vertices = corners
+        .iter()
+        .map(|(offset, tex_coords)| ColorVertex {
+            position: [center[0] + offset[0], center[1], center[2] + offset[1]],
+            normal: [0.0, 1.0, 0.0],
+            tex_coords: *tex_coords,
+            color,
+        })
+        .collect();
+
+    (vertices, vec![0, 1, 2, 0, 2, 3])
+}
+
+/// Flat ribbon following the path at each point's water height
+pub fn ribbon_mesh(path: &[[f32; 3]], width: f32, color: [f32; 3]) -> (Vec<ColorVertex>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    if path.len() < 2 {
+        return (vertices, indices);
+    }
+
+    let half_width = width / 2.0;
+    let mut distance = 0.0;
+
+    for (i, point) in path.iter().enumerate() {
+        let previous = path[i.saturating_sub(1)];
+        let next = path[(i + 1).min(path.len() - 1)];
+
+        // horizontal direction of travel, the ribbon spreads perpendicular to it
+        let direction = na::Vector2::new(next[0] - previous[0], next[2] - previous[2]);
+        let direction = if direction.norm() > f32::EPSILON {
+            direction.normalize()
+        } else {
+            na::Vector2::new(1.0, 0.0)
+        };
+        let side = na::Vector2::new(-direction.y, direction.x) * half_width;
+
+        if i > 0 {
+            let last = path[i - 1];
+            distance += ((point[0] - last[0]).powi(2) + (point[2] - last[2]).powi(2)).sqrt();
+        }
+        let v = distance / width.max(f32::EPSILON);
+
+        for (sign, u) in [(-1.0, 0.0), (1.0, 1.0)] {
+            vertices.push(ColorVertex {
+                position: [point[0] + side.x * sign, point[1], point[2] + side.y * sign],
+                normal: [0.0, 1.0, 0.0],
+                tex_coords: [u, v],
+                color,
+            });
+        }
+
+        if i > 0 {
+            let base = (i as u32 - 1) * 2;
+            indices.extend_from_slice(&[base, base + 1, base + 3, base, base + 3, base + 2]);
+        }
+    }
+
+    (vertices, indices)
+}
+
+/// Horizontal distance from a point to a polyline, along with the water height at the closest spot
+fn distance_to_path(path: &[[f32; 3]], x: f32, z: f32) -> Option<(f32, f32)> {
+    let mut closest: Option<(f32, f32)> = None;
+
+    for pair in path.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let segment = na::Vector2::new(b[0] - a[0], b[2] - a[2]);
+        let to_point = na::Vector2::new(x - a[0], z - a[2]);
+
+        let length_squared = segment.norm_squared();
+        let t = if length_squared > f32::EPSILON {
+            (to_point.dot(&segment) / length_squared).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let distance = (to_point - segment * t).norm();
+        let height = a[1] + (b[1] - a[1]) * t;
+
+        if closest.map_or(true, |(d, _)| distance < d) {
+            closest = Some((distance, height));
+        }
+    }
+
+    closest
+}
+
+/// Lowers a normalized heightmap along the river, with a sloped bank out to the full width
+pub fn carve_river(
+    heights: &mut na::DMatrix<f32>,
+    river: &River,
+    landscape_origin: [f32; 3],
+    water: &WaterSettings,
+) {
+    let path = river.path();
+    let (rows, cols) = heights.shape();
+
+    if path.len() < 2 || rows < 2 || cols < 2 || water.landscape_height_scale <= 0.0 {
+        return;
+    }
+
+    let half_width = river.width / 2.0;
+
+    for y in 0..rows {
+        for x in 0..cols {
+            let world_x = landscape_origin[0] + x as f32 / (cols - 1) as f32 * water.landscape_size;
+            let world_z = landscape_origin[2] + y as f32 / (rows - 1) as f32 * water.landscape_size;
+
+            let Some((distance, surface)) = distance_to_path(&path, world_x, world_z) else {
+                continue;
+            };
+
+            if distance > river.width {
+                continue;
+            }
+
+            let bed = (surface - river.depth - landscape_origin[1]) / water.landscape_height_scale;
+            let current = heights[(y, x)];
+
+            // full depth across the water, easing back to the terrain on the banks
+            let target = if distance <= half_width {
+                bed
+            } else {
+                let t = (distance - half_width) / half_width;
+                bed + (current - bed) * t * t
+            };
+
+            heights[(y, x)] = current.min(target).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Recreates every water mesh for the level's settings
+pub fn rebuild_water_meshes(
+    editor_render: &mut EditorRenderState,
+    device: &wgpu::Device,
+    water: &WaterSettings,
+) {
+    editor_render.water_meshes.clear();
+
+    let Some(layout) = editor_render.model_bind_group_layout.clone() else {
+        return;
+    };
+
+    let mut meshes = Vec::new();
+
+    if let Some(sea_level) = water.sea_level {
+        meshes.push(plane_mesh(
+            [0.0, sea_level, 0.0],
+            [water.sea_extent * 2.0, water.sea_extent * 2.0],
+            WATER_COLOR,
+        ));
+    }
+
+    for plane in &water.planes {
+        meshes.push(plane_mesh(plane.position, plane.size, WATER_COLOR));
+    }
+
+    for river in &water.rivers {
+        meshes.push(ribbon_mesh(&river.path(), river.width, RIVER_COLOR));
+    }
+
+    editor_render.water_meshes = meshes
+        .into_iter()
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|(vertices, indices)| ColorMesh::new(device, &layout, &vertices, &indices))
+        .collect();
+}
+
+/// Water settings of the active level, defaults when nothing was saved yet
+pub fn level_water(state_helper: &StateHelper) -> WaterSettings {
+    let (Some(level_id), Some(editor_data)) = (
+        active_level_id(state_helper),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return WaterSettings::default();
+    };
+
+    editor_data
+        .lock()
+        .unwrap()
+        .level(&level_id)
+        .map(|level| level.water.clone())
+        .unwrap_or_default()
+}
+
+/// Stores the active level's water settings and saves the editor data
+pub fn save_level_water(state_helper: &StateHelper, water: WaterSettings) {
+    let level_id = active_level_id(state_helper).expect("Couldn't get active level");
+
+    state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap()
+        .level_mut(&level_id)
+        .water = water;
+
+    state_helper.save_current_editor_data();
+}
+
+/// Rebuilds the water meshes from the active level's editor data
+pub fn refresh_level_water(state_helper: &StateHelper, device: &wgpu::Device) {
+    let Some(editor_render) = state_helper.editor_render.as_ref() else {
+        return;
+    };
+
+    let water = level_water(state_helper);
+
+    rebuild_water_meshes(&mut editor_render.lock().unwrap(), device, &water);
+}
+
+/// Where a landscape's heights are kept from before any river was carved into them
+fn uncarved_heightmap_path(heightmap_path: &Path) -> PathBuf {
+    let stem = heightmap_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    heightmap_path.with_file_name(format!("{}.uncarved.tif", stem))
+}
+
+/// The landscape component a river is carved into and its heightmap
+fn river_landscape(
+    state_helper: &StateHelper,
+    landscape_component_id: &str,
+) -> Result<(ComponentData, File), String> {
+    let saved_state = state_helper
+        .saved_state
+        .as_ref()
+        .expect("Couldn't get saved state")
+        .lock()
+        .unwrap();
+
+    let component = active_level(&saved_state, state_helper.active_level_id.as_deref())
+        .and_then(|level| level.components.as_ref())
+        .and_then(|components| components.iter().find(|c| c.id == landscape_component_id))
+        .cloned()
+        .ok_or("Couldn't find the river's landscape")?;
+
+    let heightmap = saved_state
+        .landscapes
+        .as_ref()
+        .and_then(|landscapes| landscapes.iter().find(|l| l.id == component.asset_id))
+        .and_then(|landscape| landscape.heightmap.clone())
+        .ok_or("Landscape has no heightmap")?;
+
+    Ok((component, heightmap))
+}
+
+/// Swaps the landscape's terrain and collider for ones built from its heightmap on disk
+fn reload_landscape(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    component: &ComponentData,
+    heightmap: &File,
+) {
+    let project_id = state_helper
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get();
+    let renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .expect("Couldn't get RendererState");
+
+    {
+        let mut renderer_state = renderer_state.lock().unwrap();
+        renderer_state
+            .terrain_managers
+            .retain(|t| t.id != component.id);
+        remove_component_colliders(&mut renderer_state, &component.id);
+    }
+
+    handle_add_landscape(
+        renderer_state.clone(),
+        &gpu_resources.device,
+        &gpu_resources.queue,
+        project_id.to_string(),
+        component.asset_id.clone(),
+        component.id.clone(),
+        heightmap.fileName.clone(),
+        component.generic_properties.position,
+    );
+}
+
+/// Carves every river on the river's landscape into the heights the landscape had before any
+/// carving, so carving again after an edit doesn't dig deeper
+pub fn apply_river_carve(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    river: &River,
+    water: &WaterSettings,
+) -> Result<(), String> {
+    let (component, heightmap) = river_landscape(state_helper, &river.landscape_component_id)?;
+
+    let heightmap_path = Path::new(&heightmap.normalFilePath);
+    let uncarved_path = uncarved_heightmap_path(heightmap_path);
+
+    // the first carve keeps the original for later carves and for uncarving
+    if !uncarved_path.exists() {
+        fs::copy(heightmap_path, &uncarved_path)
+            .map_err(|e| format!("Couldn't keep the original heightmap: {}", e))?;
+    }
+
+    let mut heights = load_normalized_heightmap(&uncarved_path)?;
+
+    let origin = component.generic_properties.position;
+    let other_rivers = water
+        .rivers
+        .iter()
+        .filter(|r| r.landscape_component_id == component.id && r.id != river.id);
+
+    for carved in other_rivers.chain(std::iter::once(river)) {
+        carve_river(&mut heights, carved, origin, water);
+    }
+
+    save_engine_heightmap(heightmap_path, &heights)?;
+    reload_landscape(state_helper, gpu_resources, &component, &heightmap);
+
+    Ok(())
+}
+
+/// Puts back the heights the river's landscape had before any river was carved into it
+pub fn undo_river_carve(
+    state_helper: &StateHelper,
+    gpu_resources: &GpuResources,
+    river: &River,
+) -> Result<(), String> {
+    let (component, heightmap) = river_landscape(state_helper, &river.landscape_component_id)?;
+
+    let heightmap_path = Path::new(&heightmap.normalFilePath);
+    let uncarved_path = uncarved_heightmap_path(heightmap_path);
+
+    if !uncarved_path.exists() {
+        return Err("The landscape hasn't been carved".to_string());
+    }
+
+    fs::rename(&uncarved_path, heightmap_path)
+        .map_err(|e| format!("Couldn't restore the original heightmap: {}", e))?;
+    reload_landscape(state_helper, gpu_resources, &component, &heightmap);
+
+    Ok(())
+}
+
+/// Reads an engine heightmap as 0..1 of the 16-bit range, the same scale water heights map to
+pub fn load_normalized_heightmap(path: &Path) -> Result<na::DMatrix<f32>, String> {
+    let heights = load_heightmap(path, &HeightmapFormat::Tiff)?;
+    Ok(heights.map(|h| h / u16::MAX as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn straight_river() -> River {
+        // runs along x at z = 50 with the surface at 50
+        River {
+            points: vec![[0.0, 50.0, 50.0], [50.0, 50.0, 50.0], [100.0, 50.0, 50.0]],
+            width: 20.0,
+            depth: 4.0,
+            ..Default::default()
+        }
+    }
+
+    fn one_unit_per_sample() -> WaterSettings {
+        WaterSettings {
+            landscape_size: 100.0,
+            landscape_height_scale: 100.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn catmull_rom_starts_and_ends_on_the_end_points() {
+        let points = [
+            [0.0, 0.0, 0.0],
+            [4.0, 2.0, 1.0],
+            [8.0, 0.0, 5.0],
+            [12.0, 1.0, 3.0],
+        ];
+
+        let path = catmull_rom(&points, 6);
+
+        assert_eq!(path.len(), 3 * 6 + 1);
+        assert_eq!(path[0], points[0]);
+        assert_eq!(path[path.len() - 1], points[3]);
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_every_point() {
+        let points = [[0.0, 0.0, 0.0], [4.0, 2.0, 1.0], [8.0, 0.0, 5.0]];
+
+        let path = catmull_rom(&points, 4);
+
+        assert_eq!(path[4], points[1]);
+    }
+
+    #[test]
+    fn catmull_rom_leaves_short_paths_alone() {
+        let points = [[0.0, 0.0, 0.0], [4.0, 2.0, 1.0]];
+
+        assert_eq!(catmull_rom(&points, 8), points.to_vec());
+        assert_eq!(catmull_rom(&points[..1], 8), points[..1].to_vec());
+    }
+
+    #[test]
+    fn carve_river_lowers_the_channel_to_its_bed() {
+        let mut heights = na::DMatrix::from_element(101, 101, 1.0);
+
+        carve_river(
+            &mut heights,
+            &straight_river(),
+            [0.0; 3],
+            &one_unit_per_sample(),
+        );
+
+        // surface 50, 4 deep, over a height scale of 100
+        let bed = 0.46;
+        assert!((heights[(50, 50)] - bed).abs() < 1e-5);
+        assert!((heights[(55, 20)] - bed).abs() < 1e-5);
+    }
+
+    #[test]
+    fn carve_river_eases_the_banks_back_to_the_terrain() {
+        let mut heights = na::DMatrix::from_element(101, 101, 1.0);
+
+        carve_river(
+            &mut heights,
+            &straight_river(),
+            [0.0; 3],
+            &one_unit_per_sample(),
+        );
+
+        // halfway up the bank the falloff is squared
+        let bank = 0.46 + (1.0 - 0.46) * 0.25;
+        assert!((heights[(65, 50)] - bank).abs() < 1e-5);
+        assert!(heights[(62, 50)] < heights[(68, 50)]);
+        // past the full width the terrain is untouched
+        assert_eq!(heights[(75, 50)], 1.0);
+        assert_eq!(heights[(20, 50)], 1.0);
+    }
+
+    #[test]
+    fn carve_river_never_raises_the_terrain() {
+        let mut heights = na::DMatrix::from_element(101, 101, 0.2);
+
+        carve_river(
+            &mut heights,
+            &straight_river(),
+            [0.0; 3],
+            &one_unit_per_sample(),
+        );
+
+        assert!(heights.iter().all(|&h| (h - 0.2).abs() < 1e-6));
+    }
+}
@@ -1,10 +1,40 @@
 use std::borrow::{Borrow, BorrowMut};
 use std::rc::{Rc, Weak};
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
 
 use bytemuck::Contiguous;
 use editor_state::{EditorState, ObjectEdit, StateHelper, UIMessage};
 use helpers::auth::read_auth_token;
+use helpers::camera::{
+    dolly_camera, frame_components, orbit_camera, pan_camera, CameraDrag, CameraDragMode,
+};
+use helpers::component_edits::{
+    copy_selection, delete_selection, duplicate_selection, paste_components, record_transform_edit,
+    redo_level_edit, undo_level_edit,
+};
+use helpers::editor_render::EditorRenderState;
+use helpers::gizmos::{
+    apply_component_transform, start_gizmo_drag, start_translate_drag, update_gizmo_drag,
+    with_transform, GizmoDrag, TranslateConstraint,
+};
+use helpers::headless::{parse_screenshot_args, run_screenshot};
+use helpers::hierarchy::{follower_components, is_pickable, move_followers};
+use helpers::lights::{level_lights, pick_light, preview_light_transforms};
+use helpers::measurements::{measure_tool, place_measure_point};
+use helpers::performance::{elapsed_ms, scene_timestamp_writes};
+use helpers::play_mode::{play_key_name, PlayState};
+use helpers::scene_pipeline::{
+    create_scene_pipeline, draw_level, LevelView, SceneBindings, SceneLayouts,
+};
+use helpers::selection::{
+    box_select_components, finish_selection, is_level_component, marquee_rect,
+    place_selection_gizmos, selected_components, set_selection, PointerPress, TransformSpace,
+    BOX_SELECT_THRESHOLD,
+};
+use helpers::snapping::{snap_to_surface, surface_under_ray};
+use helpers::tiles::stream_landscape_tiles_in_background;
+use helpers::viewport_layout::{ViewportLayout, ViewportRect};
 use helpers::websocket::{Call, WebSocketManager};
 use midpoint_engine::core::Rays::{create_ray_debug_mesh, create_ray_from_mouse};
 use midpoint_engine::core::RendererState::{Point, RendererState, WindowSize};
@@ -17,9 +47,8 @@ use midpoint_engine::floem_winit::dpi::{LogicalSize, PhysicalSize};
 use midpoint_engine::floem_winit::event::{
     ElementState, KeyEvent, Modifiers, MouseButton, MouseScrollDelta,
 };
-use midpoint_engine::handlers::{get_camera, handle_key_press, handle_mouse_move, Vertex};
-use midpoint_engine::helpers::saved_data::ComponentKind;
-use nalgebra::Vector3;
+use midpoint_engine::handlers::{get_camera, handle_key_press, handle_mouse_move};
+use midpoint_engine::helpers::saved_data::ComponentData;
 use uuid::Uuid;
 use views::app::app_view;
 // use winit::{event_loop, window};
@@ -61,7 +90,9 @@ pub fn get_engine_editor(handle: &EngineHandle) -> Option<Arc<Mutex<RendererStat
     })
 }
 
-fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
+fn create_render_callback<'a>(
+    editor_render: Arc<Mutex<EditorRenderState>>,
+) -> Box<RenderCallback<'a>> {
     Box::new(
         move |mut encoder: wgpu::CommandEncoder,
               frame: wgpu::SurfaceTexture,
@@ -91,6 +122,21 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
                 || engine.current_view == "animation_skeleton".to_string()
                 || engine.current_view == "animation_retarget".to_string()
             {
+                // the level's background in the scene, the animation views stay white
+                let clear_color = if engine.current_view == "scene".to_string() {
+                    editor_render.lock().unwrap().environment.wgpu_clear_color()
+                } else {
+                    wgpu::Color::WHITE
+                };
+
+                // timings for the performance view, the GPU's from timestamps when the device has them
+                let (mut frame_stats, timestamp_query_set) = {
+                    let mut editor_render = editor_render.lock().unwrap();
+                    let frame_stats = editor_render.performance.start_frame(&gpu_resources.device);
+
+                    (frame_stats, editor_render.performance.timestamp_query_set())
+                };
+
                 let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: None,
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
@@ -111,7 +157,7 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
                             // load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
                             // load: wgpu::LoadOp::Load,
                             // store: wgpu::StoreOp::Store,
-                            load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                            load: wgpu::LoadOp::Clear(clear_color),
                             store: wgpu::StoreOp::Store,
                         },
                     })],
@@ -132,43 +178,39 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
                         }),
                         stencil_ops: None, // Set this if using stencil
                     }),
-                    timestamp_writes: None,
+                    timestamp_writes: timestamp_query_set.as_deref().map(scene_timestamp_writes),
                     occlusion_query_set: None,
                 });
 
                 // println!("Render frame...");
 
                 // update terrain managers manually without step function
+                let started = Instant::now();
                 engine.update_terrain_managers(&gpu_resources.device, 0.1);
+                frame_stats.terrain_ms = elapsed_ms(started);
 
                 let viewport = engine.viewport.lock().unwrap();
-                let window_size = WindowSize {
-                    width: viewport.width as u32,
-                    height: viewport.height as u32,
-                };
-
-                // Render partial screen content
-                // render_pass.set_viewport(100.0, 100.0, 200.0, 200.0, 0.0, 1.0);
-                let aside_width = 500;
-                let timeline_height = 500;
-                let toolbar_height = 50;
-                if (engine.current_view == "animation_skeleton".to_string()) {
-                    // height minus timeline, width minus sidebar
-                    render_pass.set_scissor_rect(
-                        aside_width,
-                        0,
-                        window_size.width - aside_width,
-                        window_size.height - timeline_height,
-                    );
-                } else {
-                    // height minus toolbar, width minus sidebar
-                    render_pass.set_scissor_rect(
-                        aside_width,
-                        toolbar_height,
-                        window_size.width - aside_width,
-                        window_size.height - toolbar_height,
-                    );
-                }
+                // the panels report the space they leave, picking and input use the same rect
+                let viewport_rect = editor_render
+                    .lock()
+                    .unwrap()
+                    .viewport_layout
+                    .rect([viewport.width, viewport.height], &engine.current_view);
+
+                render_pass.set_viewport(
+                    viewport_rect.x,
+                    viewport_rect.y,
+                    viewport_rect.width,
+                    viewport_rect.height,
+                    0.0,
+                    1.0,
+                );
+                render_pass.set_scissor_rect(
+                    viewport_rect.x as u32,
+                    viewport_rect.y as u32,
+                    viewport_rect.width as u32,
+                    viewport_rect.height as u32,
+                );
 
                 render_pass.set_pipeline(
                     &engine_handle
@@ -181,13 +223,23 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
 
                 let mut camera = get_camera();
 
+                // panels resizing or collapsing change the shape of the view
+                camera.update_aspect_ratio(viewport_rect.aspect_ratio());
+
                 // TODO: bad to call on every frame?
                 camera.update();
 
-                let last_ray = engine.last_ray.expect("Couldn't get last ray");
+                // pausing play holds physics and animations still
+                let paused = editor_render.lock().unwrap().play.state == PlayState::Paused;
 
                 // update rapier collisions
-                engine.update_rapier();
+                let started = Instant::now();
+                if !paused {
+                    engine.update_rapier();
+                }
+                // models physics moves while playing follow their bodies
+                editor_render.lock().unwrap().play.step(&mut engine);
+                frame_stats.rapier_ms = elapsed_ms(started);
 
                 let camera_matrix = camera.view_projection_matrix;
                 gpu_resources.queue.write_buffer(
@@ -197,12 +249,19 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
                 );
 
                 // step animations
-                engine.step_animations_pipeline(&gpu_resources.queue);
+                let started = Instant::now();
+                if !paused {
+                    engine.step_animations_pipeline(&gpu_resources.queue);
+                }
+                frame_stats.animations_ms = elapsed_ms(started);
 
                 render_pass.set_bind_group(3, &engine.light_state.bind_group, &[]); // Set light bind group
 
+                let viewport_settings = editor_render.lock().unwrap().viewport_settings;
+
                 // draw debug raycast
-                if engine.last_ray.is_some() {
+                if let (true, Some(last_ray)) = (viewport_settings.show_debug_ray, engine.last_ray)
+                {
                     let (vertex_buffer, index_buffer, index_count) =
                         create_ray_debug_mesh(&last_ray, 1000.0, 0.0002, &gpu_resources.device);
                     render_pass.set_bind_group(0, &engine.camera_bind_group, &[]);
@@ -242,6 +301,17 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
                             );
                             render_pass.draw_indexed(0..arrow.index_count, 0, 0..1);
                         });
+
+                        // plane and free-move handles
+                        let editor_render = editor_render.lock().unwrap();
+                        editor_render.update_translate_handles(
+                            &gpu_resources.queue,
+                            engine.translation_gizmo.transform.position,
+                            engine.translation_gizmo.transform.rotation,
+                            engine.translation_gizmo.transform.scale.x,
+                        );
+                        editor_render
+                            .render_translate_handles(&mut render_pass, &engine.camera_bind_group);
                     } else if engine.active_gizmo == "rotate".to_string() {
                         engine
                             .rotation_gizmo
@@ -337,47 +407,51 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
                         }
                     }
                 } else if (engine.current_view == "scene".to_string()) {
-                    // draw cubes
-                    for cube in &engine.cubes {
-                        cube.transform.update_uniform_buffer(&gpu_resources.queue);
-                        render_pass.set_bind_group(0, &engine.camera_bind_group, &[]);
-                        render_pass.set_bind_group(1, &cube.bind_group, &[]);
+                    let mut editor_render = editor_render.lock().unwrap();
 
-                        render_pass.set_vertex_buffer(0, cube.vertex_buffer.slice(..));
-                        render_pass.set_index_buffer(
-                            cube.index_buffer.slice(..),
-                            wgpu::IndexFormat::Uint16,
-                        );
+                    editor_render.write_frame_uniforms(&gpu_resources.queue, &camera_matrix);
 
-                        render_pass.draw_indexed(0..cube.index_count as u32, 0, 0..1);
-                    }
-
-                    for model in &engine.models {
-                        for mesh in &model.meshes {
-                            mesh.transform.update_uniform_buffer(&gpu_resources.queue);
-                            render_pass.set_bind_group(0, &engine.camera_bind_group, &[]);
-                            render_pass.set_bind_group(1, &mesh.bind_group, &[]);
-                            render_pass.set_bind_group(2, &mesh.texture_bind_group, &[]);
+                    // the sky goes behind everything, then the level in the render mode
+                    editor_render.render_sky(&mut render_pass);
+                    render_pass.set_pipeline(
+                        editor_render.render_mode_pipeline().unwrap_or(
+                            engine_handle
+                                .render_pipeline
+                                .as_ref()
+                                .expect("Couldn't fetch render pipeline"),
+                        ),
+                    );
 
-                            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
-                            render_pass.set_index_buffer(
-                                mesh.index_buffer.slice(..),
-                                wgpu::IndexFormat::Uint32,
-                            );
+                    // screenshots and thumbnails draw the level the same way
+                    frame_stats.draws = draw_level(
+                        &mut render_pass,
+                        &engine,
+                        &editor_render,
+                        &gpu_resources.device,
+                        &gpu_resources.queue,
+                        &LevelView {
+                            camera_bind_group: &engine.camera_bind_group,
+                            view_projection: &camera_matrix,
+                            instanced_pipeline: editor_render.instanced_pipeline(),
+                        },
+                        None,
+                    );
 
-                            render_pass.draw_indexed(0..mesh.index_count as u32, 0, 0..1);
-                        }
-                    }
+                    // colliders and bounds go over the level
+                    editor_render.update_overlay(&gpu_resources.device, &engine);
+                    editor_render.render_overlay(&mut render_pass, &engine.camera_bind_group);
 
-                    // Render all terrain managers
-                    for terrain_manager in &engine.terrain_managers {
-                        terrain_manager.render(
-                            &mut render_pass,
-                            &engine.camera_bind_group,
-                            &gpu_resources.queue,
-                        );
-                    }
+                    frame_stats.texture_bytes = editor_render.texture_bytes();
                 }
+
+                // the pass has to end before its timestamps can be resolved
+                drop(render_pass);
+
+                editor_render
+                    .lock()
+                    .unwrap()
+                    .performance
+                    .finish_frame(&mut encoder, frame_stats);
             }
 
             // let command_buffer = encoder.finish();
@@ -394,6 +468,20 @@ fn create_render_callback<'a>() -> Box<RenderCallback<'a>> {
     )
 }
 
+/// Where the scene is drawn, clicks and keys outside it belong to the panels
+fn viewport_rect(
+    state_helper: &StateHelper,
+    viewport: &Viewport,
+    current_view: &str,
+) -> ViewportRect {
+    state_helper
+        .editor_render
+        .as_ref()
+        .map(|editor_render| editor_render.lock().unwrap().viewport_layout)
+        .unwrap_or_default()
+        .rect([viewport.width, viewport.height], current_view)
+}
+
 fn handle_cursor_moved(
     state_helper: Arc<Mutex<StateHelper>>,
     mut editor_state: Arc<Mutex<EditorState>>,
@@ -405,9 +493,12 @@ fn handle_cursor_moved(
             let camera = get_camera();
 
             let mut editor_state = editor_state.lock().unwrap();
-            let mut renderer_state = editor_state.renderer_state.lock().unwrap();
+            let renderer_state_arc = editor_state.renderer_state.clone();
+            let mut renderer_state = renderer_state_arc.lock().unwrap();
             let viewport = viewport.lock().unwrap();
 
+            editor_state.last_logical_position = [logPosX, logPoxY];
+
             if renderer_state.mouse_state.is_first_mouse {
                 renderer_state.mouse_state.last_mouse_x = position_x as f64;
                 renderer_state.mouse_state.last_mouse_y = position_y as f64;
@@ -421,24 +512,86 @@ fn handle_cursor_moved(
             renderer_state.mouse_state.last_mouse_x = position_x;
             renderer_state.mouse_state.last_mouse_y = position_y;
 
-            // Only update camera if right mouse button is pressed
-            if renderer_state.mouse_state.right_mouse_pressed {
-                handle_mouse_move(dx as f32, dy as f32);
+            match editor_state.camera_drag {
+                Some(camera_drag) => {
+                    let mut state_helper = state_helper.lock().unwrap();
+                    let settings = state_helper.editor_settings.camera;
+                    let editor_camera = &mut state_helper.editor_camera;
+
+                    match camera_drag {
+                        CameraDrag::Orbit => {
+                            orbit_camera(editor_camera, &settings, dx as f32, dy as f32)
+                        }
+                        CameraDrag::Pan => {
+                            pan_camera(editor_camera, &settings, dx as f32, dy as f32)
+                        }
+                    }
+                }
+                // Only update camera if right mouse button is pressed
+                None if renderer_state.mouse_state.right_mouse_pressed => {
+                    // while playing the mouse turns the player rather than the fly camera
+                    let editor_render = state_helper.lock().unwrap().editor_render.clone();
+                    let looked = editor_render.map_or(false, |editor_render| {
+                        editor_render
+                            .lock()
+                            .unwrap()
+                            .play
+                            .look(dx as f32, dy as f32)
+                    });
+
+                    if !looked {
+                        handle_mouse_move(dx as f32, dy as f32);
+                    }
+                }
+                None => {}
             }
 
-            // create constant raycast
+            // create constant raycast, relative to the part of the window showing the scene
+            let viewport_rect = viewport_rect(
+                &state_helper.lock().unwrap(),
+                &viewport,
+                &renderer_state.current_view,
+            );
+            let [local_x, local_y] = viewport_rect.to_local([position_x as f32, position_y as f32]);
             let ray = renderer_state.update_rays(
-                (position_x as f32, position_y as f32),
+                (local_x, local_y),
                 camera,
-                viewport.width as u32,
-                viewport.height as u32,
+                viewport_rect.width as u32,
+                viewport_rect.height as u32,
             );
 
             renderer_state.last_ray = Some(ray);
 
+            let ray_origin = ray.origin.coords;
+            let ray_direction = ray.dir;
+
             if (renderer_state.mouse_state.drag_started) {
                 renderer_state.mouse_state.drag_started = false;
                 renderer_state.mouse_state.is_dragging = true;
+
+                // rings, plane and scale handles are picked here, arrows through their colliders
+                if renderer_state.object_selected.is_some() {
+                    let (components, followers, transform_settings) = {
+                        let state_helper = state_helper.lock().unwrap();
+                        let components = selected_components(&state_helper);
+                        (
+                            follower_components(&state_helper, &components),
+                            components,
+                            state_helper.editor_settings.transform,
+                        )
+                    };
+
+                    editor_state.gizmo_drag = start_gizmo_drag(
+                        &renderer_state,
+                        components,
+                        transform_settings,
+                        ray_origin,
+                        ray_direction,
+                        camera.forward_vector(),
+                        camera.up_vector(),
+                    )
+                    .map(|selection_drag| selection_drag.with_followers(followers));
+                }
             }
             if (renderer_state.mouse_state.drag_started || renderer_state.mouse_state.is_dragging) {
                 if renderer_state.object_selected.is_some() && renderer_state.ray_intersecting {
@@ -448,218 +601,136 @@ fn handle_cursor_moved(
                             .expect("Couldn't get ray component id")
                     });
 
-                    if ray_arrow.is_some() {
-                        renderer_state.gizmo_drag_axis =
-                            Some(ray_arrow.expect("Couldn't get ray arrow").axis);
+                    let (components, followers, transform_settings) = {
+                        let state_helper = state_helper.lock().unwrap();
+                        let components = selected_components(&state_helper);
+                        (
+                            follower_components(&state_helper, &components),
+                            components,
+                            state_helper.editor_settings.transform,
+                        )
+                    };
+
+                    // the arrow colliders stay world aligned, local space picks arrows itself
+                    if ray_arrow.is_some()
+                        && renderer_state.active_gizmo == "translate"
+                        && transform_settings.space == TransformSpace::World
+                        && editor_state.gizmo_drag.is_none()
+                    {
+                        let axis = ray_arrow.expect("Couldn't get ray arrow").axis;
+                        renderer_state.gizmo_drag_axis = Some(axis);
                         renderer_state.dragging_translation_gizmo = true;
+
+                        editor_state.gizmo_drag = start_translate_drag(
+                            TranslateConstraint::Axis(axis as usize),
+                            components,
+                            transform_settings,
+                            ray_origin,
+                            ray_direction,
+                            camera.forward_vector(),
+                        )
+                        .map(|selection_drag| selection_drag.with_followers(followers));
                     }
+                }
 
-                    let state_helper = state_helper.lock().unwrap();
-                    let saved_state = state_helper
-                        .saved_state
-                        .as_ref()
-                        .expect("Couldn't get saved state");
-                    let saved_state = saved_state.lock().unwrap();
+                // translate, rotate and scale drags follow the ray from where they started
+                if let Some(selection_drag) = editor_state.gizmo_drag.as_mut() {
+                    let snap_settings = state_helper.lock().unwrap().editor_settings.snapping;
+
+                    // holding alt flips snapping for the drag, shift and ctrl add to the selection
+                    let snap = (snap_settings.enabled
+                        != renderer_state.current_modifiers.alt_key())
+                    .then_some(&snap_settings);
+
+                    let mut transforms = update_gizmo_drag(
+                        selection_drag,
+                        ray_origin,
+                        ray_direction,
+                        camera.forward_vector(),
+                        camera.up_vector(),
+                        snap,
+                    );
 
-                    let ray_component = saved_state
-                        .levels
-                        .as_ref()
-                        .expect("Couldn't get levels")
-                        .get(0)
-                        .expect("Couldn't get first level")
-                        .components
-                        .as_ref()
-                        .expect("Couldn't get components")
-                        .iter()
-                        .find(|c| {
-                            c.id == renderer_state
-                                .ray_component_id
-                                .expect("Couldn't get ray component")
-                                .to_string()
-                        });
+                    // surface snapping drops a single component, a group keeps its layout
+                    if let (
+                        Some([transform]),
+                        [component],
+                        GizmoDrag::Translate { .. },
+                    ) = (
+                        transforms.as_deref_mut(),
+                        selection_drag.components.as_slice(),
+                        &selection_drag.drag,
+                    ) {
+                        if snap_settings.surface {
+                            snap_to_surface(
+                                &renderer_state,
+                                component,
+                                transform,
+                                ray_origin,
+                                ray_direction,
+                                snap_settings.align_to_normal,
+                            );
+                        }
+                    }
 
-                    if ray_component.is_some() {
-                        // println!("Component interaction...");
-                        // TODO: select component
+                    if let Some(transforms) = transforms {
+                        selection_drag.moved = selection_drag
+                            .components
+                            .iter()
+                            .zip(transforms)
+                            .map(|(component, transform)| {
+                                apply_component_transform(
+                                    &mut renderer_state,
+                                    component,
+                                    transform,
+                                );
+                                with_transform(component, transform)
+                            })
+                            .collect();
+
+                        // children keep their place relative to the components they hang off
+                        selection_drag.followers_moved = move_followers(
+                            &mut renderer_state,
+                            &selection_drag.components,
+                            &selection_drag.moved,
+                            &selection_drag.followers,
+                        );
+
+                        place_selection_gizmos(
+                            &mut renderer_state,
+                            &selection_drag.moved,
+                            selection_drag.settings,
+                        );
+
+                        // lights are drawn by the editor rather than the engine
+                        if let Some(editor_render) =
+                            state_helper.lock().unwrap().editor_render.as_ref()
+                        {
+                            preview_light_transforms(
+                                &mut editor_render.lock().unwrap(),
+                                &selection_drag.moved,
+                            );
+                        }
                     }
                 }
-                if renderer_state.object_selected.is_some()
-                    && renderer_state.dragging_translation_gizmo
-                {
-                    let dx = dx * 0.005;
-                    let dy = dy * 0.005;
-
-                    // get component from saved state
-                    let state_helper = state_helper.lock().unwrap();
-                    let saved_state = state_helper
-                        .saved_state
-                        .as_ref()
-                        .expect("Couldn't get saved state");
-                    let saved_state = saved_state.lock().unwrap();
-                    let selected_component = saved_state
-                        .levels
-                        .as_ref()
-                        .expect("Couldn't get levels")
-                        .get(0)
-                        .expect("Couldn't get first level")
-                        .components
-                        .as_ref()
-                        .expect("Couldn't get components")
-                        .iter()
-                        .find(|c| {
-                            c.id == renderer_state
-                                .object_selected
-                                .expect("Couldn't get selected object")
-                                .to_string()
-                        })
-                        .expect("Couldn't get component match");
-
-                    let mouse_state = renderer_state.mouse_state.clone();
-
-                    // if we are pointing at a gizmo arrow
-                    if renderer_state.dragging_translation_gizmo {
-                        let ray_arrow_axis = renderer_state
-                            .gizmo_drag_axis
-                            .expect("Couldn't get gizmo axis");
-
-                        // println!("Ray intersection! {:?}", ray_arrow.axis);
-                        let current_transform = match selected_component.kind.as_ref().unwrap() {
-                            ComponentKind::Model => {
-                                let model = renderer_state
-                                    .models
-                                    .iter()
-                                    .find(|m| m.id == selected_component.id)
-                                    .expect("Couldn't find matching model");
-                                // Assuming first mesh's transform represents the whole model
-                                let mesh = &model.meshes[0];
-                                let euler = mesh.transform.rotation.euler_angles();
-                                [
-                                    mesh.transform.position,
-                                    Vector3::new(euler.0, euler.1, euler.2),
-                                    mesh.transform.scale,
-                                ]
-                            }
-                            ComponentKind::Landscape => {
-                                let terrain_manager = renderer_state
-                                    .terrain_managers
-                                    .iter()
-                                    .find(|m| m.id == selected_component.id)
-                                    .expect("Couldn't find matching landscape");
-                                let euler = terrain_manager.transform.rotation.euler_angles();
-                                [
-                                    terrain_manager.transform.position,
-                                    Vector3::new(euler.0, euler.1, euler.2),
-                                    terrain_manager.transform.scale,
-                                ]
-                            }
-                        };
-
-                        // Get camera forward vector (assuming you have access to camera)
-                        let camera_forward = camera.forward_vector();
-                        let camera_up = camera.up_vector();
-                        let camera_right = camera.right_vector();
-
-                        // Determine if we're looking from the "back" of an axis
-                        let view_alignment = match ray_arrow_axis {
-                            0 => camera_right.dot(&Vector3::new(1.0, 0.0, 0.0)).signum(), // X axis
-                            1 => camera_up.dot(&Vector3::new(0.0, 1.0, 0.0)).signum(),    // Y axis
-                            2 => -camera_forward.dot(&Vector3::new(0.0, 0.0, 1.0)).signum(), // Z axis
-                            _ => 1.0,
-                        };
-
-                        // Calculate movement based on axis constraint and view alignment
-                        let movement = match ray_arrow_axis {
-                            0 => [dx as f32 * view_alignment, 0.0, 0.0], // X axis
-                            1 => [0.0, dy as f32 * view_alignment, 0.0], // Y axis
-                            2 => [0.0, 0.0, dx as f32 * view_alignment], // Z axis
-                            _ => [dx as f32, dy as f32, 0.0],            // Unconstrained
-                        };
-
-                        // Create the new transform with view-adjusted movement
-                        let savable_transform: Option<[[f32; 3]; 3]> = Some([
-                            [
-                                current_transform[0][0] + movement[0],
-                                current_transform[0][1] - movement[1],
-                                current_transform[0][2] + movement[2],
-                            ],
-                            current_transform[1].into(), // Keep rotation unchanged
-                            current_transform[2].into(), // Keep scale unchanged
-                        ]);
-
-                        if savable_transform.is_some() {
-                            let savable_transform: [[f32; 3]; 3] =
-                                savable_transform.expect("Couldn't get savable_transform");
-
-                            let mut component_data = selected_component.clone();
-
-                            // TODO: update saved_state with new ComponentData (actually saved on mouse release)
-
-                            // TODO: update signals
-
-                            // update gizmo transform position
-                            renderer_state
-                                .translation_gizmo
-                                .transform
-                                .update_position(savable_transform[0]);
+            }
 
-                            // update_arrow_collider_position
-                            renderer_state.update_arrow_collider_position(savable_transform[0]);
+            // dragging on empty space turns the press into a box select
+            let transforming =
+                renderer_state.dragging_translation_gizmo || editor_state.gizmo_drag.is_some();
+            let logical_position = editor_state.last_logical_position;
 
-                            // update renderer_state.selected_object_data with new ComponentData
-                            component_data.generic_properties.position = savable_transform[0];
-                            component_data.generic_properties.rotation = savable_transform[1];
-                            component_data.generic_properties.scale = savable_transform[2];
+            if let Some(press) = editor_state.pointer_press.as_mut() {
+                let moved = (position_x - press.position[0]).hypot(position_y - press.position[1]);
 
-                            renderer_state.object_selected_data = Some(component_data);
+                if !transforming && (press.box_selecting || moved > BOX_SELECT_THRESHOLD) {
+                    press.box_selecting = true;
 
-                            // check object_type and set new transform in rendererstate
-                            match selected_component
-                                .kind
-                                .as_ref()
-                                .expect("Couldn't get component kind")
-                            {
-                                ComponentKind::Model => {
-                                    let mut matching_model = renderer_state
-                                        .models
-                                        .iter_mut()
-                                        .find(|m| m.id == selected_component.id)
-                                        .expect("Couldn't find matching model");
-
-                                    // update visually
-                                    matching_model.meshes.iter_mut().for_each(move |mesh| {
-                                        mesh.transform.update_position(savable_transform[0]);
-                                        mesh.transform.update_rotation(savable_transform[1]);
-                                        mesh.transform.update_scale(savable_transform[2]);
-                                    });
-
-                                    // update colliders
-                                    renderer_state
-                                        .update_model_collider_position(savable_transform[0]);
-                                }
-                                ComponentKind::Landscape => {
-                                    let mut matching_terrain_manager = renderer_state
-                                        .terrain_managers
-                                        .iter_mut()
-                                        .find(|m| m.id == selected_component.id)
-                                        .expect("Couldn't find matching landscape");
-
-                                    // visual
-                                    matching_terrain_manager
-                                        .transform
-                                        .update_position(savable_transform[0]);
-                                    matching_terrain_manager
-                                        .transform
-                                        .update_rotation(savable_transform[1]);
-                                    matching_terrain_manager
-                                        .transform
-                                        .update_scale(savable_transform[2]);
-
-                                    // physics
-                                    renderer_state
-                                        .update_landscape_collider_position(savable_transform[0]);
-                                }
-                            }
-                        }
+                    if let Some(marquee_signal) = state_helper.lock().unwrap().marquee_signal {
+                        marquee_signal.set(Some(marquee_rect(
+                            press.logical_position,
+                            logical_position,
+                        )));
                     }
                 }
             }
@@ -668,6 +739,7 @@ fn handle_cursor_moved(
 }
 
 fn handle_mouse_input(
+    state_helper: Arc<Mutex<StateHelper>>,
     mut editor_state: Arc<Mutex<EditorState>>,
     gpu_resources: std::sync::Arc<GpuResources>,
     viewport: std::sync::Arc<Mutex<Viewport>>,
@@ -675,27 +747,183 @@ fn handle_mouse_input(
 ) -> Option<Box<dyn Fn(MouseButton, ElementState)>> {
     Some(Box::new(move |button, state| {
         let mut editor_state = editor_state.lock().unwrap();
-        let mut renderer_state = editor_state.renderer_state.lock().unwrap();
+        let renderer_state_arc = editor_state.renderer_state.clone();
+        let mut renderer_state = renderer_state_arc.lock().unwrap();
 
-        if renderer_state.mouse_state.last_mouse_x < 500.0 {
-            println!("non-propagated click");
+        let viewport_rect = viewport_rect(
+            &state_helper.lock().unwrap(),
+            &viewport.lock().unwrap(),
+            &renderer_state.current_view,
+        );
+        let over_viewport = viewport_rect.contains(
+            renderer_state.mouse_state.last_mouse_x as f32,
+            renderer_state.mouse_state.last_mouse_y as f32,
+        );
+
+        // presses over the panels are theirs, releases always get through so drags end and save
+        if !over_viewport && state == ElementState::Pressed {
             return;
         }
 
-        if button == MouseButton::Right {
-            let edit_config = match state {
-                ElementState::Pressed => renderer_state.mouse_state.right_mouse_pressed = true,
-                ElementState::Released => renderer_state.mouse_state.right_mouse_pressed = false,
+        if button == MouseButton::Right || button == MouseButton::Middle {
+            let right_drag = state_helper
+                .lock()
+                .unwrap()
+                .editor_settings
+                .camera
+                .right_drag;
+            // alt always orbits, the right button looks around otherwise
+            let orbit =
+                renderer_state.current_modifiers.alt_key() || right_drag == CameraDragMode::Orbit;
+            let camera_drag = match button {
+                MouseButton::Middle => Some(CameraDrag::Pan),
+                _ => orbit.then_some(CameraDrag::Orbit),
+            };
+
+            match state {
+                ElementState::Pressed => match camera_drag {
+                    Some(camera_drag) => editor_state.camera_drag = Some(camera_drag),
+                    None => renderer_state.mouse_state.right_mouse_pressed = true,
+                },
+                ElementState::Released => {
+                    if button == MouseButton::Right {
+                        renderer_state.mouse_state.right_mouse_pressed = false;
+                    }
+
+                    // orbiting and panning move the camera, tiles stream in around it
+                    if editor_state.camera_drag.take().is_some() {
+                        drop(renderer_state);
+
+                        let camera = get_camera();
+                        stream_landscape_tiles_in_background(
+                            &state_helper.lock().unwrap(),
+                            gpu_resources.clone(),
+                            camera.position.x,
+                            camera.position.z,
+                        );
+                        return;
+                    }
+                }
             };
         }
         if button == MouseButton::Left {
             let edit_config = match state {
                 ElementState::Pressed => {
                     renderer_state.mouse_state.drag_started = true;
+
+                    editor_state.pointer_press = Some(PointerPress {
+                        position: [
+                            renderer_state.mouse_state.last_mouse_x,
+                            renderer_state.mouse_state.last_mouse_y,
+                        ],
+                        logical_position: editor_state.last_logical_position,
+                        box_selecting: false,
+                    });
                 }
                 ElementState::Released => {
+                    let was_transforming = renderer_state.dragging_translation_gizmo
+                        || editor_state.gizmo_drag.is_some();
+                    let moved = editor_state.gizmo_drag.take().map(|selection_drag| {
+                        // followers first so the active component stays last
+                        let started: Vec<ComponentData> = selection_drag
+                            .followers
+                            .into_iter()
+                            .map(|(_, follower)| follower)
+                            .chain(selection_drag.components)
+                            .collect();
+
+                        (
+                            started,
+                            [selection_drag.followers_moved, selection_drag.moved].concat(),
+                        )
+                    });
+
                     renderer_state.mouse_state.is_dragging = false;
                     renderer_state.dragging_translation_gizmo = false;
+
+                    let press = editor_state.pointer_press.take();
+                    let additive = renderer_state.current_modifiers.shift_key()
+                        || renderer_state.current_modifiers.control_key();
+
+                    // persist the transform once the drag is done
+                    if was_transforming {
+                        drop(renderer_state);
+
+                        if let Some((started, moved)) = moved {
+                            record_transform_edit(
+                                &mut state_helper.lock().unwrap(),
+                                started,
+                                moved,
+                            );
+                        }
+                    } else if let Some(press) = press.filter(|press| press.box_selecting) {
+                        let release = [
+                            renderer_state.mouse_state.last_mouse_x as f32,
+                            renderer_state.mouse_state.last_mouse_y as f32,
+                        ];
+                        drop(renderer_state);
+
+                        let view_projection = get_camera().view_projection_matrix;
+
+                        let mut state_helper = state_helper.lock().unwrap();
+                        if let Some(marquee_signal) = state_helper.marquee_signal {
+                            marquee_signal.set(None);
+                        }
+
+                        let picked = box_select_components(
+                            &state_helper,
+                            &view_projection,
+                            viewport_rect.size(),
+                            viewport_rect
+                                .to_local([press.position[0] as f32, press.position[1] as f32]),
+                            viewport_rect.to_local(release),
+                        );
+                        finish_selection(&mut state_helper, picked, false, additive);
+                    } else if !over_viewport {
+                        // a click let go over a panel picks nothing
+                    } else if measure_tool(&state_helper.lock().unwrap()).is_some() {
+                        // measure tools take clicks on surfaces instead of selecting
+                        let surface = renderer_state.last_ray.and_then(|ray| {
+                            surface_under_ray(&renderer_state, ray.origin.coords, ray.dir, "")
+                        });
+                        drop(renderer_state);
+
+                        if let Some((point, _)) = surface {
+                            place_measure_point(&state_helper.lock().unwrap(), point.into());
+                        }
+                    } else {
+                        let hit = renderer_state
+                            .ray_component_id
+                            .filter(|_| renderer_state.ray_intersecting)
+                            .map(|id| id.to_string());
+                        let ray = renderer_state.last_ray;
+                        drop(renderer_state);
+
+                        let mut state_helper = state_helper.lock().unwrap();
+
+                        // lights have no colliders, their markers are picked along the ray
+                        let light_hit = ray.and_then(|ray| {
+                            pick_light(&level_lights(&state_helper), ray.origin, ray.dir)
+                        });
+
+                        // clicks on gizmo handles and other helpers leave the selection alone
+                        match (light_hit, hit) {
+                            (Some(id), _) => {
+                                finish_selection(&mut state_helper, vec![id], true, additive)
+                            }
+                            (None, Some(id))
+                                if is_level_component(&state_helper, &id)
+                                    && is_pickable(&state_helper, &id) =>
+                            {
+                                finish_selection(&mut state_helper, vec![id], true, additive)
+                            }
+                            (None, Some(_)) => {}
+                            (None, None) if !additive => {
+                                set_selection(&mut state_helper, Vec::new())
+                            }
+                            (None, None) => {}
+                        }
+                    }
                 }
             };
         }
@@ -746,6 +974,7 @@ fn handle_mouse_input(
 }
 
 fn handle_window_resize(
+    state_helper: Arc<Mutex<StateHelper>>,
     editor_state: Arc<Mutex<EditorState>>,
     gpu_resources: std::sync::Arc<GpuResources>,
     gpu_helper: std::sync::Arc<Mutex<GpuHelper>>,
@@ -776,6 +1005,14 @@ fn handle_window_resize(
 
         camera.update_aspect_ratio(ratio);
 
+        // panels report logical pixels, the scene is drawn in physical ones
+        if logical_size.width > 0.0 {
+            if let Some(editor_render) = state_helper.lock().unwrap().editor_render.as_ref() {
+                editor_render.lock().unwrap().viewport_layout.scale_factor =
+                    size.width as f64 / logical_size.width;
+            }
+        }
+
         gpu_helper
             .lock()
             .unwrap()
@@ -784,32 +1021,48 @@ fn handle_window_resize(
 }
 
 fn handle_mouse_wheel(
+    state_helper: Arc<Mutex<StateHelper>>,
     editor_state: Arc<Mutex<EditorState>>,
     gpu_resources: std::sync::Arc<GpuResources>,
     viewport: std::sync::Arc<Mutex<Viewport>>,
 ) -> Option<Box<dyn FnMut(MouseScrollDelta)>> {
     Some(Box::new(move |delta: MouseScrollDelta| {
-        // let mut editor = editor.lock().unwrap();
+        let over_viewport = {
+            let editor_state = editor_state.lock().unwrap();
+            let renderer_state = editor_state.renderer_state.lock().unwrap();
+            viewport_rect(
+                &state_helper.lock().unwrap(),
+                &viewport.lock().unwrap(),
+                &renderer_state.current_view,
+            )
+            .contains(
+                renderer_state.mouse_state.last_mouse_x as f32,
+                renderer_state.mouse_state.last_mouse_y as f32,
+            )
+        };
 
-        // let mouse_pos = Point {
-        //     x: editor.global_top_left.x,
-        //     y: editor.global_top_left.y,
-        // };
+        if !over_viewport {
+            return;
+        }
 
-        // match delta {
-        //     MouseScrollDelta::LineDelta(_x, y) => {
-        //         // y is positive for scrolling up/away from user
-        //         // negative for scrolling down/toward user
-        //         // let zoom_factor = if y > 0.0 { 1.1 } else { 0.9 };
-        //         editor.handle_wheel(y, mouse_pos, &gpu_resources.queue);
-        //     }
-        //     MouseScrollDelta::PixelDelta(pos) => {
-        //         // Convert pixel delta if needed
-        //         let y = pos.y as f32;
-        //         // let zoom_factor = if y > 0.0 { 1.1 } else { 0.9 };
-        //         editor.handle_wheel(y, mouse_pos, &gpu_resources.queue);
-        //     }
-        // }
+        // y is positive for scrolling up/away from user, which moves the camera in
+        let steps = match delta {
+            MouseScrollDelta::LineDelta(_x, y) => y,
+            // trackpads report pixels, roughly 50 to a wheel notch
+            MouseScrollDelta::PixelDelta(pos) => pos.y as f32 / 50.0,
+        };
+
+        let mut state_helper = state_helper.lock().unwrap();
+        let settings = state_helper.editor_settings.camera;
+        dolly_camera(&mut state_helper.editor_camera, &settings, steps);
+
+        let camera = get_camera();
+        stream_landscape_tiles_in_background(
+            &state_helper,
+            gpu_resources.clone(),
+            camera.position.x,
+            camera.position.z,
+        );
     }))
 }
 
@@ -832,19 +1085,44 @@ use midpoint_engine::floem_winit::keyboard::NamedKey;
 use midpoint_engine::floem_winit::keyboard::{Key, SmolStr};
 
 fn handle_keyboard_input(
+    state_helper: Arc<Mutex<StateHelper>>,
     editor_state: Arc<Mutex<EditorState>>,
     gpu_resources: std::sync::Arc<GpuResources>,
     viewport: std::sync::Arc<Mutex<Viewport>>,
 ) -> Option<Box<dyn FnMut(KeyEvent)>> {
     Some(Box::new(move |event: KeyEvent| {
+        // while playing, held keys walk the player and trigger behaviors instead of flying
+        let key_name = play_key_name(event.logical_key.to_text().unwrap_or_default());
+        let editor_render = state_helper.lock().unwrap().editor_render.clone();
+        let playing = editor_render.map_or(false, |editor_render| {
+            let held = event.state == ElementState::Pressed;
+            editor_render
+                .lock()
+                .unwrap()
+                .play
+                .set_key_held(&key_name, held)
+        });
+
         if event.state != ElementState::Pressed {
             return;
         }
 
+        let camera_settings = state_helper.lock().unwrap().editor_settings.camera;
+
         let mut editor_state = editor_state.lock().unwrap();
         let mut renderer_state = editor_state.renderer_state.lock().unwrap();
         // Check for Ctrl+Z (undo)
         let modifiers = renderer_state.current_modifiers;
+        // component shortcuts only apply over the viewport so they don't fire while typing
+        let over_viewport = viewport_rect(
+            &state_helper.lock().unwrap(),
+            &viewport.lock().unwrap(),
+            &renderer_state.current_view,
+        )
+        .contains(
+            renderer_state.mouse_state.last_mouse_x as f32,
+            renderer_state.mouse_state.last_mouse_y as f32,
+        );
 
         drop(renderer_state);
 
@@ -852,25 +1130,86 @@ fn handle_keyboard_input(
         match logical_key_text {
             "z" => {
                 if modifiers.control_key() {
+                    drop(editor_state);
+
+                    let mut state_helper = state_helper.lock().unwrap();
                     if modifiers.shift_key() {
-                        editor_state.redo(); // Ctrl+Shift+Z
+                        // Ctrl+Shift+Z
+                        redo_level_edit(&mut state_helper);
                     } else {
-                        editor_state.undo(); // Ctrl+Z
+                        // Ctrl+Z
+                        undo_level_edit(&mut state_helper);
                     }
+                    return;
                 }
             }
             "y" => {
                 if modifiers.control_key() {
-                    editor_state.redo(); // Ctrl+Y
+                    // Ctrl+Y
+                    drop(editor_state);
+
+                    redo_level_edit(&mut state_helper.lock().unwrap());
+                    return;
                 }
             }
             _ => {}
         }
 
-        handle_key_press(
-            Arc::clone(&editor_state.renderer_state),
-            logical_key_text,
-            true,
+        // F frames the selection
+        if over_viewport && !modifiers.control_key() && logical_key_text == "f" {
+            let renderer_state = editor_state.renderer_state.lock().unwrap();
+            let mut state_helper = state_helper.lock().unwrap();
+            let components = selected_components(&state_helper);
+
+            frame_components(
+                &mut state_helper.editor_camera,
+                &renderer_state,
+                &components,
+            );
+        }
+
+        if over_viewport {
+            let mut state_helper = state_helper.lock().unwrap();
+
+            if event.logical_key == Key::Named(NamedKey::Delete) {
+                delete_selection(&mut state_helper, gpu_resources.clone());
+            } else if modifiers.control_key() {
+                match logical_key_text {
+                    "d" => duplicate_selection(&mut state_helper, gpu_resources.clone()),
+                    "c" => match copy_selection(&state_helper) {
+                        Ok(copied) => println!("Copied {} components", copied),
+                        Err(e) => println!("{}", e),
+                    },
+                    "v" => match paste_components(&mut state_helper, gpu_resources.clone()) {
+                        Ok(pasted) => println!("Pasted {} components", pasted),
+                        Err(e) => println!("{}", e),
+                    },
+                    _ => {}
+                }
+            }
+        }
+
+        // ctrl shortcuts shouldn't also move the camera
+        if !modifiers.control_key() && !playing {
+            editor_state.renderer_state.lock().unwrap().navigation_speed =
+                camera_settings.move_speed;
+            handle_key_press(
+                Arc::clone(&editor_state.renderer_state),
+                logical_key_text,
+                true,
+            );
+        }
+
+        drop(editor_state);
+
+        // moving the camera may bring landscape tiles in or out of range
+        let camera = get_camera();
+        let state_helper = state_helper.lock().unwrap();
+        stream_landscape_tiles_in_background(
+            &state_helper,
+            gpu_resources.clone(),
+            camera.position.x,
+            camera.position.z,
         );
     }))
 }
@@ -884,6 +1223,25 @@ async fn main() {
     // Initialize logging
     // tracing::fmt::init();
 
+    // scripted screenshots render the level and exit without opening a window
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(screenshot) = parse_screenshot_args(&args) {
+        let result = match screenshot {
+            Ok(screenshot) => run_screenshot(screenshot).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(path) => println!("Saved screenshot to {:?}", path),
+            Err(e) => {
+                eprintln!("Screenshot failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        return;
+    }
+
     let auth_token = read_auth_token();
 
     // TODO: show alert if auth_token is empty
@@ -915,6 +1273,19 @@ async fn main() {
     let gpu_cloned = Arc::clone(&gpu_helper);
     let gpu_cloned2 = Arc::clone(&gpu_helper);
 
+    // editor-only meshes drawn in the scene pass
+    // the window opens on the primary monitor, so its scale factor holds until the first resize
+    let editor_render = Arc::new(Mutex::new(EditorRenderState {
+        viewport_settings: state_helper.lock().unwrap().editor_settings.viewport,
+        viewport_layout: ViewportLayout {
+            scale_factor: monitor.scale_factor(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }));
+    let editor_render_2 = Arc::clone(&editor_render);
+    state_helper.lock().unwrap().editor_render = Some(Arc::clone(&editor_render));
+
     let viewport = Arc::new(Mutex::new(Viewport::new(
         window_size.width as f32,
         window_size.height as f32,
@@ -987,7 +1358,7 @@ async fn main() {
             .expect("Couldn't get window handle");
 
         // Create and set the render callback
-        let render_callback = create_render_callback();
+        let render_callback = create_render_callback(editor_render.clone());
 
         window_handle.set_encode_callback(render_callback);
         // window_handle.window_size = Some(window_size);
@@ -1018,17 +1389,13 @@ async fn main() {
                 camera.update_aspect_ratio(window_width as f32 / window_height as f32);
                 camera.update_view_projection_matrix();
 
-                let camera_matrix = camera.view_projection_matrix;
-                let camera_uniform_buffer =
-                    gpu_resources
-                        .device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Camera Uniform Buffer"),
-                            contents: bytemuck::cast_slice(camera_matrix.as_slice()),
-                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        });
-
-                let camera_uniform_buffer = Arc::new(camera_uniform_buffer);
+                // the offscreen pipeline for screenshots shares these layouts
+                let scene_layouts = SceneLayouts::new(&gpu_resources.device);
+                let scene_bindings = SceneBindings::new(
+                    &gpu_resources.device,
+                    &scene_layouts,
+                    camera.view_projection_matrix.as_slice(),
+                );
 
                 // let sampler = gpu_resources
                 //     .device
@@ -1077,145 +1444,19 @@ async fn main() {
                 // let swapchain_format = swapchain_capabilities.formats[0]; // Choosing the first available format
                 let swapchain_format = wgpu::TextureFormat::Bgra8UnormSrgb; // hardcode for now
 
-                // Create the bind group for the uniform buffer
-                let camera_bind_group_layout = gpu_resources.device.create_bind_group_layout(
-                    &wgpu::BindGroupLayoutDescriptor {
-                        label: Some("Camera Bind Group Layout"),
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                    },
-                );
-
-                let camera_bind_group_layout = Arc::new(camera_bind_group_layout);
-
-                let model_bind_group_layout = gpu_resources.device.create_bind_group_layout(
-                    &wgpu::BindGroupLayoutDescriptor {
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                        label: Some("model_bind_group_layout"),
-                    },
-                );
-
-                let model_bind_group_layout = Arc::new(model_bind_group_layout);
-
-                let light_bind_group_layout = gpu_resources.device.create_bind_group_layout(
-                    &wgpu::BindGroupLayoutDescriptor {
-                        entries: &[wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        }],
-                        label: Some("light_bind_group_layout"),
-                    },
-                );
-
-                let light_bind_group_layout = Arc::new(light_bind_group_layout);
-
-                let texture_bind_group_layout = gpu_resources.device.create_bind_group_layout(
-                    &wgpu::BindGroupLayoutDescriptor {
-                        entries: &[
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 0,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Texture {
-                                    multisampled: false,
-                                    view_dimension: wgpu::TextureViewDimension::D2Array,
-                                    sample_type: wgpu::TextureSampleType::Float {
-                                        filterable: true,
-                                    },
-                                },
-                                count: None,
-                            },
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 1,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                                count: None,
-                            },
-                            wgpu::BindGroupLayoutEntry {
-                                binding: 2,
-                                visibility: wgpu::ShaderStages::FRAGMENT,
-                                ty: wgpu::BindingType::Buffer {
-                                    ty: wgpu::BufferBindingType::Uniform,
-                                    has_dynamic_offset: false,
-                                    min_binding_size: None,
-                                },
-                                count: None,
-                            },
-                        ],
-                        label: Some("Texture Bind Group Layout"),
-                    },
-                );
-
-                let texture_bind_group_layout = Arc::new(texture_bind_group_layout);
-
-                let camera_bind_group =
-                    gpu_resources
-                        .device
-                        .create_bind_group(&wgpu::BindGroupDescriptor {
-                            layout: &camera_bind_group_layout,
-                            entries: &[wgpu::BindGroupEntry {
-                                binding: 0,
-                                resource: camera_uniform_buffer.as_entire_binding(),
-                            }],
-                            label: Some("Camera Bind Group"),
-                        });
-
-                let camera_bind_group = Arc::new(camera_bind_group);
-
-                let color_render_mode_buffer =
-                    gpu_resources
-                        .device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Color Render Mode Buffer"),
-                            contents: bytemuck::cast_slice(&[0i32]), // Default to normal mode
-                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        });
-
-                let color_render_mode_buffer = Arc::new(color_render_mode_buffer);
-
-                let texture_render_mode_buffer =
-                    gpu_resources
-                        .device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Texture Render Mode Buffer"),
-                            contents: bytemuck::cast_slice(&[1i32]), // Default to text mode
-                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        });
-
-                let texture_render_mode_buffer = Arc::new(texture_render_mode_buffer);
-
-                let reg_texture_render_mode_buffer =
-                    gpu_resources
-                        .device
-                        .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some("Regular Texture Render Mode Buffer"),
-                            contents: bytemuck::cast_slice(&[2i32]), // Default to text mode
-                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        });
-
-                let reg_texture_render_mode_buffer = Arc::new(reg_texture_render_mode_buffer);
+                {
+                    let mut editor_render = editor_render_2.lock().unwrap();
+                    editor_render.initialize(
+                        &gpu_resources.device,
+                        &gpu_resources.queue,
+                        &scene_layouts,
+                    );
+                    editor_render.create_view_pipelines(
+                        &gpu_resources.device,
+                        &scene_layouts,
+                        swapchain_format,
+                    );
+                }
 
                 // // Create a multisampled texture (do this once, not every frame)
                 // let multisampled_texture =
@@ -1239,20 +1480,6 @@ async fn main() {
                 // let multisampled_view =
                 //     multisampled_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-                let pipeline_layout =
-                    gpu_resources
-                        .device
-                        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                            label: Some("Render Pipeline Layout"),
-                            bind_group_layouts: &[
-                                &camera_bind_group_layout,
-                                &model_bind_group_layout,
-                                &texture_bind_group_layout,
-                                &light_bind_group_layout,
-                            ],
-                            push_constant_ranges: &[],
-                        });
-
                 // let depth_texture = gpu_resources
                 //     .device
                 //     .create_texture(&wgpu::TextureDescriptor {
@@ -1273,94 +1500,8 @@ async fn main() {
 
                 // let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
-                let depth_stencil_state = wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                };
-
-                // Load the shaders
-                let shader_module_vert_primary =
-                    gpu_resources
-                        .device
-                        .create_shader_module(wgpu::ShaderModuleDescriptor {
-                            label: Some("Primary Vert Shader"),
-                            source: wgpu::ShaderSource::Wgsl(
-                                include_str!("shaders/primary_vertex.wgsl").into(),
-                            ),
-                        });
-
-                let shader_module_frag_primary =
-                    gpu_resources
-                        .device
-                        .create_shader_module(wgpu::ShaderModuleDescriptor {
-                            label: Some("Primary Frag Shader"),
-                            source: wgpu::ShaderSource::Wgsl(
-                                include_str!("shaders/primary_fragment.wgsl").into(),
-                            ),
-                        });
-
-                // Configure the render pipeline
                 let render_pipeline =
-                    gpu_resources
-                        .device
-                        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                            label: Some("Midpoint Primary Render Pipeline"),
-                            layout: Some(&pipeline_layout),
-                            multiview: None,
-                            cache: None,
-                            vertex: wgpu::VertexState {
-                                module: &shader_module_vert_primary,
-                                entry_point: "main", // name of the entry point in your vertex shader
-                                buffers: &[Vertex::desc()], // Make sure your Vertex::desc() matches your vertex structure
-                                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                            },
-                            fragment: Some(wgpu::FragmentState {
-                                module: &shader_module_frag_primary,
-                                entry_point: "main", // name of the entry point in your fragment shader
-                                targets: &[Some(wgpu::ColorTargetState {
-                                    format: swapchain_format,
-                                    // blend: Some(wgpu::BlendState::REPLACE),
-                                    blend: Some(wgpu::BlendState {
-                                        color: wgpu::BlendComponent {
-                                            src_factor: wgpu::BlendFactor::SrcAlpha,
-                                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                                            operation: wgpu::BlendOperation::Add,
-                                        },
-                                        alpha: wgpu::BlendComponent {
-                                            src_factor: wgpu::BlendFactor::One,
-                                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                                            operation: wgpu::BlendOperation::Add,
-                                        },
-                                    }),
-                                    write_mask: wgpu::ColorWrites::ALL,
-                                })],
-                                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                            }),
-                            // primitive: wgpu::PrimitiveState::default(),
-                            // depth_stencil: None,
-                            // multisample: wgpu::MultisampleState::default(),
-                            primitive: wgpu::PrimitiveState {
-                                conservative: false,
-                                topology: wgpu::PrimitiveTopology::TriangleList, // how vertices are assembled into geometric primitives
-                                // strip_index_format: Some(wgpu::IndexFormat::Uint32),
-                                strip_index_format: None,
-                                front_face: wgpu::FrontFace::Ccw, // Counter-clockwise is considered the front face
-                                // none cull_mode
-                                cull_mode: None,
-                                polygon_mode: wgpu::PolygonMode::Fill,
-                                // Other properties such as conservative rasterization can be set here
-                                unclipped_depth: false,
-                            },
-                            depth_stencil: Some(depth_stencil_state), // Optional, only if you are using depth testing
-                            multisample: wgpu::MultisampleState {
-                                count: 4, // effect performance
-                                mask: !0,
-                                alpha_to_coverage_enabled: false,
-                            },
-                        });
+                    create_scene_pipeline(&gpu_resources.device, &scene_layouts, swapchain_format);
 
                 // window_handle.render_pipeline = Some(render_pipeline);
                 // window_handle.depth_view = gpu_helper.depth_view;
@@ -1371,18 +1512,18 @@ async fn main() {
                     viewport_2.clone(),
                     &gpu_resources.device,
                     &gpu_resources.queue,
-                    model_bind_group_layout.clone(),
-                    texture_bind_group_layout.clone(),
-                    reg_texture_render_mode_buffer.clone(),
-                    texture_render_mode_buffer.clone(),
-                    color_render_mode_buffer.clone(),
-                    camera_uniform_buffer.clone(),
-                    camera_bind_group.clone(),
+                    scene_layouts.model.clone(),
+                    scene_layouts.texture.clone(),
+                    scene_bindings.reg_texture_render_mode_buffer.clone(),
+                    scene_bindings.texture_render_mode_buffer.clone(),
+                    scene_bindings.color_render_mode_buffer.clone(),
+                    scene_bindings.camera_uniform_buffer.clone(),
+                    scene_bindings.camera_bind_group.clone(),
                     &camera,
                     window_width,
                     window_height,
-                    camera_bind_group_layout.clone(),
-                    light_bind_group_layout.clone(),
+                    scene_layouts.camera.clone(),
+                    scene_layouts.light.clone(),
                 )
                 .await;
 
@@ -1412,25 +1553,32 @@ async fn main() {
                     viewport_3.clone(),
                 );
                 window_handle.handle_mouse_input = handle_mouse_input(
+                    state_5.clone(),
                     editor_state.clone(),
                     gpu_resources.clone(),
                     viewport_4.clone(),
                     record_2.clone(),
                 );
                 window_handle.handle_window_resized = handle_window_resize(
+                    state_5.clone(),
                     editor_state.clone(),
                     gpu_resources.clone(),
                     gpu_cloned2.clone(),
                     viewport_4.clone(),
                 );
-                // window_handle.handle_mouse_wheel =
-                //     handle_mouse_wheel(cloned11, gpu_resources.clone(), cloned_viewport3.clone());
+                window_handle.handle_mouse_wheel = handle_mouse_wheel(
+                    state_5.clone(),
+                    editor_state.clone(),
+                    gpu_resources.clone(),
+                    viewport_4.clone(),
+                );
                 // window_handle.handle_modifiers_changed = handle_modifiers_changed(
                 //     state_3,
                 //     gpu_resources.clone(),
                 //     cloned_viewport3.clone(),
                 // );
                 window_handle.handle_keyboard_input = handle_keyboard_input(
+                    state_5.clone(),
                     editor_state.clone(),
                     gpu_resources.clone(),
                     viewport_4.clone(),
@@ -2,15 +2,59 @@
 struct FragmentInput {
     @location(0) normal: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
-    @location(2) color: vec3<f32>
+    @location(2) color: vec3<f32>,
+    @location(3) world_position: vec3<f32>
+};
+
+// a point or spot light placed in the level, matches SceneLightUniform
+struct SceneLight {
+    position: vec3<f32>,
+    range: f32,
+    direction: vec3<f32>,
+    // cosine of the spot cone's half angle, below -1 for point lights
+    cos_cutoff: f32,
+    color: vec3<f32>,
+};
+
+// the sun along with the level's other lights and its fog, matches LightUniforms in lights.rs
+struct LightUniforms {
+    direction: vec3<f32>,
+    color: vec3<f32>,
+    ambient_intensity: f32,
+    time_of_day: f32,
+    light_count: u32,
+    ambient_color: vec3<f32>,
+    fog_start: f32,
+    fog_color: vec3<f32>,
+    fog_end: f32,
+    camera_position: vec3<f32>,
+    fog_enabled: u32,
+    lights: array<SceneLight, 16>,
+};
+
+// how a surface is textured, matches SurfaceUniform in materials.rs. Only material overrides
+// (render mode 3) read more than the render mode.
+struct Surface {
+    render_mode: i32,
+    roughness: f32,
+    metallic: f32,
+    base_color: vec4<f32>,
+    tiling: vec2<f32>,
+    offset: vec2<f32>,
 };
 
 @group(2) @binding(0) var t_diffuse: texture_2d_array<f32>;
 @group(2) @binding(1) var s_diffuse: sampler;
-@group(2) @binding(2) var<uniform> renderMode: i32;
+@group(2) @binding(2) var<uniform> surface: Surface;
+// the lit view reads the lights, unlit and lit views the fog
+@group(3) @binding(0) var<uniform> light: LightUniforms;
 
-@fragment
-fn main(in: FragmentInput) -> @location(0) vec4<f32> {
+// material overrides keep base color, normal and roughness/metallic in layers 0 to 2
+fn material_uv(in: FragmentInput) -> vec2<f32> {
+    return in.tex_coords * surface.tiling + surface.offset;
+}
+
+fn base_color(in: FragmentInput) -> vec3<f32> {
     let tiling_factor: f32 = 10.0;
     let tiled_tex_coords = fract(in.tex_coords * tiling_factor);
 
@@ -32,15 +76,138 @@ fn main(in: FragmentInput) -> @location(0) vec4<f32> {
                     rockmap.rgb * rockmap_weight + 
                     soil.rgb * soil_weight;
 
-    if (renderMode == 1) { // Rendering terrain texture
-        return vec4<f32>(final_color, 1.0); 
-    } else if (renderMode == 2) { // Rendering typical texture (ex. models)
-        let reg_primary = textureSample(t_diffuse, s_diffuse, in.tex_coords, 0);
+    // sampled before branching, texture samples need uniform control flow
+    let reg_primary = textureSample(t_diffuse, s_diffuse, in.tex_coords, 0);
+    // override textures are stored linear, so the base color is brought out of sRGB here
+    let material_color = pow(textureSample(t_diffuse, s_diffuse, material_uv(in), 0).rgb, vec3<f32>(2.2));
 
-        return vec4<f32>(reg_primary.rgb, 1.0);
+    if (surface.render_mode == 1) { // Rendering terrain texture
+        return final_color;
+    } else if (surface.render_mode == 2) { // Rendering typical texture (ex. models)
+        return reg_primary.rgb;
+    } else if (surface.render_mode == 3) { // Material override
+        return material_color * surface.base_color.rgb;
     } else {
-        return vec4(in.color, 1.0); // Color mode
+        return in.color; // Color mode
+    }
+}
+
+// fades towards the fog color between the fog's start and end distance from the camera
+fn apply_fog(color: vec3<f32>, world_position: vec3<f32>) -> vec3<f32> {
+    let distance = length(world_position - light.camera_position);
+    let amount = clamp((distance - light.fog_start) / max(light.fog_end - light.fog_start, 0.0001), 0.0, 1.0);
+
+    return mix(color, light.fog_color, amount * f32(light.fog_enabled));
+}
+
+@fragment
+fn main(in: FragmentInput) -> @location(0) vec4<f32> {
+    return vec4<f32>(base_color(in), 1.0);
+}
+
+// the unlit view of the level, main stays fog free for gizmos and overlays
+@fragment
+fn fs_unlit(in: FragmentInput) -> @location(0) vec4<f32> {
+    return vec4<f32>(apply_fog(base_color(in), in.world_position), 1.0);
+}
+
+// the normal map's direction around the surface normal, the tangents come from screen space
+// derivatives since meshes don't carry any
+fn mapped_normal(N: vec3<f32>, mapped: vec3<f32>, dp1: vec3<f32>, dp2: vec3<f32>, duv1: vec2<f32>, duv2: vec2<f32>) -> vec3<f32> {
+    let dp2perp = cross(dp2, N);
+    let dp1perp = cross(N, dp1);
+    let T = dp2perp * duv1.x + dp1perp * duv2.x;
+    let B = dp2perp * duv1.y + dp1perp * duv2.y;
+    let scale = inverseSqrt(max(max(dot(T, T), dot(B, B)), 0.0000001));
+
+    return normalize(mat3x3<f32>(T * scale, B * scale, N) * mapped);
+}
+
+@fragment
+fn fs_lit(in: FragmentInput) -> @location(0) vec4<f32> {
+    // derivatives and samples need uniform control flow too
+    let uv = material_uv(in);
+    let dp1 = dpdx(in.world_position);
+    let dp2 = dpdy(in.world_position);
+    let duv1 = dpdx(uv);
+    let duv2 = dpdy(uv);
+    let normal_sample = textureSample(t_diffuse, s_diffuse, uv, 1).rgb * 2.0 - 1.0;
+    let surface_sample = textureSample(t_diffuse, s_diffuse, uv, 2);
+
+    // Normalize the normal (it might have been interpolated)
+    var N = normalize(in.normal);
+    // roughness in green and metallic in blue, as in glTF
+    var roughness = 1.0;
+    var metallic = 0.0;
+    if (surface.render_mode == 3) {
+        N = mapped_normal(N, normal_sample, dp1, dp2, duv1, duv2);
+        roughness = surface.roughness * surface_sample.g;
+        metallic = surface.metallic * surface_sample.b;
+    }
+
+    let L = normalize(-light.direction); // Light direction (pointing from surface to light)
+    
+    // Calculate diffuse lighting
+    let diffuse_strength = max(dot(N, L), 0.0);
+    
+    // Sunrise color temperature (warm oranges)
+    let sunrise_color = vec3<f32>(1.0, 0.7, 0.4);
+    
+    // Blend between sunrise color and regular sunlight based on time_of_day
+    let daylight = mix(
+        sunrise_color,
+        vec3<f32>(1.0),
+        smoothstep(0.15, 0.3, light.time_of_day)  // Smooth transition during sunrise
+    );
+    let light_color = light.color * daylight;
+    
+    // Combine ambient and diffuse lighting, ambient stays when the level has no sun
+    let ambient = light.ambient_intensity * light.ambient_color * daylight;
+    var diffuse = diffuse_strength * light_color;
+
+    // point and spot lights fade out towards their range
+    for (var i = 0u; i < min(light.light_count, 16u); i = i + 1u) {
+        let scene_light = light.lights[i];
+        let to_light = scene_light.position - in.world_position;
+        let light_distance = length(to_light);
+        let Lp = to_light / max(light_distance, 0.0001);
+
+        let falloff = clamp(1.0 - light_distance / max(scene_light.range, 0.0001), 0.0, 1.0);
+        let cone = smoothstep(
+            scene_light.cos_cutoff,
+            scene_light.cos_cutoff + 0.05,
+            dot(-Lp, scene_light.direction)
+        );
+
+        diffuse += scene_light.color * max(dot(N, Lp), 0.0) * falloff * falloff * cone;
     }
+
+    // there's no view direction in this pass, so metals lose their diffuse light and smooth
+    // surfaces get a sheen towards the sun instead of a highlight
+    let albedo = base_color(in);
+    let sheen = mix(vec3<f32>(0.04), albedo, metallic) * (1.0 - roughness) * diffuse_strength;
+
+    let lit = albedo * (ambient + diffuse * (1.0 - metallic * 0.9)) + sheen * light_color;
+
+    return vec4<f32>(apply_fog(lit, in.world_position), 1.0);
+}
+
+@fragment
+fn fs_normals(in: FragmentInput) -> @location(0) vec4<f32> {
+    return vec4<f32>(normalize(in.normal) * 0.5 + 0.5, 1.0);
+}
+
+@fragment
+fn fs_uv_checker(in: FragmentInput) -> @location(0) vec4<f32> {
+    let checks: f32 = 8.0;
+    let cell = floor(in.tex_coords * checks);
+    let parity = (i32(cell.x) + i32(cell.y)) & 1;
+
+    // tinted by the coordinates so flipped or repeated islands stand out
+    let tint = vec3<f32>(fract(in.tex_coords), 0.5);
+    let shade = select(0.35, 0.9, parity == 0);
+
+    return vec4<f32>(mix(vec3<f32>(shade), tint, 0.25), 1.0);
 }
 
 // // with lighting
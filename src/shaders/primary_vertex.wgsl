@@ -20,16 +20,37 @@ struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) normal: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
-    @location(2) color: vec3<f32>
+    @location(2) color: vec3<f32>,
+    @location(3) world_position: vec3<f32>
 };
 
-@vertex
-fn main(input: VertexInput) -> VertexOutput {
+// one copy of a mesh drawn instanced, the model matrix in columns
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>
+};
+
+fn transform_vertex(input: VertexInput, model: mat4x4<f32>) -> VertexOutput {
     var output: VertexOutput;
-    let model_position = model_uniforms.model * vec4<f32>(input.position, 1.0);
+    let model_position = model * vec4<f32>(input.position, 1.0);
     output.position = camera_uniforms.view_projection * model_position;
     output.color = input.color;
-    output.normal = input.normal;
+    // world space, so lighting and the normals view follow the model's rotation
+    output.normal = (model * vec4<f32>(input.normal, 0.0)).xyz;
     output.tex_coords = input.tex_coords;
+    output.world_position = model_position.xyz;
     return output;
 }
+
+@vertex
+fn main(input: VertexInput) -> VertexOutput {
+    return transform_vertex(input, model_uniforms.model);
+}
+
+@vertex
+fn main_instanced(input: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    return transform_vertex(input, model);
+}
@@ -0,0 +1,49 @@
+// the level's sky, one triangle covering the view drawn before anything else
+struct SkyUniforms {
+    inverse_view_projection: mat4x4<f32>,
+    // 1 for a cubemap, 2 for an equirectangular panorama, matches SkyKind in environment.rs
+    kind: u32,
+    exposure: f32,
+};
+
+struct SkyOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+};
+
+const PI: f32 = 3.14159265;
+
+@group(0) @binding(0) var<uniform> sky: SkyUniforms;
+@group(0) @binding(1) var t_cubemap: texture_cube<f32>;
+@group(0) @binding(2) var t_panorama: texture_2d<f32>;
+@group(0) @binding(3) var s_sky: sampler;
+
+@vertex
+fn vs_sky(@builtin(vertex_index) index: u32) -> SkyOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+
+    var out: SkyOutput;
+    out.position = vec4<f32>(corner, 1.0, 1.0);
+    out.ndc = corner;
+    return out;
+}
+
+@fragment
+fn fs_sky(in: SkyOutput) -> @location(0) vec4<f32> {
+    // two points along the pixel's ray, whichever depth range the projection uses
+    let near = sky.inverse_view_projection * vec4<f32>(in.ndc, 0.0, 1.0);
+    let far = sky.inverse_view_projection * vec4<f32>(in.ndc, 1.0, 1.0);
+    let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+
+    let longitude = atan2(direction.z, direction.x);
+    let latitude = asin(clamp(direction.y, -1.0, 1.0));
+    let panorama_uv = vec2<f32>(0.5 + longitude / (2.0 * PI), 0.5 - latitude / PI);
+
+    // the first mip only, the panorama's seam would throw off derivatives
+    let cubemap = textureSampleLevel(t_cubemap, s_sky, direction, 0.0).rgb;
+    let panorama = textureSampleLevel(t_panorama, s_sky, panorama_uv, 0.0).rgb;
+
+    let color = select(cubemap, panorama, sky.kind == 2u);
+
+    return vec4<f32>(color * sky.exposure, 1.0);
+}
@@ -30,6 +30,7 @@ use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 use crate::editor_state::UIMessage;
 use crate::editor_state::{EditorState, StateHelper};
 use crate::helpers::animations::{AnimationData, UIKeyframe};
+use crate::helpers::viewport_layout::update_viewport_layout;
 
 pub fn animations_view(
     state_helper: Arc<Mutex<StateHelper>>,
@@ -261,10 +262,17 @@ pub fn animations_view(
                         offset_y: 0.0,
                     };
 
-                    let keyframe_timeline = create_timeline(state, config, animation_data);
-
                     let state_4 = Arc::clone(&state_4);
                     let state_7 = Arc::clone(&state_7);
+                    let state_7c = Arc::clone(&state_7);
+
+                    // the timeline covers the bottom of the viewport
+                    let keyframe_timeline =
+                        create_timeline(state, config, animation_data).on_move(move |origin| {
+                            update_viewport_layout(&state_7c.lock().unwrap(), |layout| {
+                                layout.timeline_top = Some(origin.y)
+                            });
+                        });
                     let gpu_3 = Arc::clone(&gpu_3);
                     let viewport_3 = Arc::clone(&viewport_3);
                     let state_7b = Arc::clone(&state_7);
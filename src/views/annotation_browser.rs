@@ -0,0 +1,101 @@
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::core::Viewport::Viewport;
+use midpoint_engine::floem::peniko::Color;
+use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
+use midpoint_engine::floem::reactive::{SignalGet, SignalWith};
+use midpoint_engine::floem::style::CursorStyle;
+use midpoint_engine::floem::taffy::AlignItems;
+use midpoint_engine::floem::views::{dyn_stack, h_stack, label, scroll, text_input, v_stack};
+use midpoint_engine::floem::IntoView;
+
+use midpoint_engine::floem::views::Decorators;
+use midpoint_engine::floem::{GpuHelper, View};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::measurements::{
+    level_annotations, remove_annotation, set_annotation_text, Annotation,
+};
+
+/// Text button for the per note actions
+fn note_action(text: &'static str, action: impl Fn() + 'static) -> impl IntoView {
+    label(move || text)
+        .on_click_stop(move |_| action())
+        .style(|s| {
+            s.font_size(10.0)
+                .margin_left(5.0)
+                .color(Color::GRAY)
+                .cursor(CursorStyle::Pointer)
+        })
+}
+
+fn annotation_item(state_helper: Arc<Mutex<StateHelper>>, annotation: Annotation) -> impl View {
+    let text = create_rw_signal(annotation.text.clone());
+    let [x, y, z] = annotation.position;
+    let state_2 = Arc::clone(&state_helper);
+    let id = annotation.id.clone();
+    let id_2 = annotation.id;
+
+    v_stack((
+        h_stack((
+            text_input(text).style(|s| s.width(170.0)),
+            note_action("Save", move || {
+                set_annotation_text(&state_helper.lock().unwrap(), &id, &text.get_untracked());
+            }),
+            note_action("Delete", move || {
+                remove_annotation(&state_2.lock().unwrap(), &id_2);
+            }),
+        ))
+        .style(|s| s.align_items(AlignItems::Center)),
+        label(move || format!("At {:.1}, {:.1}, {:.1}", x, y, z))
+            .style(|s| s.font_size(10.0).color(Color::GRAY).margin_top(2.0)),
+    ))
+    .style(|s| s.width(260.0).padding_vert(4))
+}
+
+/// Notes pinned in the active level, placed with the Pin tool above the viewport
+pub fn annotation_browser(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    viewport: Arc<Mutex<Viewport>>,
+) -> impl View {
+    let annotations: RwSignal<Vec<Annotation>> = create_rw_signal(Vec::new());
+
+    let state_2 = Arc::clone(&state_helper);
+
+    create_effect(move |_| {
+        let mut state_helper = state_helper.lock().unwrap();
+
+        // runs again when another level opens
+        if let Some(active_level_signal) = state_helper.active_level_signal {
+            active_level_signal.get();
+        }
+
+        annotations.set(level_annotations(&state_helper));
+        // pins placed in the viewport refresh the list through this
+        state_helper.annotations_signal = Some(annotations);
+    });
+
+    v_stack((
+        label(|| "Choose Pin above the viewport, then click a surface to leave a note")
+            .style(|s| s.font_size(10.0).margin_bottom(8.0)),
+        label(move || {
+            if annotations.with(|annotations| annotations.is_empty()) {
+                "No notes in this level".to_string()
+            } else {
+                String::new()
+            }
+        })
+        .style(|s| s.font_size(10.0).color(Color::GRAY)),
+        scroll(
+            dyn_stack(
+                move || annotations.get(),
+                |annotation: &Annotation| (annotation.id.clone(), annotation.text.clone()),
+                move |annotation| annotation_item(state_2.clone(), annotation),
+            )
+            .style(|s| s.flex_col().width(260.0))
+            .into_view(),
+        ),
+    ))
+    .style(|s| s.width(260.0))
+}
@@ -1,30 +1,26 @@
 use midpoint_engine::core::RendererState::ObjectConfig;
 use midpoint_engine::core::Viewport::Viewport;
-use midpoint_engine::floem::common::toggle_button;
-use midpoint_engine::floem::peniko::Brush;
+use midpoint_engine::floem::action::exec_after;
+use midpoint_engine::floem::common::{simple_button, toggle_button};
 use midpoint_engine::floem::peniko::Color;
 use midpoint_engine::floem::reactive::create_effect;
 use midpoint_engine::floem::reactive::create_rw_signal;
+use midpoint_engine::floem::reactive::RwSignal;
 use midpoint_engine::floem::reactive::SignalGet;
 use midpoint_engine::floem::reactive::SignalUpdate;
-use midpoint_engine::floem::style::Foreground;
 use midpoint_engine::floem::taffy::AlignItems;
 use midpoint_engine::floem::views::h_stack;
-use midpoint_engine::floem::views::slider::slider;
-use midpoint_engine::floem::views::slider::AccentBarClass;
-use midpoint_engine::floem::views::slider::BarClass;
-use midpoint_engine::floem::views::slider::EdgeAlign;
-use midpoint_engine::floem::views::slider::HandleRadius;
-use midpoint_engine::floem::views::slider::SliderClass;
+use midpoint_engine::floem::views::v_stack;
 use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::views::{
-    container, dyn_container, empty, label, scroll, stack, tab, text_input, virtual_stack,
-    VirtualDirection, VirtualItemSize,
+    container, dyn_container, dyn_stack, empty, label, scroll, stack, tab, text_input,
+    virtual_stack, VirtualDirection, VirtualItemSize,
 };
 use midpoint_engine::helpers::saved_data::ComponentData;
 use midpoint_engine::helpers::saved_data::ComponentKind;
 use midpoint_engine::helpers::saved_data::GenericProperties;
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 use uuid::Uuid;
 use wgpu::util::DeviceExt;
 
@@ -32,12 +28,25 @@ use midpoint_engine::floem::GpuHelper;
 use midpoint_engine::floem::IntoView;
 
 use crate::editor_state::StateHelper;
+use crate::helpers::camera::{set_camera_view, CameraView};
+use crate::helpers::levels::active_level_id;
+use crate::helpers::measurements::{
+    measure_tool, set_measure_tool, viewport_labels, MeasureTool, ViewportLabel,
+};
+use crate::helpers::offscreen::save_level_screenshot;
+use crate::helpers::play_mode::{pause, play, play_state, stop_play, PlayState};
+use crate::helpers::selection::{refresh_selection_gizmos, PivotMode, TransformSpace};
+use crate::helpers::view_modes::{update_viewport_settings, RenderMode, ViewportSettings};
+use crate::helpers::viewport_layout::update_viewport_layout;
 use crate::helpers::websocket::WebSocketManager;
 
 use super::aside::project_tab_interface;
 use super::aside::welcome_tab_interface;
 use super::properties_panel::properties_view;
 
+/// How often the notes over the viewport catch up with the camera
+const LABEL_REFRESH_INTERVAL: Duration = Duration::from_millis(30);
+
 pub fn project_view(
     state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
@@ -47,7 +56,10 @@ pub fn project_view(
     let selected_object_id_signal = create_rw_signal(Uuid::nil());
     let active_gizmo_signal = create_rw_signal("translate".to_string());
     let current_view_signal = create_rw_signal("scene".to_string());
-    let navigation_speed_signal = create_rw_signal(5.0);
+    let selection_signal: RwSignal<Vec<String>> = create_rw_signal(Vec::new());
+    let marquee_signal: RwSignal<Option<(f64, f64, f64, f64)>> = create_rw_signal(None);
+    let active_level_signal = create_rw_signal(String::new());
+    let labels_signal: RwSignal<Vec<ViewportLabel>> = create_rw_signal(Vec::new());
 
     let selected_object_data_signal = create_rw_signal(ComponentData {
         id: "".to_string(),
@@ -67,7 +79,26 @@ pub fn project_view(
     let state_3 = Arc::clone(&state_helper);
     let state_4 = Arc::clone(&state_helper);
     let state_5 = Arc::clone(&state_helper);
-    let state_6 = Arc::clone(&state_helper);
+    let state_7 = Arc::clone(&state_helper);
+    let state_8 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+
+    poll_viewport_labels(Arc::clone(&state_helper), labels_signal);
+
+    // toggle buttons are active while their signal holds their value
+    let snapping = state_helper.lock().unwrap().editor_settings.snapping;
+    let snap_signal = create_rw_signal(if snapping.enabled { "snap" } else { "" }.to_string());
+    let surface_snap_signal =
+        create_rw_signal(if snapping.surface { "surface" } else { "" }.to_string());
+    let transform = state_helper.lock().unwrap().editor_settings.transform;
+    let pivot_signal = create_rw_signal(pivot_value(transform.pivot).to_string());
+    let space_signal = create_rw_signal(
+        match transform.space {
+            TransformSpace::Local => "local",
+            TransformSpace::World => "",
+        }
+        .to_string(),
+    );
 
     create_effect(move |_| {
         let state_helper = state_2.clone();
@@ -75,25 +106,14 @@ pub fn project_view(
         state_helper.object_selected_signal = Some(object_selected_signal);
         state_helper.selected_object_id_signal = Some(selected_object_id_signal);
         state_helper.selected_object_data_signal = Some(selected_object_data_signal);
+        state_helper.selection_signal = Some(selection_signal);
+        state_helper.marquee_signal = Some(marquee_signal);
+        state_helper.active_level_signal = Some(active_level_signal);
 
         // also current_view
         state_helper.current_view_signal = Some(current_view_signal);
     });
 
-    // retain navigation speed
-    create_effect(move |_| {
-        let state_helper = state_6.clone();
-        let mut state_helper = state_helper.lock().unwrap();
-        let mut renderer_state = state_helper
-            .renderer_state
-            .as_mut()
-            .expect("Couldn't get RendererState")
-            .lock()
-            .unwrap();
-        let new_navigation_speed = navigation_speed_signal.get();
-        renderer_state.navigation_speed = new_navigation_speed;
-    });
-
     container((
         project_tab_interface(
             state_helper.clone(),
@@ -102,9 +122,16 @@ pub fn project_view(
             object_selected_signal,
         ),
         // this properties pabel "covers" the tools panels which are inserted within tab_interface
+        // rebuilt when the selection changes so the inputs show its values
         dyn_container(
-            move || object_selected_signal.get(),
-            move |object_selected_real| {
+            move || {
+                (
+                    object_selected_signal.get(),
+                    selected_object_id_signal.get(),
+                    selection_signal.get(),
+                )
+            },
+            move |(object_selected_real, _, _)| {
                 if object_selected_real {
                     properties_view(
                         state_helper.clone(),
@@ -120,119 +147,547 @@ pub fn project_view(
                 }
             },
         ),
+        // the scene shows through the space left next to the panels, below the toolbar
+        v_stack((
+            dyn_container(
+                move || current_view_signal.get(),
+                move |current_view_real| {
+                    if current_view_real == "scene".to_string() {
+                        h_stack((
+                            toggle_button(
+                                "Translate",
+                                "translate",
+                                "translate".to_string(),
+                                {
+                                    let state_3 = state_3.clone();
+
+                                    move |_| {
+                                        let mut state_helper = state_3.lock().unwrap();
+                                        let mut renderer_state = state_helper
+                                            .renderer_state
+                                            .as_mut()
+                                            .expect("Couldn't get RendererState")
+                                            .lock()
+                                            .unwrap();
+
+                                        renderer_state.active_gizmo = "translate".to_string();
+
+                                        active_gizmo_signal.set("translate".to_string());
+                                    }
+                                },
+                                active_gizmo_signal,
+                            )
+                            .style(|s| s.margin_right(4.0)),
+                            toggle_button(
+                                "Rotate",
+                                "rotate",
+                                "rotate".to_string(),
+                                {
+                                    let state_4 = state_4.clone();
+
+                                    move |_| {
+                                        let mut state_helper = state_4.lock().unwrap();
+                                        let mut renderer_state = state_helper
+                                            .renderer_state
+                                            .as_mut()
+                                            .expect("Couldn't get RendererState")
+                                            .lock()
+                                            .unwrap();
+
+                                        renderer_state.active_gizmo = "rotate".to_string();
+
+                                        active_gizmo_signal.set("rotate".to_string());
+                                    }
+                                },
+                                active_gizmo_signal,
+                            )
+                            .style(|s| s.margin_right(4.0)),
+                            toggle_button(
+                                "Scale",
+                                "scale",
+                                "scale".to_string(),
+                                {
+                                    let state_5 = state_5.clone();
+
+                                    move |_| {
+                                        let mut state_helper = state_5.lock().unwrap();
+                                        let mut renderer_state = state_helper
+                                            .renderer_state
+                                            .as_mut()
+                                            .expect("Couldn't get RendererState")
+                                            .lock()
+                                            .unwrap();
+
+                                        renderer_state.active_gizmo = "scale".to_string();
+
+                                        active_gizmo_signal.set("scale".to_string());
+                                    }
+                                },
+                                active_gizmo_signal,
+                            )
+                            .style(|s| s.margin_right(12.0)),
+                            toggle_button(
+                                "Snap",
+                                "plus",
+                                "snap".to_string(),
+                                {
+                                    let state_7 = state_7.clone();
+
+                                    move |_| {
+                                        let mut state_helper = state_7.lock().unwrap();
+                                        state_helper.update_editor_settings(|settings| {
+                                            settings.snapping.enabled = !settings.snapping.enabled
+                                        });
+
+                                        let enabled = state_helper.editor_settings.snapping.enabled;
+                                        snap_signal
+                                            .set(if enabled { "snap" } else { "" }.to_string());
+                                    }
+                                },
+                                snap_signal,
+                            )
+                            .style(|s| s.margin_right(4.0)),
+                            toggle_button(
+                                "Surface",
+                                "plus",
+                                "surface".to_string(),
+                                {
+                                    let state_7 = state_7.clone();
+
+                                    move |_| {
+                                        let mut state_helper = state_7.lock().unwrap();
+                                        state_helper.update_editor_settings(|settings| {
+                                            settings.snapping.surface = !settings.snapping.surface
+                                        });
+
+                                        let surface = state_helper.editor_settings.snapping.surface;
+                                        surface_snap_signal
+                                            .set(if surface { "surface" } else { "" }.to_string());
+                                    }
+                                },
+                                surface_snap_signal,
+                            )
+                            .style(|s| s.margin_right(10.0)),
+                            pivot_button(
+                                state_7.clone(),
+                                "Median",
+                                PivotMode::Median,
+                                pivot_signal,
+                            ),
+                            pivot_button(
+                                state_7.clone(),
+                                "First",
+                                PivotMode::FirstSelected,
+                                pivot_signal,
+                            ),
+                            pivot_button(
+                                state_7.clone(),
+                                "Individual",
+                                PivotMode::IndividualOrigins,
+                                pivot_signal,
+                            ),
+                            toggle_button(
+                                "Local",
+                                "plus",
+                                "local".to_string(),
+                                {
+                                    let state_7 = state_7.clone();
+
+                                    move |_| {
+                                        let mut state_helper = state_7.lock().unwrap();
+                                        state_helper.update_editor_settings(|settings| {
+                                            settings.transform.space =
+                                                match settings.transform.space {
+                                                    TransformSpace::World => TransformSpace::Local,
+                                                    TransformSpace::Local => TransformSpace::World,
+                                                }
+                                        });
+
+                                        let local = state_helper.editor_settings.transform.space
+                                            == TransformSpace::Local;
+                                        space_signal
+                                            .set(if local { "local" } else { "" }.to_string());
+
+                                        refresh_selection_gizmos(&state_helper);
+                                    }
+                                },
+                                space_signal,
+                            ),
+                            view_button(state_7.clone(), "Persp", CameraView::Perspective)
+                                .style(|s| s.margin_left(10.0)),
+                            view_button(state_7.clone(), "Top", CameraView::Top),
+                            view_button(state_7.clone(), "Front", CameraView::Front),
+                            view_button(state_7.clone(), "Side", CameraView::Side),
+                            screenshot_button(state_7.clone(), gpu_2.clone())
+                                .style(|s| s.margin_left(10.0)),
+                            view_mode_toolbar(state_7.clone()).style(|s| s.margin_left(10.0)),
+                            measure_toolbar(state_7.clone()).style(|s| s.margin_left(10.0)),
+                            play_toolbar(state_7.clone(), gpu_2.clone())
+                                .style(|s| s.margin_left(10.0)),
+                        ))
+                        .style(|s| s.height(40.0).align_items(AlignItems::Center))
+                        .into_any()
+                    } else {
+                        empty().into_any()
+                    }
+                },
+            ),
+            viewport_area(state_8.clone()),
+        ))
+        .style(|s| s.flex_grow(1.0).flex_basis(0.0).height_full()),
+        // box select rectangle drawn over the viewport while dragging
         dyn_container(
-            move || current_view_signal.get(),
-            move |current_view_real| {
-                if current_view_real == "scene".to_string() {
-                    h_stack((
-                        toggle_button(
-                            "Translate",
-                            "translate",
-                            "translate".to_string(),
-                            {
-                                let state_3 = state_3.clone();
-
-                                move |_| {
-                                    let mut state_helper = state_3.lock().unwrap();
-                                    let mut renderer_state = state_helper
-                                        .renderer_state
-                                        .as_mut()
-                                        .expect("Couldn't get RendererState")
-                                        .lock()
-                                        .unwrap();
-
-                                    renderer_state.active_gizmo = "translate".to_string();
-
-                                    active_gizmo_signal.set("translate".to_string());
-                                }
-                            },
-                            active_gizmo_signal,
-                        )
-                        .style(|s| s.margin_right(4.0)),
-                        toggle_button(
-                            "Rotate",
-                            "rotate",
-                            "rotate".to_string(),
-                            {
-                                let state_4 = state_4.clone();
-
-                                move |_| {
-                                    let mut state_helper = state_4.lock().unwrap();
-                                    let mut renderer_state = state_helper
-                                        .renderer_state
-                                        .as_mut()
-                                        .expect("Couldn't get RendererState")
-                                        .lock()
-                                        .unwrap();
-
-                                    renderer_state.active_gizmo = "rotate".to_string();
-
-                                    active_gizmo_signal.set("rotate".to_string());
-                                }
-                            },
-                            active_gizmo_signal,
-                        )
-                        .style(|s| s.margin_right(4.0)),
-                        toggle_button(
-                            "Scale",
-                            "scale",
-                            "scale".to_string(),
-                            {
-                                let state_5 = state_5.clone();
-
-                                move |_| {
-                                    let mut state_helper = state_5.lock().unwrap();
-                                    let mut renderer_state = state_helper
-                                        .renderer_state
-                                        .as_mut()
-                                        .expect("Couldn't get RendererState")
-                                        .lock()
-                                        .unwrap();
-
-                                    renderer_state.active_gizmo = "scale".to_string();
-
-                                    active_gizmo_signal.set("scale".to_string());
-                                }
-                            },
-                            active_gizmo_signal,
-                        ),
-                        label(move || format!("Nav Speed: {:.1}x", navigation_speed_signal.get()))
-                            .style(|s| s.margin_left(10.0)),
-                        slider(|| 5.0)
-                            .style(|s| s.margin_left(6.0).width(200).height(15))
-                            .on_change_pct(move |value| {
-                                // println!("Speed changed to: {}%", value);
-                                // Here you would implement your 3D navigation speed adjustment logic
-                                if value - 4.0 < 1.0 {
-                                    navigation_speed_signal.set(((1.0 / value) - 1.0).abs());
-                                } else {
-                                    navigation_speed_signal.set(value - 4.0);
-                                }
-                            })
-                            .style(|s| {
-                                s.class(SliderClass, |s| {
-                                    s.set(Foreground, Brush::Solid(Color::WHITE))
-                                        .set(EdgeAlign, true)
-                                        .set(HandleRadius, 15.0)
-                                })
-                                .class(BarClass, |s| s.background(Color::GRAY).border_radius(100.0))
-                                .class(AccentBarClass, |s| {
-                                    s.background(Color::ROYAL_BLUE)
-                                        .border_radius(100.0)
-                                        .height(100.0)
-                                })
-                            }),
-                    ))
-                    .style(|s| s.height(40.0).align_items(AlignItems::Center))
-                    .into_any()
+            move || marquee_signal.get(),
+            move |marquee_real| {
+                if let Some((x, y, width, height)) = marquee_real {
+                    empty()
+                        .style(move |s| {
+                            s.absolute()
+                                .inset_left(x)
+                                .inset_top(y)
+                                .width(width)
+                                .height(height)
+                                .border(1.0)
+                                .border_color(Color::ROYAL_BLUE)
+                                .background(Color::rgba(0.25, 0.41, 0.88, 0.15))
+                        })
+                        .into_any()
                 } else {
                     empty().into_any()
                 }
             },
         ),
+        // pin notes and the measurement readout, moved along with the camera
+        dyn_stack(
+            move || labels_signal.get(),
+            |viewport_label: &ViewportLabel| format!("{:?}", viewport_label),
+            |viewport_label| {
+                let [x, y] = viewport_label.position;
+
+                label(move || viewport_label.text.clone()).style(move |s| {
+                    s.absolute()
+                        .inset_left(x)
+                        .inset_top(y - 20.0)
+                        .padding_horiz(4.0)
+                        .padding_vert(2.0)
+                        .font_size(11.0)
+                        .color(Color::WHITE)
+                        .background(Color::rgba(0.0, 0.0, 0.0, 0.6))
+                        .border_radius(3.0)
+                })
+            },
+        )
+        .style(|s| s.absolute().inset_left(0.0).inset_top(0.0)),
+    ))
+}
+
+/// Keeps the viewport labels where their points are on screen, until the signal goes away
+fn poll_viewport_labels(
+    state_helper: Arc<Mutex<StateHelper>>,
+    labels_signal: RwSignal<Vec<ViewportLabel>>,
+) {
+    exec_after(LABEL_REFRESH_INTERVAL, move |_| {
+        let latest = viewport_labels(&state_helper.lock().unwrap());
+        let Some(current) = labels_signal.try_get_untracked() else {
+            return;
+        };
+
+        // a still camera leaves the labels alone
+        if current != latest {
+            labels_signal.set(latest);
+        }
+
+        poll_viewport_labels(state_helper, labels_signal);
+    });
+}
+
+fn pivot_value(pivot: PivotMode) -> &'static str {
+    match pivot {
+        PivotMode::Median => "median",
+        PivotMode::FirstSelected => "first",
+        PivotMode::IndividualOrigins => "individual",
+    }
+}
+
+/// One of the pivot choices for transforming several components, only one is active at a time
+fn pivot_button(
+    state_helper: Arc<Mutex<StateHelper>>,
+    label_text: &'static str,
+    pivot: PivotMode,
+    pivot_signal: RwSignal<String>,
+) -> impl IntoView {
+    toggle_button(
+        label_text,
+        "plus",
+        pivot_value(pivot).to_string(),
+        move |_| {
+            let mut state_helper = state_helper.lock().unwrap();
+            state_helper.update_editor_settings(|settings| settings.transform.pivot = pivot);
+            pivot_signal.set(pivot_value(pivot).to_string());
+
+            refresh_selection_gizmos(&state_helper);
+        },
+        pivot_signal,
+    )
+    .style(|s| s.margin_right(4.0))
+}
+
+/// Empty space the scene renders into, wherever the layout puts it
+fn viewport_area(state_helper: Arc<Mutex<StateHelper>>) -> impl IntoView {
+    let state_2 = Arc::clone(&state_helper);
+
+    empty()
+        .on_move(move |origin| {
+            update_viewport_layout(&state_helper.lock().unwrap(), |layout| {
+                layout.origin = [origin.x, origin.y]
+            });
+        })
+        .on_resize(move |rect| {
+            update_viewport_layout(&state_2.lock().unwrap(), |layout| {
+                layout.size = Some([rect.width(), rect.height()])
+            });
+        })
+        .style(|s| s.flex_grow(1.0).width_full())
+}
+
+fn view_button(
+    state_helper: Arc<Mutex<StateHelper>>,
+    label_text: &'static str,
+    view: CameraView,
+) -> impl IntoView {
+    simple_button(label_text.to_string(), move |_| {
+        let mut state_helper = state_helper.lock().unwrap();
+        set_camera_view(&mut state_helper.editor_camera, view);
+    })
+    .style(|s| s.margin_right(4.0))
+}
+
+/// Saves the level as the viewport sees it, at the size set in the editor settings
+fn screenshot_button(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+) -> impl IntoView {
+    simple_button("Screenshot".to_string(), move |_| {
+        let state_helper = state_helper.lock().unwrap();
+        let Some(gpu_resources) = gpu_helper.lock().unwrap().gpu_resources.clone() else {
+            return;
+        };
+
+        match save_level_screenshot(&state_helper, &gpu_resources) {
+            Ok(path) => println!("Saved screenshot to {:?}", path),
+            Err(e) => println!("Couldn't take screenshot: {}", e),
+        }
+    })
+}
+
+/// Render mode buttons, only one is active at a time, then the overlay toggles
+fn view_mode_toolbar(state_helper: Arc<Mutex<StateHelper>>) -> impl IntoView {
+    let viewport_settings = state_helper.lock().unwrap().editor_settings.viewport;
+    let render_mode_signal = create_rw_signal(viewport_settings.render_mode.label().to_string());
+
+    h_stack((
+        render_mode_button(state_helper.clone(), RenderMode::Unlit, render_mode_signal),
+        render_mode_button(state_helper.clone(), RenderMode::Lit, render_mode_signal),
+        render_mode_button(
+            state_helper.clone(),
+            RenderMode::Wireframe,
+            render_mode_signal,
+        ),
+        render_mode_button(
+            state_helper.clone(),
+            RenderMode::Normals,
+            render_mode_signal,
+        ),
+        render_mode_button(
+            state_helper.clone(),
+            RenderMode::UvChecker,
+            render_mode_signal,
+        ),
+        overlay_button(
+            state_helper.clone(),
+            "Colliders",
+            viewport_settings.show_colliders,
+            |settings| &mut settings.show_colliders,
+        )
+        .style(|s| s.margin_left(6.0)),
+        overlay_button(
+            state_helper.clone(),
+            "Bounds",
+            viewport_settings.show_bounds,
+            |settings| &mut settings.show_bounds,
+        ),
+        overlay_button(
+            state_helper.clone(),
+            "Ray",
+            viewport_settings.show_debug_ray,
+            |settings| &mut settings.show_debug_ray,
+        ),
+    ))
+}
+
+/// Play, pause and stop for running the level as a game, then whether stopping keeps the
+/// selected components as play left them
+fn play_toolbar(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+) -> impl IntoView {
+    let (current_state, keep_changes) = {
+        let state_helper = state_helper.lock().unwrap();
+        (
+            play_state(&state_helper),
+            state_helper.editor_settings.play.keep_selected_changes,
+        )
+    };
+    let play_state_signal = create_rw_signal(current_state.label().to_string());
+    let keep_signal = create_rw_signal(if keep_changes { "keep" } else { "" }.to_string());
+
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+
+    h_stack((
+        toggle_button(
+            "Play",
+            "plus",
+            PlayState::Playing.label().to_string(),
+            move |_| {
+                let mut state_helper = state_helper.lock().unwrap();
+                play(&mut state_helper);
+                play_state_signal.set(play_state(&state_helper).label().to_string());
+            },
+            play_state_signal,
+        )
+        .style(|s| s.margin_right(4.0)),
+        toggle_button(
+            "Pause",
+            "plus",
+            PlayState::Paused.label().to_string(),
+            move |_| {
+                let state_helper = state_2.lock().unwrap();
+                pause(&state_helper);
+                play_state_signal.set(play_state(&state_helper).label().to_string());
+            },
+            play_state_signal,
+        )
+        .style(|s| s.margin_right(4.0)),
+        simple_button("Stop".to_string(), move |_| {
+            let Some(gpu_resources) = gpu_helper.lock().unwrap().gpu_resources.clone() else {
+                return;
+            };
+
+            let reopened = {
+                let mut state_helper = state_3.lock().unwrap();
+                stop_play(&mut state_helper, &gpu_resources);
+                state_helper
+                    .active_level_signal
+                    .zip(active_level_id(&state_helper))
+            };
+
+            // the browsers pick up the restored level once the state helper is free
+            if let Some((active_level_signal, level_id)) = reopened {
+                active_level_signal.set(level_id);
+            }
+
+            play_state_signal.set(PlayState::Editing.label().to_string());
+        })
+        .style(|s| s.margin_right(4.0)),
+        toggle_button(
+            "Keep Selected",
+            "plus",
+            "keep".to_string(),
+            move |_| {
+                let mut state_helper = state_4.lock().unwrap();
+                state_helper.update_editor_settings(|settings| {
+                    settings.play.keep_selected_changes = !settings.play.keep_selected_changes
+                });
+
+                let keep = state_helper.editor_settings.play.keep_selected_changes;
+                keep_signal.set(if keep { "keep" } else { "" }.to_string());
+            },
+            keep_signal,
+        ),
     ))
 }
 
+/// Ruler, path, area and pin tools, clicking the active one goes back to selecting
+fn measure_toolbar(state_helper: Arc<Mutex<StateHelper>>) -> impl IntoView {
+    let active_tool = measure_tool(&state_helper.lock().unwrap());
+    let tool_signal = create_rw_signal(active_tool.map_or("", |tool| tool.label()).to_string());
+
+    h_stack((
+        measure_button(state_helper.clone(), MeasureTool::Ruler, tool_signal),
+        measure_button(state_helper.clone(), MeasureTool::Path, tool_signal),
+        measure_button(state_helper.clone(), MeasureTool::Area, tool_signal),
+        measure_button(state_helper.clone(), MeasureTool::Pin, tool_signal),
+    ))
+}
+
+fn measure_button(
+    state_helper: Arc<Mutex<StateHelper>>,
+    tool: MeasureTool,
+    tool_signal: RwSignal<String>,
+) -> impl IntoView {
+    toggle_button(
+        tool.label(),
+        "plus",
+        tool.label().to_string(),
+        move |_| {
+            let state_helper = state_helper.lock().unwrap();
+            let next = (measure_tool(&state_helper) != Some(tool)).then_some(tool);
+            set_measure_tool(&state_helper, next);
+            tool_signal.set(next.map_or("", |tool| tool.label()).to_string());
+        },
+        tool_signal,
+    )
+    .style(|s| s.margin_right(4.0))
+}
+
+fn render_mode_button(
+    state_helper: Arc<Mutex<StateHelper>>,
+    mode: RenderMode,
+    render_mode_signal: RwSignal<String>,
+) -> impl IntoView {
+    toggle_button(
+        mode.label(),
+        "plus",
+        mode.label().to_string(),
+        move |_| {
+            let mut state_helper = state_helper.lock().unwrap();
+            update_viewport_settings(&mut state_helper, |settings| settings.render_mode = mode);
+            render_mode_signal.set(mode.label().to_string());
+        },
+        render_mode_signal,
+    )
+    .style(|s| s.margin_right(4.0))
+}
+
+fn overlay_button(
+    state_helper: Arc<Mutex<StateHelper>>,
+    label_text: &'static str,
+    initial: bool,
+    overlay: fn(&mut ViewportSettings) -> &mut bool,
+) -> impl IntoView {
+    let overlay_signal = create_rw_signal(if initial { label_text } else { "" }.to_string());
+
+    toggle_button(
+        label_text,
+        "plus",
+        label_text.to_string(),
+        move |_| {
+            let mut state_helper = state_helper.lock().unwrap();
+            update_viewport_settings(&mut state_helper, |settings| {
+                let shown = overlay(settings);
+                *shown = !*shown;
+            });
+
+            let shown = *overlay(&mut state_helper.editor_settings.viewport);
+            overlay_signal.set(if shown { label_text } else { "" }.to_string());
+        },
+        overlay_signal,
+    )
+    .style(|s| s.margin_right(4.0))
+}
+
 pub fn selection_view(
     state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
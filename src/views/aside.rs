@@ -222,19 +222,25 @@ pub fn project_tab_interface(
                                 scene_view(state_2.clone(), gpu_helper.clone(), viewport.clone())
                                     .into_any()
                             }
-                            "Nodes" => node_canvas().into_any(),
+                            "Nodes" => node_canvas(state_2.clone()).into_any(),
                             "Map" => {
                                 maps_view(state_2.clone(), gpu_helper.clone(), viewport.clone())
                                     .into_any()
                             }
                             "Story" => story_view(gpu_helper.clone(), viewport.clone()).into_any(),
                             "Audio" => audio_view(gpu_helper.clone(), viewport.clone()).into_any(),
-                            "Performance" => {
-                                performance_view(gpu_helper.clone(), viewport.clone()).into_any()
-                            }
-                            "Settings" => {
-                                project_settings(gpu_helper.clone(), viewport.clone()).into_any()
-                            }
+                            "Performance" => performance_view(
+                                state_2.clone(),
+                                gpu_helper.clone(),
+                                viewport.clone(),
+                            )
+                            .into_any(),
+                            "Settings" => project_settings(
+                                state_2.clone(),
+                                gpu_helper.clone(),
+                                viewport.clone(),
+                            )
+                            .into_any(),
                             _ => label(|| "Not implemented".to_owned()).into_any(),
                         },
                     )
@@ -383,7 +389,10 @@ pub fn welcome_tab_interface(
                     manager.clone(),
                 )
                 .into_any(),
-                "Settings" => editor_settings(gpu_helper.clone(), viewport.clone()).into_any(),
+                "Settings" => {
+                    editor_settings(state_2.clone(), gpu_helper.clone(), viewport.clone())
+                        .into_any()
+                }
                 _ => label(|| "Not implemented".to_owned()).into_any(),
             },
         )
@@ -6,11 +6,18 @@ use std::sync::{Arc, Mutex, MutexGuard};
 use super::shared::dynamic_img;
 use midpoint_engine::core::Viewport::Viewport;
 use midpoint_engine::floem::common::small_button;
-use midpoint_engine::floem::reactive::SignalGet;
-use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
+use midpoint_engine::floem::event::{EventListener, EventPropagation};
+use midpoint_engine::floem::peniko::Color;
+use midpoint_engine::floem::reactive::{
+    create_effect, create_memo, create_rw_signal, RwSignal, SignalUpdate,
+};
+use midpoint_engine::floem::reactive::{SignalGet, SignalWith};
+use midpoint_engine::floem::style::CursorStyle;
+use midpoint_engine::floem::taffy::AlignItems;
 use midpoint_engine::floem::taffy::{FlexDirection, FlexWrap};
+use midpoint_engine::floem::text::Weight;
 use midpoint_engine::floem::views::{
-    button, container, dyn_container, dyn_stack, empty, label, scroll, v_stack,
+    button, container, dyn_container, dyn_stack, empty, h_stack, label, scroll, text_input, v_stack,
 };
 use midpoint_engine::floem::IntoView;
 use midpoint_engine::floem_renderer::gpu_resources;
@@ -23,71 +30,306 @@ use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 
 use crate::editor_state::{EditorState, StateHelper};
+use crate::helpers::camera::placement_position;
+use crate::helpers::hierarchy::{
+    calculate_depth, descendant_components, level_hierarchy, outline_order, save_level_hierarchy,
+    sync_scene_nodes, would_create_cycle, SceneNode, SceneNodeKind,
+};
+use crate::helpers::levels::active_level_components;
+use crate::helpers::lights::{
+    add_light, level_lighting, level_lights, preview_time_of_day, set_time_of_day, LightComponent,
+    LightKind,
+};
+use crate::helpers::selection::{finish_selection, set_selection};
+
+use super::inputs::slider_input;
+
+/// Shift or ctrl add to the selection like in the viewport
+fn additive_modifiers(state_helper: &StateHelper) -> bool {
+    let renderer_state = state_helper
+        .renderer_state
+        .as_ref()
+        .expect("Couldn't get renderer state")
+        .lock()
+        .unwrap();
 
-pub fn component_item(
+    renderer_state.current_modifiers.shift_key() || renderer_state.current_modifiers.control_key()
+}
+
+/// Applies a change to the outline and saves it
+fn update_scene_nodes(
+    state_helper: &Arc<Mutex<StateHelper>>,
+    scene_nodes: RwSignal<Vec<SceneNode>>,
+    edit: impl FnOnce(&mut Vec<SceneNode>),
+) {
+    scene_nodes.update(edit);
+    save_level_hierarchy(&state_helper.lock().unwrap(), scene_nodes.get_untracked());
+}
+
+/// Text button for the visibility and lock toggles
+fn node_toggle(
     state_helper: Arc<Mutex<StateHelper>>,
-    gpu_helper: Arc<Mutex<GpuHelper>>,
-    label_text: String,
-    component_data: ComponentData,
+    scene_nodes: RwSignal<Vec<SceneNode>>,
+    node_id: String,
+    text: fn(&SceneNode) -> &'static str,
+    toggle: fn(&mut SceneNode),
+) -> impl IntoView {
+    let node_id_2 = node_id.clone();
+
+    label(move || {
+        scene_nodes.with(|nodes| {
+            nodes
+                .iter()
+                .find(|n| n.id == node_id_2)
+                .map_or("", |node| text(node))
+        })
+    })
+    .on_click_stop(move |_| {
+        update_scene_nodes(&state_helper, scene_nodes, |nodes| {
+            if let Some(node) = nodes.iter_mut().find(|n| n.id == node_id) {
+                toggle(node);
+            }
+        });
+    })
+    .style(|s| {
+        s.font_size(10.0)
+            .margin_left(5.0)
+            .color(Color::GRAY)
+            .cursor(CursorStyle::Pointer)
+    })
+}
+
+pub fn scene_node_item(
+    state_helper: Arc<Mutex<StateHelper>>,
+    scene_nodes: RwSignal<Vec<SceneNode>>,
+    dragger_id: RwSignal<String>,
+    node: SceneNode,
+    name: String,
+) -> impl IntoView {
+    let selection_signal = state_helper.lock().unwrap().selection_signal;
+    let node_id = node.id.clone();
+    let is_group = node.kind == SceneNodeKind::Group;
+    // follows reparenting, the row itself is kept while dragging
+    let indent = create_memo({
+        let node_id = node_id.clone();
+        move |_| calculate_depth(&scene_nodes.get(), &node_id) * 16
+    });
+
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+
+    h_stack((
+        // Indentation spacer
+        empty().style(move |s| s.width(indent.get() as f64)),
+        label(move || name.clone()).style(move |s| {
+            let s = s.selectable(false).flex_grow(1.0);
+            if is_group {
+                s.font_weight(Weight::BOLD)
+            } else {
+                s
+            }
+        }),
+        node_toggle(
+            state_2,
+            scene_nodes,
+            node_id.clone(),
+            |node| if node.visible { "Hide" } else { "Show" },
+            |node| node.visible = !node.visible,
+        ),
+        node_toggle(
+            state_3,
+            scene_nodes,
+            node_id.clone(),
+            |node| if node.locked { "Unlock" } else { "Lock" },
+            |node| node.locked = !node.locked,
+        ),
+    ))
+    .on_click_stop({
+        let node_id = node_id.clone();
+
+        move |_| {
+            let mut state_helper = state_helper.lock().unwrap();
+            let additive = additive_modifiers(&state_helper);
+
+            // opens the properties panel and moves the gizmo to the component, groups select
+            // everything inside them
+            if is_group {
+                let picked = descendant_components(&scene_nodes.get_untracked(), &node_id);
+                finish_selection(&mut state_helper, picked, false, additive);
+            } else {
+                finish_selection(&mut state_helper, vec![node_id.clone()], true, additive);
+            }
+        }
+    })
+    .draggable()
+    .on_event(EventListener::DragStart, {
+        let node_id = node_id.clone();
+
+        move |_| {
+            dragger_id.set(node_id.clone());
+            EventPropagation::Continue
+        }
+    })
+    .on_event(EventListener::DragOver, {
+        let node_id = node_id.clone();
+
+        move |_| {
+            let dragger_id = dragger_id.get_untracked();
+
+            // dropping onto a node makes the dragged node its child
+            if !dragger_id.is_empty() && dragger_id != node_id {
+                let nodes = scene_nodes.get_untracked();
+                let already_child = nodes
+                    .iter()
+                    .any(|n| n.id == dragger_id && n.parent_id.as_deref() == Some(&node_id));
+
+                if !already_child && !would_create_cycle(&nodes, &dragger_id, &node_id) {
+                    scene_nodes.update(|nodes| {
+                        if let Some(dragged) = nodes.iter_mut().find(|n| n.id == dragger_id) {
+                            dragged.parent_id = Some(node_id.clone());
+                        }
+                    });
+                }
+            }
+            EventPropagation::Continue
+        }
+    })
+    .on_event(EventListener::DragEnd, move |_| {
+        dragger_id.set(String::new());
+        save_level_hierarchy(&state_4.lock().unwrap(), scene_nodes.get_untracked());
+        EventPropagation::Continue
+    })
+    .dragging_style(|s| {
+        s.box_shadow_blur(3)
+            .box_shadow_color(Color::rgba(0.0, 0.0, 0.0, 0.5))
+            .box_shadow_spread(2)
+    })
+    .style(move |s| {
+        let selected =
+            selection_signal.map_or(false, |selection| selection.get().contains(&node_id));
+
+        s.width(260.0)
+            .align_items(AlignItems::Center)
+            .padding_vert(3)
+            .padding_horiz(4)
+            .border(1.0)
+            .border_color(if selected {
+                Color::ROYAL_BLUE
+            } else {
+                Color::TRANSPARENT
+            })
+            .cursor(CursorStyle::RowResize)
+            .hover(|s| s.background(Color::rgb(0.9, 0.9, 0.9)))
+    })
+}
+
+fn light_item(
+    state_helper: Arc<Mutex<StateHelper>>,
+    lights: RwSignal<Vec<LightComponent>>,
+    light_id: String,
+) -> impl IntoView {
+    let selection_signal = state_helper.lock().unwrap().selection_signal;
+    let name_id = light_id.clone();
+    let click_id = light_id.clone();
+
+    // renames show up without rebuilding the row
+    label(move || {
+        lights.with(|lights| {
+            lights
+                .iter()
+                .find(|light| light.id == name_id)
+                .map(|light| format!("{} ({})", light.name, light.kind.label()))
+                .unwrap_or_default()
+        })
+    })
+    .on_click_stop(move |_| {
+        let mut state_helper = state_helper.lock().unwrap();
+        let additive = additive_modifiers(&state_helper);
+        finish_selection(&mut state_helper, vec![click_id.clone()], true, additive);
+    })
+    .style(move |s| {
+        let selected =
+            selection_signal.map_or(false, |selection| selection.get().contains(&light_id));
+
+        s.width(260.0)
+            .selectable(false)
+            .padding_vert(3)
+            .padding_horiz(4)
+            .border(1.0)
+            .border_color(if selected {
+                Color::ROYAL_BLUE
+            } else {
+                Color::TRANSPARENT
+            })
+            .cursor(CursorStyle::Pointer)
+            .hover(|s| s.background(Color::rgb(0.9, 0.9, 0.9)))
+    })
+}
+
+/// Adds and lists the level's lights, the time of day turns its suns
+fn lights_section(
+    state_helper: Arc<Mutex<StateHelper>>,
+    lights: RwSignal<Vec<LightComponent>>,
+    time_of_day: RwSignal<f32>,
 ) -> impl View {
-    let active = create_rw_signal(false);
+    let add_button = |kind: LightKind| {
+        let state_helper = Arc::clone(&state_helper);
+        let active = create_rw_signal(false);
 
-    v_stack((
-        label(move || label_text.clone()),
         small_button(
-            "Select Component",
+            kind.label(),
             "plus",
-            {
-                move |_| {
-                    let mut state_helper = state_helper.lock().unwrap();
-
-                    // open properties panel, set selected id
-                    let object_selected_signal = state_helper
-                        .object_selected_signal
-                        .expect("Couldn't get signal");
-                    object_selected_signal.set(true);
-                    let selected_object_id_signal = state_helper
-                        .selected_object_id_signal
-                        .expect("Couldn't get signal");
-                    selected_object_id_signal
-                        .set(Uuid::from_str(&component_data.id).expect("Couldn't extract uuid"));
-                    let selected_object_data_signal = state_helper
-                        .selected_object_data_signal
-                        .expect("Couldn't get signal");
-                    selected_object_data_signal.set(component_data.clone());
-
-                    // sync other ids
-                    let mut renderer_state = state_helper
-                        .renderer_state
-                        .as_mut()
-                        .expect("Couldn't get renderer state")
-                        .lock()
-                        .unwrap();
-                    renderer_state.object_selected =
-                        Some(Uuid::from_str(&component_data.id).expect("Couldn't extract uuid"));
-                    renderer_state.object_selected_kind = component_data.kind.clone();
-                    renderer_state.object_selected_data = Some(component_data.clone());
-
-                    // gizmo displays automatically (but need to update gizmo position)
-                    let component_data = component_data.clone();
-                    renderer_state
-                        .translation_gizmo
-                        .transform
-                        .update_position(component_data.generic_properties.position);
-                    // don't forget the rapier positions
-                    // let arrows = &renderer_state.gizmo.arrows;
-                    // let cloned_arrows = [renderer_state.gizmo.arrows[0]]
-
-                    renderer_state.update_arrow_collider_position(
-                        // arrows,
-                        component_data.generic_properties.position,
-                    );
-                }
+            move |_| {
+                let mut state_helper = state_helper.lock().unwrap();
+                let id = add_light(&state_helper, kind, placement_position());
+                set_selection(&mut state_helper, vec![id]);
             },
             active,
-        ),
+        )
+        .style(|s| s.margin_right(5.0))
+    };
+
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+
+    v_stack((
+        label(|| "Lights").style(|s| {
+            s.width(260.0)
+                .padding(4)
+                .margin_top(12.0)
+                .font_weight(Weight::BOLD)
+                .border_bottom(1)
+                .border_color(Color::rgb(0.3, 0.3, 0.3))
+        }),
+        h_stack((
+            add_button(LightKind::Directional),
+            add_button(LightKind::Point),
+            add_button(LightKind::Spot),
+        ))
+        .style(|s| s.margin_vert(8.0)),
+        dyn_stack(
+            move || lights.get(),
+            |light: &LightComponent| light.id.clone(),
+            move |light| light_item(state_2.clone(), lights, light.id),
+        )
+        .style(|s| s.flex_col().width(260.0)),
+        slider_input(
+            "Time of Day:".to_string(),
+            time_of_day,
+            240.0,
+            move |value, finished| {
+                let state_helper = state_3.lock().unwrap();
+
+                if finished {
+                    set_time_of_day(&state_helper, value);
+                } else if let Some(editor_render) = state_helper.editor_render.as_ref() {
+                    preview_time_of_day(&mut editor_render.lock().unwrap(), value);
+                }
+            },
+        )
+        .style(|s| s.margin_top(8.0)),
     ))
-    .style(|s| s.width(120.0))
 }
 
 pub fn component_browser(
@@ -96,51 +338,129 @@ pub fn component_browser(
     viewport: Arc<Mutex<Viewport>>,
 ) -> impl View {
     let component_data: RwSignal<Vec<ComponentData>> = create_rw_signal(Vec::new());
+    let scene_nodes: RwSignal<Vec<SceneNode>> = create_rw_signal(Vec::new());
+    let dragger_id = create_rw_signal(String::new());
+    let group_name = create_rw_signal(String::new());
+    let group_active = create_rw_signal(false);
+    let lights: RwSignal<Vec<LightComponent>> = create_rw_signal(Vec::new());
+    let time_of_day = create_rw_signal(0.5);
 
     let state_2 = Arc::clone(&state_helper);
-    let gpu_2 = Arc::clone(&gpu_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
 
     create_effect(move |_| {
         let mut state_helper = state_helper.lock().unwrap();
-        let mut saved_state = state_helper
-            .saved_state
-            .as_mut()
-            .expect("Couldn't get saved state")
-            .lock()
-            .unwrap();
-        let mut selected_level = saved_state
-            .levels
-            .as_mut()
-            .expect("Couldn't get levels")
-            .get_mut(0)
-            .expect("Couldn't get first level");
-        component_data.set(
-            selected_level
-                .components
-                .get_or_insert_with(Vec::new)
-                .clone(),
-        );
+
+        // runs again when another level opens
+        if let Some(active_level_signal) = state_helper.active_level_signal {
+            active_level_signal.get();
+        }
+
+        component_data.set(active_level_components(&state_helper));
+        scene_nodes.set(level_hierarchy(&state_helper));
+        lights.set(level_lights(&state_helper));
+        time_of_day.set(level_lighting(&state_helper).time_of_day);
+        // component edits refresh the list through this
+        state_helper.level_components_signal = Some(component_data);
+        state_helper.level_lights_signal = Some(lights);
     });
 
-    container((scroll(
-        dyn_stack(
-            move || component_data.get(),
-            move |component_data| component_data.id.clone(),
-            move |component_data| {
-                component_item(
-                    state_2.clone(),
-                    gpu_2.clone(),
-                    component_data.generic_properties.name.clone(),
-                    component_data,
-                )
-            },
-        )
-        .style(|s| {
-            s.flex_direction(FlexDirection::Row)
-                .flex_wrap(FlexWrap::Wrap)
-        })
-        .style(|s| s.width(260.0))
-        .into_view(),
-    ),))
+    // components come and go with deletes and pastes, the outline follows
+    create_effect(move |_| {
+        let components = component_data.get();
+        scene_nodes.update(|nodes| *nodes = sync_scene_nodes(nodes, &components));
+    });
+
+    v_stack((
+        h_stack((
+            text_input(group_name)
+                .placeholder("Group name")
+                .style(|s| s.width(150.0).margin_right(5.0)),
+            small_button(
+                "Group",
+                "folder-plus",
+                move |_| {
+                    let name = group_name.get_untracked();
+                    let mut group = SceneNode::group(if name.is_empty() {
+                        "Group"
+                    } else {
+                        name.as_str()
+                    });
+                    let selection = state_2.lock().unwrap().selection.clone();
+
+                    // the selection moves into the new group, which takes the place of the
+                    // active component's closest unselected ancestor
+                    update_scene_nodes(&state_2, scene_nodes, |nodes| {
+                        let mut parent_id = selection
+                            .last()
+                            .and_then(|id| nodes.iter().find(|n| n.id == *id))
+                            .and_then(|n| n.parent_id.clone());
+                        while let Some(id) = parent_id.clone().filter(|id| selection.contains(id)) {
+                            parent_id = nodes
+                                .iter()
+                                .find(|n| n.id == id)
+                                .and_then(|n| n.parent_id.clone());
+                        }
+                        group.parent_id = parent_id;
+
+                        for node in nodes.iter_mut() {
+                            if selection.contains(&node.id) {
+                                node.parent_id = Some(group.id.clone());
+                            }
+                        }
+
+                        nodes.push(group);
+                    });
+
+                    group_name.set(String::new());
+                },
+                group_active,
+            ),
+        ))
+        .style(|s| s.margin_bottom(8.0).items_center()),
+        // dropping here takes a node out of its parent
+        label(|| "Scene")
+            .on_event(EventListener::DragOver, move |_| {
+                let dragger_id = dragger_id.get_untracked();
+
+                scene_nodes.update(|nodes| {
+                    if let Some(dragged) = nodes.iter_mut().find(|n| n.id == dragger_id) {
+                        dragged.parent_id = None;
+                    }
+                });
+                EventPropagation::Continue
+            })
+            .style(|s| {
+                s.width(260.0)
+                    .padding(4)
+                    .font_weight(Weight::BOLD)
+                    .border_bottom(1)
+                    .border_color(Color::rgb(0.3, 0.3, 0.3))
+            }),
+        scroll(
+            dyn_stack(
+                move || outline_order(&scene_nodes.get()),
+                |(node, _): &(SceneNode, usize)| node.id.clone(),
+                move |(node, _)| {
+                    let name = match node.kind {
+                        SceneNodeKind::Group => node.name.clone(),
+                        SceneNodeKind::Component => component_data.with_untracked(|components| {
+                            components
+                                .iter()
+                                .find(|c| c.id == node.id)
+                                .map(|c| c.generic_properties.name.clone())
+                                .unwrap_or_default()
+                        }),
+                    };
+
+                    scene_node_item(state_3.clone(), scene_nodes, dragger_id, node, name)
+                },
+            )
+            .style(|s| s.flex_col().width(260.0))
+            .into_view(),
+        ),
+        lights_section(state_4, lights, time_of_day),
+    ))
     .style(|s| s.width(260.0))
 }
@@ -3,16 +3,225 @@ use std::sync::{Arc, Mutex, MutexGuard};
 use midpoint_engine::floem::common::card_styles;
 use midpoint_engine::floem::views::{container, dyn_container, empty, label, v_stack};
 use midpoint_engine::core::Viewport::Viewport;
+use midpoint_engine::floem::common::toggle_button;
+use midpoint_engine::floem::reactive::{create_rw_signal, SignalUpdate};
 use wgpu::util::DeviceExt;
 
 use midpoint_engine::floem::views::Decorators;
-use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
+use midpoint_engine::floem::{GpuHelper, IntoView, View, WindowHandle};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::camera::{CameraDragMode, CameraSettings};
+use crate::helpers::offscreen::ScreenshotSettings;
+use crate::helpers::snapping::SnapSettings;
+use crate::helpers::utilities::parse_string_to_float;
+
+use super::inputs::styled_input;
+
+fn snap_step_input(
+    state_helper: Arc<Mutex<StateHelper>>,
+    label_text: &str,
+    initial_value: f32,
+    name: &str,
+    apply: fn(&mut SnapSettings, f32),
+) -> impl IntoView {
+    styled_input(
+        label_text.to_string(),
+        &initial_value.to_string(),
+        label_text,
+        Box::new(move |mut state_helper_passed, value| {
+            let Some(step) = parse_string_to_float(&value) else {
+                return;
+            };
+
+            state_helper_passed
+                .update_editor_settings(|settings| apply(&mut settings.snapping, step.max(0.0)));
+        }),
+        state_helper,
+        name.to_string(),
+    )
+}
+
+fn camera_speed_input(
+    state_helper: Arc<Mutex<StateHelper>>,
+    label_text: &str,
+    initial_value: f32,
+    name: &str,
+    apply: fn(&mut CameraSettings, f32),
+) -> impl IntoView {
+    styled_input(
+        label_text.to_string(),
+        &initial_value.to_string(),
+        label_text,
+        Box::new(move |mut state_helper_passed, value| {
+            let Some(speed) = parse_string_to_float(&value) else {
+                return;
+            };
+
+            state_helper_passed
+                .update_editor_settings(|settings| apply(&mut settings.camera, speed.max(0.0)));
+        }),
+        state_helper,
+        name.to_string(),
+    )
+}
+
+fn screenshot_size_input(
+    state_helper: Arc<Mutex<StateHelper>>,
+    label_text: &str,
+    initial_value: u32,
+    name: &str,
+    apply: fn(&mut ScreenshotSettings, u32),
+) -> impl IntoView {
+    styled_input(
+        label_text.to_string(),
+        &initial_value.to_string(),
+        label_text,
+        Box::new(move |mut state_helper_passed, value| {
+            let Ok(pixels) = value.trim().parse::<u32>() else {
+                return;
+            };
+
+            state_helper_passed
+                .update_editor_settings(|settings| apply(&mut settings.screenshot, pixels.max(1)));
+        }),
+        state_helper,
+        name.to_string(),
+    )
+}
 
 pub fn editor_settings(
+    state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
     viewport: Arc<Mutex<Viewport>>,
 ) -> impl View {
-    v_stack(((label(|| "Editor Settings"),)))
-        .style(|s| card_styles(s))
-        .style(|s| s.width(300.0))
+    let snapping = state_helper.lock().unwrap().editor_settings.snapping;
+    let align_signal = create_rw_signal(
+        if snapping.align_to_normal {
+            "align"
+        } else {
+            ""
+        }
+        .to_string(),
+    );
+    let camera = state_helper.lock().unwrap().editor_settings.camera;
+    let screenshot = state_helper.lock().unwrap().editor_settings.screenshot;
+    let orbit_signal = create_rw_signal(
+        if camera.right_drag == CameraDragMode::Orbit {
+            "orbit"
+        } else {
+            ""
+        }
+        .to_string(),
+    );
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+
+    v_stack((
+        label(|| "Editor Settings"),
+        label(|| "Snapping").style(|s| s.margin_top(10.0)),
+        snap_step_input(
+            state_helper.clone(),
+            "Grid Step",
+            snapping.translation_step,
+            "snap_translation_step",
+            |settings, step| settings.translation_step = step,
+        ),
+        snap_step_input(
+            state_helper.clone(),
+            "Angle Step (degrees)",
+            snapping.rotation_step_degrees,
+            "snap_rotation_step",
+            |settings, step| settings.rotation_step_degrees = step,
+        ),
+        snap_step_input(
+            state_helper.clone(),
+            "Scale Step",
+            snapping.scale_step,
+            "snap_scale_step",
+            |settings, step| settings.scale_step = step,
+        ),
+        toggle_button(
+            "Align to Surface Normal",
+            "plus",
+            "align".to_string(),
+            move |_| {
+                let mut state_helper = state_2.lock().unwrap();
+                state_helper.update_editor_settings(|settings| {
+                    settings.snapping.align_to_normal = !settings.snapping.align_to_normal
+                });
+
+                let align = state_helper.editor_settings.snapping.align_to_normal;
+                align_signal.set(if align { "align" } else { "" }.to_string());
+            },
+            align_signal,
+        )
+        .style(|s| s.margin_top(5.0)),
+        label(|| "Camera").style(|s| s.margin_top(10.0)),
+        camera_speed_input(
+            state_helper.clone(),
+            "Move Speed",
+            camera.move_speed,
+            "camera_move_speed",
+            |settings, speed| settings.move_speed = speed,
+        ),
+        camera_speed_input(
+            state_helper.clone(),
+            "Orbit Sensitivity (degrees per pixel)",
+            camera.orbit_sensitivity,
+            "camera_orbit_sensitivity",
+            |settings, speed| settings.orbit_sensitivity = speed,
+        ),
+        camera_speed_input(
+            state_helper.clone(),
+            "Pan Speed",
+            camera.pan_speed,
+            "camera_pan_speed",
+            |settings, speed| settings.pan_speed = speed,
+        ),
+        camera_speed_input(
+            state_helper.clone(),
+            "Zoom Speed",
+            camera.zoom_speed,
+            "camera_zoom_speed",
+            // a full step would put the camera on the pivot
+            |settings, speed| settings.zoom_speed = speed.min(0.9),
+        ),
+        toggle_button(
+            "Right Drag Orbits",
+            "plus",
+            "orbit".to_string(),
+            move |_| {
+                let mut state_helper = state_3.lock().unwrap();
+                state_helper.update_editor_settings(|settings| {
+                    settings.camera.right_drag = match settings.camera.right_drag {
+                        CameraDragMode::Fly => CameraDragMode::Orbit,
+                        CameraDragMode::Orbit => CameraDragMode::Fly,
+                    }
+                });
+
+                let orbit = state_helper.editor_settings.camera.right_drag == CameraDragMode::Orbit;
+                orbit_signal.set(if orbit { "orbit" } else { "" }.to_string());
+            },
+            orbit_signal,
+        )
+        .style(|s| s.margin_top(5.0)),
+        label(|| "Screenshots").style(|s| s.margin_top(10.0)),
+        screenshot_size_input(
+            state_helper.clone(),
+            "Width",
+            screenshot.width,
+            "screenshot_width",
+            |settings, pixels| settings.width = pixels,
+        ),
+        screenshot_size_input(
+            state_helper.clone(),
+            "Height",
+            screenshot.height,
+            "screenshot_height",
+            |settings, pixels| settings.height = pixels,
+        ),
+    ))
+    .style(|s| card_styles(s))
+    .style(|s| s.width(300.0))
 }
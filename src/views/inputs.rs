@@ -268,3 +268,67 @@ where
 
     dropdown
 }
+
+/// A horizontal slider from 0 to 1. `on_change` runs while it is dragged, then once more with
+/// `true` when the pointer is let go or leaves the track.
+pub fn slider_input(
+    label_text: String,
+    value: RwSignal<f32>,
+    width: f64,
+    on_change: impl Fn(f32, bool) + 'static,
+) -> impl IntoView {
+    let dragging = create_rw_signal(false);
+    let on_change = Rc::new(on_change);
+    let on_move = Rc::clone(&on_change);
+    let on_up = Rc::clone(&on_change);
+    let on_leave = Rc::clone(&on_change);
+
+    let value_at = move |x: f64| (x / width).clamp(0.0, 1.0) as f32;
+
+    v_stack((
+        label(move || label_text.clone()).style(|s| s.font_size(10.0).margin_bottom(1.0)),
+        container(empty().style(move |s| {
+            s.width_pct(value.get() as f64 * 100.0)
+                .height_full()
+                .border_radius(6.0)
+                .background(Color::rgb8(100, 150, 230))
+        }))
+        .on_event(EventListener::PointerDown, move |e| {
+            if let Some(point) = e.point() {
+                dragging.set(true);
+                value.set(value_at(point.x));
+                on_change(value.get_untracked(), false);
+            }
+            EventPropagation::Stop
+        })
+        .on_event(EventListener::PointerMove, move |e| {
+            if let (true, Some(point)) = (dragging.get_untracked(), e.point()) {
+                value.set(value_at(point.x));
+                on_move(value.get_untracked(), false);
+            }
+            EventPropagation::Continue
+        })
+        .on_event(EventListener::PointerUp, move |_| {
+            if dragging.get_untracked() {
+                dragging.set(false);
+                on_up(value.get_untracked(), true);
+            }
+            EventPropagation::Continue
+        })
+        .on_event(EventListener::PointerLeave, move |_| {
+            if dragging.get_untracked() {
+                dragging.set(false);
+                on_leave(value.get_untracked(), true);
+            }
+            EventPropagation::Continue
+        })
+        .style(move |s| {
+            s.width(width)
+                .height(12.0)
+                .border_radius(6.0)
+                .background(Color::LIGHT_GRAY)
+                .cursor(CursorStyle::Pointer)
+        }),
+    ))
+    .style(|s| s.margin_bottom(10))
+}
@@ -9,17 +9,19 @@ use midpoint_engine::floem::reactive::SignalGet;
 use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
 use midpoint_engine::floem::taffy::{FlexDirection, FlexWrap};
 use midpoint_engine::floem::views::{
-    container, dyn_container, dyn_stack, empty, label, scroll, v_stack,
+    container, dyn_container, dyn_stack, empty, h_stack, label, scroll, text_input, v_stack,
 };
 use midpoint_engine::floem::IntoView;
 use midpoint_engine::floem_renderer::gpu_resources;
-use midpoint_engine::handlers::handle_add_landscape;
+use midpoint_engine::handlers::{get_camera, handle_add_landscape};
 use midpoint_engine::helpers::landscapes::upscale_tiff_heightmap;
 use midpoint_engine::helpers::saved_data::{
     ComponentData, ComponentKind, File, GenericProperties, LandscapeData, LandscapeProperties,
 };
 use midpoint_engine::helpers::utilities::{get_heightmap_dir, get_rockmap_dir, get_soilmap_dir};
+use nalgebra as na;
 use rfd::FileDialog;
+use std::path::PathBuf;
 use uuid::Uuid;
 use wgpu::util::DeviceExt;
 
@@ -27,7 +29,46 @@ use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 
 use crate::editor_state::{EditorState, StateHelper};
-use crate::helpers::utilities::get_common_os_dir;
+use crate::helpers::heightmaps::{
+    load_heightmap, normalize_heights, save_engine_heightmap, HeightmapFormat,
+    HeightmapImportSettings, HEIGHTMAP_EXTENSIONS,
+};
+use crate::helpers::levels::active_level_mut;
+use crate::helpers::tiles::{import_tiled_landscape, stream_landscape_tiles, TiledLandscapeImport};
+use crate::helpers::utilities::{get_common_os_dir, parse_string_to_float};
+
+use super::topographic_map::create_topographic_preview;
+
+/// Reads the import dialog fields into a format and normalization settings
+fn heightmap_import_options(
+    path: &PathBuf,
+    raw_width: String,
+    raw_height: String,
+    vertical_scale: String,
+    base_height: String,
+) -> Result<(HeightmapFormat, HeightmapImportSettings), String> {
+    let mut format = HeightmapFormat::from_path(path).ok_or("Unsupported heightmap format")?;
+
+    if format.needs_dimensions() {
+        let width = raw_width
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| "Enter the RAW heightmap width")?;
+        let height = raw_height
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| "Enter the RAW heightmap height")?;
+
+        format = HeightmapFormat::Raw16 { width, height };
+    }
+
+    let settings = HeightmapImportSettings {
+        vertical_scale: parse_string_to_float(&vertical_scale).ok_or("Invalid vertical scale")?,
+        base_height: parse_string_to_float(&base_height).ok_or("Invalid base height")?,
+    };
+
+    Ok((format, settings))
+}
 
 pub fn landscape_item(
     state_helper: Arc<Mutex<StateHelper>>,
@@ -128,10 +169,8 @@ pub fn landscape_item(
                         }),
                         model_properties: None,
                     };
-                    let mut levels = saved_state.levels.as_mut().expect("Couldn't get levels");
-                    levels
-                        .get_mut(0)
-                        .expect("Couldn't get first level")
+                    active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+                        .expect("Couldn't get active level")
                         .components
                         .get_or_insert_with(Vec::new)
                         .push(landscape_component.clone());
@@ -271,12 +310,28 @@ pub fn landscape_browser(
 
     let state_2 = Arc::clone(&state_helper);
     let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
 
     let landscape_modal_open = create_rw_signal(false);
     let new_heightmap_path = create_rw_signal(None);
     let new_soilmap_path = create_rw_signal(None);
     let new_rockmap_path = create_rw_signal(None);
 
+    let raw_width = create_rw_signal(String::new());
+    let raw_height = create_rw_signal(String::new());
+    let vertical_scale = create_rw_signal("1.0".to_string());
+    let base_height = create_rw_signal("0.0".to_string());
+    let heightmap_preview: RwSignal<Option<na::DMatrix<f32>>> = create_rw_signal(None);
+    let import_error: RwSignal<Option<String>> = create_rw_signal(None);
+
+    let tiled_modal_open = create_rw_signal(false);
+    let tiled_name = create_rw_signal("New World".to_string());
+    let tiled_columns = create_rw_signal("4".to_string());
+    let tiled_rows = create_rw_signal("4".to_string());
+    let tiled_tile_size = create_rw_signal("1024.0".to_string());
+    let tiled_stream_distance = create_rw_signal("2048.0".to_string());
+
     create_effect(move |_| {
         let state_helper = state_helper.lock().unwrap();
         let saved_state = state_helper
@@ -298,6 +353,162 @@ pub fn landscape_browser(
         simple_button("Add Landscape".to_string(), move |_| {
             landscape_modal_open.set(true);
         }),
+        simple_button("Add Tiled Landscape".to_string(), move |_| {
+            tiled_modal_open.set(true);
+        }),
+        dyn_container(
+            move || tiled_modal_open.get(),
+            move |is_open| {
+                let state_4 = state_4.clone();
+                let gpu_2 = gpu_2.clone();
+
+                if is_open {
+                    v_stack((
+                        label(|| "Name"),
+                        text_input(tiled_name).style(|s| s.width(205.0)),
+                        simple_button("Add Heightmap".to_string(), move |_| {
+                            let file = FileDialog::new()
+                                .add_filter("heightmap", &["tif", "tiff", "png", "exr", "asc"])
+                                .set_directory("/")
+                                .pick_file();
+
+                            new_heightmap_path.set(file);
+                            import_error.set(None);
+                        }),
+                        simple_button("Add Soil Map".to_string(), move |_| {
+                            let file = FileDialog::new()
+                                .add_filter("image", &["png"])
+                                .set_directory("/")
+                                .pick_file();
+
+                            new_soilmap_path.set(file);
+                        }),
+                        simple_button("Add Rock Map".to_string(), move |_| {
+                            let file = FileDialog::new()
+                                .add_filter("image", &["png"])
+                                .set_directory("/")
+                                .pick_file();
+
+                            new_rockmap_path.set(file);
+                        }),
+                        label(|| "Columns / Rows"),
+                        h_stack((
+                            text_input(tiled_columns)
+                                .placeholder("Columns")
+                                .style(|s| s.width(100.0).margin_right(5.0)),
+                            text_input(tiled_rows)
+                                .placeholder("Rows")
+                                .style(|s| s.width(100.0)),
+                        )),
+                        label(|| "Tile Size / Stream Distance"),
+                        h_stack((
+                            text_input(tiled_tile_size)
+                                .placeholder("Tile Size")
+                                .style(|s| s.width(100.0).margin_right(5.0)),
+                            text_input(tiled_stream_distance)
+                                .placeholder("Stream Distance")
+                                .style(|s| s.width(100.0)),
+                        )),
+                        dyn_container(
+                            move || import_error.get(),
+                            move |import_error_real| {
+                                if let Some(message) = import_error_real {
+                                    label(move || message.clone()).into_any()
+                                } else {
+                                    empty().into_any()
+                                }
+                            },
+                        ),
+                        simple_button("Save Tiled Landscape".to_string(), move |_| {
+                            let (Some(heightmap_path), Some(soilmap_path), Some(rockmap_path)) = (
+                                new_heightmap_path.get(),
+                                new_soilmap_path.get(),
+                                new_rockmap_path.get(),
+                            ) else {
+                                import_error.set(Some(
+                                    "Add a heightmap, soil map and rock map".to_string(),
+                                ));
+                                return;
+                            };
+
+                            let columns = tiled_columns.get().trim().parse::<usize>();
+                            let rows = tiled_rows.get().trim().parse::<usize>();
+                            let (Ok(columns), Ok(rows)) = (columns, rows) else {
+                                import_error.set(Some("Invalid columns or rows".to_string()));
+                                return;
+                            };
+
+                            let import = TiledLandscapeImport {
+                                name: tiled_name.get(),
+                                heightmap_path,
+                                rockmap_path,
+                                soilmap_path,
+                                columns,
+                                rows,
+                                tile_size: parse_string_to_float(&tiled_tile_size.get())
+                                    .unwrap_or(1024.0),
+                                stream_distance: parse_string_to_float(
+                                    &tiled_stream_distance.get(),
+                                )
+                                .unwrap_or(2048.0),
+                            };
+
+                            let state_helper = state_4.lock().unwrap();
+
+                            match import_tiled_landscape(&state_helper, import) {
+                                Ok(grid) => {
+                                    println!(
+                                        "Added tiled landscape {} with {} tiles",
+                                        grid.name,
+                                        grid.tiles.len()
+                                    );
+
+                                    let saved_state = state_helper
+                                        .saved_state
+                                        .as_ref()
+                                        .expect("Couldn't get saved state")
+                                        .lock()
+                                        .unwrap();
+                                    landscape_data.set(
+                                        saved_state
+                                            .landscapes
+                                            .as_ref()
+                                            .expect("Couldn't get landscape data")
+                                            .clone(),
+                                    );
+                                    drop(saved_state);
+
+                                    // load the tiles around the camera straight away
+                                    let gpu_resources = gpu_2
+                                        .lock()
+                                        .unwrap()
+                                        .gpu_resources
+                                        .clone()
+                                        .expect("Couldn't get gpu resources");
+                                    let camera = get_camera();
+                                    stream_landscape_tiles(
+                                        &state_helper,
+                                        &gpu_resources,
+                                        camera.position.x,
+                                        camera.position.z,
+                                    );
+
+                                    import_error.set(None);
+                                    tiled_modal_open.set(false);
+                                }
+                                Err(e) => {
+                                    println!("Tiled landscape import failed: {}", e);
+                                    import_error.set(Some(e));
+                                }
+                            }
+                        }),
+                    ))
+                    .into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        ),
         dyn_container(
             move || landscape_modal_open.get(),
             move |is_open| {
@@ -307,13 +518,98 @@ pub fn landscape_browser(
                     v_stack((
                         simple_button("Add Heightmap".to_string(), move |_| {
                             let file = FileDialog::new()
-                                .add_filter("image", &["tif"])
+                                .add_filter("heightmap", &HEIGHTMAP_EXTENSIONS)
                                 // .add_filter("rust", &["rs", "toml"])
                                 .set_directory("/")
                                 .pick_file();
 
                             new_heightmap_path.set(file);
+                            heightmap_preview.set(None);
+                            import_error.set(None);
+                        }),
+                        dyn_container(
+                            move || {
+                                new_heightmap_path
+                                    .get()
+                                    .and_then(|path: PathBuf| HeightmapFormat::from_path(&path))
+                                    .map(|format| format.needs_dimensions())
+                                    .unwrap_or(false)
+                            },
+                            move |needs_dimensions| {
+                                if needs_dimensions {
+                                    h_stack((
+                                        text_input(raw_width)
+                                            .placeholder("Width")
+                                            .style(|s| s.width(100.0).margin_right(5.0)),
+                                        text_input(raw_height)
+                                            .placeholder("Height")
+                                            .style(|s| s.width(100.0)),
+                                    ))
+                                    .into_any()
+                                } else {
+                                    empty().into_any()
+                                }
+                            },
+                        ),
+                        label(|| "Vertical Scale / Base Height"),
+                        h_stack((
+                            text_input(vertical_scale)
+                                .placeholder("Vertical Scale")
+                                .style(|s| s.width(100.0).margin_right(5.0)),
+                            text_input(base_height)
+                                .placeholder("Base Height")
+                                .style(|s| s.width(100.0)),
+                        )),
+                        simple_button("Preview Heightmap".to_string(), move |_| {
+                            let Some(path) = new_heightmap_path.get() else {
+                                import_error.set(Some("Add a heightmap first".to_string()));
+                                return;
+                            };
+
+                            let preview = heightmap_import_options(
+                                &path,
+                                raw_width.get(),
+                                raw_height.get(),
+                                vertical_scale.get(),
+                                base_height.get(),
+                            )
+                            .and_then(|(format, settings)| {
+                                load_heightmap(&path, &format)
+                                    .map(|heights| normalize_heights(&heights, &settings))
+                            });
+
+                            match preview {
+                                Ok(heights) => {
+                                    heightmap_preview.set(Some(heights));
+                                    import_error.set(None);
+                                }
+                                Err(e) => {
+                                    println!("Heightmap preview failed: {}", e);
+                                    heightmap_preview.set(None);
+                                    import_error.set(Some(e));
+                                }
+                            }
                         }),
+                        dyn_container(
+                            move || import_error.get(),
+                            move |import_error_real| {
+                                if let Some(message) = import_error_real {
+                                    label(move || message.clone()).into_any()
+                                } else {
+                                    empty().into_any()
+                                }
+                            },
+                        ),
+                        dyn_container(
+                            move || heightmap_preview.get(),
+                            move |heightmap_preview_real| {
+                                if let Some(heights) = heightmap_preview_real {
+                                    create_topographic_preview(heights, 240.0).into_any()
+                                } else {
+                                    empty().into_any()
+                                }
+                            },
+                        ),
                         simple_button("Add Soil Map".to_string(), move |_| {
                             let file = FileDialog::new()
                                 .add_filter("image", &["png"])
@@ -349,17 +645,72 @@ pub fn landscape_browser(
                                 let original_heightmap_path = new_heightmap_path
                                     .get()
                                     .expect("Couldn't get heightmap path");
+
+                                let import_options = heightmap_import_options(
+                                    &original_heightmap_path,
+                                    raw_width.get(),
+                                    raw_height.get(),
+                                    vertical_scale.get(),
+                                    base_height.get(),
+                                );
+
+                                let (heightmap_format, import_settings) = match import_options {
+                                    Ok(options) => options,
+                                    Err(e) => {
+                                        import_error.set(Some(e));
+                                        return;
+                                    }
+                                };
+
                                 let heightmap_dir =
                                     get_heightmap_dir(&project_id.to_string(), &new_id.to_string())
                                         .expect("Couldn't get heightmap dir");
 
-                                let heightmap_name = original_heightmap_path
-                                    .file_name()
-                                    .expect("Couldn't get file name");
-                                let new_heightmap_path = heightmap_dir.join(heightmap_name);
-
-                                fs::copy(&original_heightmap_path, &new_heightmap_path)
-                                    .expect("Couldn't copy heightmap to storage directory");
+                                let heightmap_name = if heightmap_format == HeightmapFormat::Tiff
+                                    && import_settings.is_default()
+                                {
+                                    // already in the engine's format, keep the original file
+                                    let heightmap_name = original_heightmap_path
+                                        .file_name()
+                                        .expect("Couldn't get file name");
+                                    let new_heightmap_path = heightmap_dir.join(heightmap_name);
+
+                                    fs::copy(&original_heightmap_path, &new_heightmap_path)
+                                        .expect("Couldn't copy heightmap to storage directory");
+
+                                    heightmap_name.to_os_string()
+                                } else {
+                                    // convert everything else into a 16-bit tif
+                                    let heights = match load_heightmap(
+                                        &original_heightmap_path,
+                                        &heightmap_format,
+                                    ) {
+                                        Ok(heights) => heights,
+                                        Err(e) => {
+                                            import_error.set(Some(e));
+                                            return;
+                                        }
+                                    };
+                                    let normalized = normalize_heights(&heights, &import_settings);
+
+                                    let mut heightmap_name = PathBuf::from(
+                                        original_heightmap_path
+                                            .file_name()
+                                            .expect("Couldn't get file name"),
+                                    );
+                                    heightmap_name.set_extension("tif");
+
+                                    if let Err(e) = save_engine_heightmap(
+                                        &heightmap_dir.join(&heightmap_name),
+                                        &normalized,
+                                    ) {
+                                        import_error.set(Some(e));
+                                        return;
+                                    }
+
+                                    heightmap_name.into_os_string()
+                                };
+                                let new_heightmap_path = heightmap_dir.join(&heightmap_name);
 
                                 // Move Soilmap
                                 let original_soilmap_path =
@@ -1,53 +1,221 @@
-use std::future::Future;
-use std::pin::Pin;
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex};
 
-use super::shared::dynamic_img;
 use midpoint_engine::core::Viewport::Viewport;
 use midpoint_engine::floem::common::small_button;
+use midpoint_engine::floem::peniko::Color;
 use midpoint_engine::floem::reactive::SignalGet;
 use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
-use midpoint_engine::floem::taffy::{FlexDirection, FlexWrap};
-use midpoint_engine::floem::views::{
-    button, container, dyn_container, dyn_stack, empty, label, scroll, v_stack,
-};
+use midpoint_engine::floem::style::CursorStyle;
+use midpoint_engine::floem::taffy::AlignItems;
+use midpoint_engine::floem::text::Weight;
+use midpoint_engine::floem::views::{dyn_stack, h_stack, label, scroll, text_input, v_stack};
 use midpoint_engine::floem::IntoView;
-use midpoint_engine::floem_renderer::gpu_resources;
-use midpoint_engine::handlers::handle_add_model;
-use midpoint_engine::helpers::saved_data::{
-    ComponentData, ComponentKind, File, GenericProperties, LevelData,
-};
-use uuid::Uuid;
-use wgpu::util::DeviceExt;
 
 use midpoint_engine::floem::views::Decorators;
-use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
+use midpoint_engine::floem::{GpuHelper, View};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::camera::{
+    add_camera_bookmark, go_to_camera_bookmark, level_camera_bookmarks, remove_camera_bookmark,
+    CameraBookmark,
+};
+use crate::helpers::levels::{
+    active_level_id, create_level, delete_level, duplicate_level, level_name, move_level,
+    open_level, rename_level,
+};
+
+/// Level ids with their names, in project order
+fn level_list(state_helper: &StateHelper) -> Vec<(String, String)> {
+    let level_ids: Vec<String> = state_helper
+        .saved_state
+        .as_ref()
+        .and_then(|saved_state| {
+            saved_state
+                .lock()
+                .unwrap()
+                .levels
+                .as_ref()
+                .map(|levels| levels.iter().map(|level| level.id.clone()).collect())
+        })
+        .unwrap_or_default();
+
+    level_ids
+        .into_iter()
+        .enumerate()
+        .map(|(index, id)| {
+            let name = level_name(state_helper, &id, index);
+            (id, name)
+        })
+        .collect()
+}
+
+/// Opens the level in the renderer, then lets the browsers know once the state helper is free
+fn switch_level(
+    state_helper: &Arc<Mutex<StateHelper>>,
+    gpu_helper: &Arc<Mutex<GpuHelper>>,
+    level_id: &str,
+) {
+    let active_level_signal = {
+        let mut state_helper = state_helper.lock().unwrap();
+        let Some(gpu_resources) = gpu_helper.lock().unwrap().gpu_resources.clone() else {
+            return;
+        };
 
-use crate::editor_state::{EditorState, StateHelper};
+        open_level(&mut state_helper, &gpu_resources, level_id);
+        state_helper.active_level_signal
+    };
+
+    if let Some(active_level_signal) = active_level_signal {
+        active_level_signal.set(level_id.to_string());
+    }
+}
+
+/// Text button for the per level actions
+fn level_action(text: &'static str, action: impl Fn() + 'static) -> impl IntoView {
+    label(move || text)
+        .on_click_stop(move |_| action())
+        .style(|s| {
+            s.font_size(10.0)
+                .margin_left(5.0)
+                .color(Color::GRAY)
+                .cursor(CursorStyle::Pointer)
+        })
+}
 
 pub fn level_item(
     state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
-    label_text: String,
-    filename: String,
-    model_id: String,
+    levels: RwSignal<Vec<(String, String)>>,
+    active_id: RwSignal<String>,
+    level_name_input: RwSignal<String>,
+    level_id: String,
+    name: String,
 ) -> impl View {
-    let active = create_rw_signal(false);
+    let refresh = {
+        let state_helper = Arc::clone(&state_helper);
+        move || levels.set(level_list(&state_helper.lock().unwrap()))
+    };
 
-    v_stack((
-        label(move || label_text.clone()),
-        small_button(
-            "Open Level",
-            "plus",
-            {
-                move |_| {
-                    // add to scene
+    let level_id_2 = level_id.clone();
+
+    h_stack((
+        label(move || name.clone()).style({
+            let level_id = level_id.clone();
+            move |s| {
+                let s = s.selectable(false).flex_grow(1.0);
+                if active_id.get() == level_id {
+                    s.font_weight(Weight::BOLD)
+                } else {
+                    s
                 }
-            },
-            active,
-        ),
+            }
+        }),
+        level_action("Rename", {
+            let state_helper = Arc::clone(&state_helper);
+            let level_id = level_id.clone();
+            let refresh = refresh.clone();
+            move || {
+                rename_level(
+                    &state_helper.lock().unwrap(),
+                    &level_id,
+                    &level_name_input.get_untracked(),
+                );
+                level_name_input.set(String::new());
+                refresh();
+            }
+        }),
+        level_action("Duplicate", {
+            let state_helper = Arc::clone(&state_helper);
+            let level_id = level_id.clone();
+            let refresh = refresh.clone();
+            move || {
+                duplicate_level(&state_helper.lock().unwrap(), &level_id);
+                refresh();
+            }
+        }),
+        level_action("Up", {
+            let state_helper = Arc::clone(&state_helper);
+            let level_id = level_id.clone();
+            let refresh = refresh.clone();
+            move || {
+                move_level(&state_helper.lock().unwrap(), &level_id, -1);
+                refresh();
+            }
+        }),
+        level_action("Down", {
+            let state_helper = Arc::clone(&state_helper);
+            let level_id = level_id.clone();
+            let refresh = refresh.clone();
+            move || {
+                move_level(&state_helper.lock().unwrap(), &level_id, 1);
+                refresh();
+            }
+        }),
+        level_action("Delete", {
+            let state_helper = Arc::clone(&state_helper);
+            let gpu_helper = Arc::clone(&gpu_helper);
+            let level_id = level_id.clone();
+            move || {
+                let remaining: Vec<String> = levels
+                    .get_untracked()
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .filter(|id| *id != level_id)
+                    .collect();
+                let Some(fallback_id) = remaining.first() else {
+                    return;
+                };
+
+                // the level's components leave the renderer along with the level
+                if active_id.get_untracked() == level_id {
+                    switch_level(&state_helper, &gpu_helper, fallback_id);
+                }
+
+                delete_level(&state_helper.lock().unwrap(), &level_id);
+                refresh();
+            }
+        }),
+    ))
+    .on_click_stop(move |_| switch_level(&state_helper, &gpu_helper, &level_id_2))
+    .style(|s| {
+        s.width(260.0)
+            .align_items(AlignItems::Center)
+            .padding_vert(3)
+            .padding_horiz(4)
+            .cursor(CursorStyle::Pointer)
+            .hover(|s| s.background(Color::rgb(0.9, 0.9, 0.9)))
+    })
+}
+
+pub fn bookmark_item(
+    state_helper: Arc<Mutex<StateHelper>>,
+    bookmarks: RwSignal<Vec<CameraBookmark>>,
+    bookmark: CameraBookmark,
+) -> impl View {
+    let state_2 = Arc::clone(&state_helper);
+    let name = bookmark.name.clone();
+    let bookmark_id = bookmark.id.clone();
+
+    h_stack((
+        label(move || name.clone()).style(|s| s.selectable(false).flex_grow(1.0)),
+        level_action("Delete", move || {
+            let state_helper = state_2.lock().unwrap();
+            remove_camera_bookmark(&state_helper, &bookmark_id);
+            bookmarks.set(level_camera_bookmarks(&state_helper));
+        }),
     ))
-    .style(|s| s.width(120.0))
+    .on_click_stop(move |_| {
+        let mut state_helper = state_helper.lock().unwrap();
+        go_to_camera_bookmark(&mut state_helper.editor_camera, &bookmark);
+    })
+    .style(|s| {
+        s.width(260.0)
+            .align_items(AlignItems::Center)
+            .padding_vert(3)
+            .padding_horiz(4)
+            .cursor(CursorStyle::Pointer)
+            .hover(|s| s.background(Color::rgb(0.9, 0.9, 0.9)))
+    })
 }
 
 pub fn level_browser(
@@ -55,48 +223,97 @@ pub fn level_browser(
     gpu_helper: Arc<Mutex<GpuHelper>>,
     viewport: Arc<Mutex<Viewport>>,
 ) -> impl View {
-    let level_data: RwSignal<Vec<LevelData>> = create_rw_signal(Vec::new());
+    let levels: RwSignal<Vec<(String, String)>> = create_rw_signal(Vec::new());
+    let active_id = create_rw_signal(String::new());
+    let level_name_input = create_rw_signal(String::new());
+    let new_level_active = create_rw_signal(false);
+    let bookmarks: RwSignal<Vec<CameraBookmark>> = create_rw_signal(Vec::new());
+    let bookmark_name = create_rw_signal(String::new());
+    let add_bookmark_active = create_rw_signal(false);
 
     let state_2 = Arc::clone(&state_helper);
-    let gpu_2 = Arc::clone(&gpu_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+    let state_5 = Arc::clone(&state_helper);
 
     create_effect(move |_| {
         let state_helper = state_helper.lock().unwrap();
-        let saved_state = state_helper
-            .saved_state
-            .as_ref()
-            .expect("Couldn't get saved state")
-            .lock()
-            .unwrap();
+
+        // runs again when another level opens
+        if let Some(active_level_signal) = state_helper.active_level_signal {
+            active_level_signal.get();
+        }
+
         // all new projects should have 1 level created upon creation
-        level_data.set(
-            saved_state
-                .levels
-                .as_ref()
-                .expect("Couldn't get levels")
-                .clone(),
-        );
+        levels.set(level_list(&state_helper));
+        active_id.set(active_level_id(&state_helper).unwrap_or_default());
+        bookmarks.set(level_camera_bookmarks(&state_helper));
     });
 
-    container((scroll(
-        dyn_stack(
-            move || level_data.get(),
-            move |level_data| level_data.id.clone(),
-            move |level_data| {
-                level_item(
-                    state_2.clone(),
-                    gpu_2.clone(),
-                    level_data.id.clone(),
-                    level_data.id.clone(),
-                    level_data.id.clone(),
-                )
-            },
-        )
-        .into_view(),
-    ),))
-    .style(|s| {
-        s.flex_direction(FlexDirection::Row)
-            .flex_wrap(FlexWrap::Wrap)
-    })
+    v_stack((
+        h_stack((
+            text_input(level_name_input)
+                .placeholder("Level name")
+                .style(|s| s.width(150.0).margin_right(5.0)),
+            small_button(
+                "New Level",
+                "plus",
+                move |_| {
+                    let state_helper = state_2.lock().unwrap();
+                    create_level(&state_helper, &level_name_input.get_untracked());
+                    levels.set(level_list(&state_helper));
+                    level_name_input.set(String::new());
+                },
+                new_level_active,
+            ),
+        ))
+        .style(|s| s.margin_bottom(8.0).items_center()),
+        scroll(
+            dyn_stack(
+                move || levels.get(),
+                |(id, name): &(String, String)| (id.clone(), name.clone()),
+                move |(id, name)| {
+                    level_item(
+                        state_3.clone(),
+                        gpu_helper.clone(),
+                        levels,
+                        active_id,
+                        level_name_input,
+                        id,
+                        name,
+                    )
+                },
+            )
+            .style(|s| s.flex_col().width(260.0))
+            .into_view(),
+        ),
+        label(|| "Camera Bookmarks").style(|s| s.margin_top(10.0).margin_bottom(5.0)),
+        h_stack((
+            text_input(bookmark_name)
+                .placeholder("Bookmark name")
+                .style(|s| s.width(150.0).margin_right(5.0)),
+            small_button(
+                "Add",
+                "plus",
+                move |_| {
+                    let mut state_helper = state_4.lock().unwrap();
+                    add_camera_bookmark(&mut state_helper, &bookmark_name.get_untracked());
+                    bookmarks.set(level_camera_bookmarks(&state_helper));
+                    bookmark_name.set(String::new());
+                },
+                add_bookmark_active,
+            ),
+        ))
+        .style(|s| s.margin_bottom(8.0).items_center()),
+        scroll(
+            dyn_stack(
+                move || bookmarks.get(),
+                |bookmark: &CameraBookmark| bookmark.id.clone(),
+                move |bookmark| bookmark_item(state_5.clone(), bookmarks, bookmark),
+            )
+            .style(|s| s.flex_col().width(260.0))
+            .into_view(),
+        ),
+    ))
     .style(|s| s.width(260.0))
 }
@@ -0,0 +1,157 @@
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::floem::common::simple_button;
+use midpoint_engine::floem::reactive::{create_rw_signal, SignalGet, SignalUpdate};
+use midpoint_engine::floem::views::{empty, h_stack, label, v_stack, Decorators};
+use midpoint_engine::floem::IntoView;
+
+use crate::editor_state::StateHelper;
+use crate::helpers::lights::{level_lights, update_light, LightComponent, LightKind};
+use crate::helpers::utilities::parse_string_to_float;
+
+use super::inputs::styled_input;
+
+/// An input for one number on the light, skipped while it doesn't parse
+fn light_input(
+    state_helper: Arc<Mutex<StateHelper>>,
+    light_id: String,
+    title: &'static str,
+    initial_value: f32,
+    width: f64,
+    edit: fn(&mut LightComponent, f32),
+) -> impl IntoView {
+    styled_input(
+        format!("{}:", title),
+        &initial_value.to_string(),
+        title,
+        Box::new(move |state_helper_passed, value| {
+            let Some(value) = parse_string_to_float(&value) else {
+                return;
+            };
+
+            update_light(&state_helper_passed, &light_id, |light| edit(light, value));
+        }),
+        state_helper,
+        format!("light_{}", title.to_lowercase().replace(' ', "_")),
+    )
+    .style(move |s| s.width(width).margin_right(5.0))
+}
+
+/// Color, intensity, range and shadows of the selected light, saved as they are typed
+pub fn light_properties(
+    state_helper: Arc<Mutex<StateHelper>>,
+    light_id: String,
+    width: f64,
+) -> impl IntoView {
+    let Some(light) = level_lights(&state_helper.lock().unwrap())
+        .into_iter()
+        .find(|light| light.id == light_id)
+    else {
+        return empty().into_any();
+    };
+
+    let cast_shadows = create_rw_signal(light.cast_shadows);
+    let thirds = width / 3.0;
+    let state_2 = Arc::clone(&state_helper);
+
+    let name_id = light_id.clone();
+    let shadows_id = light_id.clone();
+
+    v_stack((
+        label(move || format!("{} Light", light.kind.label()))
+            .style(|s| s.margin_top(10.0).margin_bottom(5.0)),
+        styled_input(
+            "Name:".to_string(),
+            &light.name,
+            "Light name",
+            Box::new(move |state_helper_passed, value| {
+                update_light(&state_helper_passed, &name_id, |light| light.name = value);
+            }),
+            state_helper.clone(),
+            "light_name".to_string(),
+        ),
+        h_stack((
+            light_input(
+                state_helper.clone(),
+                light_id.clone(),
+                "Red",
+                light.color[0],
+                thirds,
+                |light, value| light.color[0] = value.clamp(0.0, 1.0),
+            ),
+            light_input(
+                state_helper.clone(),
+                light_id.clone(),
+                "Green",
+                light.color[1],
+                thirds,
+                |light, value| light.color[1] = value.clamp(0.0, 1.0),
+            ),
+            light_input(
+                state_helper.clone(),
+                light_id.clone(),
+                "Blue",
+                light.color[2],
+                thirds,
+                |light, value| light.color[2] = value.clamp(0.0, 1.0),
+            ),
+        )),
+        h_stack((
+            light_input(
+                state_helper.clone(),
+                light_id.clone(),
+                "Intensity",
+                light.intensity,
+                thirds,
+                |light, value| light.intensity = value.max(0.0),
+            ),
+            // suns reach everywhere
+            if light.kind == LightKind::Directional {
+                empty().into_any()
+            } else {
+                light_input(
+                    state_helper.clone(),
+                    light_id.clone(),
+                    "Range",
+                    light.range,
+                    thirds,
+                    |light, value| light.range = value.max(0.01),
+                )
+                .into_any()
+            },
+            if light.kind == LightKind::Spot {
+                light_input(
+                    state_helper.clone(),
+                    light_id.clone(),
+                    "Spot Angle",
+                    light.spot_angle,
+                    thirds,
+                    |light, value| light.spot_angle = value.clamp(1.0, 89.0),
+                )
+                .into_any()
+            } else {
+                empty().into_any()
+            },
+        )),
+        label(move || {
+            if cast_shadows.get() {
+                "Cast Shadows: On"
+            } else {
+                "Cast Shadows: Off"
+            }
+        })
+        .style(|s| s.font_size(10.0).margin_top(5.0)),
+        simple_button("Toggle Shadows".to_string(), move |_| {
+            let state_helper = state_2.lock().unwrap();
+            let value = !cast_shadows.get();
+
+            update_light(&state_helper, &shadows_id, |light| {
+                light.cast_shadows = value
+            });
+
+            cast_shadows.set(value);
+        }),
+    ))
+    .style(move |s| s.width(width))
+    .into_any()
+}
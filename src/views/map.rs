@@ -1,28 +1,141 @@
+use std::path::Path;
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use midpoint_engine::core::Viewport::Viewport;
 use midpoint_engine::floem::common::card_styles;
+use midpoint_engine::floem::common::simple_button;
 use midpoint_engine::floem::reactive::RwSignal;
 use midpoint_engine::floem::reactive::SignalGet;
 use midpoint_engine::floem::reactive::SignalUpdate;
 use midpoint_engine::floem::reactive::{create_effect, create_rw_signal};
-use midpoint_engine::floem::views::{container, dyn_container, empty, h_stack, label, v_stack};
+use midpoint_engine::floem::views::{
+    container, dyn_container, dyn_stack, empty, h_stack, label, v_stack,
+};
 use midpoint_engine::floem::IntoView;
+use midpoint_engine::helpers::saved_data::ComponentKind;
 use wgpu::util::DeviceExt;
 
 use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 
 use crate::editor_state::StateHelper;
+use crate::helpers::editor_data::EditorData;
+use crate::helpers::levels::active_level;
+use crate::helpers::water::{level_water, load_normalized_heightmap, WaterSettings};
 
-use super::topographic_map::create_topographic_map;
+use super::topographic_map::{create_overview_map, create_topographic_map, MapOverlays};
+
+/// Something in the level that can be shown on the map overview
+#[derive(Clone, Debug)]
+struct MapEntry {
+    id: String,
+    label: String,
+    heightmap_path: String,
+    /// World position of the heightmap's first corner
+    origin: [f32; 3],
+    /// World extent along x and z
+    world_size: [f32; 2],
+    tile_grid: Option<(usize, usize)>,
+}
+
+fn map_entries(state_helper: &StateHelper) -> Vec<MapEntry> {
+    let (Some(saved_state), Some(editor_data)) = (
+        state_helper.saved_state.as_ref(),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    let saved_state = saved_state.lock().unwrap();
+    let editor_data = editor_data.lock().unwrap();
+
+    let Some(level) = active_level(&saved_state, state_helper.active_level_id.as_deref()) else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    let mut tiled_components = Vec::new();
+
+    if let Some(level_data) = editor_data.level(&level.id) {
+        for grid in &level_data.tile_grids {
+            tiled_components.extend(grid.tiles.iter().map(|t| t.component_id.clone()));
+
+            if let Some(source) = grid.source_heightmap.as_ref() {
+                entries.push(MapEntry {
+                    id: grid.id.clone(),
+                    label: format!("{} ({}x{})", grid.name, grid.columns, grid.rows),
+                    heightmap_path: source.clone(),
+                    origin: grid.origin,
+                    world_size: [
+                        grid.tile_size * grid.columns as f32,
+                        grid.tile_size * grid.rows as f32,
+                    ],
+                    tile_grid: Some((grid.columns, grid.rows)),
+                });
+            }
+        }
+    }
+
+    // single landscapes, tiles are already covered by their grid
+    let landscape_size = level_data_water_size(&editor_data, &level.id);
+
+    for component in level.components.iter().flatten() {
+        if component.kind != Some(ComponentKind::Landscape)
+            || tiled_components.contains(&component.id)
+        {
+            continue;
+        }
+
+        let heightmap = saved_state
+            .landscapes
+            .as_ref()
+            .and_then(|landscapes| landscapes.iter().find(|l| l.id == component.asset_id))
+            .and_then(|landscape| landscape.heightmap.as_ref());
+
+        if let Some(heightmap) = heightmap {
+            entries.push(MapEntry {
+                id: component.id.clone(),
+                label: component.generic_properties.name.clone(),
+                heightmap_path: heightmap.normalFilePath.clone(),
+                origin: component.generic_properties.position,
+                world_size: [landscape_size, landscape_size],
+                tile_grid: None,
+            });
+        }
+    }
+
+    entries
+}
+
+fn level_data_water_size(editor_data: &EditorData, level_id: &str) -> f32 {
+    editor_data
+        .level(level_id)
+        .map(|level| level.water.landscape_size)
+        .unwrap_or_else(|| WaterSettings::default().landscape_size)
+}
 
 pub fn maps_view(
     state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
     viewport: Arc<Mutex<Viewport>>,
 ) -> impl View {
+    let state_2 = Arc::clone(&state_helper);
+
     let topo_heights: RwSignal<Option<nalgebra::DMatrix<f32>>> = create_rw_signal(None);
+    let entries: RwSignal<Vec<MapEntry>> = create_rw_signal(Vec::new());
+    let selected_map: RwSignal<Option<(nalgebra::DMatrix<f32>, MapOverlays)>> =
+        create_rw_signal(None);
+
+    create_effect(move |_| {
+        let state_helper = state_helper.lock().unwrap();
+
+        // runs again when another level opens
+        if let Some(active_level_signal) = state_helper.active_level_signal {
+            active_level_signal.get();
+        }
+
+        entries.set(map_entries(&state_helper));
+    });
 
     // create_effect(move |_| {
     //     let state_helper = state_helper.lock().unwrap();
@@ -43,9 +156,77 @@ pub fn maps_view(
     // });
 
     h_stack((
-        v_stack(((label(|| "Maps"),)))
-            .style(|s| card_styles(s))
-            .style(|s| s.width(300.0)),
+        v_stack((
+            label(|| "Maps"),
+            dyn_stack(
+                move || entries.get(),
+                move |entry| entry.id.clone(),
+                move |entry| {
+                    let state_helper = state_2.clone();
+
+                    simple_button(entry.label.clone(), move |_| {
+                        let heights =
+                            match load_normalized_heightmap(Path::new(&entry.heightmap_path)) {
+                                Ok(heights) => heights,
+                                Err(e) => {
+                                    println!("Couldn't load map heightmap: {}", e);
+                                    return;
+                                }
+                            };
+
+                        let water = level_water(&state_helper.lock().unwrap());
+                        let (rows, cols) = heights.shape();
+
+                        let water_level = water.sea_level.map(|sea_level| {
+                            (sea_level - entry.origin[1]) / water.landscape_height_scale
+                        });
+
+                        let to_grid = |point: &[f32; 3]| {
+                            [
+                                (point[0] - entry.origin[0]) / entry.world_size[0]
+                                    * (cols - 1) as f32,
+                                (point[2] - entry.origin[2]) / entry.world_size[1]
+                                    * (rows - 1) as f32,
+                            ]
+                        };
+
+                        let water_paths = water
+                            .rivers
+                            .iter()
+                            .map(|river| {
+                                let points = river.path().iter().map(to_grid).collect();
+                                let width = river.width / entry.world_size[0] * (cols - 1) as f32;
+
+                                (points, width)
+                            })
+                            .collect();
+
+                        topo_heights.set(None);
+                        selected_map.set(Some((
+                            heights,
+                            MapOverlays {
+                                tile_grid: entry.tile_grid,
+                                water_level,
+                                water_paths,
+                            },
+                        )));
+                    })
+                },
+            )
+            .style(|s| s.flex_col()),
+        ))
+        .style(|s| card_styles(s))
+        .style(|s| s.width(300.0)),
+        dyn_container(
+            move || selected_map.get(),
+            move |selected_map_real| {
+                if let Some((heights, overlays)) = selected_map_real {
+                    create_overview_map(heights, overlays).into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        ),
         dyn_container(
             move || topo_heights.get(),
             move |topo_heights_real| {
@@ -0,0 +1,236 @@
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::floem::common::simple_button;
+use midpoint_engine::floem::peniko::Color;
+use midpoint_engine::floem::reactive::{create_rw_signal, RwSignal, SignalGet, SignalUpdate};
+use midpoint_engine::floem::views::{empty, h_stack, label, v_stack, Decorators};
+use midpoint_engine::floem::{GpuHelper, IntoView};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::materials::{
+    clear_material, component_material, refresh_level_materials, update_material, MaterialOverride,
+    MaterialSlot,
+};
+use crate::helpers::utilities::parse_string_to_float;
+
+use super::inputs::{create_dropdown, styled_input, DropdownOption};
+
+/// Saves a change to the component's override and shows it in the viewport right away
+fn store_material(
+    state_helper: &StateHelper,
+    gpu_helper: &Arc<Mutex<GpuHelper>>,
+    component_id: &str,
+    base_color: RwSignal<[f32; 4]>,
+    edit: impl FnOnce(&mut MaterialOverride),
+) {
+    update_material(state_helper, component_id, edit);
+
+    if let Some(gpu_resources) = gpu_helper.lock().unwrap().gpu_resources.clone() {
+        refresh_level_materials(state_helper, &gpu_resources.device, &gpu_resources.queue);
+    }
+
+    if let Some(material) = component_material(state_helper, component_id) {
+        base_color.set(material.base_color);
+    }
+}
+
+/// An input for one number of the override, skipped while it doesn't parse
+fn material_input(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    component_id: String,
+    base_color: RwSignal<[f32; 4]>,
+    title: &'static str,
+    initial_value: f32,
+    width: f64,
+    edit: fn(&mut MaterialOverride, f32),
+) -> impl IntoView {
+    styled_input(
+        format!("{}:", title),
+        &initial_value.to_string(),
+        title,
+        Box::new(move |state_helper_passed, value| {
+            let Some(value) = parse_string_to_float(&value) else {
+                return;
+            };
+
+            store_material(
+                &state_helper_passed,
+                &gpu_helper,
+                &component_id,
+                base_color,
+                |material| edit(material, value),
+            );
+        }),
+        state_helper,
+        format!("material_{}", title.to_lowercase().replace(' ', "_")),
+    )
+    .style(move |s| s.width(width).margin_right(5.0))
+}
+
+/// Picks a texture from the project's library for one of the override's slots
+fn material_texture_dropdown(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    component_id: String,
+    base_color: RwSignal<[f32; 4]>,
+    slot: MaterialSlot,
+    material: &MaterialOverride,
+    options: Vec<DropdownOption>,
+) -> impl IntoView {
+    // a texture removed from the library shows as nothing picked
+    let initial = material
+        .texture(slot)
+        .filter(|id| options.iter().any(|option| option.id == **id))
+        .cloned()
+        .unwrap_or_default();
+
+    v_stack((
+        label(move || slot.label()).style(|s| s.font_size(10.0).margin_bottom(1.0)),
+        create_dropdown(initial, options, move |selected_id| {
+            let state_helper = state_helper.lock().unwrap();
+
+            store_material(
+                &state_helper,
+                &gpu_helper,
+                &component_id,
+                base_color,
+                |material| {
+                    *material.texture_mut(slot) =
+                        (!selected_id.is_empty()).then_some(selected_id.clone())
+                },
+            );
+        }),
+    ))
+    .style(|s| s.margin_bottom(10.0))
+}
+
+/// Base color, roughness/metallic, normal textures and tiling of the selected model, in place
+/// of what its glb contains
+pub fn material_properties(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    component_id: String,
+    width: f64,
+) -> impl IntoView {
+    let (material, options) = {
+        let state_helper = state_helper.lock().unwrap();
+        let material = component_material(&state_helper, &component_id)
+            .unwrap_or_else(|| MaterialOverride::new(&component_id));
+
+        let options: Vec<DropdownOption> = state_helper
+            .saved_state
+            .as_ref()
+            .and_then(|saved_state| saved_state.lock().unwrap().textures.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|file| DropdownOption {
+                id: file.id,
+                label: file.fileName,
+            })
+            .collect();
+
+        (material, options)
+    };
+
+    let base_color = create_rw_signal(material.base_color);
+    let thirds = width / 3.0;
+    let quarters = width / 4.0;
+    let halves = width / 2.0;
+
+    let input = |title: &'static str,
+                 initial_value: f32,
+                 width: f64,
+                 edit: fn(&mut MaterialOverride, f32)| {
+        material_input(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            component_id.clone(),
+            base_color,
+            title,
+            initial_value,
+            width,
+            edit,
+        )
+    };
+
+    let texture_dropdown = |slot: MaterialSlot| {
+        material_texture_dropdown(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            component_id.clone(),
+            base_color,
+            slot,
+            &material,
+            options.clone(),
+        )
+    };
+
+    let state_2 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+    let reset_id = component_id.clone();
+
+    v_stack((
+        h_stack((
+            label(|| "Material"),
+            // the base color as the viewport gets it, before textures and light
+            empty().style(move |s| {
+                let [r, g, b, _] = base_color.get().map(|c| c.clamp(0.0, 1.0) as f64);
+
+                s.width(20.0)
+                    .height(20.0)
+                    .margin_left(8.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(Color::GRAY)
+                    .background(Color::rgb(r, g, b))
+            }),
+        ))
+        .style(|s| s.margin_top(10.0).margin_bottom(5.0).items_center()),
+        h_stack((
+            input("Red", material.base_color[0], thirds, |m, v| {
+                m.base_color[0] = v.clamp(0.0, 1.0)
+            }),
+            input("Green", material.base_color[1], thirds, |m, v| {
+                m.base_color[1] = v.clamp(0.0, 1.0)
+            }),
+            input("Blue", material.base_color[2], thirds, |m, v| {
+                m.base_color[2] = v.clamp(0.0, 1.0)
+            }),
+        )),
+        texture_dropdown(MaterialSlot::BaseColor),
+        h_stack((
+            input("Roughness", material.roughness, halves, |m, v| {
+                m.roughness = v.clamp(0.0, 1.0)
+            }),
+            input("Metallic", material.metallic, halves, |m, v| {
+                m.metallic = v.clamp(0.0, 1.0)
+            }),
+        )),
+        texture_dropdown(MaterialSlot::RoughnessMetallic),
+        texture_dropdown(MaterialSlot::Normal),
+        h_stack((
+            input("Tiling U", material.tiling[0], quarters, |m, v| {
+                m.tiling[0] = v
+            }),
+            input("Tiling V", material.tiling[1], quarters, |m, v| {
+                m.tiling[1] = v
+            }),
+            input("Offset U", material.offset[0], quarters, |m, v| {
+                m.offset[0] = v
+            }),
+            input("Offset V", material.offset[1], quarters, |m, v| {
+                m.offset[1] = v
+            }),
+        )),
+        simple_button("Use Model Textures".to_string(), move |_| {
+            let state_helper = state_2.lock().unwrap();
+            clear_material(&state_helper, &reset_id);
+
+            if let Some(gpu_resources) = gpu_2.lock().unwrap().gpu_resources.clone() {
+                refresh_level_materials(&state_helper, &gpu_resources.device, &gpu_resources.queue);
+            }
+        }),
+    ))
+    .style(move |s| s.width(width))
+}
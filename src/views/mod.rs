@@ -1,4 +1,5 @@
 pub mod animations;
+pub mod annotation_browser;
 pub mod app;
 pub mod aside;
 pub mod audio;
@@ -10,15 +11,19 @@ pub mod keyframe_properties;
 pub mod keyframe_timeline;
 pub mod landscape_browser;
 pub mod level_browser;
+pub mod light_properties;
 pub mod map;
+pub mod material_properties;
 pub mod model_browser;
 pub mod nodes;
 pub mod part_browser;
 pub mod part_properties;
 pub mod performance;
+pub mod prefab_browser;
 pub mod project_browser;
 pub mod project_settings;
 pub mod properties_panel;
+pub mod scatter_browser;
 pub mod scene;
 pub mod shared;
 pub mod skeleton_browser;
@@ -26,3 +31,4 @@ pub mod skeleton_properties;
 pub mod story;
 pub mod texture_browser;
 pub mod topographic_map;
+pub mod water_browser;
@@ -27,21 +27,61 @@ use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 
 use crate::editor_state::{EditorState, StateHelper, UIMessage};
+use crate::helpers::instancing::register_model_asset;
+use crate::helpers::levels::active_level_mut;
+use crate::helpers::offscreen::save_model_thumbnail;
 
 // type BoxedAsyncFn = Box<dyn Fn() -> Pin<Box<dyn Future<Output = String> + Send>> + Send + Sync>;
 
 pub fn model_item(
     state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
+    project_id: String,
     label_text: String,
     filename: String,
     model_id: String,
 ) -> impl View {
     let active = create_rw_signal(false);
     let active_2 = create_rw_signal(false);
+    // bumped after rendering a new thumbnail so the image loads again
+    let thumbnail_version = create_rw_signal(0);
+
+    let state_2 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+    let model_id_2 = model_id.clone();
+    let model_id_3 = model_id.clone();
+    let filename_2 = filename.clone();
 
     v_stack((
+        dyn_container(
+            move || thumbnail_version.get(),
+            move |_| {
+                dynamic_img(
+                    format!("midpoint/projects/{}/thumbnails", project_id),
+                    format!("{}.png", model_id_2),
+                    100.0,
+                    100.0,
+                )
+            },
+        ),
         label(move || label_text.clone()),
+        small_button(
+            "Thumbnail",
+            "plus",
+            move |_| {
+                let state_helper = state_2.lock().unwrap();
+                let Some(gpu_resources) = gpu_2.lock().unwrap().gpu_resources.clone() else {
+                    return;
+                };
+
+                match save_model_thumbnail(&state_helper, &gpu_resources, &model_id_3, &filename_2)
+                {
+                    Ok(_) => thumbnail_version.update(|version| *version += 1),
+                    Err(e) => println!("Couldn't render thumbnail: {}", e),
+                }
+            },
+            active_2,
+        ),
         small_button(
             "Add to Scene",
             "plus",
@@ -76,6 +116,7 @@ pub fn model_item(
                         filename.clone(),
                         Isometry3::new(Vector3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
                     );
+                    register_model_asset(&state_helper, &component_id.to_string(), &model_id);
 
                     // create physics
                     let mut renderer_state = renderer_state.lock().unwrap();
@@ -104,10 +145,8 @@ pub fn model_item(
                         landscape_properties: None,
                         model_properties: None,
                     };
-                    let mut levels = saved_state.levels.as_mut().expect("Couldn't get levels");
-                    levels
-                        .get_mut(0)
-                        .expect("Couldn't get first level")
+                    active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+                        .expect("Couldn't get active level")
                         .components
                         .get_or_insert_with(Vec::new)
                         .push(model_component);
@@ -144,6 +183,14 @@ pub fn model_browser(
     let state_2 = Arc::clone(&state_helper);
     let state_3 = Arc::clone(&state_helper);
     let gpu_2 = Arc::clone(&gpu_helper);
+    let gpu_3 = Arc::clone(&gpu_helper);
+    let project_id = state_helper
+        .lock()
+        .unwrap()
+        .project_selected_signal
+        .expect("Couldn't get project signal")
+        .get_untracked()
+        .to_string();
 
     let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
     let tx = Arc::new(tx);
@@ -226,9 +273,25 @@ pub fn model_browser(
 
                 models.push(new_model);
 
-                model_data.set(saved_state.models.clone());
+                let models = saved_state.models.clone();
 
                 state_helper.save_saved_state(project_id, saved_state);
+
+                // the card loads its thumbnail when it's created
+                if let Some(gpu_resources) = gpu_3.lock().unwrap().gpu_resources.clone() {
+                    let filename = new_id.to_string() + ".glb";
+
+                    if let Err(e) = save_model_thumbnail(
+                        &state_helper,
+                        &gpu_resources,
+                        &new_id.to_string(),
+                        &filename,
+                    ) {
+                        println!("Couldn't render thumbnail: {}", e);
+                    }
+                }
+
+                model_data.set(models);
             }
         }),
         scroll(
@@ -239,6 +302,7 @@ pub fn model_browser(
                     model_item(
                         state_2.clone(),
                         gpu_2.clone(),
+                        project_id.clone(),
                         model_data.fileName.clone(),
                         model_data.fileName.clone(),
                         model_data.id.clone(),
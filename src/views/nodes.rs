@@ -1,3 +1,5 @@
+use std::sync::{Arc, Mutex};
+
 use midpoint_engine::floem::event::EventListener;
 use midpoint_engine::floem::event::EventPropagation;
 use midpoint_engine::floem::peniko::Color;
@@ -5,27 +7,38 @@ use midpoint_engine::floem::reactive::SignalGet;
 use midpoint_engine::floem::reactive::SignalUpdate;
 use midpoint_engine::floem::reactive::*;
 use midpoint_engine::floem::style::CursorStyle;
+use midpoint_engine::floem::taffy::AlignItems;
 use midpoint_engine::floem::taffy::FlexDirection;
 use midpoint_engine::floem::taffy::Position;
 use midpoint_engine::floem::unit::Pct;
 use midpoint_engine::floem::unit::Px;
 use midpoint_engine::floem::views::container;
+use midpoint_engine::floem::views::dyn_container;
 use midpoint_engine::floem::views::dyn_stack;
 use midpoint_engine::floem::views::empty;
 use midpoint_engine::floem::views::h_stack;
 use midpoint_engine::floem::views::label;
+use midpoint_engine::floem::views::scroll;
 use midpoint_engine::floem::views::v_stack;
 use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::IntoView;
 use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
+use midpoint_engine::helpers::saved_data::ComponentKind;
 use nalgebra::Vector2;
 use nalgebra_glm::Vec2;
 
+use crate::editor_state::StateHelper;
+use crate::helpers::behaviors::{
+    behavior_node, component_behavior, connect_ports, remove_node, set_component_behavior,
+    value_hint, BEHAVIOR_NODE_TYPES,
+};
 use crate::helpers::nodes::NodeComponent;
 use crate::helpers::nodes::NodeInputs;
 use crate::helpers::nodes::NodeType;
 use crate::helpers::nodes::Port;
 
+use super::inputs::styled_input;
+
 struct NodeCanvas {
     offset: RwSignal<Vec2>,
     start_pan_pos: RwSignal<Vec2>,
@@ -33,13 +46,66 @@ struct NodeCanvas {
     nodes: RwSignal<Vec<NodeComponent>>,
 }
 
-pub fn node_ports(
-    all_nodes: RwSignal<Vec<NodeComponent>>,
+/// The graph being edited and the component it belongs to
+#[derive(Clone)]
+struct BehaviorEditor {
+    state_helper: Arc<Mutex<StateHelper>>,
+    component_id: RwSignal<Option<String>>,
+    nodes: RwSignal<Vec<NodeComponent>>,
+    /// Output port clicked last, connected to the next input port clicked
+    pending_output: RwSignal<Option<String>>,
+}
+
+impl BehaviorEditor {
+    /// Changes the graph and saves it on the component
+    fn edit(&self, state_helper: &StateHelper, edit: impl FnOnce(&mut Vec<NodeComponent>)) {
+        self.nodes.update(edit);
+
+        if let Some(component_id) = self.component_id.get_untracked() {
+            set_component_behavior(state_helper, &component_id, self.nodes.get_untracked());
+        }
+    }
+
+    /// An output port starts a connection, an input port finishes it
+    fn click_port(&self, port: &Port) {
+        if port.is_output {
+            self.pending_output.set(Some(port.id.clone()));
+            return;
+        }
+
+        let Some(output_id) = self.pending_output.get_untracked() else {
+            return;
+        };
+        self.pending_output.set(None);
+
+        self.edit(&self.state_helper.lock().unwrap(), |nodes| {
+            connect_ports(nodes, &output_id, &port.id)
+        });
+    }
+}
+
+/// Text button for the editor's actions
+fn canvas_action(text: String, action: impl Fn() + 'static) -> impl IntoView {
+    label(move || text.clone())
+        .on_click_stop(move |_| action())
+        .style(|s| {
+            s.font_size(10.0)
+                .margin_right(8.0)
+                .color(Color::WHITE_SMOKE)
+                .cursor(CursorStyle::Pointer)
+                .selectable(false)
+        })
+}
+
+fn node_ports(
+    editor: BehaviorEditor,
     node: NodeComponent,
     ports: Vec<Port>,
     left: bool,
 ) -> impl View {
     let ports = create_rw_signal(ports);
+    let all_nodes = editor.nodes;
+    let pending_output = editor.pending_output;
 
     container(
         (dyn_stack(
@@ -48,9 +114,12 @@ pub fn node_ports(
             (move |port| {
                 let left = left.clone();
                 let display_name_2 = port.display_name.clone();
-                let connected_to = port.connected_to;
+                let connected_to = port.connected_to.clone();
+                let port_id = port.id.clone();
+                let editor = editor.clone();
+                let clicked = port.clone();
 
-                let all_nodes = all_nodes.get();
+                let all_nodes = all_nodes.get_untracked();
                 let connected_to_port = if connected_to.is_some() {
                     let connected_to = connected_to.as_ref().expect("Couldn't get connected_to");
                     if port.is_output {
@@ -105,6 +174,13 @@ pub fn node_ports(
                         empty().into_any()
                     },
                 ))
+                .on_click_stop(move |_| editor.click_port(&clicked))
+                .style(move |s| {
+                    s.cursor(CursorStyle::Pointer)
+                        .apply_if(pending_output.get().as_ref() == Some(&port_id), |s| {
+                            s.border(1.0).border_color(Color::WHITE_SMOKE)
+                        })
+                })
             }),
         ))
         .style(|s| s.flex_direction(FlexDirection::Column)),
@@ -112,12 +188,12 @@ pub fn node_ports(
     .style(|s| s.position(Position::Relative).selectable(false))
 }
 
-pub fn node_item(
+fn node_item(
+    editor: BehaviorEditor,
     node: NodeComponent,
     offset: RwSignal<Vec2>,
     start_pan_pos: RwSignal<Vec2>,
     is_panning: RwSignal<bool>,
-    nodes: RwSignal<Vec<NodeComponent>>,
     dragging_node_id: RwSignal<Option<String>>,
 ) -> impl View {
     let node_position = create_rw_signal(Vec2::new(
@@ -144,25 +220,54 @@ pub fn node_item(
 
     let node_2 = node.clone();
     let node_3 = node.clone();
+    let editor_2 = editor.clone();
+    let editor_3 = editor.clone();
+    let editor_4 = editor.clone();
+    let node_id_2 = node.id.clone();
+    let node_id_3 = node.id.clone();
+    let node_id_4 = node.id.clone();
 
     v_stack((
-        label(move || format!("{}", node_title.clone())).style(move |s| {
+        h_stack((
+            label(move || format!("{}", node_title.clone())).style(|s| s.flex_grow(1.0)),
+            canvas_action("Remove".to_string(), move || {
+                editor_2.edit(&editor_2.state_helper.lock().unwrap(), |nodes| {
+                    remove_node(nodes, &node_id_2)
+                });
+            }),
+        ))
+        .style(move |s| {
             s.background(node.get_type_color())
                 .margin_bottom(4.0)
                 .padding(4.0)
                 .color(Color::WHITE_SMOKE)
                 .selectable(false)
         }),
-        node_ports(nodes, node_2.clone(), inputs, true),
-        node_ports(nodes, node_3.clone(), outputs, false),
+        styled_input(
+            value_hint(&node_type).to_string(),
+            &node_2.value,
+            value_hint(&node_type),
+            Box::new(move |state_helper, value| {
+                editor_3.edit(&state_helper, |nodes| {
+                    if let Some(node) = nodes.iter_mut().find(|n| n.id == node_id_3) {
+                        node.value = value;
+                    }
+                });
+            }),
+            Arc::clone(&editor.state_helper),
+            format!("{}_value", node_id),
+        )
+        .style(|s| s.padding_horiz(4.0).margin_bottom(4.0)),
+        node_ports(editor.clone(), node_2.clone(), inputs, true),
+        node_ports(editor.clone(), node_3.clone(), outputs, false),
     ))
     .draggable()
     .style(move |s| {
         s.position(Position::Absolute)
             .margin_top(Px((node_position.get().y + offset.get().y) as f64))
             .margin_left(Px((node_position.get().x + offset.get().x) as f64))
-            .width(150.0)
-            .height(100.0)
+            .width(180.0)
+            .min_height(100.0)
             .border_radius(15.0)
             .background(Color::LIGHT_BLUE)
             .cursor(CursorStyle::Pointer)
@@ -206,6 +311,13 @@ pub fn node_item(
         );
 
         node_position.set(new_pos);
+
+        // the node stays where it was dropped next time the graph opens
+        editor_4.edit(&editor_4.state_helper.lock().unwrap(), |nodes| {
+            if let Some(node) = nodes.iter_mut().find(|n| n.id == node_id_4) {
+                node.initial_position = [new_pos.x.max(0.0) as u32, new_pos.y.max(0.0) as u32];
+            }
+        });
         EventPropagation::Continue
     })
     .style(move |s| {
@@ -217,350 +329,155 @@ pub fn node_item(
     })
 }
 
-pub fn node_canvas() -> impl View {
+/// Node graph behavior of a model component, run while the level plays
+pub fn node_canvas(state_helper: Arc<Mutex<StateHelper>>) -> impl View {
     let offset = create_rw_signal(Vec2::identity());
     let start_pan_pos = create_rw_signal(Vec2::identity());
     let is_panning = create_rw_signal(false);
-    let nodes: RwSignal<Vec<NodeComponent>> = create_rw_signal(create_test_nodes());
     let dragging_node_id = create_rw_signal(None::<String>);
+    let level_components = state_helper.lock().unwrap().level_components_signal;
 
-    container(
-        (dyn_stack(
-            move || nodes.get(),
-            move |node| node.id.clone(),
-            (move |node| {
-                let node_id = node.id.clone();
-                node_item(
-                    node,
-                    offset,
-                    start_pan_pos,
-                    is_panning,
-                    nodes,
-                    dragging_node_id,
-                )
-            }),
-        )),
-    )
-    .style(|s| s.width_full().height_full().background(Color::GRAY))
-    // Canvas panning
-    // .on_event(EventListener::PointerDown, move |evt| {
-    //     if dragging_node_id.get().is_none() {
-    //         is_panning.set(true);
-    //         start_pan_pos.set(Vec2::new(
-    //             evt.point().expect("Couldn't get point").x as f32,
-    //             evt.point().expect("Couldn't get point").y as f32,
-    //         ));
-    //     }
-    //     EventPropagation::Continue
-    // })
-    // .on_event(EventListener::PointerMove, move |evt| {
-    //     if is_panning.get() && dragging_node_id.get().is_none() {
-    //         let position = Vec2::new(
-    //             evt.point().expect("Couldn't get point").x as f32,
-    //             evt.point().expect("Couldn't get point").y as f32,
-    //         );
-    //         let delta = position - start_pan_pos.get();
-    //         offset.update(|off| *off += delta);
-    //         start_pan_pos.set(position);
-    //     }
-    //     EventPropagation::Continue
-    // })
-    // .on_event(EventListener::PointerUp, move |_| {
-    //     is_panning.set(false);
-    //     EventPropagation::Continue
-    // })
-}
+    let editor = BehaviorEditor {
+        state_helper,
+        component_id: create_rw_signal(None),
+        nodes: create_rw_signal(Vec::new()),
+        pending_output: create_rw_signal(None),
+    };
+    let component_id = editor.component_id;
+    let nodes = editor.nodes;
+    let editor_2 = editor.clone();
 
-pub fn create_test_nodes() -> Vec<NodeComponent> {
-    vec![
-        // setting up state
-        NodeComponent {
-            id: "stringa".to_string(),
-            title: "Name (String)".to_string(),
-            initial_position: [50, 50],
-            node_type: NodeType::String,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "string_value1".to_string(),
-                input_name: Some("value".to_string()),
-                connected_to: Some("name_54".to_string()),
-                display_name: "String Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "valuea".to_string(),
-            title: "Value (String)".to_string(),
-            initial_position: [250, 50],
-            node_type: NodeType::String,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "string_value2".to_string(),
-                input_name: Some("value".to_string()),
-                connected_to: Some("value_54".to_string()),
-                display_name: "String Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "datatypea".to_string(),
-            title: "Data Type (DateType)".to_string(),
-            initial_position: [450, 50],
-            node_type: NodeType::DataType,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "datatype_value2".to_string(),
-                input_name: Some("datatype".to_string()),
-                connected_to: Some("datatype_54".to_string()),
-                display_name: "Data Type Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "persistenta".to_string(),
-            title: "Persistent (Boolean)".to_string(),
-            initial_position: [650, 50],
-            node_type: NodeType::Boolean,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "bool_value2".to_string(),
-                input_name: Some("boolean".to_string()),
-                connected_to: Some("persistent_54".to_string()),
-                display_name: "Boolean Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "state1".to_string(),
-            title: "Player Health (ReactiveState)".to_string(),
-            initial_position: [100, 200],
-            node_type: NodeType::ReactiveState,
-            // node_inputs: NodeInputs::State {
-            //     name: "Health State".to_string(),
-            //     value: "80".to_string(),
-            //     data_type: crate::helpers::nodes::DataType::String,
-            //     persistent: false,
-            // },
-            ui_inputs: vec![
-                Port {
-                    id: "name_54".to_string(),
-                    input_name: Some("name".to_string()),
-                    connected_to: Some("stringa".to_string()),
-                    display_name: "Name".to_string(),
-                    // connection_type: PortType::Variable,
-                    is_output: false,
-                },
-                Port {
-                    id: "value_54".to_string(),
-                    input_name: Some("value".to_string()),
-                    connected_to: Some("string_value2".to_string()),
-                    display_name: "Value".to_string(),
-                    // connection_type: PortType::Variable,
-                    is_output: false,
-                },
-                Port {
-                    id: "datatype_54".to_string(),
-                    input_name: Some("data_type".to_string()),
-                    connected_to: Some("datatype_value2".to_string()),
-                    display_name: "Data Type".to_string(),
-                    // connection_type: PortType::Variable,
-                    is_output: false,
-                },
-                Port {
-                    id: "persistent_54".to_string(),
-                    input_name: Some("persistent".to_string()),
-                    connected_to: Some("bool_value2".to_string()),
-                    display_name: "Persistent".to_string(),
-                    // connection_type: PortType::Variable,
-                    is_output: false,
-                },
-            ],
-            ui_outputs: vec![Port {
-                id: "health_out".to_string(),
-                input_name: None,
-                connected_to: Some("dependencies1_in".to_string()),
-                display_name: "ReactiveState Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        // setting up effect
-        NodeComponent {
-            id: "execution_order1a".to_string(),
-            title: "Execution Order (Integer)".to_string(),
-            initial_position: [50, 250],
-            node_type: NodeType::Integer,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "integer1".to_string(),
-                input_name: Some("integer".to_string()),
-                connected_to: Some("exec_order1_in".to_string()),
-                display_name: "Integer Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "parallel1b".to_string(),
-            title: "Parallel (Boolean)".to_string(),
-            initial_position: [250, 250],
-            node_type: NodeType::Boolean,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "bool45".to_string(),
-                input_name: Some("boolean".to_string()),
-                connected_to: Some("parallel1_in".to_string()),
-                display_name: "Boolean Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "effect1".to_string(),
-            title: "Detect Warning (Effect)".to_string(),
-            initial_position: [350, 500],
-            node_type: NodeType::Effect,
-            // node_inputs: NodeInputs::Effect {
-            //     dependencies: Vec::new(),
-            //     execution_order: 0,
-            //     parallel: false,
-            // },
-            ui_inputs: vec![
-                //     Port {
-                //     id: "effect1_in".to_string(),
-                //     input_name: Some("TBD".to_string()),
-                //     connected_to: Some("health_out".to_string()),
-                //     display_name: "Effect 1 In".to_string(),
-                //     is_output: false,
-                // }
-                Port {
-                    id: "dependencies1_in".to_string(),
-                    input_name: Some("dependencies".to_string()),
-                    connected_to: Some("health_out".to_string()), // connects to State
-                    display_name: "Dependencies".to_string(),
-                    is_output: false,
-                },
-                Port {
-                    id: "exec_order1_in".to_string(),
-                    input_name: Some("exec_order".to_string()),
-                    connected_to: Some("integer1".to_string()),
-                    display_name: "Exec. Order".to_string(),
-                    is_output: false,
+    let components = h_stack((
+        label(|| "Behavior of").style(|s| s.font_size(10.0).margin_right(8.0)),
+        scroll(
+            dyn_stack(
+                move || {
+                    level_components
+                        .map(|components| components.get())
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|component| component.kind == Some(ComponentKind::Model))
+                        .collect::<Vec<_>>()
                 },
-                Port {
-                    id: "parallel1_in".to_string(),
-                    input_name: Some("parallel".to_string()),
-                    connected_to: Some("bool45".to_string()),
-                    display_name: "Parallel".to_string(),
-                    is_output: false,
-                },
-            ],
-            ui_outputs: vec![Port {
-                id: "warning_out".to_string(),
-                input_name: None,
-                connected_to: Some("disval1_in".to_string()),
-                display_name: "On Effect".to_string(),
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        // displaying to ui
-        NodeComponent {
-            id: "elemtyp23".to_string(),
-            title: "Element Type (Elem. Type)".to_string(),
-            initial_position: [50, 350],
-            node_type: NodeType::UIElementType,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "elemtypeoutput2".to_string(),
-                input_name: Some("elem_type".to_string()),
-                connected_to: Some("eltype1_in".to_string()),
-                display_name: "Elem. Type Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "stylehere2".to_string(),
-            title: "Bar Style (Style)".to_string(),
-            initial_position: [50, 350],
-            node_type: NodeType::Style,
-            ui_inputs: vec![],
-            ui_outputs: vec![Port {
-                id: "styleout".to_string(),
-                input_name: Some("style".to_string()),
-                connected_to: Some("style1_in".to_string()),
-                display_name: "Style Output".to_string(),
-                // connection_type: PortType::Variable,
-                is_output: true,
-            }],
-            parent: None,
-            children: Vec::new(),
-        },
-        NodeComponent {
-            id: "ui1".to_string(),
-            title: "Update Health Bar (UI)".to_string(),
-            initial_position: [600, 500],
-            node_type: NodeType::UI,
-            // node_inputs: NodeInputs::UI {
-            //     element_type: crate::helpers::nodes::UIElementType::ProgressBar,
-            //     layout: crate::helpers::nodes::LayoutType::Absolute,
-            //     style: "temp_style".to_string(),
-            // },
-            ui_inputs: vec![
-                //     Port {
-                //     id: "ui1_in".to_string(),
-                //     input_name: Some("TBD".to_string()),
-                //     display_name: "UI In".to_string(),
-                //     connected_to: Some("warning_out".to_string()),
-                //     is_output: false,
-                // }
-                Port {
-                    id: "disval1_in".to_string(),
-                    input_name: Some("display_value".to_string()),
-                    display_name: "Display Value".to_string(),
-                    connected_to: Some("warning_out".to_string()),
-                    is_output: false,
-                },
-                Port {
-                    id: "eltype1_in".to_string(),
-                    input_name: Some("element_type".to_string()),
-                    display_name: "Element Type".to_string(),
-                    connected_to: Some("elemtypeoutput2".to_string()),
-                    is_output: false,
-                },
-                Port {
-                    id: "style1_in".to_string(),
-                    input_name: Some("style".to_string()),
-                    display_name: "Style".to_string(),
-                    connected_to: Some("styleout".to_string()),
-                    is_output: false,
+                move |component| component.id.clone(),
+                move |component| {
+                    let editor = editor.clone();
+                    let id = component.id.clone();
+                    let id_2 = component.id.clone();
+
+                    canvas_action(component.generic_properties.name.clone(), move || {
+                        let nodes = component_behavior(&editor.state_helper.lock().unwrap(), &id);
+
+                        editor.pending_output.set(None);
+                        editor.nodes.set(nodes);
+                        editor.component_id.set(Some(id.clone()));
+                    })
+                    .style(move |s| {
+                        s.apply_if(component_id.get().as_ref() == Some(&id_2), |s| {
+                            s.color(Color::LIGHT_BLUE)
+                        })
+                    })
                 },
-            ],
-            ui_outputs: vec![],
-            parent: None,
-            children: Vec::new(),
-        },
-    ]
+            )
+            .style(|s| s.flex_row()),
+        ),
+    ))
+    .style(|s| s.align_items(AlignItems::Center).margin_bottom(8.0));
+
+    v_stack((
+        components,
+        dyn_container(
+            move || component_id.get().is_some(),
+            move |picked| {
+                if !picked {
+                    return label(|| "Pick a model to give it a behavior")
+                        .style(|s| s.font_size(10.0))
+                        .into_any();
+                }
+
+                let editor = editor_2.clone();
+                let editor_3 = editor.clone();
+                let toolbar = dyn_stack(
+                    || BEHAVIOR_NODE_TYPES.to_vec(),
+                    |node_type| node_type.clone(),
+                    move |node_type| {
+                        let editor = editor_3.clone();
+
+                        canvas_action(format!("+ {:?}", node_type), move || {
+                            let count = editor.nodes.get_untracked().len() as u32;
+                            let node = behavior_node(
+                                node_type.clone(),
+                                [20 + 30 * (count % 10), 20 + 30 * (count % 10)],
+                            );
+
+                            editor.edit(&editor.state_helper.lock().unwrap(), |nodes| {
+                                nodes.push(node)
+                            });
+                        })
+                    },
+                )
+                .style(|s| s.flex_row())
+                .style(|s| s.margin_bottom(8.0));
+
+                v_stack((
+                    toolbar,
+                    label(|| "Click an output, then an input to connect them")
+                        .style(|s| s.font_size(10.0).margin_bottom(4.0)),
+                    container(dyn_stack(
+                        move || nodes.get(),
+                        // positions and values change without building the node again, which
+                        // would drop the focus of its value input
+                        move |node| {
+                            (
+                                node.id.clone(),
+                                node.ui_inputs.clone(),
+                                node.ui_outputs.clone(),
+                            )
+                        },
+                        move |node| {
+                            node_item(
+                                editor.clone(),
+                                node,
+                                offset,
+                                start_pan_pos,
+                                is_panning,
+                                dragging_node_id,
+                            )
+                        },
+                    ))
+                    .style(|s| s.width_full().height_full().background(Color::GRAY)),
+                    // Canvas panning
+                    // .on_event(EventListener::PointerDown, move |evt| {
+                    //     if dragging_node_id.get().is_none() {
+                    //         is_panning.set(true);
+                    //         start_pan_pos.set(Vec2::new(
+                    //             evt.point().expect("Couldn't get point").x as f32,
+                    //             evt.point().expect("Couldn't get point").y as f32,
+                    //         ));
+                    //     }
+                    //     EventPropagation::Continue
+                    // })
+                    // .on_event(EventListener::PointerMove, move |evt| {
+                    //     if is_panning.get() && dragging_node_id.get().is_none() {
+                    //         let position = Vec2::new(
+                    //             evt.point().expect("Couldn't get point").x as f32,
+                    //             evt.point().expect("Couldn't get point").y as f32,
+                    //         );
+                    //         let delta = position - start_pan_pos.get();
+                    //         offset.update(|off| *off += delta);
+                    //         start_pan_pos.set(position);
+                    //     }
+                    //     EventPropagation::Continue
+                    // })
+                    // .on_event(EventListener::PointerUp, move |_| {
+                    //     is_panning.set(false);
+                    //     EventPropagation::Continue
+                    // })
+                ))
+                .style(|s| s.width_full().height_full())
+                .into_any()
+            },
+        ),
+    ))
+    .style(|s| s.width_full().height_full())
 }
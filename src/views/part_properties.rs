@@ -26,6 +26,7 @@ use midpoint_engine::floem::views::Decorators;
 use midpoint_engine::floem::{GpuHelper, IntoView, View, WindowHandle};
 
 use crate::editor_state::StateHelper;
+use crate::helpers::hierarchy::{calculate_depth, get_all_child_ids, would_create_cycle};
 
 pub fn part_properties(
     state_helper: Arc<Mutex<StateHelper>>,
@@ -111,7 +112,7 @@ pub fn joint_tree(
         move || joints.get(),
         |joint: &Joint| joint.id.clone(),
         move |joint| {
-            let depth = calculate_depth(&joints.get(), joint.id.clone());
+            let depth = calculate_depth(&joints.get(), &joint.id);
             joint_item(
                 state_helper.clone(),
                 joints,
@@ -125,21 +126,6 @@ pub fn joint_tree(
     .into_view()
 }
 
-fn calculate_depth(joints: &[Joint], joint_id: String) -> usize {
-    let mut depth = 0;
-    let mut current_joint = joints.iter().find(|b| b.id == joint_id);
-
-    while let Some(joint) = current_joint {
-        if let Some(parent_id) = joint.parent_id.clone() {
-            depth += 1;
-            current_joint = joints.iter().find(|b| b.id == parent_id);
-        } else {
-            break;
-        }
-    }
-    depth
-}
-
 pub fn joint_item(
     state_helper: Arc<Mutex<StateHelper>>,
     joints: RwSignal<Vec<Joint>>,
@@ -157,7 +143,7 @@ pub fn joint_item(
 
     create_effect(move |_| {
         let joints = joints.get();
-        let new_depth = calculate_depth(&joints, joint_id_clone.clone());
+        let new_depth = calculate_depth(&joints, &joint_id_clone);
         println!(
             "depth effect {:?} {:?}",
             joint_name_clone.clone(),
@@ -276,36 +262,6 @@ pub fn joint_item(
     })
 }
 
-fn would_create_cycle(joints: &mut Vec<Joint>, dragged_id: &str, new_parent_id: &str) -> bool {
-    let mut current_id: Option<_> = Some(new_parent_id);
-    while let Some(id) = current_id {
-        if id == dragged_id {
-            return true;
-        }
-        current_id = joints
-            .iter()
-            .find(|j| j.id == id)
-            .and_then(|j| j.parent_id.as_deref());
-    }
-    false
-}
-
-fn get_all_child_ids(joints: &mut Vec<Joint>, parent_id: &str) -> Vec<String> {
-    let mut children = Vec::new();
-    let mut to_process = vec![parent_id];
-
-    while let Some(current_id) = to_process.pop() {
-        for joint in joints.iter() {
-            if joint.parent_id.as_deref() == Some(current_id) {
-                children.push(joint.id.clone());
-                to_process.push(&joint.id);
-            }
-        }
-    }
-
-    children
-}
-
 // fn calculate_new_world_position(
 //     joints: &mut Vec<Joint>,
 //     child: &Joint,
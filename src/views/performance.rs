@@ -1,18 +1,271 @@
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use midpoint_engine::floem::common::card_styles;
-use midpoint_engine::floem::views::{container, dyn_container, empty, label, v_stack};
 use midpoint_engine::core::Viewport::Viewport;
-use wgpu::util::DeviceExt;
+use midpoint_engine::floem::action::exec_after;
+use midpoint_engine::floem::common::{card_styles, simple_button};
+use midpoint_engine::floem::context::PaintCx;
+use midpoint_engine::floem::kurbo;
+use midpoint_engine::floem::peniko::Color;
+use midpoint_engine::floem::reactive::{
+    create_rw_signal, RwSignal, SignalGet, SignalUpdate, SignalWith,
+};
+use midpoint_engine::floem::style::Style;
+use midpoint_engine::floem::views::{dyn_container, h_stack, label, v_stack, Decorators};
+use midpoint_engine::floem::{GpuHelper, IntoView, View, ViewId};
+use midpoint_engine::floem_renderer::Renderer;
 
-use midpoint_engine::floem::views::Decorators;
-use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
+use crate::editor_state::StateHelper;
+use crate::helpers::performance::{save_capture, FrameStats};
+
+const GRAPH_WIDTH: f64 = 260.0;
+const GRAPH_HEIGHT: f64 = 80.0;
+/// The graph's top, two frames at 60 fps
+const GRAPH_MAX_MS: f32 = 33.3;
+const REFRESH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the view shows, copied out of the render state on every refresh
+#[derive(Clone, Default)]
+struct PerformanceSnapshot {
+    history: Vec<FrameStats>,
+    has_gpu_timer: bool,
+}
+
+impl PerformanceSnapshot {
+    fn average(&self, value_of: impl Fn(&FrameStats) -> f32) -> f32 {
+        if self.history.is_empty() {
+            return 0.0;
+        }
+
+        self.history.iter().map(value_of).sum::<f32>() / self.history.len() as f32
+    }
+
+    fn average_gpu_ms(&self) -> Option<f32> {
+        let timed: Vec<f32> = self
+            .history
+            .iter()
+            .filter_map(|frame| frame.gpu_ms)
+            .collect();
+
+        (!timed.is_empty()).then(|| timed.iter().sum::<f32>() / timed.len() as f32)
+    }
+
+    fn latest(&self) -> FrameStats {
+        self.history.last().copied().unwrap_or_default()
+    }
+}
+
+fn take_snapshot(state_helper: &StateHelper) -> PerformanceSnapshot {
+    let Some(editor_render) = state_helper.editor_render.as_ref() else {
+        return PerformanceSnapshot::default();
+    };
+    let editor_render = editor_render.lock().unwrap();
+    let performance = &editor_render.performance;
+
+    PerformanceSnapshot {
+        history: performance.history.iter().copied().collect(),
+        has_gpu_timer: performance.has_gpu_timer(),
+    }
+}
+
+/// Refreshes the snapshot until the view is closed and its signal goes away
+fn poll_snapshot(state_helper: Arc<Mutex<StateHelper>>, snapshot: RwSignal<PerformanceSnapshot>) {
+    exec_after(REFRESH_INTERVAL, move |_| {
+        let latest = take_snapshot(&state_helper.lock().unwrap());
+
+        if snapshot.try_update(|snapshot| *snapshot = latest).is_some() {
+            poll_snapshot(state_helper, snapshot);
+        }
+    });
+}
+
+fn start_capture(state_helper: &StateHelper) {
+    if let Some(editor_render) = state_helper.editor_render.as_ref() {
+        editor_render.lock().unwrap().performance.capture = Some(Vec::new());
+    }
+}
+
+/// Stops the running capture and writes it into the project
+fn export_capture(state_helper: &StateHelper) -> Result<String, String> {
+    let frames = state_helper
+        .editor_render
+        .as_ref()
+        .and_then(|editor_render| editor_render.lock().unwrap().performance.capture.take())
+        .ok_or("No capture running")?;
+
+    let project_id = state_helper
+        .project_selected_signal
+        .ok_or("No project selected")?
+        .get()
+        .to_string();
+    let path = save_capture(&project_id, &frames)?;
+
+    Ok(format!(
+        "Saved {} frames to {}",
+        frames.len(),
+        path.display()
+    ))
+}
+
+/// Frame times as bars, oldest on the left
+struct FrameGraph {
+    id: ViewId,
+    frame_times: Vec<f32>,
+    style: Style,
+}
+
+impl FrameGraph {
+    fn new(frame_times: Vec<f32>) -> Self {
+        Self {
+            id: ViewId::new(),
+            frame_times,
+            style: Style::new().width(GRAPH_WIDTH).height(GRAPH_HEIGHT),
+        }
+    }
+}
+
+impl View for FrameGraph {
+    fn id(&self) -> ViewId {
+        self.id
+    }
+
+    fn paint(&mut self, cx: &mut PaintCx) {
+        let background = kurbo::Rect::new(0.0, 0.0, GRAPH_WIDTH, GRAPH_HEIGHT);
+        cx.fill(&background, &Color::rgb8(30, 30, 30), 0.0);
+
+        if self.frame_times.is_empty() {
+            return;
+        }
+
+        let bar_width = GRAPH_WIDTH / self.frame_times.len() as f64;
+
+        for (index, frame_ms) in self.frame_times.iter().enumerate() {
+            let height = (frame_ms / GRAPH_MAX_MS).min(1.0) as f64 * GRAPH_HEIGHT;
+            // slower than 60 and 30 fps stand out
+            let color = if *frame_ms > 33.3 {
+                Color::rgb8(220, 70, 70)
+            } else if *frame_ms > 16.7 {
+                Color::rgb8(230, 190, 60)
+            } else {
+                Color::rgb8(80, 190, 100)
+            };
+
+            let bar = kurbo::Rect::new(
+                index as f64 * bar_width,
+                GRAPH_HEIGHT - height,
+                (index + 1) as f64 * bar_width,
+                GRAPH_HEIGHT,
+            );
+            cx.fill(&bar, &color, 0.0);
+        }
+    }
+
+    fn view_style(&self) -> Option<Style> {
+        Some(self.style.clone())
+    }
+}
+
+fn stat_label(text: impl Fn() -> String + 'static) -> impl IntoView {
+    label(text).style(|s| s.font_size(10.0).margin_bottom(3.0))
+}
 
 pub fn performance_view(
+    state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
     viewport: Arc<Mutex<Viewport>>,
 ) -> impl View {
-    v_stack(((label(|| "Performance"),)))
-        .style(|s| card_styles(s))
-        .style(|s| s.width(300.0))
+    let snapshot = create_rw_signal(take_snapshot(&state_helper.lock().unwrap()));
+    let capture_status = create_rw_signal(String::new());
+
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+
+    poll_snapshot(state_helper, snapshot);
+
+    v_stack((
+        label(|| "Performance"),
+        dyn_container(
+            move || snapshot.get(),
+            |snapshot| {
+                FrameGraph::new(
+                    snapshot
+                        .history
+                        .iter()
+                        .map(|frame| frame.frame_ms)
+                        .collect(),
+                )
+            },
+        )
+        .style(|s| s.margin_top(10.0).margin_bottom(10.0)),
+        stat_label(move || {
+            let frame_ms = snapshot.with(|snapshot| snapshot.average(|frame| frame.frame_ms));
+            let fps = if frame_ms > 0.0 {
+                1000.0 / frame_ms
+            } else {
+                0.0
+            };
+
+            format!("Frame: {:.2} ms ({:.0} fps)", frame_ms, fps)
+        }),
+        stat_label(move || {
+            snapshot.with(|snapshot| match snapshot.average_gpu_ms() {
+                Some(gpu_ms) => format!("GPU (scene pass): {:.2} ms", gpu_ms),
+                None if snapshot.has_gpu_timer => "GPU (scene pass): waiting...".to_string(),
+                None => "GPU timing isn't available on this device".to_string(),
+            })
+        }),
+        label(|| "CPU").style(|s| s.margin_top(10.0).margin_bottom(5.0)),
+        stat_label(move || {
+            let rapier_ms = snapshot.with(|snapshot| snapshot.average(|frame| frame.rapier_ms));
+            format!("Physics (update_rapier): {:.2} ms", rapier_ms)
+        }),
+        stat_label(move || {
+            let animations_ms =
+                snapshot.with(|snapshot| snapshot.average(|frame| frame.animations_ms));
+            format!("Animations: {:.2} ms", animations_ms)
+        }),
+        stat_label(move || {
+            let terrain_ms = snapshot.with(|snapshot| snapshot.average(|frame| frame.terrain_ms));
+            format!("Terrain managers: {:.2} ms", terrain_ms)
+        }),
+        label(|| "Scene").style(|s| s.margin_top(10.0).margin_bottom(5.0)),
+        stat_label(move || {
+            let draws = snapshot.with(|snapshot| snapshot.latest().draws);
+            format!(
+                "Draw calls: {} ({} triangles)",
+                draws.draw_calls, draws.triangles
+            )
+        }),
+        stat_label(move || {
+            let culled = snapshot.with(|snapshot| snapshot.latest().draws.culled);
+            format!("Outside the view: {} cubes and models", culled)
+        }),
+        stat_label(move || {
+            let texture_bytes = snapshot.with(|snapshot| snapshot.latest().texture_bytes);
+            format!(
+                "Texture memory: {:.1} MB",
+                texture_bytes as f64 / (1024.0 * 1024.0)
+            )
+        }),
+        stat_label(|| "Terrain draws and engine textures aren't counted".to_string()),
+        h_stack((
+            simple_button("Start Capture".to_string(), move |_| {
+                start_capture(&state_2.lock().unwrap());
+                capture_status.set("Capturing...".to_string());
+            })
+            .style(|s| s.margin_right(5.0)),
+            simple_button("Stop and Export CSV".to_string(), move |_| {
+                let status = match export_capture(&state_3.lock().unwrap()) {
+                    Ok(status) => status,
+                    Err(e) => format!("Couldn't export capture: {}", e),
+                };
+
+                capture_status.set(status);
+            }),
+        ))
+        .style(|s| s.margin_top(10.0)),
+        label(move || capture_status.get()).style(|s| s.font_size(10.0).margin_top(5.0)),
+    ))
+    .style(|s| card_styles(s))
+    .style(|s| s.width(300.0))
 }
@@ -0,0 +1,194 @@
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::core::Viewport::Viewport;
+use midpoint_engine::floem::common::{simple_button, small_button};
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
+use midpoint_engine::floem::views::{
+    dyn_container, dyn_stack, empty, h_stack, label, scroll, text_input, v_stack,
+};
+use midpoint_engine::floem::IntoView;
+
+use midpoint_engine::floem::views::Decorators;
+use midpoint_engine::floem::{GpuHelper, View};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::prefabs::{
+    apply_instance_to_prefab, component_prefab_instance, create_prefab, delete_prefab,
+    place_prefab, project_prefabs, revert_prefab_instance, Prefab, PrefabInstance,
+};
+
+/// Instance the active component belongs to
+fn active_instance(state_helper: &StateHelper) -> Result<PrefabInstance, String> {
+    let active_id = state_helper
+        .selection
+        .last()
+        .ok_or("Select a component placed from a prefab")?;
+
+    component_prefab_instance(state_helper, active_id)
+        .ok_or("The active component isn't part of a prefab".to_string())
+}
+
+fn report(prefab_error: RwSignal<Option<String>>, result: Result<(), String>) {
+    match result {
+        Ok(()) => prefab_error.set(None),
+        Err(e) => {
+            println!("Prefab failed: {}", e);
+            prefab_error.set(Some(e));
+        }
+    }
+}
+
+pub fn prefab_item(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    prefab: Prefab,
+    prefabs: RwSignal<Vec<Prefab>>,
+    prefab_error: RwSignal<Option<String>>,
+) -> impl IntoView {
+    let place_active = create_rw_signal(false);
+    let delete_active = create_rw_signal(false);
+
+    let state_2 = Arc::clone(&state_helper);
+    let prefab_id = prefab.id.clone();
+    let prefab_id_2 = prefab.id.clone();
+    let name = prefab.name.clone();
+    let count = prefab.components.len();
+
+    v_stack((
+        label(move || format!("{} ({} components)", name, count)),
+        h_stack((
+            small_button(
+                "Place",
+                "plus",
+                move |_| {
+                    let mut state_helper = state_helper.lock().unwrap();
+                    let Some(gpu_resources) = gpu_helper.lock().unwrap().gpu_resources.clone()
+                    else {
+                        return;
+                    };
+
+                    let result =
+                        place_prefab(&mut state_helper, gpu_resources, &prefab_id, [0.0; 3]);
+                    report(prefab_error, result);
+                },
+                place_active,
+            ),
+            small_button(
+                "Delete",
+                "trash",
+                move |_| {
+                    let state_helper = state_2.lock().unwrap();
+                    delete_prefab(&state_helper, &prefab_id_2);
+                    prefabs.set(project_prefabs(&state_helper));
+                },
+                delete_active,
+            ),
+        )),
+    ))
+    .style(|s| s.margin_bottom(5.0))
+}
+
+pub fn prefab_browser(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    viewport: Arc<Mutex<Viewport>>,
+) -> impl View {
+    let prefabs: RwSignal<Vec<Prefab>> = create_rw_signal(Vec::new());
+    let prefab_name = create_rw_signal(String::new());
+    let save_active = create_rw_signal(false);
+    let prefab_error: RwSignal<Option<String>> = create_rw_signal(None);
+
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+    let state_5 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+    let gpu_3 = Arc::clone(&gpu_helper);
+
+    create_effect(move |_| {
+        let state_helper = state_helper.lock().unwrap();
+
+        prefabs.set(project_prefabs(&state_helper));
+    });
+
+    v_stack((
+        label(|| "Save the selection as a prefab").style(|s| s.font_size(10.0)),
+        h_stack((
+            text_input(prefab_name)
+                .placeholder("Prefab name")
+                .style(|s| s.width(150.0).margin_right(5.0)),
+            small_button(
+                "Save",
+                "plus",
+                move |_| {
+                    let state_helper = state_2.lock().unwrap();
+                    let result = create_prefab(&state_helper, &prefab_name.get_untracked());
+
+                    if result.is_ok() {
+                        prefab_name.set(String::new());
+                    }
+                    report(prefab_error, result.map(|_| ()));
+                    prefabs.set(project_prefabs(&state_helper));
+                },
+                save_active,
+            ),
+        ))
+        .style(|s| s.margin_bottom(8.0).items_center()),
+        label(|| "Active component's instance").style(|s| s.font_size(10.0)),
+        h_stack((
+            simple_button("Apply to Prefab".to_string(), move |_| {
+                let state_helper = state_3.lock().unwrap();
+                let Some(gpu_resources) = gpu_2.lock().unwrap().gpu_resources.clone() else {
+                    return;
+                };
+
+                let result = active_instance(&state_helper).and_then(|instance| {
+                    apply_instance_to_prefab(&state_helper, &gpu_resources, &instance.id)
+                });
+                report(prefab_error, result);
+                prefabs.set(project_prefabs(&state_helper));
+            }),
+            simple_button("Revert Overrides".to_string(), move |_| {
+                let state_helper = state_4.lock().unwrap();
+                let Some(gpu_resources) = gpu_3.lock().unwrap().gpu_resources.clone() else {
+                    return;
+                };
+
+                let result = active_instance(&state_helper).and_then(|instance| {
+                    revert_prefab_instance(&state_helper, &gpu_resources, &instance.id)
+                });
+                report(prefab_error, result);
+            }),
+        ))
+        .style(|s| s.margin_bottom(8.0)),
+        dyn_container(
+            move || prefab_error.get(),
+            move |prefab_error_real| {
+                if let Some(message) = prefab_error_real {
+                    label(move || message.clone()).into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        ),
+        label(|| "Prefabs").style(|s| s.margin_top(10.0)),
+        scroll(
+            dyn_stack(
+                move || prefabs.get(),
+                move |prefab| (prefab.id.clone(), prefab.components.len()),
+                move |prefab| {
+                    prefab_item(
+                        state_5.clone(),
+                        gpu_helper.clone(),
+                        prefab,
+                        prefabs,
+                        prefab_error,
+                    )
+                },
+            )
+            .style(|s| s.flex_col()),
+        ),
+    ))
+    .style(|s| s.width(260.0))
+}
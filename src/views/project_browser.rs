@@ -32,19 +32,47 @@ use midpoint_engine::floem::IntoView;
 use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 
 use crate::editor_state::StateHelper;
+use crate::helpers::editor_data::{load_editor_data, EditorData};
+use crate::helpers::environment::refresh_level_environment;
+use crate::helpers::hierarchy::refresh_hidden_components;
+use crate::helpers::levels::restore_active_level;
+use crate::helpers::lights::refresh_level_lights;
+use crate::helpers::materials::refresh_level_materials;
+use crate::helpers::measurements::refresh_level_annotations;
+use crate::helpers::offscreen::project_thumbnail_path;
 use crate::helpers::projects::{get_projects, ProjectInfo};
+use crate::helpers::water::refresh_level_water;
 use crate::helpers::websocket::WebSocketManager;
 
+use super::shared::dynamic_img;
+
 pub fn project_item(
     project_info: ProjectInfo,
     sortable_items: RwSignal<Vec<ProjectInfo>>,
     project_label: String,
     icon_name: &'static str,
 ) -> impl IntoView {
+    // projects show their last screenshot once they have one
+    let has_thumbnail = project_thumbnail_path(&project_info.name)
+        .map(|path| path.exists())
+        .unwrap_or(false);
+
     h_stack((
-        svg(create_icon(icon_name))
-            .style(|s| s.width(24).height(24).color(Color::BLACK))
-            .style(|s| s.margin_right(7.0)),
+        if has_thumbnail {
+            container(dynamic_img(
+                format!("midpoint/projects/{}", project_info.name),
+                "thumbnail.png".to_string(),
+                48.0,
+                27.0,
+            ))
+            .style(|s| s.margin_right(7.0))
+            .into_any()
+        } else {
+            svg(create_icon(icon_name))
+                .style(|s| s.width(24).height(24).color(Color::BLACK))
+                .style(|s| s.margin_right(7.0))
+                .into_any()
+        },
         // .on_event_stop(
         //     floem::event::EventListener::PointerDown,
         //     |_| { /* Disable dragging for this view */ },
@@ -86,6 +114,7 @@ pub fn project_browser(
 
     let project_list = create_rw_signal(projects);
     let loading_project = create_rw_signal(false);
+    let open_error: RwSignal<Option<String>> = create_rw_signal(None);
 
     v_stack((
         dyn_container(
@@ -123,6 +152,11 @@ pub fn project_browser(
 
             let saved_state = Arc::new(Mutex::new(new_state));
             state_helper.saved_state = Some(saved_state.clone());
+            state_helper.editor_data = Some(Arc::new(Mutex::new(EditorData::default())));
+            state_helper.active_level_id = None;
+            // undo can't reach into another project
+            state_helper.history.lock().unwrap().clear();
+            refresh_hidden_components(&state_helper);
 
             let project_selected = state_helper
                 .project_selected_signal
@@ -154,6 +188,18 @@ pub fn project_browser(
 
             println!("Project selected {:?}", project_id);
         }),
+        dyn_container(
+            move || open_error.get(),
+            move |open_error_real| {
+                if let Some(message) = open_error_real {
+                    label(move || message.clone())
+                        .style(|s| s.width(260.0).margin_bottom(4.0).color(Color::RED))
+                        .into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        ),
         (label(|| "Select a Project").style(|s| s.margin_bottom(4.0))),
         scroll(
             dyn_stack(
@@ -176,6 +222,20 @@ pub fn project_browser(
                                 return EventPropagation::Continue;
                             }
 
+                            // saving over an editor.json that can't be read would lose every
+                            // level's editor data, so the project stays closed instead
+                            let editor_data = match load_editor_data(&project.name) {
+                                Ok(editor_data) => editor_data,
+                                Err(e) => {
+                                    open_error.set(Some(format!(
+                                        "{}. Fix or move the file to open the project.",
+                                        e
+                                    )));
+                                    return EventPropagation::Stop;
+                                }
+                            };
+                            open_error.set(None);
+
                             loading_project.set(true);
 
                             // join the WebSocket group for this project
@@ -195,6 +255,10 @@ pub fn project_browser(
                                 .expect("Couldn't get project saved state");
                             let saved_state = Arc::new(Mutex::new(saved_state));
                             state_helper.saved_state = Some(saved_state.clone());
+                            state_helper.active_level_id = editor_data.active_level_id.clone();
+                            state_helper.editor_data = Some(Arc::new(Mutex::new(editor_data)));
+                            state_helper.history.lock().unwrap().clear();
+                            refresh_hidden_components(&state_helper);
 
                             // update the UI signal
                             let project_selected = state_helper
@@ -228,6 +292,25 @@ pub fn project_browser(
                                     .expect("Couldn't get RendererState"),
                             );
 
+                            // water lives in the editor data rather than the engine's saved state
+                            if let Some(gpu_resources) = gpu_2.lock().unwrap().gpu_resources.clone()
+                            {
+                                refresh_level_water(&state_helper, &gpu_resources.device);
+                                refresh_level_lights(&state_helper);
+                                refresh_level_annotations(&state_helper);
+                                refresh_level_materials(
+                                    &state_helper,
+                                    &gpu_resources.device,
+                                    &gpu_resources.queue,
+                                );
+                                refresh_level_environment(
+                                    &state_helper,
+                                    &gpu_resources.device,
+                                    &gpu_resources.queue,
+                                );
+                                restore_active_level(&mut state_helper, &gpu_resources);
+                            }
+
                             println!("Project selected {:?}", project.name.clone());
 
                             EventPropagation::Stop
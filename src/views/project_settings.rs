@@ -1,18 +1,300 @@
-use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::{Arc, Mutex};
 
-use midpoint_engine::floem::common::card_styles;
-use midpoint_engine::floem::views::{container, dyn_container, empty, label, v_stack};
 use midpoint_engine::core::Viewport::Viewport;
-use wgpu::util::DeviceExt;
+use midpoint_engine::floem::common::{card_styles, simple_button};
+use midpoint_engine::floem::reactive::{create_rw_signal, SignalGet, SignalUpdate};
+use midpoint_engine::floem::views::{dyn_container, empty, h_stack, label, v_stack, Decorators};
+use midpoint_engine::floem::{GpuHelper, IntoView, View};
 
-use midpoint_engine::floem::views::Decorators;
-use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
+use crate::editor_state::StateHelper;
+use crate::helpers::environment::{
+    level_environment, refresh_level_environment, update_level_environment, EnvironmentSettings,
+    SkyKind, SKY_FACE_LABELS,
+};
+use crate::helpers::utilities::parse_string_to_float;
+
+use super::inputs::{create_dropdown, styled_input, DropdownOption};
+
+const SETTINGS_WIDTH: f64 = 280.0;
+
+/// Saves a change to the active level's environment and shows it in the viewport right away
+fn store_environment(
+    state_helper: &StateHelper,
+    gpu_helper: &Arc<Mutex<GpuHelper>>,
+    edit: impl FnOnce(&mut EnvironmentSettings),
+) {
+    update_level_environment(state_helper, edit);
+
+    if let Some(gpu_resources) = gpu_helper.lock().unwrap().gpu_resources.clone() {
+        refresh_level_environment(state_helper, &gpu_resources.device, &gpu_resources.queue);
+    }
+}
+
+/// An input for one number of the environment, skipped while it doesn't parse
+fn environment_input(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    title: String,
+    initial_value: f32,
+    width: f64,
+    edit: impl Fn(&mut EnvironmentSettings, f32) + 'static,
+) -> impl IntoView {
+    let name = format!("environment_{}", title.to_lowercase().replace(' ', "_"));
+
+    styled_input(
+        format!("{}:", title),
+        &initial_value.to_string(),
+        &title,
+        Box::new(move |state_helper_passed, value| {
+            let Some(value) = parse_string_to_float(&value) else {
+                return;
+            };
+
+            store_environment(&state_helper_passed, &gpu_helper, |environment| {
+                edit(environment, value)
+            });
+        }),
+        state_helper,
+        name,
+    )
+    .style(move |s| s.width(width).margin_right(5.0))
+}
+
+/// Red, green and blue of one of the environment's colors
+fn color_inputs(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    title: &'static str,
+    color: [f32; 3],
+    color_of: fn(&mut EnvironmentSettings) -> &mut [f32; 3],
+) -> impl IntoView {
+    let input = |channel: usize, channel_label: &str| {
+        environment_input(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            format!("{} {}", title, channel_label),
+            color[channel],
+            SETTINGS_WIDTH / 3.0,
+            move |environment, value| color_of(environment)[channel] = value.clamp(0.0, 1.0),
+        )
+    };
+
+    h_stack((input(0, "Red"), input(1, "Green"), input(2, "Blue")))
+}
+
+/// Picks a texture from the project's library for the sky
+fn sky_texture_dropdown(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    title: &'static str,
+    texture_id: Option<String>,
+    options: Vec<DropdownOption>,
+    texture_of: impl Fn(&mut EnvironmentSettings) -> &mut Option<String> + Clone + 'static,
+) -> impl IntoView {
+    // a texture removed from the library shows as nothing picked
+    let initial = texture_id
+        .filter(|id| options.iter().any(|option| option.id == *id))
+        .unwrap_or_default();
+
+    v_stack((
+        label(move || title).style(|s| s.font_size(10.0).margin_bottom(1.0)),
+        create_dropdown(initial, options, move |selected_id| {
+            let state_helper = state_helper.lock().unwrap();
+
+            store_environment(&state_helper, &gpu_helper, |environment| {
+                *texture_of(environment) = (!selected_id.is_empty()).then_some(selected_id.clone())
+            });
+        }),
+    ))
+    .style(|s| s.margin_bottom(10.0))
+}
+
+/// The sky, background, ambient color and fog of the level open in the editor
+fn level_environment_settings(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+) -> impl IntoView {
+    let (environment, options) = {
+        let state_helper = state_helper.lock().unwrap();
+
+        let options: Vec<DropdownOption> = state_helper
+            .saved_state
+            .as_ref()
+            .and_then(|saved_state| saved_state.lock().unwrap().textures.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|file| DropdownOption {
+                id: file.id,
+                label: file.fileName,
+            })
+            .collect();
+
+        (level_environment(&state_helper), options)
+    };
+
+    let sky_kind = create_rw_signal(environment.sky);
+    let fog_enabled = create_rw_signal(environment.fog_enabled);
+
+    let sky_button = |kind: SkyKind| {
+        let state_helper = state_helper.clone();
+        let gpu_helper = gpu_helper.clone();
+
+        simple_button(kind.label().to_string(), move |_| {
+            let state_helper = state_helper.lock().unwrap();
+
+            store_environment(&state_helper, &gpu_helper, |environment| {
+                environment.sky = kind
+            });
+
+            sky_kind.set(kind);
+        })
+        .style(|s| s.margin_right(5.0))
+    };
+
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+    let gpu_3 = Arc::clone(&gpu_helper);
+
+    let input = |title: &str, initial_value: f32, edit: fn(&mut EnvironmentSettings, f32)| {
+        environment_input(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            title.to_string(),
+            initial_value,
+            SETTINGS_WIDTH / 2.0,
+            edit,
+        )
+    };
+
+    v_stack((
+        label(|| "Level Environment").style(|s| s.margin_top(10.0).margin_bottom(5.0)),
+        label(move || format!("Sky: {}", sky_kind.get().label()))
+            .style(|s| s.font_size(10.0).margin_bottom(5.0)),
+        h_stack((
+            sky_button(SkyKind::None),
+            sky_button(SkyKind::Cubemap),
+            sky_button(SkyKind::Equirectangular),
+        ))
+        .style(|s| s.margin_bottom(10.0)),
+        // the textures the picked kind of sky is made of
+        dyn_container(
+            move || sky_kind.get(),
+            move |kind| {
+                // picks made since the panel opened show too
+                let current = level_environment(&state_2.lock().unwrap());
+                let face_dropdown = |face: usize| {
+                    sky_texture_dropdown(
+                        state_2.clone(),
+                        gpu_2.clone(),
+                        SKY_FACE_LABELS[face],
+                        current.sky_faces[face].clone(),
+                        options.clone(),
+                        move |environment| &mut environment.sky_faces[face],
+                    )
+                };
+
+                match kind {
+                    SkyKind::None => empty().into_any(),
+                    SkyKind::Cubemap => v_stack((
+                        face_dropdown(0),
+                        face_dropdown(1),
+                        face_dropdown(2),
+                        face_dropdown(3),
+                        face_dropdown(4),
+                        face_dropdown(5),
+                    ))
+                    .into_any(),
+                    SkyKind::Equirectangular => sky_texture_dropdown(
+                        state_2.clone(),
+                        gpu_2.clone(),
+                        "Panorama (HDR or image)",
+                        current.sky_panorama.clone(),
+                        options.clone(),
+                        |environment| &mut environment.sky_panorama,
+                    )
+                    .into_any(),
+                }
+            },
+        ),
+        input("Sky Exposure", environment.sky_exposure, |e, v| {
+            e.sky_exposure = v.max(0.0)
+        }),
+        label(|| "Clear Color").style(|s| s.font_size(10.0).margin_top(5.0)),
+        color_inputs(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            "Clear",
+            environment.clear_color,
+            |e| &mut e.clear_color,
+        ),
+        label(|| "Ambient Color").style(|s| s.font_size(10.0).margin_top(5.0)),
+        color_inputs(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            "Ambient",
+            environment.ambient_color,
+            |e| &mut e.ambient_color,
+        ),
+        label(move || {
+            if fog_enabled.get() {
+                "Distance Fog: On"
+            } else {
+                "Distance Fog: Off"
+            }
+        })
+        .style(|s| s.font_size(10.0).margin_top(5.0)),
+        simple_button("Toggle Fog".to_string(), move |_| {
+            let state_helper = state_3.lock().unwrap();
+            let value = !fog_enabled.get();
+
+            store_environment(&state_helper, &gpu_3, |environment| {
+                environment.fog_enabled = value
+            });
+
+            fog_enabled.set(value);
+        }),
+        color_inputs(
+            state_helper.clone(),
+            gpu_helper.clone(),
+            "Fog",
+            environment.fog_color,
+            |e| &mut e.fog_color,
+        ),
+        h_stack((
+            input("Fog Start", environment.fog_start, |e, v| {
+                e.fog_start = v.max(0.0)
+            }),
+            input("Fog End", environment.fog_end, |e, v| {
+                e.fog_end = v.max(0.0)
+            }),
+        )),
+    ))
+    .style(|s| s.width(SETTINGS_WIDTH))
+}
 
 pub fn project_settings(
+    state_helper: Arc<Mutex<StateHelper>>,
     gpu_helper: Arc<Mutex<GpuHelper>>,
     viewport: Arc<Mutex<Viewport>>,
 ) -> impl View {
-    v_stack(((label(|| "Project Settings"),)))
-        .style(|s| card_styles(s))
-        .style(|s| s.width(300.0))
+    let active_level_signal = state_helper.lock().unwrap().active_level_signal;
+
+    v_stack((
+        label(|| "Project Settings"),
+        // rebuilt when another level opens so the inputs show its values
+        dyn_container(
+            move || active_level_signal.map(|signal| signal.get()),
+            move |level_id| match level_id.filter(|id| !id.is_empty()) {
+                Some(_) => {
+                    level_environment_settings(state_helper.clone(), gpu_helper.clone()).into_any()
+                }
+                None => label(|| "Open a level to edit its environment")
+                    .style(|s| s.font_size(10.0).margin_top(10.0))
+                    .into_any(),
+            },
+        ),
+    ))
+    .style(|s| card_styles(s))
+    .style(|s| s.width(300.0))
 }
@@ -1,6 +1,7 @@
 use midpoint_engine::core::RendererState::ObjectConfig;
 use midpoint_engine::core::Viewport::Viewport;
 use midpoint_engine::floem::common::card_styles;
+use midpoint_engine::floem::common::simple_button;
 use midpoint_engine::floem::common::small_button;
 use midpoint_engine::floem::views::dropdown::dropdown;
 use midpoint_engine::floem::views::text;
@@ -29,154 +30,336 @@ use midpoint_engine::floem::IntoView;
 
 use crate::editor_state::EditorState;
 use crate::editor_state::StateHelper;
+use crate::helpers::component_edits::{
+    copy_selection, delete_selection, duplicate_selection, paste_components, record_transform_edit,
+};
+use crate::helpers::gizmos::apply_component_transform;
+use crate::helpers::hierarchy::{follower_components, move_followers};
 use crate::helpers::landscapes::save_landscape_texture;
+use crate::helpers::levels::{active_level, active_level_mut};
+use crate::helpers::lights::is_light_component;
+use crate::helpers::play_mode::{is_simulated, set_simulated};
+use crate::helpers::selection::{place_selection_gizmos, selected_components, set_selection};
+use crate::helpers::tiles::TileStreamSettings;
 use crate::helpers::utilities::parse_string_to_float;
 
 use super::inputs::create_dropdown;
 use super::inputs::styled_input;
 use super::inputs::DropdownOption;
+use super::light_properties::light_properties;
+use super::material_properties::material_properties;
+
+/// The transform fields the properties panel can edit across the selection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransformField {
+    Position,
+    /// Shown and typed in degrees, stored in radians
+    Rotation,
+    Scale,
+}
 
-pub fn update_position(
-    // state_helper: Arc<Mutex<StateHelper>>,
-    mut state_helper: MutexGuard<StateHelper>, // may be truly unecessary as guard here
-    selected_object_id_signal: RwSignal<Uuid>,
-    selected_object_data: RwSignal<ComponentData>,
+impl TransformField {
+    fn get(&self, component: &ComponentData) -> [f32; 3] {
+        let properties = &component.generic_properties;
+
+        match self {
+            TransformField::Position => properties.position,
+            TransformField::Rotation => properties.rotation.map(|r| r.to_degrees()),
+            TransformField::Scale => properties.scale,
+        }
+    }
+}
+
+/// Sets one axis of a transform field on every selected component
+pub fn update_transform(
+    mut state_helper: MutexGuard<StateHelper>,
+    field: TransformField,
+    axis: usize,
     value: String,
-    axis: &str,
 ) {
-    println!("updating position");
-    // let mut state_helper = state_helper.lock().unwrap();
-    let mut renderer_state = state_helper
-        .renderer_state
-        .as_mut()
-        .expect("Couldn't get RendererState");
-    let mut renderer_state = renderer_state.lock().unwrap();
-    // let mut component_data = selected_component.clone();
-    let mut component_data = selected_object_data.get();
-    let selected_component = component_data.clone();
-
-    let current_position = renderer_state.translation_gizmo.transform.position;
-
-    // for X only
-    let mut new_position = current_position.clone();
-    println!("parsing value {:?}", value);
-    // new_position.x = value.parse::<f32>().expect("Couldn't parse x as float");
-    let parsed_value = parse_string_to_float(&value);
-
-    if parsed_value.is_none() {
+    let Some(parsed_value) = parse_string_to_float(&value) else {
         println!("invalid input");
         return;
-    }
-
-    let parsed_value = parsed_value.expect("Couldn't get parsed value");
+    };
 
-    match axis {
-        "x" => new_position.x = parsed_value,
-        "y" => new_position.y = parsed_value,
-        "z" => new_position.z = parsed_value,
-        _ => println!("not real axis"),
+    let components = selected_components(&state_helper);
+    if components.is_empty() {
+        return;
     }
 
-    let new_position = [new_position.x, new_position.y, new_position.z];
-
-    // update gizmo transform position
-    renderer_state
-        .translation_gizmo
-        .transform
-        .update_position(new_position);
-
-    // update_arrow_collider_position
-    renderer_state.update_arrow_collider_position(new_position);
-
-    // update renderer_state.selected_object_data with new ComponentData
-    let mut new_data = component_data.clone();
-    new_data.generic_properties.position = new_position;
-
-    renderer_state.object_selected_data = Some(new_data.clone());
-
-    selected_object_data.set(new_data.clone());
-
-    // check object_type and set new transform in rendererstate
-    match selected_component
-        .kind
-        .as_ref()
-        .expect("Couldn't get component kind")
-    {
-        ComponentKind::Model => {
-            let mut matching_model = renderer_state
-                .models
-                .iter_mut()
-                .find(|m| m.id == selected_component.id)
-                .expect("Couldn't find matching model");
-
-            // update visually
-            matching_model.meshes.iter_mut().for_each(move |mesh| {
-                mesh.transform.update_position(new_position);
-            });
-
-            // update colliders
-            renderer_state.update_model_collider_position(new_position);
-        }
-        ComponentKind::Landscape => {
-            let mut matching_terrain_manager = renderer_state
-                .terrain_managers
-                .iter_mut()
-                .find(|m| m.id == selected_component.id)
-                .expect("Couldn't find matching landscape");
-
-            // visual
-            matching_terrain_manager
-                .transform
-                .update_position(new_position);
-
-            // physics
-            renderer_state.update_landscape_collider_position(new_position);
+    let moved: Vec<ComponentData> = components
+        .iter()
+        .map(|component| {
+            let mut moved = component.clone();
+            let properties = &mut moved.generic_properties;
+
+            match field {
+                TransformField::Position => properties.position[axis] = parsed_value,
+                TransformField::Rotation => properties.rotation[axis] = parsed_value.to_radians(),
+                TransformField::Scale => properties.scale[axis] = parsed_value.max(0.01),
+            }
+
+            moved
+        })
+        .collect();
+
+    let followers = follower_components(&state_helper, &components);
+
+    let followers_moved = {
+        let mut renderer_state = state_helper
+            .renderer_state
+            .as_ref()
+            .expect("Couldn't get RendererState")
+            .lock()
+            .unwrap();
+
+        // update meshes, colliders and the gizmo
+        for component in &moved {
+            let properties = &component.generic_properties;
+            apply_component_transform(
+                &mut renderer_state,
+                component,
+                [properties.position, properties.rotation, properties.scale],
+            );
         }
-    }
 
-    drop(renderer_state);
+        place_selection_gizmos(
+            &mut renderer_state,
+            &moved,
+            state_helper.editor_settings.transform,
+        );
+
+        move_followers(&mut renderer_state, &components, &moved, &followers)
+    };
+
+    // save in saved_state for undo, the active component last
+    let started: Vec<ComponentData> = followers
+        .into_iter()
+        .map(|(_, follower)| follower)
+        .chain(components)
+        .collect();
+    record_transform_edit(
+        &mut state_helper,
+        started,
+        [followers_moved, moved].concat(),
+    );
+}
+
+/// The axis value shared by every component, None when they differ
+fn shared_transform_value(
+    components: &[ComponentData],
+    field: TransformField,
+    axis: usize,
+) -> Option<f32> {
+    let first = field.get(components.first()?)[axis];
+
+    components
+        .iter()
+        .all(|component| (field.get(component)[axis] - first).abs() < 1e-4)
+        .then_some(first)
+}
+
+/// X, Y and Z inputs for a transform field, blank with a "Mixed" placeholder where the
+/// selection disagrees
+fn transform_inputs(
+    state_helper: Arc<Mutex<StateHelper>>,
+    title: &'static str,
+    field: TransformField,
+    input_width: f64,
+) -> impl IntoView {
+    let components = selected_components(&state_helper.lock().unwrap());
+
+    let input = |axis: usize, axis_label: &'static str| {
+        let shared = shared_transform_value(&components, field, axis);
+        let initial_value = shared.map(|value| value.to_string()).unwrap_or_default();
+        let placeholder = match shared {
+            Some(_) => format!("{} {}", axis_label, title),
+            None => "Mixed".to_string(),
+        };
+
+        styled_input(
+            format!("{}:", axis_label),
+            &initial_value,
+            &placeholder,
+            Box::new(move |state_helper_passed, value| {
+                update_transform(state_helper_passed, field, axis, value)
+            }),
+            state_helper.clone(),
+            format!("{}_{}", title.to_lowercase(), axis_label.to_lowercase()),
+        )
+        .style(move |s| s.width(input_width).margin_right(5.0))
+    };
+
+    v_stack((
+        label(move || title).style(|s| s.font_size(12.0).margin_bottom(2.0)),
+        h_stack((input(0, "X"), input(1, "Y"), input(2, "Z"))),
+    ))
+    .style(|s| s.margin_bottom(5.0))
+}
 
-    // save in saved_state
-    let mut saved_state = state_helper
-        .saved_state
-        .as_mut()
-        .expect("Couldn't get saved state")
+/// Finds the tile grid a landscape component belongs to, if any, and applies an edit to its tile
+/// Duplicate, delete and clipboard buttons for the selection
+fn component_actions(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+) -> impl IntoView {
+    let gpu_resources = gpu_helper
         .lock()
-        .unwrap();
+        .unwrap()
+        .gpu_resources
+        .clone()
+        .expect("Couldn't get gpu resources");
 
-    // Update the component within the saved state
-    let level = saved_state
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_resources);
+    let gpu_3 = Arc::clone(&gpu_resources);
+
+    h_stack((
+        simple_button("Duplicate".to_string(), move |_| {
+            let mut state_helper = state_helper.lock().unwrap();
+            duplicate_selection(&mut state_helper, gpu_resources.clone());
+        }),
+        simple_button("Delete".to_string(), move |_| {
+            let mut state_helper = state_2.lock().unwrap();
+            delete_selection(&mut state_helper, gpu_2.clone());
+        }),
+        simple_button("Copy".to_string(), move |_| {
+            let state_helper = state_3.lock().unwrap();
+            if let Err(e) = copy_selection(&state_helper) {
+                println!("{}", e);
+            }
+        }),
+        simple_button("Paste".to_string(), move |_| {
+            let mut state_helper = state_4.lock().unwrap();
+            if let Err(e) = paste_components(&mut state_helper, gpu_3.clone()) {
+                println!("{}", e);
+            }
+        }),
+    ))
+    .style(|s| s.margin_bottom(12.0).gap(4.0))
+}
+
+fn update_tile_streaming(
+    state_helper: &StateHelper,
+    component_id: &str,
+    edit: impl FnOnce(&mut TileStreamSettings),
+) {
+    let Some(editor_data) = state_helper.editor_data.as_ref() else {
+        return;
+    };
+
+    let mut editor_data = editor_data.lock().unwrap();
+    let tile = editor_data
         .levels
-        .as_mut()
-        .expect("Couldn't get level")
-        .get_mut(0)
-        .expect("Couldn't get first level");
-
-    if let Some(components) = level.components.as_mut() {
-        if let Some(component) = components
-            .iter_mut()
-            .find(|c| c.id == selected_object_id_signal.get().to_string())
-        {
-            *component = new_data.clone(); // This correctly updates the component in place
-        }
+        .iter_mut()
+        .flat_map(|level| level.tile_grids.iter_mut())
+        .find_map(|grid| grid.tile_for_component_mut(component_id));
+
+    if let Some(tile) = tile {
+        edit(&mut tile.streaming);
+        drop(editor_data);
+        state_helper.save_current_editor_data();
     }
+}
 
-    drop(saved_state);
+pub fn tile_streaming_properties(
+    state_helper: Arc<Mutex<StateHelper>>,
+    selected_object_id_signal: RwSignal<Uuid>,
+) -> impl IntoView {
+    let component_id = selected_object_id_signal.get().to_string();
+
+    let streaming = {
+        let state_helper = state_helper.lock().unwrap();
+        state_helper.editor_data.as_ref().and_then(|editor_data| {
+            editor_data
+                .lock()
+                .unwrap()
+                .levels
+                .iter()
+                .flat_map(|level| level.tile_grids.iter())
+                .find_map(|grid| grid.tile_for_component(&component_id))
+                .map(|tile| tile.streaming.clone())
+        })
+    };
 
-    let project_id = state_helper
-        .project_selected_signal
-        .expect("Couldn't get project signal")
-        .get();
+    let Some(streaming) = streaming else {
+        return empty().into_any();
+    };
 
-    let saved_state = state_helper
-        .saved_state
-        .as_ref()
-        .expect("Couldn't get saved state")
-        .lock()
-        .unwrap();
+    let always_loaded = create_rw_signal(streaming.always_loaded);
+    let state_2 = Arc::clone(&state_helper);
 
-    // Save the updated state
-    state_helper.save_saved_state(project_id, saved_state);
+    v_stack((
+        label(|| "Tile Streaming").style(|s| s.margin_top(10.0)),
+        styled_input(
+            "Stream Distance Scale:".to_string(),
+            &streaming.distance_scale.to_string(),
+            "Distance Scale",
+            Box::new({
+                move |state_helper_passed, value| {
+                    let Some(distance_scale) = parse_string_to_float(&value) else {
+                        return;
+                    };
+
+                    update_tile_streaming(
+                        &state_helper_passed,
+                        &selected_object_id_signal.get().to_string(),
+                        |streaming| streaming.distance_scale = distance_scale.max(0.0),
+                    );
+                }
+            }),
+            state_helper,
+            "tile_distance_scale".to_string(),
+        ),
+        label(move || {
+            if always_loaded.get() {
+                "Always Loaded: On"
+            } else {
+                "Always Loaded: Off"
+            }
+        })
+        .style(|s| s.font_size(10.0).margin_top(5.0)),
+        simple_button("Toggle Always Loaded".to_string(), move |_| {
+            let state_helper = state_2.lock().unwrap();
+            let value = !always_loaded.get();
+
+            update_tile_streaming(
+                &state_helper,
+                &selected_object_id_signal.get().to_string(),
+                |streaming| streaming.always_loaded = value,
+            );
+
+            always_loaded.set(value);
+        }),
+    ))
+    .into_any()
+}
+
+/// Whether the model falls and collides while the level plays
+pub fn play_physics_properties(
+    state_helper: Arc<Mutex<StateHelper>>,
+    component_id: String,
+) -> impl IntoView {
+    let simulated = create_rw_signal(is_simulated(&state_helper.lock().unwrap(), &component_id));
+
+    v_stack((
+        label(|| "Play").style(|s| s.margin_top(10.0)),
+        label(move || {
+            if simulated.get() {
+                "Physics in Play: On"
+            } else {
+                "Physics in Play: Off"
+            }
+        })
+        .style(|s| s.font_size(10.0).margin_top(5.0)),
+        simple_button("Toggle Physics in Play".to_string(), move |_| {
+            let value = !simulated.get();
+            set_simulated(&state_helper.lock().unwrap(), &component_id, value);
+            simulated.set(value);
+        }),
+    ))
 }
 
 pub fn properties_view(
@@ -198,9 +381,15 @@ pub fn properties_view(
     let state_8 = Arc::clone(&state_helper);
     let state_9 = Arc::clone(&state_helper);
     let state_10 = Arc::clone(&state_helper);
+    let state_11 = Arc::clone(&state_helper);
+    let state_12 = Arc::clone(&state_helper);
+    let state_13 = Arc::clone(&state_helper);
+    let state_14 = Arc::clone(&state_helper);
+    let state_15 = Arc::clone(&state_helper);
 
     let gpu_2 = Arc::clone(&gpu_helper);
     let gpu_3 = Arc::clone(&gpu_helper);
+    let gpu_4 = Arc::clone(&gpu_helper);
 
     let aside_width = 260.0;
     let quarters = (aside_width / 4.0) + (5.0 * 4.0);
@@ -208,6 +397,7 @@ pub fn properties_view(
     let halfs = (aside_width / 2.0) + (5.0 * 2.0);
 
     let back_active = RwSignal::new(false);
+    let selection_signal = state_helper.lock().unwrap().selection_signal;
     let texture_options: RwSignal<Vec<DropdownOption>> = create_rw_signal(Vec::new());
     let initial_rockmap = create_rw_signal("".to_string());
     let initial_soil = create_rw_signal("".to_string());
@@ -234,9 +424,9 @@ pub fn properties_view(
         texture_options.set(dropdown_options);
     });
 
-    let landscape_property_list = if selected_object_data.get().kind.expect("Couldn't get kind")
-        == ComponentKind::Landscape
-    {
+    let selected_data = selected_object_data.get();
+
+    let kind_property_list = if selected_data.kind == Some(ComponentKind::Landscape) {
         v_stack((
             label(|| "Rockmap Texture"),
             // selected_object_data.get() or saved_data? saved_data requires lock and signals
@@ -255,16 +445,19 @@ pub fn properties_view(
                         .expect("Couldn't get RendererState")
                         .lock()
                         .unwrap();
-                    let levels = saved_state.levels.clone();
                     let component_id = selected_object_id_signal.get();
 
                     // add to saved_state
-                    save_landscape_texture(
-                        levels,
-                        component_id.to_string(),
-                        LandscapeTextureKinds::Rockmap,
-                        selected_id.clone(),
-                    );
+                    if let Some(level) =
+                        active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+                    {
+                        save_landscape_texture(
+                            level,
+                            component_id.to_string(),
+                            LandscapeTextureKinds::Rockmap,
+                            selected_id.clone(),
+                        );
+                    }
 
                     let available_textures = saved_state
                         .textures
@@ -272,16 +465,12 @@ pub fn properties_view(
                         .unwrap_or(Vec::new())
                         .to_owned();
 
-                    let components = saved_state
-                        .levels
-                        .as_ref()
-                        .expect("Couldn't get levels")
-                        .get(0)
-                        .as_ref()
-                        .expect("Couldn't get first level")
-                        .components
-                        .as_ref()
-                        .expect("Couldn't get components");
+                    let components =
+                        active_level(&saved_state, state_helper.active_level_id.as_deref())
+                            .expect("Couldn't get active level")
+                            .components
+                            .as_ref()
+                            .expect("Couldn't get components");
                     let landscape_component = components
                         .iter()
                         .find(|l| l.id == component_id.to_string())
@@ -289,26 +478,6 @@ pub fn properties_view(
                         .expect("Couldn't get landscape component")
                         .to_owned();
 
-                    // update landscape_component landscape_properties.rockmap_texture_id
-                    let mut mutable_binding = saved_state
-                        .levels
-                        .as_mut()
-                        .expect("Couldn't get levels")
-                        .get_mut(0);
-                    let mut landscape_properties = mutable_binding
-                        .as_mut()
-                        .expect("Couldn't get first level")
-                        .components
-                        .as_mut()
-                        .expect("Couldn't get components")
-                        .iter_mut()
-                        .find(|l| l.id == component_id.to_string())
-                        .expect("Couldn't get landscape component")
-                        .landscape_properties
-                        .as_mut()
-                        .expect("Couldn't get landscape properties");
-                    landscape_properties.rockmap_texture_id = Some(selected_id.clone());
-
                     let landscapes = saved_state
                         .landscapes
                         .as_ref()
@@ -395,15 +564,19 @@ pub fn properties_view(
                         .expect("Couldn't get RendererState")
                         .lock()
                         .unwrap();
-                    let levels = saved_state.levels.clone();
                     let component_id = selected_object_id_signal.get();
 
-                    save_landscape_texture(
-                        levels,
-                        component_id.to_string(),
-                        LandscapeTextureKinds::Rockmap,
-                        selected_id.clone(),
-                    );
+                    // add to saved_state
+                    if let Some(level) =
+                        active_level_mut(&mut saved_state, state_helper.active_level_id.as_deref())
+                    {
+                        save_landscape_texture(
+                            level,
+                            component_id.to_string(),
+                            LandscapeTextureKinds::Soil,
+                            selected_id.clone(),
+                        );
+                    }
 
                     let available_textures = saved_state
                         .textures
@@ -421,16 +594,12 @@ pub fn properties_view(
                     //     .find(|l| l.id == component_id.to_string())
                     //     .to_owned();
 
-                    let components = saved_state
-                        .levels
-                        .as_ref()
-                        .expect("Couldn't get levels")
-                        .get(0)
-                        .as_ref()
-                        .expect("Couldn't get first level")
-                        .components
-                        .as_ref()
-                        .expect("Couldn't get components");
+                    let components =
+                        active_level(&saved_state, state_helper.active_level_id.as_deref())
+                            .expect("Couldn't get active level")
+                            .components
+                            .as_ref()
+                            .expect("Couldn't get components");
                     let landscape_component = components
                         .iter()
                         .find(|l| l.id == component_id.to_string())
@@ -438,26 +607,6 @@ pub fn properties_view(
                         .expect("Couldn't get landscape component")
                         .to_owned();
 
-                    // update landscape_component landscape_properties.soil_texture_id (condense with above?)
-                    let mut mutable_binding = saved_state
-                        .levels
-                        .as_mut()
-                        .expect("Couldn't get levels")
-                        .get_mut(0);
-                    let mut landscape_properties = mutable_binding
-                        .as_mut()
-                        .expect("Couldn't get first level")
-                        .components
-                        .as_mut()
-                        .expect("Couldn't get components")
-                        .iter_mut()
-                        .find(|l| l.id == component_id.to_string())
-                        .expect("Couldn't get landscape component")
-                        .landscape_properties
-                        .as_mut()
-                        .expect("Couldn't get landscape properties");
-                    landscape_properties.soil_texture_id = Some(selected_id.clone());
-
                     let landscapes = saved_state
                         .landscapes
                         .as_ref()
@@ -528,9 +677,18 @@ pub fn properties_view(
                     }
                 },
             ),
+            tile_streaming_properties(state_11, selected_object_id_signal),
         ))
         .style(move |s| s.width(aside_width))
         .into_any()
+    } else if is_light_component(&selected_data) {
+        light_properties(state_13, selected_data.id.clone(), aside_width).into_any()
+    } else if selected_data.kind == Some(ComponentKind::Model) {
+        v_stack((
+            material_properties(state_14, gpu_4, selected_data.id.clone(), aside_width),
+            play_physics_properties(state_15, selected_data.id.clone()),
+        ))
+        .into_any()
     } else {
         empty().into_any()
     };
@@ -544,109 +702,38 @@ pub fn properties_view(
                     move |_| {
                         println!("Click back!");
                         // this action runs on_click_stop so should stop propagation
-                        object_selected_signal.update(|v| {
-                            *v = false;
-                        });
                         selected_object_id_signal.update(|v| {
                             *v = Uuid::nil();
                         });
                         // let mut editor_state = editor_state2.lock().unwrap();
                         // editor_state.selected_polygon_id = Uuid::nil();
                         // editor_state.polygon_selected = false;
+                        // clears the whole selection, which also closes this panel
                         let mut state_helper = state_helper.lock().unwrap();
-                        let mut renderer_state = state_helper
-                            .renderer_state
-                            .as_mut()
-                            .expect("Couldn't get RendererState")
-                            .lock()
-                            .unwrap();
-                        renderer_state.object_selected = None;
+                        set_selection(&mut state_helper, Vec::new());
                     }
                 },
                 back_active,
             )
             .style(|s| s.margin_right(7.0)),
             label(|| "Properties").style(|s| s.font_size(24.0).font_weight(Weight::THIN)),
+            label(move || {
+                let selected = selection_signal.map_or(0, |selection| selection.get().len());
+
+                if selected > 1 {
+                    format!("{} selected", selected)
+                } else {
+                    String::new()
+                }
+            })
+            .style(|s| s.margin_left(10.0).color(Color::GRAY)),
         ))
-        .style(|s| s.margin_bottom(12.0)),
-        h_stack((
-            styled_input(
-                "X:".to_string(),
-                &selected_object_data
-                    .read()
-                    .borrow()
-                    .generic_properties
-                    .position[0]
-                    .to_string(),
-                "X Position",
-                Box::new({
-                    move |mut state_helper_passed, value| {
-                        update_position(
-                            state_helper_passed,
-                            selected_object_id_signal,
-                            selected_object_data,
-                            value,
-                            "x",
-                        )
-                    }
-                }),
-                state_2,
-                "x".to_string(),
-            )
-            .style(move |s| s.width(thirds).margin_right(5.0)),
-            styled_input(
-                "Y:".to_string(),
-                &selected_object_data
-                    .read()
-                    .borrow()
-                    .generic_properties
-                    .position[1]
-                    .to_string(),
-                "Y Position",
-                Box::new({
-                    move |mut state_helper_passed, value| {
-                        // make DRY for Y
-                        update_position(
-                            state_helper_passed,
-                            selected_object_id_signal,
-                            selected_object_data,
-                            value,
-                            "y",
-                        )
-                    }
-                }),
-                state_3,
-                "y".to_string(),
-            )
-            .style(move |s| s.width(thirds).margin_right(5.0)),
-            styled_input(
-                "Z:".to_string(),
-                &selected_object_data
-                    .read()
-                    .borrow()
-                    .generic_properties
-                    .position[2]
-                    .to_string(),
-                "Z Position",
-                Box::new({
-                    move |mut state_helper_passed, value| {
-                        // make DRY for Z
-                        update_position(
-                            state_helper_passed,
-                            selected_object_id_signal,
-                            selected_object_data,
-                            value,
-                            "z",
-                        )
-                    }
-                }),
-                state_4,
-                "z".to_string(),
-            )
-            .style(move |s| s.width(thirds)),
-        ))
-        .style(move |s| s.width(aside_width)),
-        landscape_property_list,
+        .style(|s| s.margin_bottom(12.0).items_center()),
+        component_actions(state_12, gpu_helper),
+        transform_inputs(state_2, "Position", TransformField::Position, thirds),
+        transform_inputs(state_3, "Rotation", TransformField::Rotation, thirds),
+        transform_inputs(state_4, "Scale", TransformField::Scale, thirds),
+        kind_property_list,
     ))
     .style(|s| card_styles(s))
     .style(|s| {
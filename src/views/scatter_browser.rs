@@ -0,0 +1,398 @@
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::core::Viewport::Viewport;
+use midpoint_engine::floem::common::{simple_button, small_button};
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
+use midpoint_engine::floem::views::{
+    dyn_container, dyn_stack, empty, h_stack, label, scroll, text_input, v_stack,
+};
+use midpoint_engine::floem::IntoView;
+use midpoint_engine::helpers::saved_data::{ComponentKind, File};
+
+use midpoint_engine::floem::views::Decorators;
+use midpoint_engine::floem::{GpuHelper, View};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::levels::{active_level, active_level_id};
+use crate::helpers::scatter::{
+    apply_scatter_layer, remove_scatter_instances, ScatterLayer, ScatterMask, ScatterMaskMap,
+};
+use crate::helpers::utilities::parse_string_to_float;
+
+use super::inputs::{create_dropdown, DropdownOption};
+
+fn level_scatter_layers(state_helper: &StateHelper) -> Vec<ScatterLayer> {
+    let (Some(saved_state), Some(editor_data)) = (
+        state_helper.saved_state.as_ref(),
+        state_helper.editor_data.as_ref(),
+    ) else {
+        return Vec::new();
+    };
+
+    let saved_state = saved_state.lock().unwrap();
+    let editor_data = editor_data.lock().unwrap();
+
+    active_level(&saved_state, state_helper.active_level_id.as_deref())
+        .and_then(|level| editor_data.level(&level.id))
+        .map(|level_data| level_data.scatter_layers.clone())
+        .unwrap_or_default()
+}
+
+/// Replaces or inserts the layer in the active level's editor data and saves it
+fn store_scatter_layer(state_helper: &StateHelper, layer: Option<ScatterLayer>, layer_id: &str) {
+    let level_id = active_level_id(state_helper).expect("Couldn't get active level");
+
+    let mut editor_data = state_helper
+        .editor_data
+        .as_ref()
+        .expect("Couldn't get editor data")
+        .lock()
+        .unwrap();
+    let layers = &mut editor_data.level_mut(&level_id).scatter_layers;
+
+    layers.retain(|l| l.id != layer_id);
+    if let Some(layer) = layer {
+        layers.push(layer);
+    }
+
+    drop(editor_data);
+
+    state_helper.save_current_editor_data();
+}
+
+fn run_scatter_layer(
+    state_helper: &StateHelper,
+    gpu_helper: &Arc<Mutex<GpuHelper>>,
+    mut layer: ScatterLayer,
+) -> Result<(), String> {
+    let gpu_resources = gpu_helper
+        .lock()
+        .unwrap()
+        .gpu_resources
+        .clone()
+        .expect("Couldn't get gpu resources");
+
+    layer.instance_ids = apply_scatter_layer(state_helper, &gpu_resources, &layer)?;
+
+    let layer_id = layer.id.clone();
+    store_scatter_layer(state_helper, Some(layer), &layer_id);
+
+    Ok(())
+}
+
+fn range_inputs(
+    label_text: &'static str,
+    min: RwSignal<String>,
+    max: RwSignal<String>,
+) -> impl View {
+    v_stack((
+        label(move || label_text).style(|s| s.font_size(10.0).margin_top(5.0)),
+        h_stack((
+            text_input(min)
+                .placeholder("Min")
+                .style(|s| s.width(100.0).margin_right(5.0)),
+            text_input(max).placeholder("Max").style(|s| s.width(100.0)),
+        )),
+    ))
+}
+
+fn parse_range(min: RwSignal<String>, max: RwSignal<String>) -> Result<[f32; 2], String> {
+    match (
+        parse_string_to_float(&min.get()),
+        parse_string_to_float(&max.get()),
+    ) {
+        (Some(min), Some(max)) if min <= max => Ok([min, max]),
+        _ => Err("Invalid range".to_string()),
+    }
+}
+
+pub fn scatter_layer_item(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    layer: ScatterLayer,
+    scatter_layers: RwSignal<Vec<ScatterLayer>>,
+) -> impl View {
+    let state_2 = Arc::clone(&state_helper);
+    let regenerate_active = create_rw_signal(false);
+    let remove_active = create_rw_signal(false);
+
+    let layer_2 = layer.clone();
+    let label_text = format!("{} ({} instances)", layer.name, layer.instance_ids.len());
+
+    v_stack((
+        label(move || label_text.clone()),
+        h_stack((
+            small_button(
+                "Regenerate",
+                "plus",
+                move |_| {
+                    let state_helper = state_helper.lock().unwrap();
+
+                    if let Err(e) = run_scatter_layer(&state_helper, &gpu_helper, layer.clone()) {
+                        println!("Couldn't regenerate scatter: {}", e);
+                    }
+
+                    scatter_layers.set(level_scatter_layers(&state_helper));
+                },
+                regenerate_active,
+            )
+            .style(|s| s.margin_right(5.0)),
+            small_button(
+                "Remove",
+                "trash",
+                move |_| {
+                    let state_helper = state_2.lock().unwrap();
+
+                    remove_scatter_instances(&state_helper, &layer_2);
+
+                    let saved_state = state_helper
+                        .saved_state
+                        .as_ref()
+                        .expect("Couldn't get saved state")
+                        .lock()
+                        .unwrap();
+                    let project_id = state_helper
+                        .project_selected_signal
+                        .expect("Couldn't get project signal")
+                        .get();
+                    state_helper.save_saved_state(project_id, saved_state);
+
+                    store_scatter_layer(&state_helper, None, &layer_2.id);
+
+                    scatter_layers.set(level_scatter_layers(&state_helper));
+                },
+                remove_active,
+            ),
+        )),
+    ))
+    .style(|s| s.margin_bottom(10.0))
+}
+
+pub fn scatter_browser(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    viewport: Arc<Mutex<Viewport>>,
+) -> impl View {
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+
+    let defaults = ScatterLayer::default();
+
+    let scatter_layers: RwSignal<Vec<ScatterLayer>> = create_rw_signal(Vec::new());
+    let landscape_options: RwSignal<Vec<DropdownOption>> = create_rw_signal(Vec::new());
+    let model_files: RwSignal<Vec<File>> = create_rw_signal(Vec::new());
+    let selected_models: RwSignal<Vec<String>> = create_rw_signal(Vec::new());
+
+    let name = create_rw_signal(defaults.name.clone());
+    let landscape_component_id = create_rw_signal(String::new());
+    let density = create_rw_signal(defaults.density.to_string());
+    let slope_min = create_rw_signal(defaults.slope_range[0].to_string());
+    let slope_max = create_rw_signal(defaults.slope_range[1].to_string());
+    let height_min = create_rw_signal(defaults.height_range[0].to_string());
+    let height_max = create_rw_signal(defaults.height_range[1].to_string());
+    let rotation_min = create_rw_signal(defaults.rotation_range[0].to_string());
+    let rotation_max = create_rw_signal(defaults.rotation_range[1].to_string());
+    let scale_min = create_rw_signal(defaults.scale_range[0].to_string());
+    let scale_max = create_rw_signal(defaults.scale_range[1].to_string());
+    let seed = create_rw_signal(defaults.seed.to_string());
+    let world_size = create_rw_signal(defaults.world_size.to_string());
+    let height_scale = create_rw_signal(defaults.height_scale.to_string());
+    let mask_map = create_rw_signal(String::new());
+    let mask_channel = create_rw_signal("0".to_string());
+    let mask_threshold = create_rw_signal("0.5".to_string());
+    let scatter_error: RwSignal<Option<String>> = create_rw_signal(None);
+
+    create_effect(move |_| {
+        let state_helper = state_helper.lock().unwrap();
+        // runs again when another level opens
+        if let Some(active_level_signal) = state_helper.active_level_signal {
+            active_level_signal.get();
+        }
+
+        scatter_layers.set(level_scatter_layers(&state_helper));
+
+        let saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+
+        let landscapes = active_level(&saved_state, state_helper.active_level_id.as_deref())
+            .and_then(|level| level.components.as_ref())
+            .map(|components| {
+                components
+                    .iter()
+                    .filter(|c| c.kind == Some(ComponentKind::Landscape))
+                    .map(|c| DropdownOption {
+                        id: c.id.clone(),
+                        label: c.generic_properties.name.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        landscape_options.set(landscapes);
+        model_files.set(saved_state.models.clone());
+    });
+
+    let mask_options = vec![
+        DropdownOption {
+            id: "rockmap".to_string(),
+            label: "Rock Map".to_string(),
+        },
+        DropdownOption {
+            id: "soil".to_string(),
+            label: "Soil Map".to_string(),
+        },
+    ];
+    let channel_options = ["Red", "Green", "Blue", "Alpha"]
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| DropdownOption {
+            id: i.to_string(),
+            label: channel.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    v_stack((
+        label(|| "Name").style(|s| s.font_size(10.0)),
+        text_input(name).style(|s| s.width(205.0)),
+        label(|| "Landscape").style(|s| s.font_size(10.0).margin_top(5.0)),
+        dyn_container(
+            move || landscape_options.get(),
+            move |options| {
+                create_dropdown(
+                    landscape_component_id.get_untracked(),
+                    options,
+                    move |selected_id| landscape_component_id.set(selected_id),
+                )
+                .into_any()
+            },
+        ),
+        label(|| "Models").style(|s| s.font_size(10.0).margin_top(5.0)),
+        dyn_stack(
+            move || model_files.get(),
+            move |model| model.id.clone(),
+            move |model| {
+                let model_id = model.id.clone();
+                let model_id_2 = model.id.clone();
+                let file_name = model.fileName.clone();
+
+                simple_button(file_name, move |_| {
+                    selected_models.update(|selected| {
+                        if let Some(index) = selected.iter().position(|id| *id == model_id) {
+                            selected.remove(index);
+                        } else {
+                            selected.push(model_id.clone());
+                        }
+                    });
+                })
+                .style(move |s| {
+                    if selected_models.get().contains(&model_id_2) {
+                        s.border(2.0)
+                    } else {
+                        s
+                    }
+                })
+            },
+        )
+        .style(|s| s.flex_col()),
+        label(|| "Density (per 100x100)").style(|s| s.font_size(10.0).margin_top(5.0)),
+        text_input(density).style(|s| s.width(100.0)),
+        range_inputs("Slope (degrees)", slope_min, slope_max),
+        range_inputs("Height (0-1)", height_min, height_max),
+        range_inputs("Rotation (degrees)", rotation_min, rotation_max),
+        range_inputs("Scale", scale_min, scale_max),
+        label(|| "Mask").style(|s| s.font_size(10.0).margin_top(5.0)),
+        create_dropdown(String::new(), mask_options, move |selected_id| {
+            mask_map.set(selected_id)
+        }),
+        h_stack((
+            create_dropdown("0".to_string(), channel_options, move |selected_id| {
+                mask_channel.set(selected_id)
+            }),
+            text_input(mask_threshold)
+                .placeholder("Threshold")
+                .style(|s| s.width(100.0).margin_left(5.0)),
+        )),
+        label(|| "Seed").style(|s| s.font_size(10.0).margin_top(5.0)),
+        text_input(seed).style(|s| s.width(100.0)),
+        range_inputs("Landscape Size / Height Scale", world_size, height_scale),
+        dyn_container(
+            move || scatter_error.get(),
+            move |scatter_error_real| {
+                if let Some(message) = scatter_error_real {
+                    label(move || message.clone()).into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        ),
+        simple_button("Scatter".to_string(), move |_| {
+            let layer = (|| -> Result<ScatterLayer, String> {
+                if landscape_component_id.get().is_empty() {
+                    return Err("Select a landscape".to_string());
+                }
+                if selected_models.get().is_empty() {
+                    return Err("Select at least one model".to_string());
+                }
+
+                let mask = match mask_map.get().as_str() {
+                    "rockmap" => Some(ScatterMaskMap::Rockmap),
+                    "soil" => Some(ScatterMaskMap::Soil),
+                    _ => None,
+                }
+                .map(|map| ScatterMask {
+                    map,
+                    channel: mask_channel.get().parse().unwrap_or(0),
+                    threshold: parse_string_to_float(&mask_threshold.get()).unwrap_or(0.5),
+                });
+
+                Ok(ScatterLayer {
+                    name: name.get(),
+                    landscape_component_id: landscape_component_id.get(),
+                    model_ids: selected_models.get(),
+                    density: parse_string_to_float(&density.get()).ok_or("Invalid density")?,
+                    slope_range: parse_range(slope_min, slope_max)?,
+                    height_range: parse_range(height_min, height_max)?,
+                    mask,
+                    rotation_range: parse_range(rotation_min, rotation_max)?,
+                    scale_range: parse_range(scale_min, scale_max)?,
+                    seed: seed.get().trim().parse().map_err(|_| "Invalid seed")?,
+                    world_size: parse_string_to_float(&world_size.get())
+                        .ok_or("Invalid landscape size")?,
+                    height_scale: parse_string_to_float(&height_scale.get())
+                        .ok_or("Invalid height scale")?,
+                    ..Default::default()
+                })
+            })();
+
+            let state_helper = state_2.lock().unwrap();
+
+            match layer.and_then(|layer| run_scatter_layer(&state_helper, &gpu_2, layer)) {
+                Ok(()) => scatter_error.set(None),
+                Err(e) => {
+                    println!("Scatter failed: {}", e);
+                    scatter_error.set(Some(e));
+                }
+            }
+
+            scatter_layers.set(level_scatter_layers(&state_helper));
+        }),
+        label(|| "Scatter Layers").style(|s| s.margin_top(10.0)),
+        scroll(
+            dyn_stack(
+                move || scatter_layers.get(),
+                move |layer| (layer.id.clone(), layer.instance_ids.len()),
+                move |layer| {
+                    scatter_layer_item(state_3.clone(), gpu_helper.clone(), layer, scatter_layers)
+                },
+            )
+            .style(|s| s.flex_col()),
+        ),
+    ))
+    .style(|s| s.width(260.0))
+}
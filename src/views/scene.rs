@@ -25,11 +25,15 @@ use midpoint_engine::floem::{GpuHelper, View, WindowHandle};
 
 use crate::editor_state::StateHelper;
 
+use super::annotation_browser::annotation_browser;
 use super::component_browser::component_browser;
 use super::landscape_browser::landscape_browser;
 use super::level_browser::level_browser;
 use super::model_browser::model_browser;
+use super::prefab_browser::prefab_browser;
+use super::scatter_browser::scatter_browser;
 use super::texture_browser::texture_browser;
+use super::water_browser::water_browser;
 
 pub fn scene_view(
     state_helper: Arc<Mutex<StateHelper>>,
@@ -41,9 +45,19 @@ pub fn scene_view(
     let state_4 = Arc::clone(&state_helper);
     let state_5 = Arc::clone(&state_helper);
 
-    let tabs: im::Vector<&str> = vec!["Levels", "Components", "Models", "Landscapes", "Textures"]
-        .into_iter()
-        .collect();
+    let tabs: im::Vector<&str> = vec![
+        "Levels",
+        "Components",
+        "Prefabs",
+        "Models",
+        "Landscapes",
+        "Scatter",
+        "Water",
+        "Textures",
+        "Notes",
+    ]
+    .into_iter()
+    .collect();
     let (tabs, _set_tabs) = create_signal(tabs);
     let (active_tab, set_active_tab) = create_signal(0);
 
@@ -146,6 +160,9 @@ pub fn scene_view(
                     component_browser(state_2.clone(), gpu_helper.clone(), viewport.clone())
                         .into_any()
                 }
+                "Prefabs" => {
+                    prefab_browser(state_2.clone(), gpu_helper.clone(), viewport.clone()).into_any()
+                }
                 "Models" => {
                     model_browser(state_2.clone(), gpu_helper.clone(), viewport.clone()).into_any()
                 }
@@ -153,10 +170,19 @@ pub fn scene_view(
                     landscape_browser(state_2.clone(), gpu_helper.clone(), viewport.clone())
                         .into_any()
                 }
+                "Scatter" => scatter_browser(state_2.clone(), gpu_helper.clone(), viewport.clone())
+                    .into_any(),
+                "Water" => {
+                    water_browser(state_2.clone(), gpu_helper.clone(), viewport.clone()).into_any()
+                }
                 "Textures" => {
                     texture_browser(state_2.clone(), gpu_helper.clone(), viewport.clone())
                         .into_any()
                 }
+                "Notes" => {
+                    annotation_browser(state_2.clone(), gpu_helper.clone(), viewport.clone())
+                        .into_any()
+                }
                 _ => label(|| "Not implemented".to_owned()).into_any(),
             },
         )
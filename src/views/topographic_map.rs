@@ -12,12 +12,15 @@ use midpoint_engine::floem_renderer::Renderer;
 
 use nalgebra as na;
 
+use crate::helpers::contours::{extract_contours, ContourLevel};
+
 const MAX_DISPLAY_DIMENSION: usize = 256; // Adjust this based on performance needs
 
 pub struct TopographicMapView {
     id: ViewId,
     original_heights: na::DMatrix<f32>,
     downsampled_heights: na::DMatrix<f32>,
+    contours: Vec<ContourLevel>,
     config: TopographicConfig,
     style: Style,
 }
@@ -32,6 +35,18 @@ pub struct TopographicConfig {
     offset_y: f64,
     zoom: f64,
     color_scheme: Vec<Color>,
+    overlays: MapOverlays,
+}
+
+/// Extra information drawn over the heights on the map overview
+#[derive(Clone, Debug, Default)]
+pub struct MapOverlays {
+    /// Columns and rows of a landscape tile grid to outline over the map
+    pub tile_grid: Option<(usize, usize)>,
+    /// Cells at or below this height are drawn as water
+    pub water_level: Option<f32>,
+    /// River paths in grid space with their width in cells
+    pub water_paths: Vec<(Vec<[f32; 2]>, f32)>,
 }
 
 impl Default for TopographicConfig {
@@ -49,6 +64,7 @@ impl Default for TopographicConfig {
                 Color::rgb8(204, 153, 102),
                 Color::rgb8(255, 255, 255),
             ],
+            overlays: MapOverlays::default(),
         }
     }
 }
@@ -57,11 +73,14 @@ impl TopographicMapView {
     pub fn new(heights: na::DMatrix<f32>, config: Option<TopographicConfig>) -> Self {
         let config = config.unwrap_or_default();
         let downsampled = Self::downsample_heights(&heights);
+        // contours are traced at full resolution, only the elevation fill is downsampled
+        let contours = extract_contours(&heights, config.contour_interval);
 
         Self {
             id: ViewId::new(),
             original_heights: heights,
             downsampled_heights: downsampled,
+            contours,
             config: config.clone(),
             style: Style::new()
                 .position(Position::Absolute)
@@ -123,188 +142,117 @@ impl TopographicMapView {
         downsampled
     }
 
-    // fn draw_contour_lines(&self, cx: &mut PaintCx) {
-    //     let (rows, cols) = self.downsampled_heights.shape();
-    //     let min_height = self.downsampled_heights.min();
-    //     let max_height = self.downsampled_heights.max();
-
-    //     let pixel_width = self.config.width / cols as f64;
-    //     let pixel_height = self.config.height / rows as f64;
-
-    //     // Generate contours using marching squares algorithm
-    //     let mut height = min_height;
-    //     while height <= max_height {
-    //         let is_major = (height / self.config.major_interval).round()
-    //             * self.config.major_interval
-    //             == height;
-
-    //         let mut path = BezPath::new();
-    //         let mut path_started = false;
-
-    //         for y in 0..rows - 1 {
-    //             for x in 0..cols - 1 {
-    //                 let cell_corners = [
-    //                     self.downsampled_heights[(y, x)],
-    //                     self.downsampled_heights[(y, x + 1)],
-    //                     self.downsampled_heights[(y + 1, x + 1)],
-    //                     self.downsampled_heights[(y + 1, x)],
-    //                 ];
-
-    //                 if let Some(contour_segments) = self.get_contour_segments(
-    //                     x,
-    //                     y,
-    //                     height,
-    //                     &cell_corners,
-    //                     pixel_width,
-    //                     pixel_height,
-    //                 ) {
-    //                     for segment in contour_segments {
-    //                         if !path_started {
-    //                             path.move_to(segment.0);
-    //                             path_started = true;
-    //                         }
-    //                         path.line_to(segment.1);
-    //                     }
-    //                 }
-    //             }
-    //         }
-
-    //         let stroke_width = if is_major { 2.0 } else { 1.0 };
-    //         cx.stroke(&path, &Color::BLACK, stroke_width);
-
-    //         height += self.config.contour_interval;
-    //     }
-    // }
-
     fn draw_contour_lines(&self, cx: &mut PaintCx) {
-        let (rows, cols) = self.downsampled_heights.shape();
-        let min_height = self.downsampled_heights.min();
-        let max_height = self.downsampled_heights.max();
+        let (rows, cols) = self.original_heights.shape();
 
         let pixel_width = self.config.width / cols as f64;
         let pixel_height = self.config.height / rows as f64;
 
-        let mut height = min_height;
-        while height <= max_height {
-            let is_major = (height / self.config.major_interval).round()
+        for level in &self.contours {
+            let is_major = ((level.height / self.config.major_interval).round()
                 * self.config.major_interval
-                == height;
-
-            // Create a new path for each height level
-            let mut paths: Vec<BezPath> = Vec::new();
-            let mut current_path = BezPath::new();
-            let mut path_started = false;
-
-            for y in 0..rows - 1 {
-                for x in 0..cols - 1 {
-                    let cell_corners = [
-                        self.downsampled_heights[(y, x)],
-                        self.downsampled_heights[(y, x + 1)],
-                        self.downsampled_heights[(y + 1, x + 1)],
-                        self.downsampled_heights[(y + 1, x)],
-                    ];
-
-                    // If we cross the contour level
-                    if cell_corners.iter().any(|&v| v <= height)
-                        && cell_corners.iter().any(|&v| v > height)
-                    {
-                        let x_base = x as f64 * pixel_width + self.config.offset_x;
-                        let y_base = y as f64 * pixel_height + self.config.offset_y;
-
-                        // Simple linear interpolation for intersection points
-                        if !path_started {
-                            current_path = BezPath::new();
-                            current_path.move_to(Point::new(x_base, y_base));
-                            path_started = true;
-                        }
-                        current_path
-                            .line_to(Point::new(x_base + pixel_width, y_base + pixel_height));
-                    } else if path_started {
-                        // End the current path when we stop crossing the contour
-                        paths.push(current_path.clone());
-                        path_started = false;
-                    }
-                }
+                - level.height)
+                .abs()
+                < f32::EPSILON;
+
+            let stroke_width = if is_major { 2.0 } else { 1.0 };
 
-                // End path at row boundaries
-                if path_started {
-                    paths.push(current_path.clone());
-                    path_started = false;
+            for line in &level.lines {
+                // grid points sit at the center of their elevation cell
+                let to_point = |p: &[f32; 2]| {
+                    Point::new(
+                        (p[0] as f64 + 0.5) * pixel_width + self.config.offset_x,
+                        (p[1] as f64 + 0.5) * pixel_height + self.config.offset_y,
+                    )
+                };
+
+                let mut path = BezPath::new();
+                path.move_to(to_point(&line.points[0]));
+                for point in &line.points[1..] {
+                    path.line_to(to_point(point));
+                }
+                if line.closed {
+                    path.close_path();
                 }
-            }
 
-            // Draw all paths for this contour level
-            let stroke_width = if is_major { 2.0 } else { 1.0 };
-            for path in paths {
                 cx.stroke(&path, &Color::BLACK, stroke_width);
             }
-
-            height += self.config.contour_interval;
         }
     }
 
-    fn get_contour_segments(
-        &self,
-        x: usize,
-        y: usize,
-        height: f32,
-        values: &[f32; 4],
-        pixel_width: f64,
-        pixel_height: f64,
-    ) -> Option<Vec<(Point, Point)>> {
-        // Determine which corners are above the contour level
-        let cell_config = values
-            .iter()
-            .enumerate()
-            .fold(0, |acc, (i, &val)| acc | ((val >= height) as u8) << i);
-
-        // Skip if all corners are above or below
-        if cell_config == 0 || cell_config == 15 {
-            return None;
+    fn draw_tile_bounds(&self, cx: &mut PaintCx) {
+        let Some((columns, rows)) = self.config.overlays.tile_grid else {
+            return;
+        };
+
+        let tile_width = self.config.width / columns as f64;
+        let tile_height = self.config.height / rows as f64;
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let rect = kurbo::Rect::new(
+                    column as f64 * tile_width + self.config.offset_x,
+                    row as f64 * tile_height + self.config.offset_y,
+                    (column + 1) as f64 * tile_width + self.config.offset_x,
+                    (row + 1) as f64 * tile_height + self.config.offset_y,
+                );
+
+                cx.stroke(&rect, &Color::RED, 2.0);
+            }
         }
+    }
 
-        let x_base = x as f64 * pixel_width + self.config.offset_x;
-        let y_base = y as f64 * pixel_height + self.config.offset_y;
+    fn draw_water(&self, cx: &mut PaintCx) {
+        let water_color = Color::rgba8(40, 100, 200, 200);
 
-        // Linear interpolation helper
-        let lerp =
-            |v0: f32, v1: f32, h: f32| -> f64 { ((h - v0) / (v1 - v0)).clamp(0.0, 1.0) as f64 };
+        if let Some(water_level) = self.config.overlays.water_level {
+            let (rows, cols) = self.downsampled_heights.shape();
+            let pixel_width = self.config.width / cols as f64;
+            let pixel_height = self.config.height / rows as f64;
 
-        // Calculate intersection points
-        let mut segments = Vec::new();
-        match cell_config {
-            1 | 14 => {
-                let p1 = Point::new(
-                    x_base,
-                    y_base + pixel_height * lerp(values[0], values[3], height),
-                );
-                let p2 = Point::new(
-                    x_base + pixel_width * lerp(values[0], values[1], height),
-                    y_base,
-                );
-                segments.push((p1, p2));
+            for y in 0..rows {
+                for x in 0..cols {
+                    if self.downsampled_heights[(y, x)] > water_level {
+                        continue;
+                    }
+
+                    let rect = kurbo::Rect::new(
+                        x as f64 * pixel_width + self.config.offset_x,
+                        y as f64 * pixel_height + self.config.offset_y,
+                        (x + 1) as f64 * pixel_width + self.config.offset_x,
+                        (y + 1) as f64 * pixel_height + self.config.offset_y,
+                    );
+
+                    cx.fill(&rect, &water_color, 1.0);
+                }
             }
-            2 | 13 => {
-                let p1 = Point::new(
-                    x_base + pixel_width * lerp(values[0], values[1], height),
-                    y_base,
-                );
-                let p2 = Point::new(
-                    x_base + pixel_width,
-                    y_base + pixel_height * lerp(values[1], values[2], height),
-                );
-                segments.push((p1, p2));
+        }
+
+        let (rows, cols) = self.original_heights.shape();
+        let pixel_width = self.config.width / cols as f64;
+        let pixel_height = self.config.height / rows as f64;
+
+        for (points, width) in &self.config.overlays.water_paths {
+            if points.len() < 2 {
+                continue;
             }
-            // Add more cases for other configurations
-            _ => {
-                // Default simple diagonal for other cases
-                let p1 = Point::new(x_base, y_base);
-                let p2 = Point::new(x_base + pixel_width, y_base + pixel_height);
-                segments.push((p1, p2));
+
+            let to_point = |p: &[f32; 2]| {
+                Point::new(
+                    (p[0] as f64 + 0.5) * pixel_width + self.config.offset_x,
+                    (p[1] as f64 + 0.5) * pixel_height + self.config.offset_y,
+                )
+            };
+
+            let mut path = BezPath::new();
+            path.move_to(to_point(&points[0]));
+            for point in &points[1..] {
+                path.line_to(to_point(point));
             }
-        }
 
-        Some(segments)
+            let stroke_width = (*width as f64 * pixel_width).max(2.0);
+            cx.stroke(&path, &water_color, stroke_width);
+        }
     }
 
     fn draw_elevation_colors(&self, cx: &mut PaintCx) {
@@ -337,94 +285,6 @@ impl TopographicMapView {
         }
     }
 
-    // fn draw_contour_lines(&self, cx: &mut PaintCx) {
-    //     let (rows, cols) = self.downsampled_heights.shape();
-    //     let min_height = self.downsampled_heights.min();
-    //     let max_height = self.downsampled_heights.max();
-
-    //     let pixel_width = self.config.width / cols as f64;
-    //     let pixel_height = self.config.height / rows as f64;
-
-    //     // Calculate number of contour levels
-    //     let num_levels = ((max_height - min_height) / self.config.contour_interval).ceil() as i32;
-
-    //     for i in 0..=num_levels {
-    //         let height = min_height + i as f32 * self.config.contour_interval;
-    //         let is_major =
-    //             (i as f32 * self.config.contour_interval) % self.config.major_interval == 0.0;
-
-    //         let mut path = BezPath::new();
-    //         let mut current_line = Vec::new();
-
-    //         for y in 0..rows - 1 {
-    //             for x in 0..cols - 1 {
-    //                 let z00 = self.downsampled_heights[(y, x)];
-    //                 let z10 = self.downsampled_heights[(y, x + 1)];
-    //                 let z11 = self.downsampled_heights[(y + 1, x + 1)];
-    //                 let z01 = self.downsampled_heights[(y + 1, x)];
-
-    //                 // Check if contour passes through this cell
-    //                 let min_z = z00.min(z10).min(z11).min(z01);
-    //                 let max_z = z00.max(z10).max(z11).max(z01);
-
-    //                 if height >= min_z && height <= max_z {
-    //                     let x_base = x as f64 * pixel_width + self.config.offset_x;
-    //                     let y_base = y as f64 * pixel_height + self.config.offset_y;
-
-    //                     // Calculate intersections on cell edges
-    //                     let mut intersections = Vec::new();
-
-    //                     // Check left edge
-    //                     if (z00 <= height && z01 > height) || (z00 > height && z01 <= height) {
-    //                         let t = (height - z00) / (z01 - z00);
-    //                         intersections
-    //                             .push(Point::new(x_base, y_base + pixel_height * t as f64));
-    //                     }
-
-    //                     // Check right edge
-    //                     if (z10 <= height && z11 > height) || (z10 > height && z11 <= height) {
-    //                         let t = (height - z10) / (z11 - z10);
-    //                         intersections.push(Point::new(
-    //                             x_base + pixel_width,
-    //                             y_base + pixel_height * t as f64,
-    //                         ));
-    //                     }
-
-    //                     // Check top edge
-    //                     if (z00 <= height && z10 > height) || (z00 > height && z10 <= height) {
-    //                         let t = (height - z00) / (z10 - z00);
-    //                         intersections.push(Point::new(x_base + pixel_width * t as f64, y_base));
-    //                     }
-
-    //                     // Check bottom edge
-    //                     if (z01 <= height && z11 > height) || (z01 > height && z11 <= height) {
-    //                         let t = (height - z01) / (z11 - z01);
-    //                         intersections.push(Point::new(
-    //                             x_base + pixel_width * t as f64,
-    //                             y_base + pixel_height,
-    //                         ));
-    //                     }
-
-    //                     // If we found exactly 2 intersections, draw a line segment
-    //                     if intersections.len() == 2 {
-    //                         if current_line.is_empty() {
-    //                             path.move_to(intersections[0]);
-    //                         }
-    //                         path.line_to(intersections[1]);
-    //                         current_line.push(intersections[1]);
-    //                     }
-    //                 }
-    //             }
-    //         }
-
-    //         // Draw the contour line with appropriate style
-    //         let stroke_width = if is_major { 2.0 } else { 0.5 };
-    //         // let stroke = Stroke::new(Color::BLACK).with_width(stroke_width);
-
-    //         cx.stroke(&path, &Color::BLACK, stroke_width);
-    //     }
-    // }
-
     // fn draw_elevation_colors(&self, cx: &mut PaintCx) {
     //     let (rows, cols) = self.downsampled_heights.shape();
     //     let min_height = self.downsampled_heights.min();
@@ -463,12 +323,13 @@ impl View for TopographicMapView {
 
     fn paint(&mut self, cx: &mut PaintCx) {
         // First draw the elevation colors as the base layer
-        // self.draw_elevation_colors(cx);
+        self.draw_elevation_colors(cx);
         // Then overlay the contour lines
         self.draw_contour_lines(cx);
-
-        // after
-        self.draw_elevation_colors(cx);
+        // Water sits over the contours so shorelines read clearly
+        self.draw_water(cx);
+        // Tile bounds go on top so they stay visible over dense contours
+        self.draw_tile_bounds(cx);
     }
 
     fn view_style(&self) -> Option<Style> {
@@ -502,7 +363,7 @@ pub fn create_topographic_map(heights: na::DMatrix<f32>) -> impl View {
     // let handle_up = handle.clone();
     // let handle_wheel = handle.clone();
 
-    container((topo_map))
+    container(topo_map)
         .style(|s| {
             s.width(1024.0)
                 .height(1024.0)
@@ -563,3 +424,49 @@ pub fn create_topographic_map(heights: na::DMatrix<f32>) -> impl View {
             EventPropagation::Continue
         })
 }
+
+/// Smaller map used to check a heightmap before it is imported
+pub fn create_topographic_preview(heights: na::DMatrix<f32>, size: f64) -> impl View {
+    // source units vary a lot between formats, so space contours by the data's own range
+    let range = heights.max() - heights.min();
+    let contour_interval = if range > f32::EPSILON {
+        range / 20.0
+    } else {
+        1.0
+    };
+
+    let config = TopographicConfig {
+        width: size,
+        height: size,
+        contour_interval,
+        major_interval: contour_interval * 5.0,
+        ..Default::default()
+    };
+
+    let topo_map = TopographicMapView::new(heights, Some(config));
+
+    container(topo_map).style(move |s| s.width(size).height(size).background(Color::LIGHT_GRAY))
+}
+
+/// Overview of a level's landscape with tile bounds and water drawn over it
+pub fn create_overview_map(heights: na::DMatrix<f32>, overlays: MapOverlays) -> impl View {
+    let range = heights.max() - heights.min();
+    let contour_interval = if range > f32::EPSILON {
+        range / 20.0
+    } else {
+        1.0
+    };
+
+    let config = TopographicConfig {
+        width: 1024.0,
+        height: 1024.0,
+        contour_interval,
+        major_interval: contour_interval * 5.0,
+        overlays,
+        ..Default::default()
+    };
+
+    let topo_map = TopographicMapView::new(heights, Some(config));
+
+    container(topo_map).style(|s| s.width(1024.0).height(1024.0).background(Color::LIGHT_GRAY))
+}
@@ -0,0 +1,448 @@
+use std::sync::{Arc, Mutex};
+
+use midpoint_engine::core::Viewport::Viewport;
+use midpoint_engine::floem::common::{simple_button, small_button};
+use midpoint_engine::floem::reactive::SignalGet;
+use midpoint_engine::floem::reactive::{create_effect, create_rw_signal, RwSignal, SignalUpdate};
+use midpoint_engine::floem::views::{
+    dyn_container, dyn_stack, empty, h_stack, label, scroll, text_input, v_stack,
+};
+use midpoint_engine::floem::IntoView;
+use midpoint_engine::helpers::saved_data::ComponentKind;
+use uuid::Uuid;
+
+use midpoint_engine::floem::views::Decorators;
+use midpoint_engine::floem::{GpuHelper, View};
+
+use crate::editor_state::StateHelper;
+use crate::helpers::levels::active_level;
+use crate::helpers::utilities::parse_string_to_float;
+use crate::helpers::water::{
+    apply_river_carve, level_water, refresh_level_water, save_level_water, undo_river_carve, River,
+    WaterPlane, WaterSettings,
+};
+
+use super::inputs::{create_dropdown, DropdownOption};
+
+/// Saves the water settings and rebuilds the meshes drawn in the scene
+fn store_water(
+    state_helper: &StateHelper,
+    gpu_helper: &Arc<Mutex<GpuHelper>>,
+    water: WaterSettings,
+) {
+    save_level_water(state_helper, water);
+
+    let gpu_resources = gpu_helper
+        .lock()
+        .unwrap()
+        .gpu_resources
+        .clone()
+        .expect("Couldn't get gpu resources");
+
+    refresh_level_water(state_helper, &gpu_resources.device);
+}
+
+/// Reads "x, y, z" into a world position
+fn parse_point(input: &str) -> Option<[f32; 3]> {
+    let values = input
+        .split(',')
+        .map(parse_string_to_float)
+        .collect::<Option<Vec<f32>>>()?;
+
+    match values.as_slice() {
+        [x, y, z] => Some([*x, *y, *z]),
+        _ => None,
+    }
+}
+
+fn labeled_input(label_text: &'static str, value: RwSignal<String>) -> impl View {
+    v_stack((
+        label(move || label_text).style(|s| s.font_size(10.0).margin_top(5.0)),
+        text_input(value).style(|s| s.width(100.0)),
+    ))
+    .style(|s| s.margin_right(5.0))
+}
+
+/// Label and handler of a button on a plane or river
+type WaterItemAction = (&'static str, Box<dyn Fn()>);
+
+fn water_item(
+    label_text: String,
+    on_remove: impl Fn() + 'static,
+    actions: [Option<WaterItemAction>; 2],
+) -> impl View {
+    let remove_active = create_rw_signal(false);
+    let [first, second] = actions;
+
+    v_stack((
+        label(move || label_text.clone()),
+        h_stack((
+            action_button(first),
+            action_button(second),
+            small_button("Remove", "trash", move |_| on_remove(), remove_active),
+        )),
+    ))
+    .style(|s| s.margin_bottom(10.0))
+}
+
+/// Button for an item action next to Remove, nothing when the slot is empty
+fn action_button(action: Option<WaterItemAction>) -> impl IntoView {
+    let active = create_rw_signal(false);
+
+    match action {
+        Some((action_label, on_action)) => {
+            small_button(action_label, "plus", move |_| on_action(), active)
+                .style(|s| s.margin_right(5.0))
+                .into_any()
+        }
+        None => empty().into_any(),
+    }
+}
+
+pub fn water_browser(
+    state_helper: Arc<Mutex<StateHelper>>,
+    gpu_helper: Arc<Mutex<GpuHelper>>,
+    viewport: Arc<Mutex<Viewport>>,
+) -> impl View {
+    let state_2 = Arc::clone(&state_helper);
+    let state_3 = Arc::clone(&state_helper);
+    let state_4 = Arc::clone(&state_helper);
+    let state_5 = Arc::clone(&state_helper);
+    let state_6 = Arc::clone(&state_helper);
+    let gpu_2 = Arc::clone(&gpu_helper);
+    let gpu_3 = Arc::clone(&gpu_helper);
+    let gpu_4 = Arc::clone(&gpu_helper);
+    let gpu_5 = Arc::clone(&gpu_helper);
+
+    let defaults = WaterSettings::default();
+    let river_defaults = River::default();
+
+    let water: RwSignal<WaterSettings> = create_rw_signal(defaults.clone());
+    let landscape_options: RwSignal<Vec<DropdownOption>> = create_rw_signal(Vec::new());
+
+    let sea_level = create_rw_signal(String::new());
+    let sea_extent = create_rw_signal(defaults.sea_extent.to_string());
+    let landscape_size = create_rw_signal(defaults.landscape_size.to_string());
+    let height_scale = create_rw_signal(defaults.landscape_height_scale.to_string());
+
+    let plane_name = create_rw_signal("New Lake".to_string());
+    let plane_x = create_rw_signal("0".to_string());
+    let plane_y = create_rw_signal("0".to_string());
+    let plane_z = create_rw_signal("0".to_string());
+    let plane_width = create_rw_signal("100".to_string());
+    let plane_depth = create_rw_signal("100".to_string());
+
+    let river_name = create_rw_signal(river_defaults.name.clone());
+    let river_landscape_id = create_rw_signal(String::new());
+    let river_width = create_rw_signal(river_defaults.width.to_string());
+    let river_depth = create_rw_signal(river_defaults.depth.to_string());
+    let river_point = create_rw_signal(String::new());
+    let river_points: RwSignal<Vec<[f32; 3]>> = create_rw_signal(Vec::new());
+
+    let water_error: RwSignal<Option<String>> = create_rw_signal(None);
+
+    create_effect(move |_| {
+        let state_helper = state_helper.lock().unwrap();
+        // runs again when another level opens
+        if let Some(active_level_signal) = state_helper.active_level_signal {
+            active_level_signal.get();
+        }
+
+        let level_water = level_water(&state_helper);
+
+        sea_level.set(
+            level_water
+                .sea_level
+                .map(|level| level.to_string())
+                .unwrap_or_default(),
+        );
+        sea_extent.set(level_water.sea_extent.to_string());
+        landscape_size.set(level_water.landscape_size.to_string());
+        height_scale.set(level_water.landscape_height_scale.to_string());
+        water.set(level_water);
+
+        let saved_state = state_helper
+            .saved_state
+            .as_ref()
+            .expect("Couldn't get saved state")
+            .lock()
+            .unwrap();
+
+        let landscapes = active_level(&saved_state, state_helper.active_level_id.as_deref())
+            .and_then(|level| level.components.as_ref())
+            .map(|components| {
+                components
+                    .iter()
+                    .filter(|c| c.kind == Some(ComponentKind::Landscape))
+                    .map(|c| DropdownOption {
+                        id: c.id.clone(),
+                        label: c.generic_properties.name.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        landscape_options.set(landscapes);
+    });
+
+    v_stack((
+        label(|| "Sea Level (empty for none)").style(|s| s.font_size(10.0)),
+        text_input(sea_level).style(|s| s.width(100.0)),
+        h_stack((
+            labeled_input("Sea Extent", sea_extent),
+            labeled_input("Landscape Size", landscape_size),
+        )),
+        labeled_input("Height Scale", height_scale),
+        simple_button("Save Water Settings".to_string(), move |_| {
+            let settings = (|| -> Result<WaterSettings, String> {
+                let sea_level = match sea_level.get().trim() {
+                    "" => None,
+                    level => Some(parse_string_to_float(level).ok_or("Invalid sea level")?),
+                };
+
+                Ok(WaterSettings {
+                    sea_level,
+                    sea_extent: parse_string_to_float(&sea_extent.get())
+                        .ok_or("Invalid sea extent")?,
+                    landscape_size: parse_string_to_float(&landscape_size.get())
+                        .ok_or("Invalid landscape size")?,
+                    landscape_height_scale: parse_string_to_float(&height_scale.get())
+                        .ok_or("Invalid height scale")?,
+                    ..water.get()
+                })
+            })();
+
+            match settings {
+                Ok(settings) => {
+                    store_water(&state_2.lock().unwrap(), &gpu_2, settings.clone());
+                    water.set(settings);
+                    water_error.set(None);
+                }
+                Err(e) => water_error.set(Some(e)),
+            }
+        })
+        .style(|s| s.margin_top(5.0)),
+        label(|| "Water Planes").style(|s| s.margin_top(10.0)),
+        label(|| "Name").style(|s| s.font_size(10.0)),
+        text_input(plane_name).style(|s| s.width(205.0)),
+        h_stack((
+            labeled_input("X", plane_x),
+            labeled_input("Surface Y", plane_y),
+        )),
+        h_stack((
+            labeled_input("Z", plane_z),
+            labeled_input("Width", plane_width),
+        )),
+        labeled_input("Depth", plane_depth),
+        simple_button("Add Water Plane".to_string(), move |_| {
+            let plane = (|| -> Option<WaterPlane> {
+                Some(WaterPlane {
+                    id: Uuid::new_v4().to_string(),
+                    name: plane_name.get(),
+                    position: [
+                        parse_string_to_float(&plane_x.get())?,
+                        parse_string_to_float(&plane_y.get())?,
+                        parse_string_to_float(&plane_z.get())?,
+                    ],
+                    size: [
+                        parse_string_to_float(&plane_width.get())?,
+                        parse_string_to_float(&plane_depth.get())?,
+                    ],
+                })
+            })();
+
+            let Some(plane) = plane else {
+                water_error.set(Some("Invalid water plane".to_string()));
+                return;
+            };
+
+            let mut settings = water.get();
+            settings.planes.push(plane);
+
+            store_water(&state_3.lock().unwrap(), &gpu_3, settings.clone());
+            water.set(settings);
+            water_error.set(None);
+        })
+        .style(|s| s.margin_top(5.0)),
+        label(|| "Rivers").style(|s| s.margin_top(10.0)),
+        label(|| "Name").style(|s| s.font_size(10.0)),
+        text_input(river_name).style(|s| s.width(205.0)),
+        label(|| "Landscape").style(|s| s.font_size(10.0).margin_top(5.0)),
+        dyn_container(
+            move || landscape_options.get(),
+            move |options| {
+                create_dropdown(
+                    river_landscape_id.get_untracked(),
+                    options,
+                    move |selected_id| river_landscape_id.set(selected_id),
+                )
+                .into_any()
+            },
+        ),
+        h_stack((
+            labeled_input("Width", river_width),
+            labeled_input("Depth", river_depth),
+        )),
+        label(|| "Point (x, y, z)").style(|s| s.font_size(10.0).margin_top(5.0)),
+        h_stack((
+            text_input(river_point).style(|s| s.width(150.0).margin_right(5.0)),
+            simple_button("Add Point".to_string(), move |_| {
+                match parse_point(&river_point.get()) {
+                    Some(point) => {
+                        river_points.update(|points| points.push(point));
+                        river_point.set(String::new());
+                    }
+                    None => water_error.set(Some("Invalid point".to_string())),
+                }
+            }),
+        )),
+        label(move || format!("{} points", river_points.get().len())),
+        simple_button("Save River".to_string(), move |_| {
+            let river = (|| -> Result<River, String> {
+                if river_landscape_id.get().is_empty() {
+                    return Err("Select a landscape".to_string());
+                }
+                if river_points.get().len() < 2 {
+                    return Err("A river needs at least two points".to_string());
+                }
+
+                Ok(River {
+                    name: river_name.get(),
+                    landscape_component_id: river_landscape_id.get(),
+                    points: river_points.get(),
+                    width: parse_string_to_float(&river_width.get()).ok_or("Invalid width")?,
+                    depth: parse_string_to_float(&river_depth.get()).ok_or("Invalid depth")?,
+                    ..Default::default()
+                })
+            })();
+
+            match river {
+                Ok(river) => {
+                    let mut settings = water.get();
+                    settings.rivers.push(river);
+
+                    store_water(&state_4.lock().unwrap(), &gpu_4, settings.clone());
+                    water.set(settings);
+                    river_points.set(Vec::new());
+                    water_error.set(None);
+                }
+                Err(e) => water_error.set(Some(e)),
+            }
+        })
+        .style(|s| s.margin_top(5.0)),
+        dyn_container(
+            move || water_error.get(),
+            move |water_error_real| {
+                if let Some(message) = water_error_real {
+                    label(move || message.clone()).into_any()
+                } else {
+                    empty().into_any()
+                }
+            },
+        ),
+        scroll(
+            v_stack((
+                dyn_stack(
+                    move || water.get().planes,
+                    move |plane| plane.id.clone(),
+                    move |plane| {
+                        let state_helper = state_5.clone();
+                        let gpu_helper = gpu_5.clone();
+
+                        water_item(
+                            plane.name.clone(),
+                            move || {
+                                let mut settings = water.get();
+                                settings.planes.retain(|p| p.id != plane.id);
+
+                                store_water(
+                                    &state_helper.lock().unwrap(),
+                                    &gpu_helper,
+                                    settings.clone(),
+                                );
+                                water.set(settings);
+                            },
+                            [None, None],
+                        )
+                    },
+                )
+                .style(|s| s.flex_col()),
+                dyn_stack(
+                    move || water.get().rivers,
+                    move |river| river.id.clone(),
+                    move |river| {
+                        let state_helper = state_6.clone();
+                        let state_helper_2 = state_6.clone();
+                        let gpu_helper = gpu_helper.clone();
+                        let state_helper_3 = state_6.clone();
+                        let gpu_helper_2 = gpu_helper.clone();
+                        let gpu_helper_3 = gpu_helper.clone();
+                        let river_2 = river.clone();
+                        let river_3 = river.clone();
+
+                        water_item(
+                            format!("{} ({} points)", river.name, river.points.len()),
+                            move || {
+                                let mut settings = water.get();
+                                settings.rivers.retain(|r| r.id != river.id);
+
+                                store_water(
+                                    &state_helper.lock().unwrap(),
+                                    &gpu_helper,
+                                    settings.clone(),
+                                );
+                                water.set(settings);
+                            },
+                            [
+                                Some((
+                                    "Carve",
+                                    Box::new(move || {
+                                        let gpu_resources = gpu_helper_2
+                                            .lock()
+                                            .unwrap()
+                                            .gpu_resources
+                                            .clone()
+                                            .expect("Couldn't get gpu resources");
+
+                                        if let Err(e) = apply_river_carve(
+                                            &state_helper_2.lock().unwrap(),
+                                            &gpu_resources,
+                                            &river_2,
+                                            &water.get(),
+                                        ) {
+                                            println!("Couldn't carve river: {}", e);
+                                            water_error.set(Some(e));
+                                        }
+                                    }),
+                                )),
+                                Some((
+                                    "Uncarve",
+                                    Box::new(move || {
+                                        let gpu_resources = gpu_helper_3
+                                            .lock()
+                                            .unwrap()
+                                            .gpu_resources
+                                            .clone()
+                                            .expect("Couldn't get gpu resources");
+
+                                        if let Err(e) = undo_river_carve(
+                                            &state_helper_3.lock().unwrap(),
+                                            &gpu_resources,
+                                            &river_3,
+                                        ) {
+                                            println!("Couldn't uncarve river: {}", e);
+                                            water_error.set(Some(e));
+                                        }
+                                    }),
+                                )),
+                            ],
+                        )
+                    },
+                )
+                .style(|s| s.flex_col()),
+            ))
+            .style(|s| s.margin_top(10.0)),
+        ),
+    ))
+    .style(|s| s.width(260.0))
+}